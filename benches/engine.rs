@@ -0,0 +1,115 @@
+// Benchmarks for the minesweeper engine. Run with `cargo bench`.
+//
+// Performance budget, measured on the 500x500 board (release build). Changes to the
+// engine that push these over budget need a good reason:
+//      board generation    < 20 ms
+//      numeral population  < 20 ms
+//      cascade reveal      < 50 ms
+//      win check           < 1 us
+use criterion::{black_box, criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion};
+
+use minesofrust_web::minesweeper::*;
+
+const SEED: u64 = 0x6d696e6573;
+
+/// Board sizes as (width, height, number of mines)
+const BOARD_SIZES: [(u32, u32, u32); 3] = [(30, 16, 80), (100, 100, 2000), (500, 500, 50000)];
+
+fn size_label(width: u32, height: u32) -> String {
+    format!("{}x{}", width, height)
+}
+
+/// Finds a blank, non-mine square to start a cascade from
+fn find_blank(gb: &GameBoard) -> Option<(u32, u32)> {
+    (0..gb.height)
+        .flat_map(|y| (0..gb.width).map(move |x| (x, y)))
+        .find(|&(x, y)| {
+            let sqr = gb.get_square(x, y).unwrap();
+            !sqr.is_mine() && sqr.numeral == 0
+        })
+}
+
+fn bench_generation(c: &mut Criterion) {
+    let mut group = c.benchmark_group("generation");
+    for (width, height, num_mines) in BOARD_SIZES {
+        group.bench_with_input(
+            BenchmarkId::from_parameter(size_label(width, height)),
+            &(width, height, num_mines),
+            |b, &(width, height, num_mines)| {
+                b.iter(|| {
+                    let mut gb = GameBoard::new(width, height);
+                    gb.populate_mines_around(num_mines, Some(Coordinate::from((0, 0))))
+                        .unwrap();
+                    black_box(gb)
+                })
+            },
+        );
+    }
+    group.finish();
+}
+
+fn bench_numerals(c: &mut Criterion) {
+    let mut group = c.benchmark_group("numerals");
+    for (width, height, num_mines) in BOARD_SIZES {
+        let gb = GameBoard::new_benchmark_board(width, height, num_mines, SEED).unwrap();
+        group.bench_with_input(
+            BenchmarkId::from_parameter(size_label(width, height)),
+            &gb,
+            |b, gb| {
+                b.iter_batched(
+                    || gb.clone(),
+                    |mut gb| {
+                        gb.populate_numerals().unwrap();
+                        gb
+                    },
+                    BatchSize::LargeInput,
+                )
+            },
+        );
+    }
+    group.finish();
+}
+
+fn bench_cascade(c: &mut Criterion) {
+    let mut group = c.benchmark_group("cascade");
+    for (width, height, _) in BOARD_SIZES {
+        // A sparse board so that the flood fill covers most of it
+        let num_mines = width * height / 50;
+        let gb = GameBoard::new_benchmark_board(width, height, num_mines, SEED).unwrap();
+        let (x, y) = find_blank(&gb).expect("Benchmark board has no blank squares");
+        group.bench_with_input(
+            BenchmarkId::from_parameter(size_label(width, height)),
+            &gb,
+            |b, gb| {
+                b.iter_batched(
+                    || gb.clone(),
+                    |mut gb| gb.reveal(x, y).unwrap(),
+                    BatchSize::LargeInput,
+                )
+            },
+        );
+    }
+    group.finish();
+}
+
+fn bench_win_check(c: &mut Criterion) {
+    let mut group = c.benchmark_group("win_check");
+    for (width, height, num_mines) in BOARD_SIZES {
+        let gb = GameBoard::new_benchmark_board(width, height, num_mines, SEED).unwrap();
+        group.bench_with_input(
+            BenchmarkId::from_parameter(size_label(width, height)),
+            &gb,
+            |b, gb| b.iter(|| black_box(gb.is_win_configuration())),
+        );
+    }
+    group.finish();
+}
+
+criterion_group!(
+    benches,
+    bench_generation,
+    bench_numerals,
+    bench_cascade,
+    bench_win_check
+);
+criterion_main!(benches);
@@ -0,0 +1,19 @@
+// Boards shared in the Minesweeper Board Format can't panic the app, whatever's in them.
+// Run with `cargo +nightly fuzz run board_mbf` from the repository root.
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use minesofrust_web::minesweeper::*;
+
+fuzz_target!(|data: &[u8]| {
+    let Ok(mut board) = GameBoard::from_mbf(data) else {
+        return;
+    };
+    // Whatever's read can be written out and read back the same
+    let written = board
+        .to_mbf()
+        .expect("a board read from MBF can be written");
+    let reread = GameBoard::from_mbf(&written).expect("a written board reads back");
+    assert_eq!(reread.to_ascii(true), board.to_ascii(true));
+    let _ = board.play(0, 0, RevealType::Reveal);
+});
@@ -0,0 +1,17 @@
+// Boards shared as text can't panic the app, whatever's in them. Run with
+// `cargo +nightly fuzz run board_text` from the repository root.
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use minesofrust_web::minesweeper::*;
+
+fuzz_target!(|text: &str| {
+    let Ok(mut board) = GameBoard::from_ascii(text) else {
+        return;
+    };
+    // Whatever's read can be written out and read back the same
+    let written = board.to_ascii(true);
+    let reread = GameBoard::from_ascii(&written).expect("a written board reads back");
+    assert_eq!(reread.to_ascii(true), written);
+    let _ = board.play(0, 0, RevealType::Reveal);
+});
@@ -0,0 +1,32 @@
+// Any sequence of plays, on or off the board, gives errors rather than panics and leaves
+// the board consistent. Run with `cargo +nightly fuzz run play_sequence` from the
+// repository root.
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use minesofrust_web::minesweeper::*;
+
+const PLAY_TYPES: [RevealType; 4] = [
+    RevealType::Reveal,
+    RevealType::RevealChord,
+    RevealType::Chord,
+    RevealType::Flag,
+];
+
+fuzz_target!(|data: &[u8]| {
+    // The board's size, mines and seed, then three bytes to a play: its column, row and
+    // type. Columns and rows aren't wrapped, so plays can miss the board.
+    let [width, height, mines, seed, plays @ ..] = data else {
+        return;
+    };
+    let (width, height) = (*width as u32 % 32 + 1, *height as u32 % 32 + 1);
+    let mut board = GameBoard::new_seeded(width, height, *seed as u64);
+    if board.populate_mines(*mines as u32).is_err() || board.populate_numerals().is_err() {
+        return;
+    }
+    plays.chunks_exact(3).for_each(|play| {
+        let play_type = PLAY_TYPES[play[2] as usize % PLAY_TYPES.len()].clone();
+        let _ = board.play(play[0] as u32, play[1] as u32, play_type);
+        assert!(board.num_flags() + board.num_revealed() <= width * height);
+    });
+});
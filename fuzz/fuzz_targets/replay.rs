@@ -0,0 +1,30 @@
+// Replays shared or saved with a leaderboard entry can't panic the app when they're played
+// back, whatever's in them. Run with `cargo +nightly fuzz run replay` from the repository
+// root.
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use minesofrust_web::minesweeper::*;
+use minesofrust_web::plays::PlayList;
+
+fuzz_target!(|json: &str| {
+    let Ok(plays) = serde_json::from_str::<PlayList>(json) else {
+        return;
+    };
+    // Played back on an expert board the same way the game does, mines first placed around
+    // the first reveal
+    let mut board = GameBoard::new_seeded(30, 16, 7);
+    plays.list.iter().filter(|e| e.is_click()).for_each(|e| {
+        let reveal = matches!(e.play_type, RevealType::Reveal | RevealType::RevealChord);
+        if reveal && !board.is_populated {
+            if board
+                .populate_mines_around(99, Some(e.coord.clone()))
+                .is_err()
+                || board.populate_numerals().is_err()
+            {
+                return;
+            }
+        }
+        let _ = board.play(e.coord.x, e.coord.y, e.play_type.clone());
+    });
+});
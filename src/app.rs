@@ -1,96 +1,385 @@
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")] // hide console window on Windows in release
 
-use std::process;
+use std::cell::RefCell;
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::PathBuf;
+use std::rc::Rc;
+use std::time::Duration;
+#[cfg(feature = "net")]
 use std::time::SystemTime;
 
 use anyhow::Result;
-use eframe::{egui, glow, Theme};
+use chrono::NaiveDate;
+use eframe::{egui, glow};
 use egui::{
     Color32, Key, KeyboardShortcut, Modifiers, Pos2, RichText, Stroke, Vec2, ViewportCommand,
-    Visuals,
+    Visuals, WindowLevel,
 };
 use egui_extras::install_image_loaders;
+use egui_plot::{Bar, BarChart, Line, Plot, PlotPoints, Points};
 use itertools::iproduct;
 
+#[cfg(not(target_arch = "wasm32"))]
+use crate::autosave::{Autosaver, SavedGame};
+#[cfg(not(target_arch = "wasm32"))]
+use crate::bugreport::{self, BoardCounts};
+use crate::calibrate::{AnalysisJob, BoardStats, Distribution};
+use crate::clock::GameClock;
+use crate::config;
 use crate::constants;
+#[cfg(feature = "net")]
+use crate::coop::{CoopMessage, CoopSession, GUEST, HOST};
+#[cfg(not(target_arch = "wasm32"))]
+use crate::crash;
 use crate::enums::*;
+use crate::events::{GameEvent, GameEvents, GameObserver};
+#[cfg(not(target_arch = "wasm32"))]
+use crate::export::{write_replay_gif, ReplayOptions};
+use crate::fog::{fog_alpha, FogMemory};
+use crate::hotseat::{HotSeat, TurnResult};
+use crate::i18n::{format_date_time, set_language, tr, tr_with};
+#[cfg(not(target_arch = "wasm32"))]
+use crate::logging;
 use crate::minesweeper::*;
+use crate::mode::{mode_for, GameMode, PuzzleMode, TutorialMode};
+#[cfg(feature = "net")]
+use crate::net::{Finish, NetSession};
+use crate::notify;
+#[cfg(not(target_arch = "wasm32"))]
+use crate::overlay::OverlayStats;
+use crate::placement::MinePlacement;
+use crate::plays::*;
+#[cfg(not(target_arch = "wasm32"))]
+use crate::profile::Profile;
+use crate::progress::{game_points, points_for_level, Progress};
+use crate::puzzle::{self, Puzzle, PuzzleProgress};
+#[cfg(all(feature = "scripting", not(target_arch = "wasm32")))]
+use crate::script;
+use crate::segment::*;
+use crate::shape::BoardShape;
+use crate::solver::{self, GuessTracker, NumeralConstraint, PostMortem};
+#[cfg(feature = "net")]
+use crate::spectate::{Broadcaster, SpectateEvent, Spectator, SpectatorView};
+#[cfg(feature = "livesplit")]
+use crate::splits::SplitTimer;
 use crate::state::*;
+#[cfg(all(feature = "sync", not(target_arch = "wasm32")))]
+use crate::sync::{sync_profile_within, ProfileSync, SyncSettings};
 use crate::toggle::*;
-use serde::{Deserialize, Serialize};
+#[cfg(all(feature = "tray", not(target_arch = "wasm32")))]
+use crate::tray::{Tray, TrayAction};
+use crate::tutorial::Tutorial;
+#[cfg(feature = "updates")]
+use crate::update::UpdateCheck;
+
+use crate::history::{
+    rolling_win_rate, GameHistory, GameRecord, LossCause, Penalties, SessionRecord, SessionStats,
+};
+#[cfg(all(feature = "hotkeys", not(target_arch = "wasm32")))]
+use crate::hotkeys::{parse_hotkey, GlobalHotkeys, HotkeyAction};
+use crate::input::{BoardInput, FlagPaint, KeyboardCursor, TapAction, TouchGestures};
+use crate::leader::{
+    adjusted_time, Entry, EntryFilter, EntryIntegrity, Handicap, LeaderBoards, LeaderboardCategory,
+    SortColumn,
+};
+#[cfg(feature = "online")]
+use crate::online::{OnlineLeaderboard, Submission, SubmissionQueue};
+
+/// Number of recent games plotted in the statistics window unless the player picks another
+const DEFAULT_STATISTICS_NUM_GAMES: usize = 50;
+
+/// Number of games the rolling win rate is averaged over
+const WIN_RATE_WINDOW: usize = 10;
+
+/// Boards generated by each run of the board analysis window unless another number is picked
+const DEFAULT_BOARD_ANALYSIS_NUM_BOARDS: u32 = 200;
+
+const BOTTOM_PANEL_MIN_HEIGHT: f32 = 40.0;
+
+/// Longest the app waits on the sync server when it's closing
+#[cfg(all(feature = "sync", not(target_arch = "wasm32")))]
+const EXIT_SYNC_TIMEOUT: Duration = Duration::from_secs(5);
+
+const SHORTCUT_NEW_GAME: KeyboardShortcut = KeyboardShortcut::new(Modifiers::COMMAND, Key::N);
+const SHORTCUT_RESTART_GAME: KeyboardShortcut = KeyboardShortcut::new(Modifiers::COMMAND, Key::R);
+const SHORTCUT_PAUSE: KeyboardShortcut = KeyboardShortcut::new(Modifiers::COMMAND, Key::P);
+const SHORTCUT_EXIT: KeyboardShortcut = KeyboardShortcut::new(Modifiers::COMMAND, Key::Q);
+const SHORTCUT_HIDE: KeyboardShortcut = KeyboardShortcut::new(Modifiers::COMMAND, Key::H);
+const SHORTCUT_COMPACT_MODE: KeyboardShortcut = KeyboardShortcut::new(Modifiers::COMMAND, Key::M);
+const SHORTCUT_ALWAYS_ON_TOP: KeyboardShortcut = KeyboardShortcut::new(Modifiers::COMMAND, Key::T);
+const SHORTCUT_SETTINGS: KeyboardShortcut = KeyboardShortcut::new(Modifiers::COMMAND, Key::Comma);
+const SHORTCUT_ABOUT: KeyboardShortcut = KeyboardShortcut::new(Modifiers::NONE, Key::F1);
+const SHORTCUT_ZEN_MODE: KeyboardShortcut = KeyboardShortcut::new(Modifiers::NONE, Key::F11);
 
-use crate::leader::LeaderBoards;
+/// Held to send out a sonar ping in fog of war mode
+const SONAR_KEY: Key = Key::S;
+
+/// Show the layer above or below on a layered board
+const LAYER_UP_KEY: Key = Key::PageUp;
+const LAYER_DOWN_KEY: Key = Key::PageDown;
+
+/// Third party work shipped in the app, as (what, license, link) for the About window
+const CREDITS: &[(&str, &str, &str)] = &[
+    (
+        "Board and face artwork",
+        "Made for Mines of Rust",
+        env!("CARGO_PKG_REPOSITORY"),
+    ),
+    (
+        "egui, eframe and egui_plot",
+        "MIT OR Apache-2.0",
+        "https://github.com/emilk/egui",
+    ),
+    (
+        "Hack, Ubuntu Light and Noto Emoji fonts (bundled with egui)",
+        "MIT, Ubuntu Font Licence and OFL",
+        "https://github.com/emilk/egui/tree/master/crates/epaint/fonts",
+    ),
+];
+
+/// Seconds between repaints while waiting to see whether a touch becomes a long press
+const TOUCH_REPAINT_INTERVAL: f64 = 0.05;
 
 /// Settings as 'true' will allow the window to be resized and will print the dimensions to the console.
 const DBG_WINDOW_RESIZABLE: bool = false;
 
-#[derive(Debug, Clone, Default, Deserialize, Serialize)]
-struct PlayEntry {
-    #[allow(dead_code)]
-    coord: Coordinate,
-    play_type: RevealType,
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum SettingsTab {
+    Gameplay,
+    Appearance,
+    Input,
+    Audio,
+    Advanced,
 }
 
-#[derive(Debug, Clone, Default, Deserialize, Serialize)]
-struct PlayList {
-    pub list: Vec<PlayEntry>,
-}
+impl SettingsTab {
+    const ALL: [SettingsTab; 5] = [
+        SettingsTab::Gameplay,
+        SettingsTab::Appearance,
+        SettingsTab::Input,
+        SettingsTab::Audio,
+        SettingsTab::Advanced,
+    ];
 
-impl PlayList {
-    pub fn push(&mut self, entry: PlayEntry) {
-        self.list.push(entry);
+    fn as_str(&self) -> &'static str {
+        match *self {
+            SettingsTab::Gameplay => tr("tab-gameplay"),
+            SettingsTab::Appearance => tr("tab-appearance"),
+            SettingsTab::Input => tr("tab-input"),
+            SettingsTab::Audio => tr("tab-audio"),
+            SettingsTab::Advanced => tr("tab-advanced"),
+        }
     }
+}
 
-    pub fn clear(&mut self) {
-        self.list.clear();
+/// How the leaderboard window is filtered and sorted
+#[derive(Clone)]
+struct LeaderboardView {
+    filter: EntryFilter,
+    from_text: String,
+    to_text: String,
+    sort: SortColumn,
+    ascending: bool,
+    /// Only the fastest `top` wins, and only the player's own
+    top_only: bool,
+    top: usize,
+    mine: bool,
+    /// Page shown of each leaderboard, by title
+    pages: BTreeMap<String, usize>,
+    /// Board waiting on the player to confirm clearing it
+    confirm_clear: Option<LeaderboardCategory>,
+}
+
+/// Something that throws away the game in progress
+#[derive(Clone, PartialEq)]
+enum AbandonAction {
+    NewGame,
+    Restart,
+    Exit,
+}
+
+impl AbandonAction {
+    fn as_str(&self) -> &'static str {
+        match self {
+            AbandonAction::NewGame => tr("new-game"),
+            AbandonAction::Restart => tr("restart"),
+            AbandonAction::Exit => tr("exit"),
+        }
     }
+}
+
+/// A new game, restart or exit waiting on the player to confirm it
+#[derive(Clone)]
+struct AbandonPrompt {
+    action: AbandonAction,
+    dont_ask_again: bool,
+}
+
+/// The file a profile is imported from, and how the last export or import went
+#[cfg(not(target_arch = "wasm32"))]
+#[derive(Clone, Default)]
+struct ProfileTransfer {
+    path: String,
+    /// Take the profile's settings too, not just merge in its leaderboards and history
+    import_settings: bool,
+    message: Option<String>,
+}
+
+/// What the player picks in the welcome window the first time the app is run
+#[derive(Clone)]
+struct Onboarding {
+    difficulty: GameDifficulty,
+    theme: VisualTheme,
+    player_name: String,
+    input_style: InputStyle,
+    /// Play the tutorial once the choices are made
+    tutorial: bool,
+}
 
-    #[allow(dead_code)]
-    pub fn len(&self) -> usize {
-        self.list.len()
+impl Onboarding {
+    fn new(state: &AppState) -> Self {
+        Onboarding {
+            difficulty: GameDifficulty::Beginner,
+            theme: state.theme.clone(),
+            player_name: state.player_name.clone(),
+            input_style: InputStyle::Classic,
+            tutorial: true,
+        }
     }
+}
+
+/// What's picked in the board debugger, for calling into the engine by hand
+#[derive(Clone, Default)]
+struct BoardDebugger {
+    board: usize,
+    /// Where `play()` is called, on the board's own coordinates, and the kind of play
+    x: u32,
+    y: u32,
+    play_type: RevealType,
+    /// What the engine gave back for the last change
+    result: String,
+}
+
+/// The script being written in the script console, and what it printed
+#[cfg(all(feature = "scripting", not(target_arch = "wasm32")))]
+#[derive(Clone)]
+struct ScriptConsole {
+    source: String,
+    /// The file in the scripts folder the script is loaded from and saved to
+    file_name: String,
+    output: Vec<String>,
+    /// The script's `on_play` is run after each of the player's plays
+    run_after_plays: bool,
+}
 
-    pub fn clicks(&self) -> u32 {
-        self.list.len() as u32
+#[cfg(all(feature = "scripting", not(target_arch = "wasm32")))]
+impl Default for ScriptConsole {
+    fn default() -> Self {
+        ScriptConsole {
+            source: script::EXAMPLE_SCRIPT.to_string(),
+            file_name: "script.rhai".to_string(),
+            output: vec![],
+            run_after_plays: false,
+        }
     }
+}
 
-    pub fn reveals(&self) -> u32 {
-        self.list
-            .iter()
-            .map(|e| match e.play_type {
-                RevealType::Reveal | RevealType::RevealChord => 1,
-                _ => 0,
-            })
-            .collect::<Vec<u32>>()
+/// The squares a cascade revealed, shown a ring at a time spreading out from the square
+/// played
+#[derive(Clone)]
+struct RevealRings {
+    board: usize,
+    width: u32,
+    /// The ring each square of the board was revealed in, row by row
+    depths: Rc<Vec<Option<u32>>>,
+    last: u32,
+    /// When the first ring was shown, in egui's time
+    start: Option<f64>,
+}
+
+impl RevealRings {
+    /// The rings to show for a play on `board`, unless it didn't spread
+    fn new(board: usize, gameboard: &GameBoard, result: &PlayResult) -> Option<Self> {
+        let last = result.changes.iter().map(|c| c.depth).max()?;
+        if last == 0 {
+            return None;
+        }
+        let mut depths = vec![None; (gameboard.width * gameboard.height) as usize];
+        result
+            .changes
             .iter()
-            .sum()
+            .filter(|c| c.kind == ChangeKind::Revealed)
+            .for_each(|c| {
+                depths[(c.coord.y * gameboard.width + c.coord.x) as usize] = Some(c.depth)
+            });
+        Some(RevealRings {
+            board,
+            width: gameboard.width,
+            depths: Rc::new(depths),
+            last,
+            start: None,
+        })
     }
 
-    pub fn flagged(&self) -> u32 {
-        self.list
-            .iter()
-            .map(|e| match e.play_type {
-                RevealType::Flag => 1,
-                _ => 0,
-            })
-            .collect::<Vec<u32>>()
-            .iter()
-            .sum()
+    /// The furthest ring out that's shown by `time`
+    fn shown(&mut self, time: f64) -> u32 {
+        let start = *self.start.get_or_insert(time);
+        ((time - start) / constants::REVEAL_RING_SECONDS) as u32
     }
 
-    pub fn chords(&self) -> u32 {
-        self.list
-            .iter()
-            .map(|e| match e.play_type {
-                RevealType::Chord | RevealType::RevealChord => 1,
-                _ => 0,
-            })
-            .collect::<Vec<u32>>()
-            .iter()
-            .sum()
+    /// Whether the square is still to be shown covered when rings as far out as `shown` are
+    fn is_covered(&self, x: u32, y: u32, shown: u32) -> bool {
+        self.depths
+            .get((y * self.width + x) as usize)
+            .copied()
+            .flatten()
+            .is_some_and(|depth| depth > shown)
+    }
+}
+
+/// A win faster than any the player had before in its leaderboard category
+#[derive(Clone)]
+struct PersonalBest {
+    time: f64,
+    /// The best time it beat, unless it's the first win in the category
+    previous: Option<f64>,
+}
+
+/// How squares are drawn, from the settings of whoever is looking at them
+struct SquareStyle {
+    numeral_style: NumeralStyle,
+    skin: BoardSkin,
+    /// Mines are shown faintly while playing, for practice
+    mines_visible: bool,
+}
+
+/// A chord held back because it would set off a mine, waiting to be made again to go ahead
+#[derive(Clone)]
+struct ChordWarning {
+    coord: Coordinate,
+    /// When it was held back, in seconds into the game
+    time: f64,
+}
+
+impl Default for LeaderboardView {
+    fn default() -> Self {
+        LeaderboardView {
+            filter: EntryFilter::default(),
+            from_text: String::new(),
+            to_text: String::new(),
+            sort: SortColumn::Time,
+            ascending: true,
+            top_only: false,
+            top: 10,
+            mine: false,
+            pages: BTreeMap::new(),
+            confirm_clear: None,
+        }
     }
 }
 
+/// Wall clock time, which spectators use to keep time with the game they're watching
+#[cfg(feature = "net")]
 fn now() -> f64 {
     match SystemTime::now().duration_since(SystemTime::UNIX_EPOCH) {
         Ok(n) => n.as_secs_f64(),
@@ -100,44 +389,425 @@ fn now() -> f64 {
 
 #[derive(Clone)]
 pub struct MinesOfRustApp {
-    gameboard: GameBoard,
+    /// The boards in play, all of which have to be cleared to win
+    boards: Vec<GameBoard>,
+    /// Where each board was drawn last frame
+    board_rects: Vec<egui::Rect>,
+    /// Where the mini-map of boards too big for the window was drawn last frame
+    minimap_rect: Option<egui::Rect>,
+    /// Where the mini-map has asked the boards to be scrolled to, for the next frame
+    board_scroll_to: Option<Vec2>,
     state: AppState,
     image_loaders_installed: bool,
     detonated_on: Option<Coordinate>,
+    chord_warning: Option<ChordWarning>,
+    /// One for each board, brought up to date while the guess indicator is shown
+    guess_trackers: Vec<GuessTracker>,
+    /// A chord has been held back this game, telling the player a flag is wrong
+    chord_warned: bool,
+    /// Practicing with the mines shown, for learning patterns and checking generated boards
+    mines_visible: bool,
+    /// The mines have been shown at some point this game
+    mines_seen: bool,
+    /// The guess indicator has been shown at some point this game
+    guess_indicator_used: bool,
+    /// This game was recovered after the app closed without finishing it
+    recovered: bool,
+    /// A game found saved when the app started, which it didn't get to finish
+    #[cfg(not(target_arch = "wasm32"))]
+    recovered_game: Option<SavedGame>,
+    /// When the game was last saved, in UI seconds, and how many plays it had then
+    #[cfg(not(target_arch = "wasm32"))]
+    autosaved: Option<(f64, usize)>,
+    /// Writes and removes the saved game in progress
+    #[cfg(not(target_arch = "wasm32"))]
+    autosaver: Rc<Autosaver>,
+    /// When the overlay stats were last written, in UI seconds, and what they were
+    #[cfg(not(target_arch = "wasm32"))]
+    overlay_written: Option<(f64, OverlayStats)>,
+    /// Something has happened in the game since the overlay stats were last written
+    #[cfg(not(target_arch = "wasm32"))]
+    overlay_stale: bool,
+    /// How many plays the game in progress had when it was last noted for saving if the
+    /// app crashes
+    #[cfg(not(target_arch = "wasm32"))]
+    crash_recorded: Option<usize>,
+    /// The report of the last time the app crashed, until the player dismisses it
+    #[cfg(not(target_arch = "wasm32"))]
+    crash_report: Option<String>,
+    /// Recent log messages are shown, in debug builds
+    #[cfg(not(target_arch = "wasm32"))]
+    debug_console_visible: bool,
+    /// The board debugger, while it's open in a debug build
+    board_debugger: Option<BoardDebugger>,
+    /// The boards were changed in the board debugger this game
+    board_debugged: bool,
+    /// A copy of each board taken when it was last changed other than by a play, with how
+    /// many plays had been made then. A play report makes the board before the play from it.
+    #[cfg(not(target_arch = "wasm32"))]
+    board_snapshots: Vec<Option<(GameBoard, usize)>>,
+    /// Mode set in place of the one the settings are for
+    custom_mode: Option<Rc<dyn GameMode>>,
+    /// The last cascade, while it's still spreading out on screen
+    reveal_rings: Option<RevealRings>,
+    /// The numeral being read with the flag button held or Alt down on it, by board and
+    /// where it is on the board
+    constraint_focus: Option<(usize, Coordinate)>,
+    /// When the squares the last reveal proved safe are due to be auto-opened, in egui's
+    /// input time
+    auto_open_due: Option<f64>,
+    /// The script console, while it's open
+    #[cfg(all(feature = "scripting", not(target_arch = "wasm32")))]
+    script_console: Option<ScriptConsole>,
+    /// The plays being made are a script's, so they don't set its `on_play` off again
+    #[cfg(all(feature = "scripting", not(target_arch = "wasm32")))]
+    script_playing: bool,
+    /// A script made plays this game
+    script_played: bool,
+    /// Mistakes made this game, when it's played with time penalties
+    penalties: Option<Penalties>,
+    /// The squares a wrong flag has been charged for this game, by board
+    penalized_flags: Vec<(usize, Coordinate)>,
+    /// The game just won was the player's fastest yet
+    personal_best: Option<PersonalBest>,
+    /// The board is one from the leaderboard, played again
+    leaderboard_replay: bool,
+    /// When the sonar ping going out started, in UI seconds, while the sonar key is held
+    sonar_since: Option<f64>,
+    /// Layer of a layered board that's shown, from the top
+    layer: u32,
     game_state: GameState,
-    game_started: f64,
-    game_finished: f64,
+    clock: GameClock,
     game_settings: GameSettings,
     leaderboards: LeaderBoards,
     leaderboard_visible: bool,
+    leaderboard_view: LeaderboardView,
     gamestats_visible: bool,
+    /// Show where the player clicked over the board once the game is over
+    show_heatmap: bool,
+    /// Where the last replay was exported to, or why it couldn't be
+    replay_export_message: Option<String>,
+    statistics_visible: bool,
+    about_visible: bool,
+    /// Two player race in progress, if any
+    hotseat: Option<HotSeat>,
+    hotseat_setup_visible: bool,
+    /// Puzzles to play, the campaign's first
+    puzzles: Vec<Puzzle>,
+    puzzle_progress: PuzzleProgress,
+    /// Index of the puzzle being played, if any
+    puzzle: Option<usize>,
+    puzzles_visible: bool,
+    /// Tutorial being played, if any
+    tutorial: Option<Tutorial>,
+    /// Nothing had been saved when the app started, so the welcome window is shown once
+    /// it's running
+    first_run: bool,
+    /// The welcome window, shown on the first run until the player has made their choices
+    onboarding: Option<Onboarding>,
+    #[cfg(not(target_arch = "wasm32"))]
+    profile_visible: bool,
+    #[cfg(not(target_arch = "wasm32"))]
+    profile_transfer: ProfileTransfer,
+    /// Settings being edited in the settings window, not yet applied. `None` while the
+    /// window is closed.
+    settings_draft: Option<AppState>,
+    settings_tab: SettingsTab,
+    statistics_num_games: usize,
+    /// Clear History was clicked, and is waiting to be confirmed
+    confirm_clear_history: bool,
+    /// Debug window measuring generated boards of the current difficulty
+    board_analysis_visible: bool,
+    board_analysis_num_boards: u32,
+    board_analysis: Option<BoardStats>,
+    /// The boards being analyzed, while they're being generated
+    board_analysis_job: Option<AnalysisJob>,
+    history: GameHistory,
+    /// Points and level earned from the history, kept up to date as games are added to it
+    progress: Progress,
     plays: PlayList,
-    wins: u32,
-    losses: u32,
+    session: SessionStats,
+    post_mortem: Option<PostMortem>,
+    post_mortem_visible: bool,
+    /// What's happened in the game since the last frame, and who's listening
+    events: GameEvents,
+    flag_paint: FlagPaint,
+    keyboard_cursor: KeyboardCursor,
+    fog_memory: FogMemory,
+    /// The reveal button is being held down on the board
+    board_pressed: bool,
+    /// Height of the bottom panel when it was last shown
+    bottom_panel_height: f32,
+    touch_gestures: TouchGestures,
+    window_focused: bool,
+    error_message: Option<String>,
+    abandon_prompt: Option<AbandonPrompt>,
+    /// Times a flag has been placed, or refused, with no mines left to flag. The mine
+    /// counter shakes each time it goes up.
+    flag_warnings: u32,
+    /// The player has agreed to exit, so the window's next close request goes through
+    exit_confirmed: bool,
+    persistence_warnings: Vec<String>,
+    alternate_config_dir: String,
+    #[cfg(feature = "online")]
+    online: OnlineLeaderboard,
+    #[cfg(feature = "updates")]
+    update_check: UpdateCheck,
+    #[cfg(feature = "livesplit")]
+    split_timer: SplitTimer,
+    /// The banner about a newer release has been closed for this session
+    #[cfg(feature = "updates")]
+    update_dismissed: bool,
+    #[cfg(feature = "online")]
+    global_leaderboard_visible: bool,
+    /// Icon in the system tray, once it's been made
+    #[cfg(all(feature = "tray", not(target_arch = "wasm32")))]
+    tray: Option<Rc<Tray>>,
+    #[cfg(all(feature = "tray", not(target_arch = "wasm32")))]
+    hidden_in_tray: bool,
+    /// Hotkeys that work while the window isn't focused, once they're listened for
+    #[cfg(all(feature = "hotkeys", not(target_arch = "wasm32")))]
+    global_hotkeys: Option<Rc<GlobalHotkeys>>,
+    #[cfg(all(feature = "sync", not(target_arch = "wasm32")))]
+    sync_settings: SyncSettings,
+    #[cfg(all(feature = "sync", not(target_arch = "wasm32")))]
+    profile_sync: ProfileSync,
+    /// Network race lobby joined or hosted, if any
+    #[cfg(feature = "net")]
+    net: Option<NetSession>,
+    #[cfg(feature = "net")]
+    net_lobby_visible: bool,
+    /// Streams this player's games to spectators while set
+    #[cfg(feature = "net")]
+    broadcaster: Option<Broadcaster>,
+    /// Someone else's games being watched, if any
+    #[cfg(feature = "net")]
+    spectator: Option<Spectator>,
+    #[cfg(feature = "net")]
+    spectate_visible: bool,
+    /// Co-op game hosted or joined, if any
+    #[cfg(feature = "net")]
+    coop: Option<CoopSession>,
+    #[cfg(feature = "net")]
+    coop_visible: bool,
+}
+
+/// Paints a tile's artwork over `rect`. egui rasterizes SVGs at the size they're drawn in
+/// points, so ask for the size in pixels instead to keep them sharp on high-DPI displays.
+fn paint_tile(ui: &egui::Ui, image: egui::Image<'_>, rect: egui::Rect) {
+    image
+        .fit_to_exact_size(rect.size() * ui.ctx().pixels_per_point())
+        .paint_at(ui, rect);
+}
+
+/// The color `t` of the way from `from` to `to`
+fn mix_colors(from: Color32, to: Color32, t: f32) -> Color32 {
+    (egui::Rgba::from(from) * (1.0 - t) + egui::Rgba::from(to) * t).into()
+}
+
+fn numeral_image(numeral: u32) -> Option<egui::ImageSource<'static>> {
+    match numeral {
+        1 => Some(egui::include_image!("../assets/1.svg")),
+        2 => Some(egui::include_image!("../assets/2.svg")),
+        3 => Some(egui::include_image!("../assets/3.svg")),
+        4 => Some(egui::include_image!("../assets/4.svg")),
+        5 => Some(egui::include_image!("../assets/5.svg")),
+        6 => Some(egui::include_image!("../assets/6.svg")),
+        7 => Some(egui::include_image!("../assets/7.svg")),
+        8 => Some(egui::include_image!("../assets/8.svg")),
+        _ => None,
+    }
+}
+
+/// A new file in `dir`, or the home directory if there isn't one, named for the time so it
+/// doesn't replace an earlier export
+#[cfg(not(target_arch = "wasm32"))]
+fn export_path(dir: Option<PathBuf>, prefix: &str, extension: &str) -> PathBuf {
+    let stamp = chrono::Local::now().format("%Y%m%d-%H%M%S");
+    dir.or_else(dirs::home_dir)
+        .unwrap_or_default()
+        .join(format!("{}-{}.{}", prefix, stamp, extension))
+}
+
+/// Unwraps a loaded config file, falling back to the default and recording a warning for the
+/// player if it couldn't be read.
+#[cfg(not(target_arch = "wasm32"))]
+fn load_or_warn<T: Default>(loaded: Result<Option<T>>, warnings: &mut Vec<String>) -> T {
+    match loaded {
+        Ok(v) => v.unwrap_or_default(),
+        Err(e) => {
+            log::warn!("{:#}", e);
+            warnings.push(format!("{:#}", e));
+            T::default()
+        }
+    }
 }
 
 #[cfg(not(target_arch = "wasm32"))]
 impl MinesOfRustApp {
     pub fn load_from_persistence() -> MinesOfRustApp {
-        let state = AppState::load_from_userhome().unwrap_or_default();
-        let leaderboards = LeaderBoards::load_from_userhome().unwrap_or_default();
-        let settings = GameSettings::settings_for_difficulty(&state.difficulty);
+        let mut persistence_warnings = vec![];
+        let loaded_state = AppState::load_from_userhome();
+        let first_run = matches!(loaded_state, Ok(None));
+        let state = load_or_warn(loaded_state, &mut persistence_warnings);
+        let leaderboards = load_or_warn(
+            LeaderBoards::load_from_userhome(),
+            &mut persistence_warnings,
+        );
+        let history = load_or_warn(GameHistory::load_from_userhome(), &mut persistence_warnings);
+        let puzzle_progress = load_or_warn(
+            PuzzleProgress::load_from_userhome(),
+            &mut persistence_warnings,
+        );
+        #[cfg(feature = "online")]
+        let submission_queue = load_or_warn(
+            SubmissionQueue::load_from_userhome(),
+            &mut persistence_warnings,
+        );
+        #[cfg(all(feature = "sync", not(target_arch = "wasm32")))]
+        let sync_settings = load_or_warn(
+            SyncSettings::load_from_userhome(),
+            &mut persistence_warnings,
+        );
+        let settings =
+            GameSettings::settings_for_mode(&state.difficulty, state.no_flags, state.num_boards);
+        let recovered_game = SavedGame::load_from_userhome().unwrap_or_else(|e| {
+            log::warn!("Failed to recover the last game: {:#}", e);
+            None
+        });
+        crash::record(&state, None);
 
         MinesOfRustApp {
-            gameboard: GameBoard::new(settings.width, settings.height),
+            boards: MinesOfRustApp::new_boards(&settings),
+            board_rects: vec![],
+            minimap_rect: None,
+            board_scroll_to: None,
             state,
             image_loaders_installed: false,
             detonated_on: None,
+            chord_warning: None,
+            guess_trackers: vec![],
+            chord_warned: false,
+            mines_visible: false,
+            mines_seen: false,
+            guess_indicator_used: false,
+            recovered: false,
+            recovered_game,
+            autosaved: None,
+            autosaver: Rc::default(),
+            overlay_written: None,
+            overlay_stale: true,
+            crash_recorded: None,
+            crash_report: crash::load_crash_report(),
+            debug_console_visible: false,
+            board_debugger: None,
+            board_debugged: false,
+            #[cfg(not(target_arch = "wasm32"))]
+            board_snapshots: vec![],
+            custom_mode: None,
+            reveal_rings: None,
+            constraint_focus: None,
+            auto_open_due: None,
+            #[cfg(all(feature = "scripting", not(target_arch = "wasm32")))]
+            script_console: None,
+            #[cfg(all(feature = "scripting", not(target_arch = "wasm32")))]
+            script_playing: false,
+            script_played: false,
+            penalties: None,
+            penalized_flags: vec![],
+            personal_best: None,
+            leaderboard_replay: false,
+            sonar_since: None,
+            layer: 0,
             game_state: GameState::NotStarted,
-            game_started: 0.0,
-            game_finished: 0.0,
+            clock: GameClock::default(),
             game_settings: settings,
             leaderboards,
             leaderboard_visible: false,
+            leaderboard_view: LeaderboardView::default(),
             gamestats_visible: false,
+            show_heatmap: false,
+            replay_export_message: None,
+            statistics_visible: false,
+            about_visible: false,
+            hotseat: None,
+            hotseat_setup_visible: false,
+            puzzles: puzzle::campaign()
+                .into_iter()
+                .chain(puzzle::load_user_puzzles())
+                .collect(),
+            puzzle_progress,
+            puzzle: None,
+            puzzles_visible: false,
+            tutorial: None,
+            first_run,
+            onboarding: None,
+            #[cfg(not(target_arch = "wasm32"))]
+            profile_visible: false,
+            #[cfg(not(target_arch = "wasm32"))]
+            profile_transfer: ProfileTransfer::default(),
+            settings_draft: None,
+            settings_tab: SettingsTab::Gameplay,
+            statistics_num_games: DEFAULT_STATISTICS_NUM_GAMES,
+            confirm_clear_history: false,
+            board_analysis_visible: false,
+            board_analysis_num_boards: DEFAULT_BOARD_ANALYSIS_NUM_BOARDS,
+            board_analysis: None,
+            board_analysis_job: None,
+            progress: Progress::from_history(&history),
+            history,
             plays: PlayList::default(),
-            wins: 0,
-            losses: 0,
+            session: SessionStats::default(),
+            post_mortem: None,
+            post_mortem_visible: false,
+            events: GameEvents::default(),
+            flag_paint: FlagPaint::default(),
+            keyboard_cursor: KeyboardCursor::default(),
+            fog_memory: FogMemory::default(),
+            board_pressed: false,
+            bottom_panel_height: BOTTOM_PANEL_MIN_HEIGHT,
+            touch_gestures: TouchGestures::default(),
+            window_focused: true,
+            error_message: None,
+            abandon_prompt: None,
+            flag_warnings: 0,
+            exit_confirmed: false,
+            persistence_warnings,
+            alternate_config_dir: String::new(),
+            #[cfg(feature = "online")]
+            online: OnlineLeaderboard::new(submission_queue),
+            #[cfg(feature = "updates")]
+            update_check: UpdateCheck::default(),
+            #[cfg(feature = "livesplit")]
+            split_timer: SplitTimer::default(),
+            #[cfg(feature = "updates")]
+            update_dismissed: false,
+            #[cfg(feature = "online")]
+            global_leaderboard_visible: false,
+            #[cfg(all(feature = "tray", not(target_arch = "wasm32")))]
+            tray: None,
+            #[cfg(all(feature = "tray", not(target_arch = "wasm32")))]
+            hidden_in_tray: false,
+            #[cfg(all(feature = "hotkeys", not(target_arch = "wasm32")))]
+            global_hotkeys: None,
+            #[cfg(all(feature = "sync", not(target_arch = "wasm32")))]
+            sync_settings,
+            #[cfg(all(feature = "sync", not(target_arch = "wasm32")))]
+            profile_sync: ProfileSync::default(),
+            #[cfg(feature = "net")]
+            net: None,
+            #[cfg(feature = "net")]
+            net_lobby_visible: false,
+            #[cfg(feature = "net")]
+            broadcaster: None,
+            #[cfg(feature = "net")]
+            spectator: None,
+            #[cfg(feature = "net")]
+            spectate_visible: false,
+            #[cfg(feature = "net")]
+            coop: None,
+            #[cfg(feature = "net")]
+            coop_visible: false,
         }
     }
 }
@@ -148,592 +818,6233 @@ impl MinesOfRustApp {
         let settings = GameSettings::beginner();
         let state = AppState::default();
         let leaderboards = LeaderBoards::default();
+        let history = GameHistory::default();
 
         MinesOfRustApp {
-            gameboard: GameBoard::new(settings.width, settings.height),
+            boards: MinesOfRustApp::new_boards(&settings),
+            board_rects: vec![],
+            minimap_rect: None,
+            board_scroll_to: None,
             state,
             image_loaders_installed: false,
             detonated_on: None,
+            chord_warning: None,
+            guess_trackers: vec![],
+            chord_warned: false,
+            mines_visible: false,
+            mines_seen: false,
+            guess_indicator_used: false,
+            recovered: false,
+            penalties: None,
+            penalized_flags: vec![],
+            personal_best: None,
+            leaderboard_replay: false,
+            board_debugger: None,
+            board_debugged: false,
+            #[cfg(not(target_arch = "wasm32"))]
+            board_snapshots: vec![],
+            custom_mode: None,
+            reveal_rings: None,
+            constraint_focus: None,
+            auto_open_due: None,
+            #[cfg(all(feature = "scripting", not(target_arch = "wasm32")))]
+            script_console: None,
+            #[cfg(all(feature = "scripting", not(target_arch = "wasm32")))]
+            script_playing: false,
+            script_played: false,
+            sonar_since: None,
+            layer: 0,
             game_state: GameState::NotStarted,
-            game_started: 0.0,
-            game_finished: 0.0,
+            clock: GameClock::default(),
             game_settings: settings,
             leaderboards,
             leaderboard_visible: false,
+            leaderboard_view: LeaderboardView::default(),
             gamestats_visible: false,
+            show_heatmap: false,
+            replay_export_message: None,
+            statistics_visible: false,
+            about_visible: false,
+            hotseat: None,
+            hotseat_setup_visible: false,
+            puzzles: puzzle::campaign(),
+            puzzle_progress: PuzzleProgress::default(),
+            puzzle: None,
+            puzzles_visible: false,
+            tutorial: None,
+            first_run: false,
+            onboarding: None,
+            #[cfg(not(target_arch = "wasm32"))]
+            profile_visible: false,
+            #[cfg(not(target_arch = "wasm32"))]
+            profile_transfer: ProfileTransfer::default(),
+            settings_draft: None,
+            settings_tab: SettingsTab::Gameplay,
+            statistics_num_games: DEFAULT_STATISTICS_NUM_GAMES,
+            confirm_clear_history: false,
+            board_analysis_visible: false,
+            board_analysis_num_boards: DEFAULT_BOARD_ANALYSIS_NUM_BOARDS,
+            board_analysis: None,
+            board_analysis_job: None,
+            progress: Progress::from_history(&history),
+            history,
             plays: PlayList::default(),
-            wins: 0,
-            losses: 0,
+            session: SessionStats::default(),
+            post_mortem: None,
+            post_mortem_visible: false,
+            events: GameEvents::default(),
+            flag_paint: FlagPaint::default(),
+            keyboard_cursor: KeyboardCursor::default(),
+            fog_memory: FogMemory::default(),
+            board_pressed: false,
+            bottom_panel_height: BOTTOM_PANEL_MIN_HEIGHT,
+            touch_gestures: TouchGestures::default(),
+            window_focused: true,
+            error_message: None,
+            abandon_prompt: None,
+            flag_warnings: 0,
+            exit_confirmed: false,
+            persistence_warnings: vec![],
+            alternate_config_dir: String::new(),
+            #[cfg(feature = "online")]
+            online: OnlineLeaderboard::default(),
+            #[cfg(feature = "updates")]
+            update_check: UpdateCheck::default(),
+            #[cfg(feature = "livesplit")]
+            split_timer: SplitTimer::default(),
+            #[cfg(feature = "updates")]
+            update_dismissed: false,
+            #[cfg(feature = "online")]
+            global_leaderboard_visible: false,
+            #[cfg(all(feature = "tray", not(target_arch = "wasm32")))]
+            tray: None,
+            #[cfg(all(feature = "tray", not(target_arch = "wasm32")))]
+            hidden_in_tray: false,
+            #[cfg(all(feature = "hotkeys", not(target_arch = "wasm32")))]
+            global_hotkeys: None,
+            #[cfg(all(feature = "sync", not(target_arch = "wasm32")))]
+            sync_settings: SyncSettings::default(),
+            #[cfg(all(feature = "sync", not(target_arch = "wasm32")))]
+            profile_sync: ProfileSync::default(),
+            #[cfg(feature = "net")]
+            net: None,
+            #[cfg(feature = "net")]
+            net_lobby_visible: false,
+            #[cfg(feature = "net")]
+            broadcaster: None,
+            #[cfg(feature = "net")]
+            spectator: None,
+            #[cfg(feature = "net")]
+            spectate_visible: false,
+            #[cfg(feature = "net")]
+            coop: None,
+            #[cfg(feature = "net")]
+            coop_visible: false,
+        }
+    }
+}
+
+#[cfg(feature = "online")]
+impl MinesOfRustApp {
+    fn global_leaderboard_ui(&mut self, ui: &mut egui::Ui) {
+        if !self.state.online_enabled || self.state.online_endpoint.trim().is_empty() {
+            ui.label(tr("global-leaderboard-disabled"));
+            return;
+        }
+
+        let busy = self.online.is_busy();
+        let difficulty = self.state.difficulty.clone();
+        ui.horizontal(|ui| {
+            if ui
+                .add_enabled(!busy, egui::Button::new(tr("refresh")))
+                .clicked()
+            {
+                self.online
+                    .fetch(ui.ctx(), &self.state.online_endpoint, difficulty.clone());
+            }
+            if self.online.num_pending() > 0 {
+                ui.label(tr_with(
+                    "wins-waiting-to-be-sent",
+                    &[("count", &self.online.num_pending())],
+                ));
+                if ui
+                    .add_enabled(!busy, egui::Button::new(tr("send-now")))
+                    .clicked()
+                {
+                    self.online.flush(ui.ctx(), &self.state.online_endpoint);
+                }
+            }
+            if busy {
+                ui.spinner();
+            }
+        });
+        if let Some(e) = self.online.last_error() {
+            ui.colored_label(constants::COLOR_WARNING, e);
+        }
+
+        ui.heading(difficulty.name());
+        match self.online.global_entries(&difficulty) {
+            Some(entries) => {
+                egui::Grid::new("global_leaderboard")
+                    .num_columns(4)
+                    .spacing([50.0, 5.0])
+                    .striped(true)
+                    .show(ui, |ui| {
+                        entries.iter().enumerate().for_each(|(i, e)| {
+                            ui.label(format!("{}", i + 1));
+                            ui.label(&e.player_name);
+                            ui.label(format!("{:.2}", e.time));
+                            ui.label(format_date_time(&e.date));
+                            ui.end_row();
+                        });
+                    });
+            }
+            // Fetch on first view, but don't keep retrying a server that isn't answering
+            None if !busy && self.online.last_error().is_none() => {
+                self.online
+                    .fetch(ui.ctx(), &self.state.online_endpoint, difficulty);
+            }
+            None => {}
         }
     }
 }
 
 impl eframe::App for MinesOfRustApp {
+    /// Clear behind the panels in zen mode, so the window can be seen through
+    fn clear_color(&self, visuals: &Visuals) -> [f32; 4] {
+        if self.state.zen_mode {
+            Color32::TRANSPARENT.to_normalized_gamma_f32()
+        } else {
+            visuals.panel_fill.to_normalized_gamma_f32()
+        }
+    }
+
     fn update(&mut self, ctx: &egui::Context, frame: &mut eframe::Frame) {
-        self.on_update(ctx, frame).expect("Failed to update UI");
+        if let Err(e) = self.on_update(ctx, frame) {
+            self.report_error(e);
+        }
     }
 
     fn on_exit(&mut self, _gl: Option<&glow::Context>) {
-        self.state.save_to_userhome();
-        self.leaderboards.save_to_userhome();
+        // A game left unfinished on purpose isn't one to recover
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            self.remove_autosave();
+            self.autosaver.finish();
+        }
+        // Saved first, so a server that never answers can't cost the player anything
+        self.save_to_userhome();
+        // The app is closing, so there's no frame left to pick up a background sync
+        #[cfg(all(feature = "sync", not(target_arch = "wasm32")))]
+        if self.sync_settings.enabled && !self.profile_sync.is_syncing() {
+            match sync_profile_within(&self.sync_settings, self.profile(), EXIT_SYNC_TIMEOUT) {
+                Ok(profile) => {
+                    self.merge_profile(&profile);
+                    self.save_to_userhome();
+                }
+                Err(e) => log::warn!("Failed to sync profile: {:#}", e),
+            }
+        }
     }
 }
 
 impl MinesOfRustApp {
-    fn update_difficulty_settings(&mut self) {
-        self.game_settings = match self.state.difficulty {
-            GameDifficulty::Beginner => GameSettings::beginner(),
-            GameDifficulty::Intermediate => GameSettings::intermediate(),
-            GameDifficulty::Expert => GameSettings::expert(),
-            // _ => unimplemented!(),
-        };
+    /// Shows an error to the player in a dialog rather than bringing down the UI
+    fn report_error(&mut self, error: impl std::fmt::Display) {
+        log::error!("{}", error);
+        self.error_message = Some(error.to_string());
     }
 
-    fn reset_new_game(&mut self, ctx: &egui::Context) -> Result<(), Error> {
-        self.gameboard = GameBoard::new(self.game_settings.width, self.game_settings.height);
-        self.plays.clear();
-        self.game_state = GameState::NotStarted;
-        self.detonated_on = None;
-        self.game_started = now();
-
-        ctx.send_viewport_cmd(ViewportCommand::InnerSize(Vec2 {
-            x: self.game_settings.ui_width,
-            y: self.game_settings.ui_height,
-        }));
-
-        Ok(())
+    fn error_ui(&mut self, ctx: &egui::Context) {
+        let mut open = true;
+        let mut dismissed = false;
+        egui::Window::new(tr("window-error"))
+            .open(&mut open)
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_CENTER, [0.0, 0.0])
+            .show(ctx, |ui| {
+                ui.label(self.error_message.as_deref().unwrap_or_default());
+                ui.vertical_centered(|ui| {
+                    dismissed = ui.button(tr("ok")).clicked();
+                });
+            });
+        if !open || dismissed {
+            self.error_message = None;
+        }
     }
 
-    fn reset_existing_game(&mut self, _ctx: &egui::Context) -> Result<(), Error> {
-        self.gameboard.reset_existing();
-
-        self.plays.clear();
-        self.game_state = GameState::NotStarted;
-        self.game_started = now();
-
-        Ok(())
+    /// Starts a new game, restarts or exits, first asking the player to confirm if that
+    /// would throw away a game in progress
+    fn abandon_game(&mut self, ctx: &egui::Context, action: AbandonAction) {
+        if self.asks_before_abandoning() {
+            self.abandon_prompt = Some(AbandonPrompt {
+                action,
+                dont_ask_again: false,
+            });
+        } else {
+            self.run_abandon_action(ctx, action);
+        }
     }
 
-    fn start_game(&mut self, first_click: Coordinate) -> Result<(), Error> {
-        println!(
-            "Starting game with fist click at x={}, y={}",
-            first_click.x, first_click.y
-        );
+    fn asks_before_abandoning(&self) -> bool {
+        self.state.confirm_abandon
+            && matches!(self.game_state, GameState::Playing | GameState::Paused)
+    }
 
-        // Make sure we remove any previous mines
-        //self.gameboard.reset();
-        if !self.gameboard.is_populated {
-            self.gameboard
-                .populate_mines_around(self.game_settings.num_mines, Some(first_click))?;
+    fn run_abandon_action(&mut self, ctx: &egui::Context, action: AbandonAction) {
+        let result = match action {
+            AbandonAction::NewGame => self.reset_new_game(ctx),
+            AbandonAction::Restart => self.reset_existing_game(ctx),
+            AbandonAction::Exit => {
+                self.exit_confirmed = true;
+                ctx.send_viewport_cmd(ViewportCommand::Close);
+                Ok(())
+            }
+        };
+        if let Err(e) = result {
+            self.report_error(e);
         }
+    }
 
-        self.game_started = now();
-        self.game_state = GameState::Playing;
-
-        if self.game_settings.use_numerals {
-            self.gameboard.populate_numerals()?;
+    fn abandon_prompt_ui(&mut self, ctx: &egui::Context) {
+        let Some(prompt) = &mut self.abandon_prompt else {
+            return;
+        };
+        let mut open = true;
+        let mut confirmed = false;
+        let mut cancelled = false;
+        egui::Window::new(tr("window-abandon-game"))
+            .open(&mut open)
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_CENTER, [0.0, 0.0])
+            .show(ctx, |ui| {
+                ui.label(tr("abandon-game-lost"));
+                ui.checkbox(&mut prompt.dont_ask_again, tr("dont-ask-again"));
+                ui.horizontal(|ui| {
+                    confirmed = ui.button(prompt.action.as_str()).clicked();
+                    cancelled = ui.button(tr("cancel")).clicked();
+                });
+            });
+        if confirmed {
+            let prompt = self.abandon_prompt.take().unwrap();
+            if prompt.dont_ask_again {
+                self.state.confirm_abandon = false;
+                self.save_to_userhome();
+            }
+            self.run_abandon_action(ctx, prompt.action);
+        } else if !open || cancelled {
+            self.abandon_prompt = None;
         }
+    }
 
-        Ok(())
+    /// Saves the settings and leaderboards, recording a warning for the player on failure
+    fn save_to_userhome(&mut self) {
+        if let Err(e) = self.state.save_to_userhome() {
+            log::warn!("Failed to save settings: {:#}", e);
+            self.persistence_warnings.push(tr_with(
+                "failed-to-save-settings",
+                &[("error", &format!("{:#}", e))],
+            ));
+        }
+        if let Err(e) = self.leaderboards.save_to_userhome() {
+            log::warn!("Failed to save leaderboard: {:#}", e);
+            self.persistence_warnings.push(tr_with(
+                "failed-to-save-leaderboard",
+                &[("error", &format!("{:#}", e))],
+            ));
+        }
+        if let Err(e) = self.history.save_to_userhome() {
+            log::warn!("Failed to save game history: {:#}", e);
+            self.persistence_warnings.push(tr_with(
+                "failed-to-save-history",
+                &[("error", &format!("{:#}", e))],
+            ));
+        }
+        if let Err(e) = self.puzzle_progress.save_to_userhome() {
+            log::warn!("Failed to save puzzle progress: {:#}", e);
+            self.persistence_warnings.push(tr_with(
+                "failed-to-save-puzzles",
+                &[("error", &format!("{:#}", e))],
+            ));
+        }
+        #[cfg(feature = "online")]
+        if let Err(e) = self.online.save_to_userhome() {
+            log::warn!("Failed to save online submission queue: {:#}", e);
+            self.persistence_warnings.push(tr_with(
+                "failed-to-save-submissions",
+                &[("error", &format!("{:#}", e))],
+            ));
+        }
+        #[cfg(all(feature = "sync", not(target_arch = "wasm32")))]
+        if let Err(e) = self.sync_settings.save_to_userhome() {
+            log::warn!("Failed to save sync settings: {:#}", e);
+            self.persistence_warnings.push(tr_with(
+                "failed-to-save-sync-settings",
+                &[("error", &format!("{:#}", e))],
+            ));
+        }
     }
 
-    fn leaderboard_ui(&mut self, ctx: &egui::Context) {
-        egui::Window::new("Leaderboard")
-            .open(&mut self.leaderboard_visible)
-            .vscroll(true)
-            .hscroll(true)
+    fn persistence_warning_ui(&mut self, ctx: &egui::Context) {
+        egui::TopBottomPanel::top("persistence_warning_panel")
+            .resizable(false)
             .show(ctx, |ui| {
-                egui::CollapsingHeader::new("Beginner")
-                    .default_open(self.state.difficulty == GameDifficulty::Beginner)
-                    .show(ui, |ui| {
-                        egui::Grid::new("leaderboard")
-                            .num_columns(2)
-                            .spacing([50.0, 5.0])
-                            .striped(true)
-                            .show(ui, |ui| {
-                                self.leaderboards.beginner.entries.iter().for_each(|e| {
-                                    ui.label(&e.player_name);
-                                    ui.label(format!("{:.2}", e.time));
-                                    ui.label(format!("{}", e.date.format("%Y-%m-%d %H:%M")));
-                                    ui.end_row();
-                                });
-                            });
-                    });
-
-                egui::CollapsingHeader::new("Intermediate")
-                    .default_open(self.state.difficulty == GameDifficulty::Intermediate)
-                    .show(ui, |ui| {
-                        egui::Grid::new("leaderboard")
-                            .num_columns(3)
-                            .spacing([50.0, 5.0])
-                            .striped(true)
-                            .show(ui, |ui| {
-                                self.leaderboards.intermediate.entries.iter().for_each(|e| {
-                                    ui.label(&e.player_name);
-                                    ui.label(format!("{:.2}", e.time));
-                                    ui.label(format!("{}", e.date.format("%Y-%m-%d %H:%M")));
-                                    ui.end_row();
-                                });
-                            });
-                    });
-
-                egui::CollapsingHeader::new("Expert")
-                    .default_open(self.state.difficulty == GameDifficulty::Expert)
-                    .show(ui, |ui| {
-                        egui::Grid::new("leaderboard")
-                            .num_columns(2)
-                            .spacing([50.0, 5.0])
-                            .striped(true)
-                            .show(ui, |ui| {
-                                self.leaderboards.expert.entries.iter().for_each(|e| {
-                                    ui.label(&e.player_name);
-                                    ui.label(format!("{:.2}", e.time));
-                                    ui.label(format!("{}", e.date.format("%Y-%m-%d %H:%M")));
-                                    ui.end_row();
-                                });
-                            });
-                    });
+                self.persistence_warnings.iter().for_each(|w| {
+                    ui.colored_label(constants::COLOR_WARNING, w);
+                });
+                ui.horizontal(|ui| {
+                    ui.label(tr("config-directory"));
+                    ui.text_edit_singleline(&mut self.alternate_config_dir);
+                    if ui.button(tr("use-directory")).clicked()
+                        && !self.alternate_config_dir.trim().is_empty()
+                    {
+                        config::set_config_dir(PathBuf::from(self.alternate_config_dir.trim()));
+                        self.persistence_warnings.clear();
+                        self.save_to_userhome();
+                    }
+                    if ui.button(tr("dismiss")).clicked() {
+                        self.persistence_warnings.clear();
+                    }
+                });
             });
     }
 
-    fn gamestats_ui(&mut self, ctx: &egui::Context) {
-        egui::Window::new("Game Stats")
-            .open(&mut self.gamestats_visible)
-            .vscroll(true)
-            .hscroll(true)
-            .show(ctx, |ui| {
-                egui::Grid::new("leaderboard")
-                    .num_columns(2)
-                    .spacing([50.0, 5.0])
-                    .striped(true)
-                    .show(ui, |ui| {
-                        ui.label("Reveal Clicks:");
-                        ui.label(format!("{}", self.plays.reveals()));
-                        ui.end_row();
+    /// Asks for the latest release once a day, if the player has turned the check on. A
+    /// check that fails isn't tried again until the next day.
+    #[cfg(feature = "updates")]
+    fn check_for_updates(&mut self, ctx: &egui::Context) {
+        match self.update_check.poll() {
+            Some(Ok(release)) => self.state.latest_release = Some(release),
+            Some(Err(e)) => log::warn!("{}", e),
+            None => {}
+        }
+        let today = chrono::Local::now().format("%Y-%m-%d").to_string();
+        if self.state.check_for_updates
+            && !self.update_check.is_checking()
+            && self.state.update_checked_on.as_deref() != Some(today.as_str())
+        {
+            self.state.update_checked_on = Some(today);
+            self.update_check.start(ctx);
+        }
+    }
 
-                        ui.label("Chord Clicks:");
-                        ui.label(format!("{}", self.plays.chords()));
-                        ui.end_row();
+    #[cfg(feature = "updates")]
+    fn update_banner_ui(&mut self, ctx: &egui::Context) {
+        let Some(release) = self.state.latest_release.clone() else {
+            return;
+        };
+        egui::TopBottomPanel::top("update_panel")
+            .resizable(false)
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    ui.label(tr_with(
+                        "update-available",
+                        &[("version", &release.version)],
+                    ));
+                    ui.hyperlink_to(tr("download-update"), &release.url);
+                    if ui.button(tr("dismiss")).clicked() {
+                        self.update_dismissed = true;
+                    }
+                });
+            });
+    }
 
-                        ui.label("Flag Clicks:");
-                        ui.label(format!("{}", self.plays.flagged()));
-                        ui.end_row();
+    fn update_difficulty_settings(&mut self) {
+        // Hot-seat races, co-op games and layered boards are played on a single board, and
+        // co-op players need to be able to flag
+        let num_boards = if self.hotseat.is_some() || self.is_coop() || self.state.layers > 1 {
+            1
+        } else {
+            self.state.num_boards
+        };
+        let no_flags = self.state.no_flags && !self.is_coop();
+        self.game_settings =
+            GameSettings::settings_for_mode(&self.state.difficulty, no_flags, num_boards);
+        // The co-op guest builds the board from the host's seed, which only works for the
+        // standard spread of mines on a single layered rectangle
+        if !self.is_coop() {
+            let settings = &mut self.game_settings;
+            settings.placement = self.state.mine_placement;
+            settings.shape = self.state.board_shape;
+            settings.num_mines =
+                settings
+                    .shape
+                    .mines_for(settings.width, settings.height, settings.num_mines);
+            settings.layers = self.state.layers;
+            if settings.layers > 1 {
+                settings.num_mines = (settings.num_mines as f64
+                    * settings.layers as f64
+                    * constants::LAYERED_MINES_FRACTION)
+                    .round() as u32;
+            }
+        }
+    }
 
-                        ui.label("Total Clicks:");
-                        ui.label(format!("{}", self.plays.clicks()));
-                        ui.end_row();
+    #[cfg(feature = "net")]
+    fn is_coop(&self) -> bool {
+        self.coop.is_some()
+    }
 
-                        let num_sqrs_worked =
-                            self.gameboard.num_flags() + self.gameboard.num_revealed();
-                        ui.label("Squares Revealed + Flagged:");
-                        ui.label(format!("{}", num_sqrs_worked));
-                        ui.end_row();
+    #[cfg(not(feature = "net"))]
+    fn is_coop(&self) -> bool {
+        false
+    }
 
-                        ui.label("Efficiency:");
-                        if num_sqrs_worked > 0 {
-                            ui.label(format!(
-                                "{:.2}%",
-                                (1.0 - self.plays.clicks() as f32 / num_sqrs_worked as f32) * 100.0
-                            ));
-                        }
-                        ui.end_row();
+    /// Mines can't be shown during a network race
+    #[cfg(feature = "net")]
+    fn is_net_racing(&self) -> bool {
+        self.net.is_some()
+    }
 
-                        ui.label("Session Wins:");
-                        ui.label(format!(
-                            "{} of {} games",
-                            self.wins,
-                            self.wins + self.losses
-                        ));
-                    });
-            });
+    #[cfg(not(feature = "net"))]
+    fn is_net_racing(&self) -> bool {
+        false
     }
 
-    fn on_update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) -> Result<(), Error> {
-        if !self.image_loaders_installed {
-            install_image_loaders(ctx);
-            self.image_loaders_installed = true;
-        }
+    /// A fresh board for each board in play
+    fn new_boards(settings: &GameSettings) -> Vec<GameBoard> {
+        (0..settings.num_boards)
+            .map(|_| settings.new_board(rand::random()))
+            .collect()
+    }
 
-        if self.leaderboard_visible {
-            self.leaderboard_ui(ctx);
+    fn reset_new_game(&mut self, ctx: &egui::Context) -> Result<(), Error> {
+        #[cfg(feature = "net")]
+        if self.ask_coop_host(CoopMessage::NewGame {
+            difficulty: self.state.difficulty.clone(),
+        }) {
+            return Ok(());
         }
+        // The game being replaced is finished off first, in case it ended this frame
+        self.handle_events(ctx);
+        #[cfg(feature = "net")]
+        self.finish_net_race(false);
 
-        if self.gamestats_visible {
-            self.gamestats_ui(ctx);
+        self.boards = MinesOfRustApp::new_boards(&self.game_settings);
+        self.guess_trackers.clear();
+        #[cfg(not(target_arch = "wasm32"))]
+        self.board_snapshots.clear();
+        #[cfg(feature = "livesplit")]
+        self.split_timer.reset();
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            self.overlay_stale = true;
         }
+        self.reset_play();
+        self.recovered = false;
+        self.leaderboard_replay = false;
+        self.board_debugged = false;
+        self.script_played = false;
+        self.layer = 0;
+        #[cfg(feature = "net")]
+        self.broadcast(SpectateEvent::Game {
+            name: self.state.net_name.clone(),
+            settings: self.game_settings.clone(),
+        });
 
-        match self.state.theme {
+        let panel_height = if self.state.compact_mode {
+            self.bottom_panel_height
+        } else {
+            0.0
+        };
+        // The window sizes in the settings fit a single board
+        let square_size = ctx.style().spacing.interact_size.x;
+        let single_board = square_size
+            * Vec2::new(
+                self.game_settings.width as f32,
+                self.game_settings.height as f32,
+            );
+        let extra = self.boards_size(square_size) - single_board;
+        ctx.send_viewport_cmd(ViewportCommand::InnerSize(Vec2 {
+            x: self.game_settings.ui_width + extra.x,
+            y: self.game_settings.ui_height - panel_height + extra.y,
+        }));
+
+        // The second player of a hot-seat race gets the first player's board, opened the
+        // same way
+        if let Some((seed, first_click)) = self.hotseat.as_ref().and_then(|h| h.opening()) {
+            self.open_seeded_board(seed, first_click);
+        }
+
+        if let Some(board) = self.fixed_board()? {
+            self.boards = vec![board];
+        }
+
+        #[cfg(feature = "net")]
+        if let Some(coop) = &mut self.coop {
+            coop.flags.clear();
+            coop.send(&CoopMessage::Start {
+                difficulty: self.state.difficulty.clone(),
+                seed: self.boards[0].seed,
+            });
+        }
+
+        #[cfg(not(target_arch = "wasm32"))]
+        self.snapshot_boards();
+        Ok(())
+    }
+
+    /// Replaces the new game's board with the one made from `seed`, opened at `first_click`.
+    /// Mines are placed around the first click, so both are needed to play the same board.
+    fn open_seeded_board(&mut self, seed: u64, first_click: Coordinate) {
+        self.boards = vec![self.game_settings.new_board(seed)];
+        self.guess_trackers.clear();
+        self.play(first_click, RevealType::Reveal);
+    }
+
+    /// Starts a game on the board a leaderboard entry was won on, opened where it was
+    fn replay_entry(
+        &mut self,
+        ctx: &egui::Context,
+        category: &LeaderboardCategory,
+        seed: u64,
+        first_click: Coordinate,
+    ) {
+        if self.is_net_racing() || self.is_coop() {
+            return;
+        }
+        self.hotseat = None;
+        self.puzzle = None;
+        self.tutorial = None;
+        self.state.difficulty = category.difficulty.clone();
+        self.game_settings =
+            GameSettings::settings_for_mode(&self.state.difficulty, category.no_flags, 1);
+        if let Err(e) = self.reset_new_game(ctx) {
+            self.report_error(e);
+            return;
+        }
+        self.open_seeded_board(seed, first_click);
+        self.leaderboard_replay = true;
+    }
+
+    /// Whether the square at `coord` is outlined as a missed safe square and as a wrong flag.
+    /// They're pointed out while the post-mortem is open.
+    fn post_mortem_marks(&self, coord: &Coordinate) -> (bool, bool) {
+        match &self.post_mortem {
+            Some(p) if self.post_mortem_visible => {
+                (p.missed_safe.contains(coord), p.wrong_flags.contains(coord))
+            }
+            _ => (false, false),
+        }
+    }
+
+    fn reset_existing_game(&mut self, ctx: &egui::Context) -> Result<(), Error> {
+        #[cfg(feature = "net")]
+        if self.ask_coop_host(CoopMessage::Restart) {
+            return Ok(());
+        }
+        self.handle_events(ctx);
+        self.boards.iter_mut().for_each(|b| b.reset_existing());
+        // A puzzle's start is opened for the player again, and the tutorial starts over
+        if let Some(board) = self.fixed_board()? {
+            self.boards = vec![board];
+        }
+        self.guess_trackers.clear();
+        #[cfg(feature = "net")]
+        self.broadcast(SpectateEvent::Restart);
+        #[cfg(feature = "net")]
+        if let Some(coop) = &mut self.coop {
+            coop.flags.clear();
+            coop.send(&CoopMessage::Restart);
+        }
+
+        self.reset_play();
+        #[cfg(not(target_arch = "wasm32"))]
+        self.snapshot_boards();
+
+        Ok(())
+    }
+
+    /// Clears what the last game played left behind, for both a new game and a restart.
+    /// What's kept for a restart, like how the board came to be, is left to the callers.
+    fn reset_play(&mut self) {
+        self.plays.clear();
+        self.fog_memory.reset();
+        self.game_state = GameState::NotStarted;
+        self.detonated_on = None;
+        self.chord_warning = None;
+        self.chord_warned = false;
+        self.mines_seen = self.mines_visible;
+        self.guess_indicator_used = false;
+        self.penalties = self.state.time_penalties.then(Penalties::default);
+        self.penalized_flags.clear();
+        // A restart plays the same mines again, so the post-mortem would give them away
+        self.post_mortem = None;
+        self.post_mortem_visible = false;
+        self.personal_best = None;
+        self.reveal_rings = None;
+        self.auto_open_due = None;
+        self.clock.reset();
+    }
+
+    /// Starts the game on its first reveal. A board's mines are placed around the first
+    /// square revealed on it, so each board of a multi-board game starts when it's first
+    /// played on.
+    fn start_game(&mut self, board: usize, first_click: Coordinate) -> Result<(), Error> {
+        let placement = self.game_mode().placement();
+        let gameboard = &mut self.boards[board];
+        if self.game_state != GameState::NotStarted && gameboard.is_populated {
+            return Ok(());
+        }
+        log::debug!(
+            "Starting board {} with fist click at x={}, y={}",
+            board + 1,
+            first_click.x,
+            first_click.y
+        );
+
+        if !gameboard.is_populated {
+            if let Some(hotseat) = &mut self.hotseat {
+                hotseat.set_opening(gameboard.seed, first_click.clone());
+            }
+            gameboard.populate_mines_with(
+                placement.as_ref(),
+                self.game_settings.num_mines,
+                Some(first_click.clone()),
+            )?;
+            if self.game_settings.use_numerals {
+                gameboard.populate_numerals()?;
+            }
+            #[cfg(not(target_arch = "wasm32"))]
+            self.snapshot_board(board);
+            #[cfg(feature = "net")]
+            self.broadcast(SpectateEvent::Populate {
+                board,
+                seed: self.boards[board].seed,
+                first_click,
+            });
+        }
+
+        if self.game_state == GameState::NotStarted {
+            self.clock.start();
+            self.game_state = GameState::Playing;
+            self.events.emit(GameEvent::Started);
+        }
+
+        Ok(())
+    }
+
+    /// A row of a leaderboard, with the time less the player's handicap if any of the
+    /// players have one
+    fn leaderboard_entry_ui(ui: &mut egui::Ui, e: &Entry, adjusted: Option<f64>) {
+        ui.label(&e.player_name);
+        ui.label(format!("{:.2}", e.time));
+        if let Some(adjusted) = adjusted {
+            ui.label(format!("{:.2}", adjusted));
+        }
+        ui.label(if e.flagged {
+            tr("style-flagged")
+        } else {
+            tr("style-nf")
+        });
+        if e.bbbv > 0 {
+            ui.label(format!("{}", e.bbbv));
+        } else {
+            ui.label("");
+        }
+        ui.label(
+            e.bbbv_per_second()
+                .map_or(String::new(), |b| format!("{:.2}", b)),
+        );
+        ui.label(
+            e.efficiency()
+                .map_or(String::new(), |e| format!("{:.0}%", e)),
+        );
+        ui.label(format_date_time(&e.date));
+        match e.integrity() {
+            EntryIntegrity::Valid => match e.verified {
+                Some(true) => ui.label(tr("verified")).on_hover_text(tr("tip-verified")),
+                Some(false) => ui
+                    .colored_label(constants::COLOR_WARNING, tr("replay-mismatch"))
+                    .on_hover_text(tr("tip-replay-mismatch")),
+                None => ui.label(""),
+            },
+            EntryIntegrity::Unsigned => ui
+                .label(tr("unverified"))
+                .on_hover_text(tr("tip-unverified")),
+            EntryIntegrity::Tampered => ui
+                .colored_label(constants::COLOR_WARNING, tr("modified"))
+                .on_hover_text(tr("tip-modified")),
+        };
+    }
+
+    /// Column header that sorts by `column` when clicked, or reverses the order if the
+    /// leaderboard is already sorted by it
+    fn sort_header_ui(
+        ui: &mut egui::Ui,
+        view: &mut LeaderboardView,
+        label: &str,
+        column: SortColumn,
+    ) {
+        let selected = view.sort == column;
+        let text = match (selected, view.ascending) {
+            (true, true) => format!("{} ⬆", label),
+            (true, false) => format!("{} ⬇", label),
+            (false, _) => label.to_string(),
+        };
+        if ui
+            .selectable_label(selected, RichText::new(text).strong())
+            .clicked()
+        {
+            if selected {
+                view.ascending = !view.ascending;
+            } else {
+                view.sort = column;
+                view.ascending = true;
+            }
+        }
+    }
+
+    /// Text field for a YYYY-MM-DD date, highlighted if it can't be parsed
+    fn date_filter_ui(ui: &mut egui::Ui, text: &mut String) -> Option<NaiveDate> {
+        let parse = |t: &str| NaiveDate::parse_from_str(t.trim(), "%Y-%m-%d").ok();
+        let invalid = parse(text).is_none() && !text.trim().is_empty();
+        let mut edit = egui::TextEdit::singleline(text)
+            .hint_text("YYYY-MM-DD")
+            .desired_width(90.0);
+        if invalid {
+            edit = edit.text_color(constants::COLOR_WARNING);
+        }
+        if ui.add(edit).changed() {
+            // Redraw so the highlight reflects the edited text
+            ui.ctx().request_repaint();
+        }
+        parse(text)
+    }
+
+    fn leaderboard_filter_ui(&mut self, ui: &mut egui::Ui) {
+        let view = &mut self.leaderboard_view;
+        ui.horizontal(|ui| {
+            ui.label(tr("search-label"));
+            ui.add(
+                egui::TextEdit::singleline(&mut view.filter.search)
+                    .hint_text(tr("search-hint"))
+                    .desired_width(160.0),
+            );
+            ui.checkbox(&mut view.top_only, tr("show-top"));
+            ui.add_enabled(
+                view.top_only,
+                egui::DragValue::new(&mut view.top).clamp_range(1..=1000),
+            );
+            ui.checkbox(&mut view.mine, tr("show-mine"))
+                .on_hover_text(tr_with(
+                    "tip-show-mine",
+                    &[("name", &self.state.player_name)],
+                ));
+        });
+        view.filter.top = view.top_only.then_some(view.top);
+        view.filter.player = view.mine.then(|| self.state.player_name.clone());
+        ui.horizontal(|ui| {
+            ui.label(tr("player-label"));
+            ui.add(egui::TextEdit::singleline(&mut view.filter.player_name).desired_width(120.0));
+            ui.label(tr("from"));
+            view.filter.from = MinesOfRustApp::date_filter_ui(ui, &mut view.from_text);
+            ui.label(tr("to"));
+            view.filter.to = MinesOfRustApp::date_filter_ui(ui, &mut view.to_text);
+            ui.label(tr("style-label"));
+            egui::ComboBox::from_id_source("leaderboard_style")
+                .selected_text(match view.filter.flagged {
+                    None => tr("style-all"),
+                    Some(true) => tr("style-flagged"),
+                    Some(false) => tr("style-no-flags"),
+                })
+                .show_ui(ui, |ui| {
+                    ui.selectable_value(&mut view.filter.flagged, None, tr("style-all"));
+                    ui.selectable_value(&mut view.filter.flagged, Some(true), tr("style-flagged"));
+                    ui.selectable_value(
+                        &mut view.filter.flagged,
+                        Some(false),
+                        tr("style-no-flags"),
+                    );
+                });
+            if ui.button(tr("clear-filters")).clicked() {
+                view.filter = EntryFilter::default();
+                view.from_text.clear();
+                view.to_text.clear();
+                view.top_only = false;
+                view.mine = false;
+            }
+        });
+    }
+
+    fn leaderboard_table_ui(&mut self, ui: &mut egui::Ui, category: LeaderboardCategory) {
+        let board = self.leaderboards.board(&category);
+        let view = &mut self.leaderboard_view;
+        let handicaps = &self.leaderboards.handicaps;
+        let indices = board.filtered_sorted(&view.filter, handicaps, view.sort, view.ascending);
+        // Long leaderboards are shown a page at a time
+        let pages: Vec<&[usize]> = indices.chunks(constants::LEADERBOARD_PAGE_SIZE).collect();
+        let num_pages = pages.len();
+        let page = view.pages.entry(category.title()).or_default();
+        *page = (*page).min(num_pages.saturating_sub(1));
+        let shown = pages.get(*page).copied().unwrap_or_default();
+        let mut to_delete = None;
+        let mut to_replay = None;
+        let mut to_verify = None;
+
+        egui::Grid::new(("leaderboard", category.title()))
+            .num_columns(11)
+            .spacing([30.0, 5.0])
+            .striped(true)
+            .show(ui, |ui| {
+                MinesOfRustApp::sort_header_ui(ui, view, tr("player"), SortColumn::PlayerName);
+                MinesOfRustApp::sort_header_ui(ui, view, tr("time"), SortColumn::Time);
+                if !handicaps.is_empty() {
+                    MinesOfRustApp::sort_header_ui(
+                        ui,
+                        view,
+                        tr("adjusted-time"),
+                        SortColumn::AdjustedTime,
+                    );
+                }
+                ui.label(RichText::new(tr("style")).strong());
+                ui.label(RichText::new("3BV").strong());
+                ui.label(RichText::new("3BV/s").strong());
+                ui.label(RichText::new(tr("efficiency")).strong())
+                    .on_hover_text(tr("tip-efficiency"));
+                MinesOfRustApp::sort_header_ui(ui, view, tr("date"), SortColumn::Date);
+                ui.end_row();
+
+                shown.iter().for_each(|&i| {
+                    let entry = &board.entries[i];
+                    let adjusted = (!handicaps.is_empty()).then(|| adjusted_time(handicaps, entry));
+                    MinesOfRustApp::leaderboard_entry_ui(ui, entry, adjusted);
+                    // Multi-board entries only keep the first board's seed
+                    if ui
+                        .add_enabled(
+                            entry.first_click.is_some() && category.num_boards == 1,
+                            egui::Button::new("▶").small(),
+                        )
+                        .on_hover_text(tr("tip-replay-board"))
+                        .clicked()
+                    {
+                        to_replay = entry.first_click.clone().map(|c| (entry.seed, c));
+                    }
+                    if ui
+                        .add_enabled(entry.replay.is_some(), egui::Button::new("✔").small())
+                        .on_hover_text(tr("tip-verify-entry"))
+                        .clicked()
+                    {
+                        to_verify = Some(i);
+                    }
+                    if ui
+                        .small_button("🗑")
+                        .on_hover_text(tr("tip-delete-entry"))
+                        .clicked()
+                    {
+                        to_delete = Some(i);
+                    }
+                    ui.end_row();
+                });
+            });
+
+        if num_pages > 1 {
+            ui.horizontal(|ui| {
+                let page = view.pages.entry(category.title()).or_default();
+                if ui.add_enabled(*page > 0, egui::Button::new("◀")).clicked() {
+                    *page -= 1;
+                }
+                ui.label(tr_with(
+                    "page-of",
+                    &[
+                        ("page", &(*page + 1).to_string()),
+                        ("pages", &num_pages.to_string()),
+                    ],
+                ));
+                if ui
+                    .add_enabled(*page + 1 < num_pages, egui::Button::new("▶"))
+                    .clicked()
+                {
+                    *page += 1;
+                }
+            });
+        }
+
+        if view.confirm_clear.as_ref() == Some(&category) {
+            ui.horizontal(|ui| {
+                ui.colored_label(
+                    constants::COLOR_WARNING,
+                    tr_with("delete-all-entries", &[("board", &category.title())]),
+                );
+                if ui.button(tr("yes")).clicked() {
+                    self.leaderboards.board_mut(&category).clear();
+                    self.leaderboard_view.confirm_clear = None;
+                    self.save_to_userhome();
+                }
+                if ui.button(tr("no")).clicked() {
+                    self.leaderboard_view.confirm_clear = None;
+                }
+            });
+        } else if !board.entries.is_empty() && ui.button(tr("clear-board")).clicked() {
+            view.confirm_clear = Some(category.clone());
+        }
+
+        if let Some(i) = to_delete {
+            self.leaderboards.board_mut(&category).remove(i);
+            self.save_to_userhome();
+        }
+        if let Some(i) = to_verify {
+            let entry = &mut self.leaderboards.board_mut(&category).entries[i];
+            let verified = entry.verify_replay(&category);
+            if let Err(e) = &verified {
+                log::warn!(
+                    "{} on {} failed verification: {}",
+                    entry.player_name,
+                    category.title(),
+                    e
+                );
+            }
+            entry.verified = Some(verified.is_ok());
+            self.save_to_userhome();
+        }
+        if let Some((seed, first_click)) = to_replay {
+            self.replay_entry(&ui.ctx().clone(), &category, seed, first_click);
+        }
+    }
+
+    fn leaderboard_ui(&mut self, ctx: &egui::Context) {
+        let mut open = self.leaderboard_visible;
+        egui::Window::new(tr("window-leaderboard"))
+            .open(&mut open)
+            .vscroll(true)
+            .hscroll(true)
+            .show(ctx, |ui| {
+                #[cfg(feature = "online")]
+                {
+                    ui.horizontal(|ui| {
+                        ui.selectable_value(
+                            &mut self.global_leaderboard_visible,
+                            false,
+                            tr("local"),
+                        );
+                        ui.selectable_value(
+                            &mut self.global_leaderboard_visible,
+                            true,
+                            tr("global"),
+                        );
+                    });
+                    ui.separator();
+                    if self.global_leaderboard_visible {
+                        self.global_leaderboard_ui(ui);
+                        return;
+                    }
+                }
+                self.local_leaderboard_ui(ui);
+            });
+        self.leaderboard_visible = open;
+    }
+
+    /// Handicaps for the players on the leaderboards, taken off their times in the adjusted
+    /// time column
+    fn handicaps_ui(&mut self, ui: &mut egui::Ui) {
+        let mut players: BTreeSet<String> = LeaderboardCategory::all()
+            .flat_map(|c| self.leaderboards.board(&c).entries.iter())
+            .map(|e| e.player_name.clone())
+            .collect();
+        let handicaps = &mut self.leaderboards.handicaps;
+        players.extend(handicaps.keys().cloned());
+        egui::Grid::new("handicaps")
+            .num_columns(3)
+            .spacing([20.0, 5.0])
+            .show(ui, |ui| {
+                players.iter().for_each(|player| {
+                    let mut handicap = handicaps.get(player).copied();
+                    ui.label(player);
+                    egui::ComboBox::from_id_source(("handicap", player))
+                        .selected_text(match handicap {
+                            None => tr("handicap-none"),
+                            Some(Handicap::Percent(_)) => tr("handicap-percent"),
+                            Some(Handicap::Seconds(_)) => tr("handicap-seconds"),
+                        })
+                        .show_ui(ui, |ui| {
+                            if ui
+                                .selectable_label(handicap.is_none(), tr("handicap-none"))
+                                .clicked()
+                            {
+                                handicap = None;
+                            }
+                            let percent = matches!(handicap, Some(Handicap::Percent(_)));
+                            if ui
+                                .selectable_label(percent, tr("handicap-percent"))
+                                .clicked()
+                                && !percent
+                            {
+                                handicap =
+                                    Some(Handicap::Percent(constants::DEFAULT_HANDICAP_PERCENT));
+                            }
+                            let seconds = matches!(handicap, Some(Handicap::Seconds(_)));
+                            if ui
+                                .selectable_label(seconds, tr("handicap-seconds"))
+                                .clicked()
+                                && !seconds
+                            {
+                                handicap =
+                                    Some(Handicap::Seconds(constants::DEFAULT_HANDICAP_SECONDS));
+                            }
+                        });
+                    match &mut handicap {
+                        Some(Handicap::Percent(p)) => {
+                            ui.add(egui::DragValue::new(p).clamp_range(0.0..=90.0).suffix("%"))
+                        }
+                        Some(Handicap::Seconds(s)) => ui.add(
+                            egui::DragValue::new(s)
+                                .clamp_range(0.0..=999.0)
+                                .speed(0.1)
+                                .suffix(tr("unit-seconds")),
+                        ),
+                        None => ui.label(""),
+                    };
+                    match handicap {
+                        Some(h) => handicaps.insert(player.clone(), h),
+                        None => handicaps.remove(player),
+                    };
+                    ui.end_row();
+                });
+            });
+    }
+
+    fn local_leaderboard_ui(&mut self, ui: &mut egui::Ui) {
+        self.leaderboard_filter_ui(ui);
+        egui::CollapsingHeader::new(tr("handicaps"))
+            .default_open(false)
+            .show(ui, |ui| {
+                ui.label(tr("handicaps-help"));
+                self.handicaps_ui(ui);
+            });
+        ui.separator();
+
+        // Multi-board leaderboards are only listed once they have entries
+        let current = self.leaderboard_category();
+        let categories: Vec<LeaderboardCategory> = LeaderboardCategory::all()
+            .filter(|c| {
+                c.num_boards == 1 || *c == current || !self.leaderboards.board(c).entries.is_empty()
+            })
+            .collect();
+        categories.into_iter().for_each(|category| {
+            egui::CollapsingHeader::new(category.title())
+                .default_open(category == current)
+                .show(ui, |ui| {
+                    self.leaderboard_table_ui(ui, category.clone());
+                });
+        });
+    }
+
+    fn gamestats_ui(&mut self, ctx: &egui::Context) {
+        let elapsed = self.elapsed_time();
+        let num_sqrs_worked = self.num_flags() + self.num_revealed();
+        // 3BV isn't known until mines have been placed
+        let bbbv = self.is_populated().then(|| self.bbbv());
+        // Each co-op player's flags, and once the game is over, how many of them were on mines
+        #[cfg(feature = "net")]
+        let coop_flags: Vec<(String, usize, Option<usize>)> = self
+            .coop
+            .iter()
+            .flat_map(|coop| {
+                let view = self.boards[0].player_view(self.game_state.game_ended());
+                [HOST, GUEST].map(|player| {
+                    let placed: Vec<&Coordinate> = coop.flags.placed_by(player).collect();
+                    let on_mines = self.game_state.game_ended().then(|| {
+                        placed
+                            .iter()
+                            .filter(|c| view.square_at(c).is_ok_and(|s| s == SquareView::Flagged))
+                            .count()
+                    });
+                    (coop.names[player].clone(), placed.len(), on_mines)
+                })
+            })
+            .collect();
+        #[cfg(not(target_arch = "wasm32"))]
+        let mut export_replay = false;
+        egui::Window::new(tr("window-game-stats"))
+            .open(&mut self.gamestats_visible)
+            .vscroll(true)
+            .hscroll(true)
+            .show(ctx, |ui| {
+                egui::Grid::new("leaderboard")
+                    .num_columns(2)
+                    .spacing([50.0, 5.0])
+                    .striped(true)
+                    .show(ui, |ui| {
+                        ui.label(tr("reveal-clicks"));
+                        ui.label(format!("{}", self.plays.reveals()));
+                        ui.end_row();
+
+                        ui.label(tr("chord-clicks"));
+                        ui.label(format!("{}", self.plays.chords()));
+                        ui.end_row();
+
+                        ui.label(tr("flag-clicks"));
+                        ui.label(format!("{}", self.plays.flagged()));
+                        ui.end_row();
+
+                        ui.label(tr("total-clicks"));
+                        ui.label(format!("{}", self.plays.clicks()));
+                        ui.end_row();
+
+                        ui.label(tr("wasted-clicks"));
+                        ui.label(format!("{}", self.plays.wasted()))
+                            .on_hover_text(tr("tip-wasted-clicks"));
+                        ui.end_row();
+
+                        if self.plays.sonars() > 0 {
+                            ui.label(tr("sonar-pings"));
+                            ui.label(format!("{}", self.plays.sonars()));
+                            ui.end_row();
+                        }
+
+                        ui.label(tr("guesses-taken"));
+                        ui.label(tr_with(
+                            "guesses-breakdown",
+                            &[
+                                ("guesses", &self.plays.guesses()),
+                                ("avoidable", &self.plays.avoidable_guesses()),
+                            ],
+                        ))
+                        .on_hover_text(tr("tip-guesses-taken"));
+                        ui.end_row();
+
+                        if self.game_state == GameState::EndedWin && self.plays.is_flawless() {
+                            ui.label("");
+                            ui.label(
+                                RichText::new(tr("flawless"))
+                                    .color(constants::COLOR_NO_GUESS_NEEDED),
+                            )
+                            .on_hover_text(tr("tip-flawless"));
+                            ui.end_row();
+                        }
+
+                        if let Some(penalties) = self.penalties.as_ref().filter(|_| {
+                            matches!(self.game_state, GameState::EndedWin | GameState::EndedLoss)
+                        }) {
+                            ui.label(tr("time-penalties"));
+                            ui.label(tr_with(
+                                "penalty-breakdown",
+                                &[
+                                    ("seconds", &penalties.seconds()),
+                                    ("flags", &penalties.wrong_flags),
+                                    ("chords", &penalties.held_chords),
+                                ],
+                            ));
+                            ui.end_row();
+                        }
+
+                        ui.label(tr("squares-revealed-flagged"));
+                        ui.label(format!("{}", num_sqrs_worked));
+                        ui.end_row();
+
+                        ui.label(tr("bbbv-label"));
+                        if let Some(bbbv) = bbbv {
+                            ui.label(format!("{}", bbbv));
+                        }
+                        ui.end_row();
+
+                        ui.label(tr("bbbv-per-second-label"));
+                        if let Some(bbbv) = bbbv.filter(|_| elapsed > 0.0) {
+                            ui.label(format!("{:.2}", bbbv as f64 / elapsed));
+                        }
+                        ui.end_row();
+
+                        ui.label(tr("efficiency-label"));
+                        if let Some(e) = bbbv.and_then(|b| self.plays.efficiency(b)) {
+                            ui.label(format!("{:.0}%", e))
+                                .on_hover_text(tr("tip-efficiency"));
+                        }
+                        ui.end_row();
+
+                        ui.label(tr("flags-per-mine"));
+                        if let Some(f) = self.plays.flags_per_mine(self.game_settings.num_mines) {
+                            ui.label(format!("{:.2}", f));
+                        }
+                        ui.end_row();
+
+                        ui.label(tr("useful-chords"));
+                        if let Some(c) = self.plays.chord_usefulness() {
+                            ui.label(format!("{:.0}%", c))
+                                .on_hover_text(tr("tip-useful-chords"));
+                        }
+                        ui.end_row();
+
+                        #[cfg(feature = "net")]
+                        coop_flags.iter().for_each(|(name, placed, on_mines)| {
+                            ui.label(tr_with("flags-by", &[("name", name)]));
+                            match on_mines {
+                                Some(m) => ui.label(tr_with(
+                                    "flags-on-mines",
+                                    &[("placed", placed), ("on_mines", &m)],
+                                )),
+                                None => ui.label(placed.to_string()),
+                            };
+                            ui.end_row();
+                        });
+                    });
+
+                ui.add_enabled(
+                    self.game_state.game_ended(),
+                    egui::Checkbox::new(&mut self.show_heatmap, tr("show-click-heatmap")),
+                )
+                .on_hover_text(tr("tip-show-click-heatmap"));
+
+                #[cfg(not(target_arch = "wasm32"))]
+                {
+                    ui.horizontal(|ui| {
+                        ui.label(tr("replay-square-size"));
+                        ui.add(
+                            egui::DragValue::new(&mut self.state.replay_cell_size)
+                                .clamp_range(8..=64)
+                                .suffix(tr("unit-pixels")),
+                        );
+                        ui.label(tr("replay-seconds-per-play"));
+                        ui.add(
+                            egui::DragValue::new(&mut self.state.replay_seconds_per_play)
+                                .clamp_range(0.05..=2.0)
+                                .speed(0.01)
+                                .suffix(tr("unit-seconds")),
+                        );
+                    });
+                    export_replay = ui
+                        .add_enabled(
+                            self.game_state.game_ended(),
+                            egui::Button::new(tr("export-replay")),
+                        )
+                        .on_hover_text(tr("tip-export-replay"))
+                        .clicked();
+                    if let Some(message) = &self.replay_export_message {
+                        ui.label(message);
+                    }
+                }
+
+                ui.separator();
+                egui::Grid::new("session_stats")
+                    .num_columns(2)
+                    .spacing([50.0, 5.0])
+                    .striped(true)
+                    .show(ui, |ui| {
+                        [
+                            GameDifficulty::Beginner,
+                            GameDifficulty::Intermediate,
+                            GameDifficulty::Expert,
+                        ]
+                        .iter()
+                        .for_each(|d| {
+                            ui.label(tr_with("session-difficulty", &[("difficulty", &d.name())]));
+                            MinesOfRustApp::session_record_ui(ui, self.session.for_difficulty(d));
+                            ui.end_row();
+                        });
+
+                        ui.label(tr("session-practice"))
+                            .on_hover_text(tr("tip-session-practice"));
+                        MinesOfRustApp::session_record_ui(ui, &self.session.practice);
+                        ui.end_row();
+                    });
+                if ui.button(tr("reset-session")).clicked() {
+                    self.session.reset();
+                }
+            });
+
+        #[cfg(not(target_arch = "wasm32"))]
+        if export_replay {
+            self.export_replay();
+        }
+    }
+
+    /// Saves the game just played as an animated GIF in the pictures folder
+    #[cfg(not(target_arch = "wasm32"))]
+    fn export_replay(&mut self) {
+        let path = export_path(dirs::picture_dir(), "minesofrust", "gif");
+        let options = ReplayOptions {
+            cell_size: self.state.replay_cell_size,
+            seconds_per_play: self.state.replay_seconds_per_play,
+        };
+        let (columns, _) = self.game_settings.board_layout();
+        let result = std::fs::File::create(&path)
+            .map_err(anyhow::Error::from)
+            .and_then(|file| {
+                write_replay_gif(
+                    &self.boards,
+                    columns,
+                    &self.plays,
+                    &options,
+                    std::io::BufWriter::new(file),
+                )
+            });
+        self.replay_export_message = Some(match result {
+            Ok(()) => tr_with("replay-exported", &[("path", &path.display())]),
+            Err(e) => tr_with("replay-export-failed", &[("error", &format!("{:#}", e))]),
+        });
+    }
+
+    /// Exports everything kept about the player to one file, or merges one back in
+    #[cfg(not(target_arch = "wasm32"))]
+    fn profile_ui(&mut self, ctx: &egui::Context) {
+        let mut open = self.profile_visible;
+        let mut export = false;
+        let mut import = false;
+        #[cfg(all(feature = "sync", not(target_arch = "wasm32")))]
+        let mut sync_now = false;
+        egui::Window::new(tr("window-profile"))
+            .open(&mut open)
+            .resizable(false)
+            .show(ctx, |ui| {
+                let transfer = &mut self.profile_transfer;
+                ui.label(tr("profile-intro"));
+                export = ui
+                    .button(tr("export-profile"))
+                    .on_hover_text(tr("tip-export-profile"))
+                    .clicked();
+                ui.separator();
+                ui.horizontal(|ui| {
+                    ui.label(tr("profile-file"));
+                    ui.text_edit_singleline(&mut transfer.path);
+                });
+                ui.checkbox(&mut transfer.import_settings, tr("import-settings"))
+                    .on_hover_text(tr("tip-import-settings"));
+                import = ui
+                    .add_enabled(
+                        !transfer.path.trim().is_empty(),
+                        egui::Button::new(tr("import-profile")),
+                    )
+                    .on_hover_text(tr("tip-import-profile"))
+                    .clicked();
+                if let Some(message) = &transfer.message {
+                    ui.label(message);
+                }
+
+                #[cfg(all(feature = "sync", not(target_arch = "wasm32")))]
+                {
+                    ui.separator();
+                    sync_now =
+                        Self::profile_sync_ui(ui, &mut self.sync_settings, &self.profile_sync);
+                }
+            });
+        self.profile_visible = open;
+        if export {
+            self.export_profile();
+        }
+        if import {
+            self.import_profile();
+        }
+        #[cfg(all(feature = "sync", not(target_arch = "wasm32")))]
+        if sync_now {
+            self.save_to_userhome();
+            self.profile_sync
+                .start(ctx, &self.sync_settings, self.profile());
+        }
+    }
+
+    /// Where the profile is synced to, returning true if the player asked to sync it now
+    #[cfg(all(feature = "sync", not(target_arch = "wasm32")))]
+    fn profile_sync_ui(ui: &mut egui::Ui, settings: &mut SyncSettings, sync: &ProfileSync) -> bool {
+        egui::Grid::new("profile_sync_grid")
+            .num_columns(2)
+            .show(ui, |ui| {
+                ui.label(tr("sync-on-start-and-exit"));
+                toggle_ui(ui, &mut settings.enabled)
+                    .on_hover_text(tr("tip-sync-on-start-and-exit"));
+                ui.end_row();
+
+                ui.label(tr("sync-service"));
+                ui.horizontal(|ui| {
+                    SyncService::all().into_iter().for_each(|s| {
+                        ui.radio_value(&mut settings.service, s, s.as_str());
+                    });
+                });
+                ui.end_row();
+
+                ui.label(tr("sync-url"));
+                ui.text_edit_singleline(&mut settings.url)
+                    .on_hover_text(tr("tip-sync-url"));
+                ui.end_row();
+
+                let (username, password) = match settings.service {
+                    SyncService::WebDav => (tr("sync-username"), tr("sync-password")),
+                    SyncService::S3 => (tr("sync-access-key"), tr("sync-secret-key")),
+                };
+                ui.label(username);
+                ui.text_edit_singleline(&mut settings.username);
+                ui.end_row();
+
+                ui.label(password);
+                ui.add(egui::TextEdit::singleline(&mut settings.password).password(true));
+                ui.end_row();
+
+                if settings.service == SyncService::S3 {
+                    ui.label(tr("sync-region"));
+                    ui.text_edit_singleline(&mut settings.region)
+                        .on_hover_text(tr("tip-sync-region"));
+                    ui.end_row();
+                }
+            });
+
+        let sync_now = ui
+            .add_enabled(
+                settings.is_configured() && !sync.is_syncing(),
+                egui::Button::new(tr("sync-now")),
+            )
+            .on_hover_text(tr("tip-sync-now"))
+            .clicked();
+        if sync.is_syncing() {
+            ui.label(tr("syncing"));
+        } else if let Some(e) = sync.last_error() {
+            ui.colored_label(constants::COLOR_WARNING, e);
+        } else if let Some(date) = sync.last_synced() {
+            ui.label(tr_with("last-synced", &[("date", &format_date_time(date))]));
+        }
+        sync_now
+    }
+
+    /// Everything kept about the player, to export or sync
+    #[cfg(not(target_arch = "wasm32"))]
+    fn profile(&self) -> Profile {
+        Profile::new(
+            self.state.clone(),
+            self.leaderboards.clone(),
+            self.history.clone(),
+            self.puzzle_progress.clone(),
+        )
+    }
+
+    /// Adds the wins and games of `profile` that the player doesn't have yet
+    #[cfg(not(target_arch = "wasm32"))]
+    fn merge_profile(&mut self, profile: &Profile) {
+        let max_entries = self.state.max_leaderboard_entries();
+        self.leaderboards.merge(&profile.leaderboards, max_entries);
+        self.history.merge(&profile.history);
+        self.puzzle_progress.merge(&profile.puzzles);
+        self.progress = Progress::from_history(&self.history);
+    }
+
+    /// Saves the settings, leaderboards and game history to a new profile in the documents
+    /// folder, and fills it in as the file to import
+    #[cfg(not(target_arch = "wasm32"))]
+    fn export_profile(&mut self) {
+        let path = export_path(dirs::document_dir(), "minesofrust-profile", "toml");
+        self.profile_transfer.message = Some(match self.profile().save(&path) {
+            Ok(()) => {
+                self.profile_transfer.path = path.display().to_string();
+                tr_with("profile-exported", &[("path", &path.display())])
+            }
+            Err(e) => tr_with("profile-export-failed", &[("error", &format!("{:#}", e))]),
+        });
+    }
+
+    /// Merges a profile's leaderboards and game history into the player's own, taking its
+    /// settings too if asked to
+    #[cfg(not(target_arch = "wasm32"))]
+    fn import_profile(&mut self) {
+        let path = PathBuf::from(self.profile_transfer.path.trim());
+        self.profile_transfer.message = Some(match Profile::load(&path) {
+            Ok(profile) => {
+                self.merge_profile(&profile);
+                if self.profile_transfer.import_settings {
+                    self.state.apply_settings(&profile.settings);
+                }
+                self.save_to_userhome();
+                tr("profile-imported").to_string()
+            }
+            Err(e) => tr_with("profile-import-failed", &[("error", &format!("{:#}", e))]),
+        });
+    }
+
+    fn post_mortem_ui(&mut self, ctx: &egui::Context) {
+        let Some(post_mortem) = &self.post_mortem else {
+            return;
+        };
+        egui::Window::new(tr("window-post-game-analysis"))
+            .open(&mut self.post_mortem_visible)
+            .resizable(false)
+            .show(ctx, |ui| {
+                ui.label(post_mortem.verdict());
+                if !post_mortem.wrong_flags.is_empty() {
+                    ui.label(tr_with(
+                        "chord-wrong-flags",
+                        &[("flags", &post_mortem.wrong_flags.len())],
+                    ));
+                }
+                if !post_mortem.missed_safe.is_empty() {
+                    ui.label(tr("missed-squares-outlined"));
+                }
+            });
+    }
+
+    fn session_record_ui(ui: &mut egui::Ui, record: &SessionRecord) {
+        ui.label(tr_with(
+            "session-record",
+            &[("wins", &record.wins), ("games", &record.games())],
+        ))
+        .on_hover_text(tr_with(
+            "tip-session-record",
+            &[
+                ("guesses", &record.guess_losses),
+                ("misclicks", &record.misclick_losses),
+            ],
+        ));
+    }
+
+    fn statistics_ui(&mut self, ctx: &egui::Context) {
+        let mut open = self.statistics_visible;
+        let mut clear_history = false;
+        egui::Window::new(tr("window-statistics"))
+            .open(&mut open)
+            .vscroll(true)
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    ui.label(tr_with(
+                        "statistics-last",
+                        &[("difficulty", &self.state.difficulty.name())],
+                    ));
+                    ui.add(egui::Slider::new(&mut self.statistics_num_games, 10..=500));
+                    ui.label(tr("games"));
+                });
+
+                if self.confirm_clear_history {
+                    ui.horizontal(|ui| {
+                        ui.colored_label(constants::COLOR_WARNING, tr("clear-history-confirm"));
+                        if ui.button(tr("yes")).clicked() {
+                            clear_history = true;
+                            self.confirm_clear_history = false;
+                        }
+                        if ui.button(tr("no")).clicked() {
+                            self.confirm_clear_history = false;
+                        }
+                    });
+                } else if !self.history.games.is_empty() && ui.button(tr("clear-history")).clicked()
+                {
+                    self.confirm_clear_history = true;
+                }
+
+                let games = self
+                    .history
+                    .last_n(&self.state.difficulty, self.statistics_num_games);
+                if games.is_empty() {
+                    ui.label(tr("no-games-played"));
+                    return;
+                }
+
+                let game_points = |f: fn(&GameRecord) -> Option<f64>| -> Vec<[f64; 2]> {
+                    games
+                        .iter()
+                        .enumerate()
+                        .filter_map(|(i, g)| f(g).map(|v| [(i + 1) as f64, v]))
+                        .collect()
+                };
+                let times = game_points(|g| g.won.then_some(g.time));
+                let bbbv_per_second = game_points(|g| g.bbbv_per_second());
+                let win_rate = rolling_win_rate(&games, WIN_RATE_WINDOW);
+
+                ui.label(tr("winning-times-seconds"));
+                MinesOfRustApp::statistics_plot_ui(ui, "statistics_times", times);
+                ui.label(tr_with("win-rate", &[("games", &WIN_RATE_WINDOW)]));
+                MinesOfRustApp::statistics_plot_ui(ui, "statistics_win_rate", win_rate);
+                ui.label("3BV/s");
+                MinesOfRustApp::statistics_plot_ui(ui, "statistics_bbbv", bbbv_per_second);
+            });
+        self.statistics_visible = open;
+
+        if clear_history {
+            self.history.clear();
+            self.progress = Progress::from_history(&self.history);
+            self.save_to_userhome();
+        }
+    }
+
+    /// Generates boards of the current difficulty and shows what they're like, for tuning
+    /// the difficulty presets. Only offered in debug builds.
+    fn board_analysis_ui(&mut self, ctx: &egui::Context) {
+        let mut open = self.board_analysis_visible;
+        egui::Window::new(tr("window-board-analysis"))
+            .open(&mut open)
+            .vscroll(true)
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    ui.label(tr_with(
+                        "board-analysis-difficulty",
+                        &[("difficulty", &self.state.difficulty.name())],
+                    ));
+                    ui.add(
+                        egui::DragValue::new(&mut self.board_analysis_num_boards)
+                            .clamp_range(1..=100000),
+                    );
+                    ui.label(tr("boards"));
+                    // Big batches take a while, so they're generated in the background
+                    if let Some(job) = &self.board_analysis_job {
+                        ui.add(
+                            egui::ProgressBar::new(job.fraction_done())
+                                .show_percentage()
+                                .animate(true),
+                        );
+                        if ui.button(tr("cancel")).clicked() {
+                            job.cancel();
+                            self.board_analysis_job = None;
+                        }
+                    } else if ui.button(tr("analyze")).clicked() {
+                        let settings =
+                            GameSettings::settings_for_difficulty(&self.state.difficulty);
+                        self.board_analysis_job = Some(AnalysisJob::start(
+                            &settings,
+                            self.board_analysis_num_boards,
+                        ));
+                    }
+                });
+                if let Some(result) = self.board_analysis_job.as_ref().and_then(|j| j.poll()) {
+                    self.board_analysis_job = None;
+                    match result {
+                        Ok(stats) => self.board_analysis = Some(stats),
+                        Err(e) => self.report_error(e),
+                    }
+                }
+
+                let Some(stats) = &self.board_analysis else {
+                    return;
+                };
+                ui.separator();
+                ui.label(tr_with(
+                    "board-analysis-summary",
+                    &[
+                        ("boards", &stats.num_boards),
+                        ("seed", &stats.seed),
+                        ("guesses", &stats.guess_required),
+                        ("rate", &format!("{:.1}", stats.guess_rate())),
+                    ],
+                ));
+                egui::Grid::new("board_analysis")
+                    .num_columns(6)
+                    .spacing([20.0, 5.0])
+                    .striped(true)
+                    .show(ui, |ui| {
+                        [
+                            "",
+                            tr("column-min"),
+                            tr("column-median"),
+                            "90%",
+                            tr("column-max"),
+                            tr("column-mean"),
+                        ]
+                        .iter()
+                        .for_each(|h| {
+                            ui.label(*h);
+                        });
+                        ui.end_row();
+                        [("3BV", &stats.bbbv), (tr("opening"), &stats.opening)]
+                            .iter()
+                            .for_each(|(name, d)| {
+                                ui.label(*name);
+                                ui.label(d.min().to_string());
+                                ui.label(d.percentile(50.0).to_string());
+                                ui.label(d.percentile(90.0).to_string());
+                                ui.label(d.max().to_string());
+                                ui.label(format!("{:.1}", d.mean()));
+                                ui.end_row();
+                            });
+                    });
+
+                ui.label("3BV");
+                MinesOfRustApp::histogram_ui(ui, "board_analysis_bbbv", &stats.bbbv);
+                ui.label(tr("opening-size-squares"));
+                MinesOfRustApp::histogram_ui(ui, "board_analysis_opening", &stats.opening);
+            });
+        // Nobody is waiting on the boards once the window is closed
+        if !open {
+            if let Some(job) = self.board_analysis_job.take() {
+                job.cancel();
+            }
+        }
+        self.board_analysis_visible = open;
+    }
+
+    /// The latest log messages, newest at the bottom, for seeing what the app is doing
+    /// without a terminal. Only offered in debug builds.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn debug_console_ui(&mut self, ctx: &egui::Context) {
+        let mut open = self.debug_console_visible;
+        let lines = logging::recent();
+        egui::Window::new(tr("window-debug-console"))
+            .open(&mut open)
+            .default_width(600.0)
+            .show(ctx, |ui| {
+                if ui.button(tr("copy")).clicked() {
+                    ui.output_mut(|o| o.copied_text = lines.join("\n"));
+                }
+                ui.separator();
+                egui::ScrollArea::both()
+                    .max_height(300.0)
+                    .stick_to_bottom(true)
+                    .show(ui, |ui| {
+                        lines.iter().for_each(|l| {
+                            ui.monospace(l);
+                        });
+                    });
+            });
+        self.debug_console_visible = open;
+    }
+
+    /// The squares of a board as they really are, mines and all, for working on the engine.
+    /// Clicking a square adds or removes a mine and right-clicking flags it, and `play()`
+    /// can be called anywhere, going straight to the board. Only offered in debug builds.
+    fn board_debugger_ui(&mut self, ctx: &egui::Context) {
+        let Some(mut debugger) = self.board_debugger.take() else {
+            return;
+        };
+        let mut open = true;
+        let mut edited = false;
+        let num_boards = self.boards.len();
+        debugger.board = debugger.board.min(num_boards.saturating_sub(1));
+        egui::Window::new(tr("window-board-debugger"))
+            .open(&mut open)
+            .vscroll(true)
+            .hscroll(true)
+            .show(ctx, |ui| {
+                if num_boards > 1 {
+                    ui.add(
+                        egui::Slider::new(&mut debugger.board, 0..=num_boards - 1)
+                            .custom_formatter(|n, _| format!("{}", n as usize + 1))
+                            .text(tr("boards")),
+                    );
+                }
+                let Some(board) = self.boards.get_mut(debugger.board) else {
+                    return;
+                };
+                ui.monospace(format!(
+                    "{}x{}, {} layers, {} mines, seed {:016x}",
+                    board.width,
+                    board.height,
+                    board.layers(),
+                    board.num_mines,
+                    board.seed
+                ));
+                ui.monospace(format!(
+                    "populated {}, 3BV {}, {} flags, {} revealed, {} detonated",
+                    board.is_populated,
+                    board.bbbv(),
+                    board.num_flags(),
+                    board.num_revealed(),
+                    board.num_detonated()
+                ));
+                ui.label(tr("tip-board-debugger"));
+                ui.separator();
+
+                egui::Grid::new("board_debugger_squares")
+                    .spacing([2.0, 2.0])
+                    .show(ui, |ui| {
+                        (0..board.height).for_each(|y| {
+                            (0..board.width).for_each(|x| {
+                                let Ok(sqr) = board.get_square(x, y) else {
+                                    return;
+                                };
+                                let text = match sqr {
+                                    s if s.is_void() => "_".to_string(),
+                                    s if s.is_mine() => "*".to_string(),
+                                    s if s.numeral > 0 => s.numeral.to_string(),
+                                    _ => ".".to_string(),
+                                };
+                                let mut text = RichText::new(text).monospace();
+                                if sqr.is_flagged {
+                                    text = text.color(constants::COLOR_WARNING);
+                                }
+                                let response = ui
+                                    .selectable_label(sqr.is_revealed, text)
+                                    .on_hover_text(format!("({}, {}) {:?}", x, y, sqr));
+                                if response.clicked() {
+                                    debugger.result = format!("{:?}", board.toggle_mine(x, y));
+                                    edited = true;
+                                }
+                                if response.secondary_clicked() {
+                                    debugger.result = format!("{:?}", board.flag(x, y));
+                                    edited = true;
+                                }
+                            });
+                            ui.end_row();
+                        });
+                    });
+                ui.separator();
+
+                ui.horizontal(|ui| {
+                    if ui.button(tr("populate-numerals")).clicked() {
+                        debugger.result = format!("{:?}", board.populate_numerals());
+                        edited = true;
+                    }
+                    ui.separator();
+                    ui.label("x");
+                    ui.add(egui::DragValue::new(&mut debugger.x));
+                    ui.label("y");
+                    ui.add(egui::DragValue::new(&mut debugger.y));
+                    egui::ComboBox::from_id_source("board_debugger_play_type")
+                        .selected_text(format!("{:?}", debugger.play_type))
+                        .show_ui(ui, |ui| {
+                            [
+                                RevealType::Reveal,
+                                RevealType::RevealChord,
+                                RevealType::Chord,
+                                RevealType::Flag,
+                            ]
+                            .into_iter()
+                            .for_each(|t| {
+                                let text = format!("{:?}", t);
+                                ui.selectable_value(&mut debugger.play_type, t, text);
+                            });
+                        });
+                    if ui.button("play()").clicked() {
+                        debugger.result = format!(
+                            "{:?}",
+                            board.play(debugger.x, debugger.y, debugger.play_type.clone())
+                        );
+                        edited = true;
+                    }
+                });
+                ui.monospace(&debugger.result);
+            });
+        if edited {
+            log::debug!("Board debugger: {}", debugger.result);
+            self.board_debugged = true;
+            #[cfg(not(target_arch = "wasm32"))]
+            self.snapshot_boards();
+            self.check_game_end();
+        }
+        if open {
+            self.board_debugger = Some(debugger);
+        }
+    }
+
+    #[cfg(all(feature = "scripting", not(target_arch = "wasm32")))]
+    fn script_console_ui(&mut self, ctx: &egui::Context) {
+        let Some(mut console) = self.script_console.take() else {
+            return;
+        };
+        let mut open = true;
+        let mut run = false;
+        egui::Window::new(tr("window-script-console"))
+            .open(&mut open)
+            .default_width(480.0)
+            .show(ctx, |ui| {
+                ui.label(tr("tip-script-console"));
+                ui.horizontal(|ui| {
+                    ui.text_edit_singleline(&mut console.file_name);
+                    if ui.button(tr("load-script")).clicked() {
+                        match script::read_script_file(&console.file_name) {
+                            Ok(source) => console.source = source,
+                            Err(e) => console.output.push(format!("{:#}", e)),
+                        }
+                    }
+                    if ui.button(tr("save-script")).clicked() {
+                        if let Err(e) =
+                            script::write_script_file(&console.file_name, &console.source)
+                        {
+                            console.output.push(format!("{:#}", e));
+                        }
+                    }
+                });
+                egui::ScrollArea::vertical()
+                    .id_source("script_console_source")
+                    .max_height(320.0)
+                    .show(ui, |ui| {
+                        ui.add(
+                            egui::TextEdit::multiline(&mut console.source)
+                                .code_editor()
+                                .desired_rows(16)
+                                .desired_width(f32::INFINITY),
+                        );
+                    });
+                ui.horizontal(|ui| {
+                    run = ui.button(tr("run-script")).clicked();
+                    ui.checkbox(&mut console.run_after_plays, tr("run-after-plays"))
+                        .on_hover_text(tr("tip-run-after-plays"));
+                    if ui.button(tr("clear-output")).clicked() {
+                        console.output.clear();
+                    }
+                });
+                ui.separator();
+                egui::ScrollArea::vertical()
+                    .id_source("script_console_output")
+                    .max_height(160.0)
+                    .stick_to_bottom(true)
+                    .show(ui, |ui| {
+                        console.output.iter().for_each(|line| {
+                            ui.monospace(line);
+                        });
+                    });
+            });
+        if open {
+            self.script_console = Some(console);
+            if run {
+                self.run_script(ctx, None);
+            }
+        }
+    }
+
+    /// Runs the script in the console, or only its `function`, and then makes the plays it
+    /// made for real, until the game ends
+    #[cfg(all(feature = "scripting", not(target_arch = "wasm32")))]
+    fn run_script(&mut self, ctx: &egui::Context, function: Option<&str>) {
+        let single_board = self.boards.len() == 1 && self.game_settings.layers == 1;
+        let Some(console) = &mut self.script_console else {
+            return;
+        };
+        if !single_board {
+            console.output.push(tr("script-single-board").to_string());
+            return;
+        }
+        let run = script::run(
+            &console.source,
+            function,
+            &self.boards[0],
+            &self.game_settings,
+        );
+        self.script_playing = true;
+        run.plays.into_iter().for_each(|(coord, play_type)| {
+            if !self.game_state.game_ended() && self.game_state != GameState::Paused {
+                self.script_played = true;
+                self.apply_play(coord, play_type, PlayKind::Click);
+            }
+        });
+        // The script's own plays are dealt with while they're known to be the script's
+        self.handle_events(ctx);
+        self.script_playing = false;
+        if let Some(console) = &mut self.script_console {
+            console.output.extend(run.output);
+            console.output.extend(run.error);
+        }
+    }
+
+    /// Runs the script's `on_play` after the player's plays, if the console is set to
+    #[cfg(all(feature = "scripting", not(target_arch = "wasm32")))]
+    fn run_script_hook(&mut self, ctx: &egui::Context) {
+        let hooked = self
+            .script_console
+            .as_ref()
+            .is_some_and(|c| c.run_after_plays && script::defines(&c.source, script::ON_PLAY));
+        if hooked && !self.script_playing && self.game_state == GameState::Playing {
+            self.run_script(ctx, Some(script::ON_PLAY));
+        }
+    }
+
+    fn histogram_ui(ui: &mut egui::Ui, id: &str, distribution: &Distribution) {
+        let bins = distribution.histogram(20);
+        let bin_width = bins.get(1).map_or(1, |b| b.0 - bins[0].0) as f64;
+        let bars = bins
+            .iter()
+            .map(|(start, count)| {
+                Bar::new(*start as f64 + bin_width / 2.0, *count as f64).width(bin_width)
+            })
+            .collect();
+        Plot::new(id)
+            .height(120.0)
+            .width(400.0)
+            .allow_drag(false)
+            .allow_zoom(false)
+            .allow_scroll(false)
+            .show(ui, |plot_ui| plot_ui.bar_chart(BarChart::new(bars)));
+    }
+
+    /// A small line plot of values against game number
+    fn statistics_plot_ui(ui: &mut egui::Ui, id: &str, points: Vec<[f64; 2]>) {
+        Plot::new(id)
+            .height(120.0)
+            .width(400.0)
+            .allow_drag(false)
+            .allow_zoom(false)
+            .allow_scroll(false)
+            .show(ui, |plot_ui| {
+                plot_ui.line(Line::new(PlotPoints::from(points.clone())));
+                plot_ui.points(Points::new(PlotPoints::from(points)).radius(2.0));
+            });
+    }
+
+    fn on_update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) -> Result<(), Error> {
+        if !self.image_loaders_installed {
+            install_image_loaders(ctx);
+            self.image_loaders_installed = true;
+
+            #[cfg(not(target_arch = "wasm32"))]
+            self.restore_window_geometry(ctx);
+            if self.state.always_on_top {
+                self.set_always_on_top(ctx, true);
+            }
+
+            if self.first_run {
+                self.onboarding = Some(Onboarding::new(&self.state));
+            }
+
+            // Send any wins that were left queued when the app last closed
+            #[cfg(feature = "online")]
+            if self.state.online_enabled {
+                self.online.flush(ctx, &self.state.online_endpoint);
+            }
+
+            #[cfg(all(feature = "tray", not(target_arch = "wasm32")))]
+            match Tray::new(ctx) {
+                Ok(tray) => self.tray = Some(Rc::new(tray)),
+                Err(e) => log::warn!("Failed to add the tray icon: {:#}", e),
+            }
+
+            #[cfg(all(feature = "hotkeys", not(target_arch = "wasm32")))]
+            match GlobalHotkeys::new(ctx) {
+                Ok(hotkeys) => self.global_hotkeys = Some(Rc::new(hotkeys)),
+                Err(e) => log::warn!("Failed to listen for global hotkeys: {:#}", e),
+            }
+
+            #[cfg(all(feature = "sync", not(target_arch = "wasm32")))]
+            if self.sync_settings.enabled {
+                self.profile_sync
+                    .start(ctx, &self.sync_settings, self.profile());
+            }
+        }
+
+        // Like the theme, a new language is previewed while the settings window is open
+        set_language(self.settings_draft.as_ref().unwrap_or(&self.state).language);
+
+        #[cfg(all(feature = "tray", not(target_arch = "wasm32")))]
+        self.handle_tray_actions(ctx);
+        #[cfg(all(feature = "hotkeys", not(target_arch = "wasm32")))]
+        self.handle_global_hotkeys(ctx);
+
+        self.check_window_focus(ctx);
+
+        // Closing the window throws the game away too
+        if ctx.input(|i| i.viewport().close_requested())
+            && !self.exit_confirmed
+            && self.asks_before_abandoning()
+        {
+            ctx.send_viewport_cmd(ViewportCommand::CancelClose);
+            self.abandon_game(ctx, AbandonAction::Exit);
+        }
+
+        #[cfg(not(target_arch = "wasm32"))]
+        self.track_window_geometry(ctx);
+
+        #[cfg(feature = "online")]
+        self.online.poll(ctx, &self.state.online_endpoint);
+
+        #[cfg(feature = "updates")]
+        self.check_for_updates(ctx);
+
+        #[cfg(all(feature = "sync", not(target_arch = "wasm32")))]
+        if let Some(profile) = self.profile_sync.poll() {
+            self.merge_profile(&profile);
+            self.save_to_userhome();
+        }
+
+        #[cfg(feature = "net")]
+        self.update_net_race(ctx);
+
+        #[cfg(feature = "net")]
+        self.update_coop(ctx);
+
+        self.tick(ctx);
+
+        if self.leaderboard_visible {
+            self.leaderboard_ui(ctx);
+        }
+
+        if self.gamestats_visible {
+            self.gamestats_ui(ctx);
+        }
+
+        if self.statistics_visible {
+            self.statistics_ui(ctx);
+        }
+
+        if self.post_mortem_visible {
+            self.post_mortem_ui(ctx);
+        }
+
+        if self.board_analysis_visible {
+            self.board_analysis_ui(ctx);
+        }
+
+        #[cfg(not(target_arch = "wasm32"))]
+        if self.debug_console_visible {
+            self.debug_console_ui(ctx);
+        }
+        if self.board_debugger.is_some() {
+            self.board_debugger_ui(ctx);
+        }
+        #[cfg(all(feature = "scripting", not(target_arch = "wasm32")))]
+        if self.script_console.is_some() {
+            self.script_console_ui(ctx);
+        }
+
+        if self.about_visible {
+            self.about_ui(ctx);
+        }
+
+        if self.hotseat_setup_visible {
+            self.hotseat_setup_ui(ctx);
+        }
+
+        if self.puzzles_visible {
+            self.puzzles_ui(ctx);
+        }
+
+        #[cfg(not(target_arch = "wasm32"))]
+        if self.profile_visible {
+            self.profile_ui(ctx);
+        }
+
+        if self.hotseat.is_some() {
+            self.hotseat_ui(ctx);
+        }
+
+        #[cfg(feature = "net")]
+        if self.net_lobby_visible {
+            self.net_lobby_ui(ctx);
+        }
+
+        #[cfg(feature = "net")]
+        if self.spectate_visible {
+            self.spectate_ui(ctx);
+        }
+
+        #[cfg(feature = "net")]
+        if self.coop_visible {
+            self.coop_ui(ctx);
+        }
+
+        if self.settings_draft.is_some() {
+            self.settings_ui(ctx);
+        }
+
+        if self.error_message.is_some() {
+            self.error_ui(ctx);
+        }
+
+        if self.abandon_prompt.is_some() {
+            self.abandon_prompt_ui(ctx);
+        }
+
+        #[cfg(not(target_arch = "wasm32"))]
+        if self.recovered_game.is_some() {
+            self.recovered_game_ui(ctx);
+        }
+
+        #[cfg(not(target_arch = "wasm32"))]
+        if self.crash_report.is_some() {
+            self.crash_report_ui(ctx);
+        }
+
+        if !self.persistence_warnings.is_empty() {
+            self.persistence_warning_ui(ctx);
+        }
+
+        #[cfg(feature = "updates")]
+        if self.state.check_for_updates
+            && !self.update_dismissed
+            && self
+                .state
+                .latest_release
+                .as_ref()
+                .is_some_and(|r| r.is_newer())
+        {
+            self.update_banner_ui(ctx);
+        }
+
+        if self.tutorial.is_some() {
+            self.tutorial_ui(ctx);
+        }
+
+        if self.personal_best.is_some() {
+            self.personal_best_ui(ctx);
+        }
+
+        if self.onboarding.is_some() {
+            self.onboarding_ui(ctx);
+        }
+
+        // Theme changes are previewed while the settings or welcome window is open
+        let theme = match (&self.settings_draft, &self.onboarding) {
+            (Some(draft), _) => &draft.theme,
+            (None, Some(onboarding)) => &onboarding.theme,
+            (None, None) => &self.state.theme,
+        };
+        match theme {
             VisualTheme::Dark => ctx.set_visuals(Visuals::dark()),
             VisualTheme::Light => ctx.set_visuals(Visuals::light()),
         }
 
-        if DBG_WINDOW_RESIZABLE {
-            println!(
-                "width: {}, height: {}",
-                ctx.available_rect().width(),
-                ctx.available_rect().height()
-            );
-        }
+        if DBG_WINDOW_RESIZABLE {
+            log::debug!(
+                "width: {}, height: {}",
+                ctx.available_rect().width(),
+                ctx.available_rect().height()
+            );
+        }
+
+        self.handle_shortcuts(ctx);
+
+        if !self.state.zen_mode {
+            egui::TopBottomPanel::top("menu_bar").show(ctx, |ui| {
+                egui::menu::bar(ui, |ui| self.menu_bar_ui(ctx, ui));
+            });
+        }
+
+        egui::TopBottomPanel::top("top_panel")
+            .resizable(false)
+            .min_height(50.0)
+            .show_animated(ctx, !self.state.zen_mode, |ui| {
+                let resp = ui.columns(3, |columns| {
+                    self.mine_counter_ui(&mut columns[0]);
+                    columns[2].with_layout(egui::Layout::top_down(egui::Align::Max), |ui| {
+                        self.timer_ui(ui);
+                    });
+                    columns[1].vertical_centered(|ui| self.face_ui(ui)).inner
+                });
+                if resp.clicked_by(egui::PointerButton::Primary) {
+                    self.abandon_game(ctx, AbandonAction::NewGame);
+                } else if resp.clicked_by(egui::PointerButton::Secondary) {
+                    self.abandon_game(ctx, AbandonAction::Restart);
+                }
+            });
+
+        // In zen mode, whatever's behind the window shows through as much as the player likes
+        let mut central_frame = egui::Frame::central_panel(&ctx.style());
+        if self.state.zen_mode {
+            central_frame.fill = central_frame
+                .fill
+                .gamma_multiply(1.0 - self.state.zen_transparency);
+        }
+        egui::CentralPanel::default()
+            .frame(central_frame)
+            .show(ctx, |ui| {
+                if self.state.zen_mode {
+                    self.zen_timer_ui(ui);
+                }
+                ui.vertical_centered(|ui| {
+                    if self
+                        .hotseat
+                        .as_ref()
+                        .is_some_and(|h| h.is_waiting_for_next_turn())
+                    {
+                        self.game_board_handoff_ui(ui);
+                    } else if self.game_state != GameState::Paused {
+                        self.game_board_ui(
+                            ui,
+                            !self.game_state.game_ended(),
+                            ctx.pointer_latest_pos(),
+                        );
+                    } else {
+                        self.game_board_paused_ui(ui);
+                    }
+                    if self.state.show_progress {
+                        self.progress_bar_ui(ui);
+                    }
+                });
+            });
+
+        if !self.state.compact_mode && !self.state.zen_mode {
+            self.bottom_panel_ui(ctx);
+        }
+
+        // Plays made while drawing the board are acted on at the start of the next frame
+        if self.events.has_pending() {
+            ctx.request_repaint();
+        }
+
+        // Nobody is watching the clock while the window is in the background. Otherwise, only
+        // wake up when the timer display will change; input triggers its own repaints.
+        if self.game_state == GameState::Playing && self.window_focused {
+            ctx.request_repaint_after(self.timer_repaint_interval());
+        }
+        Ok(())
+    }
+
+    fn handle_shortcuts(&mut self, ctx: &egui::Context) {
+        if ctx.input_mut(|i| i.consume_shortcut(&SHORTCUT_NEW_GAME)) {
+            log::debug!("ctrl+n is pressed, resetting game");
+            self.abandon_game(ctx, AbandonAction::NewGame);
+        }
+        if ctx.input_mut(|i| i.consume_shortcut(&SHORTCUT_RESTART_GAME)) {
+            log::debug!("ctrl+r is pressed, resetting existing game");
+            self.abandon_game(ctx, AbandonAction::Restart);
+        }
+        if ctx.input_mut(|i| i.consume_shortcut(&SHORTCUT_EXIT)) {
+            log::debug!("Boss can see screen. Ctrl+q is pressed, exiting");
+            self.abandon_game(ctx, AbandonAction::Exit);
+        }
+        if ctx.input_mut(|i| i.consume_shortcut(&SHORTCUT_PAUSE)) {
+            log::debug!("Ctrl+q is pressed, toggling pause status");
+            self.toggle_pause_state();
+        }
+        if ctx.input_mut(|i| i.consume_shortcut(&SHORTCUT_HIDE)) {
+            log::debug!("Boss can see screen. Ctrl+h is pressed, hiding window");
+            self.hide_window(ctx);
+        }
+        if ctx.input_mut(|i| i.consume_shortcut(&SHORTCUT_COMPACT_MODE)) {
+            log::debug!("Ctrl+m is pressed, toggling compact mode");
+            self.set_compact_mode(ctx, !self.state.compact_mode);
+        }
+        if ctx.input_mut(|i| i.consume_shortcut(&SHORTCUT_SETTINGS)) {
+            self.open_settings();
+        }
+        if ctx.input_mut(|i| i.consume_shortcut(&SHORTCUT_ABOUT)) {
+            self.about_visible = !self.about_visible;
+        }
+        if ctx.input_mut(|i| i.consume_shortcut(&SHORTCUT_ALWAYS_ON_TOP)) {
+            log::debug!("Ctrl+t is pressed, toggling always on top");
+            self.set_always_on_top(ctx, !self.state.always_on_top);
+        }
+        if ctx.input_mut(|i| i.consume_shortcut(&SHORTCUT_ZEN_MODE)) {
+            self.state.zen_mode = !self.state.zen_mode;
+        }
+    }
+
+    fn menu_bar_ui(&mut self, ctx: &egui::Context, ui: &mut egui::Ui) {
+        let menu_item = |ui: &mut egui::Ui, text: &str, shortcut: Option<&KeyboardShortcut>| {
+            let mut button = egui::Button::new(text);
+            if let Some(s) = shortcut {
+                button = button.shortcut_text(ctx.format_shortcut(s));
+            }
+            let clicked = ui.add(button).clicked();
+            if clicked {
+                ui.close_menu();
+            }
+            clicked
+        };
+
+        ui.menu_button(tr("menu-game"), |ui| {
+            if menu_item(ui, tr("menu-new"), Some(&SHORTCUT_NEW_GAME)) {
+                self.abandon_game(ctx, AbandonAction::NewGame);
+            } else if menu_item(ui, tr("menu-restart"), Some(&SHORTCUT_RESTART_GAME)) {
+                self.abandon_game(ctx, AbandonAction::Restart);
+            }
+
+            ui.menu_button(tr("menu-difficulty"), |ui| {
+                [
+                    GameDifficulty::Beginner,
+                    GameDifficulty::Intermediate,
+                    GameDifficulty::Expert,
+                ]
+                .into_iter()
+                .for_each(|d| {
+                    let selected = self.state.difficulty == d;
+                    if ui.radio(selected, d.name()).clicked() && !selected {
+                        ui.close_menu();
+                        self.set_difficulty(ctx, d);
+                    }
+                });
+            });
+
+            if ui
+                .checkbox(&mut self.state.no_flags, tr("no-flags"))
+                .on_hover_text(tr("tip-no-flags"))
+                .clicked()
+            {
+                ui.close_menu();
+                self.apply_game_mode(ctx);
+            }
+
+            if ui
+                .add_enabled(
+                    !self.is_net_racing(),
+                    egui::Checkbox::new(&mut self.mines_visible, tr("show-mines")),
+                )
+                .on_hover_text(tr("tip-show-mines"))
+                .clicked()
+            {
+                ui.close_menu();
+                self.mines_seen |= self.mines_visible;
+            }
+
+            ui.menu_button(tr("menu-boards"), |ui| {
+                [
+                    (1, tr("one-board")),
+                    (2, tr("two-boards")),
+                    (4, tr("four-boards")),
+                ]
+                .into_iter()
+                .for_each(|(n, label)| {
+                    let selected = self.state.num_boards == n;
+                    if ui.radio(selected, label).clicked() && !selected {
+                        ui.close_menu();
+                        self.state.num_boards = n;
+                        self.apply_game_mode(ctx);
+                    }
+                });
+            })
+            .response
+            .on_hover_text(tr("tip-boards"));
+
+            ui.menu_button(tr("menu-mine-placement"), |ui| {
+                MinePlacement::all().into_iter().for_each(|p| {
+                    let selected = self.state.mine_placement == p;
+                    if ui.radio(selected, p.name()).clicked() && !selected {
+                        ui.close_menu();
+                        self.state.mine_placement = p;
+                        self.apply_game_mode(ctx);
+                    }
+                });
+            })
+            .response
+            .on_hover_text(tr("tip-mine-placement"));
+
+            ui.menu_button(tr("menu-board-shape"), |ui| {
+                BoardShape::all().into_iter().for_each(|s| {
+                    let selected = self.state.board_shape == s;
+                    if ui.radio(selected, s.name()).clicked() && !selected {
+                        ui.close_menu();
+                        self.state.board_shape = s;
+                        self.apply_game_mode(ctx);
+                    }
+                });
+            })
+            .response
+            .on_hover_text(tr("tip-board-shape"));
+
+            ui.menu_button(tr("menu-layers"), |ui| {
+                [
+                    (1, tr("one-layer")),
+                    (2, tr("two-layers")),
+                    (3, tr("three-layers")),
+                ]
+                .into_iter()
+                .for_each(|(n, label)| {
+                    let selected = self.state.layers == n;
+                    if ui.radio(selected, label).clicked() && !selected {
+                        ui.close_menu();
+                        self.state.layers = n;
+                        self.apply_game_mode(ctx);
+                    }
+                });
+            })
+            .response
+            .on_hover_text(tr("tip-layers"));
+
+            if menu_item(ui, tr("menu-hot-seat-race"), None) {
+                self.hotseat_setup_visible = true;
+            }
+            if menu_item(ui, tr("menu-puzzles"), None) {
+                self.puzzles_visible = true;
+            }
+            #[cfg(feature = "net")]
+            if menu_item(ui, tr("menu-network-race"), None) {
+                self.net_lobby_visible = true;
+            }
+            #[cfg(feature = "net")]
+            if menu_item(ui, tr("menu-spectate"), None) {
+                self.spectate_visible = true;
+            }
+            #[cfg(feature = "net")]
+            if menu_item(ui, tr("menu-co-op"), None) {
+                self.coop_visible = true;
+            }
+
+            let pause_text = if self.game_state == GameState::Paused {
+                "Resume"
+            } else {
+                "Pause"
+            };
+            if menu_item(ui, pause_text, Some(&SHORTCUT_PAUSE)) {
+                self.toggle_pause_state();
+            }
+
+            ui.separator();
+            if menu_item(ui, tr("menu-hide"), Some(&SHORTCUT_HIDE)) {
+                self.hide_window(ctx);
+            }
+            if menu_item(ui, tr("menu-exit"), Some(&SHORTCUT_EXIT)) {
+                self.abandon_game(ctx, AbandonAction::Exit);
+            }
+        });
+
+        ui.menu_button(tr("menu-options"), |ui| {
+            if menu_item(ui, tr("menu-settings"), Some(&SHORTCUT_SETTINGS)) {
+                self.open_settings();
+            }
+            #[cfg(not(target_arch = "wasm32"))]
+            if menu_item(ui, tr("menu-profile"), None) {
+                self.profile_visible = true;
+            }
+        });
+
+        ui.menu_button(tr("menu-view"), |ui| {
+            let mut always_on_top = self.state.always_on_top;
+            if ui
+                .checkbox(&mut always_on_top, tr("always-on-top"))
+                .changed()
+            {
+                self.set_always_on_top(ctx, always_on_top);
+            }
+            let mut compact = self.state.compact_mode;
+            if ui
+                .checkbox(&mut compact, tr("compact-mode"))
+                .on_hover_text(tr("tip-compact-mode"))
+                .changed()
+            {
+                self.set_compact_mode(ctx, compact);
+            }
+            if menu_item(ui, tr("menu-zen-mode"), Some(&SHORTCUT_ZEN_MODE)) {
+                self.state.zen_mode = true;
+            }
+
+            ui.separator();
+            if menu_item(ui, tr("menu-leaderboard"), None) {
+                self.leaderboard_visible = true;
+            }
+            if menu_item(ui, tr("menu-game-stats"), None) {
+                self.gamestats_visible = true;
+            }
+            if menu_item(ui, tr("menu-statistics"), None) {
+                self.statistics_visible = true;
+            }
+            if cfg!(debug_assertions) && menu_item(ui, tr("menu-board-analysis"), None) {
+                self.board_analysis_visible = true;
+            }
+            #[cfg(not(target_arch = "wasm32"))]
+            if cfg!(debug_assertions) && menu_item(ui, tr("menu-debug-console"), None) {
+                self.debug_console_visible = true;
+            }
+            if cfg!(debug_assertions)
+                && menu_item(ui, tr("menu-board-debugger"), None)
+                && self.board_debugger.is_none()
+            {
+                self.board_debugger = Some(BoardDebugger::default());
+            }
+            #[cfg(all(feature = "scripting", not(target_arch = "wasm32")))]
+            if menu_item(ui, tr("menu-script-console"), None) && self.script_console.is_none() {
+                self.script_console = Some(ScriptConsole::default());
+            }
+        });
+
+        ui.menu_button(tr("menu-help"), |ui| {
+            if menu_item(ui, tr("menu-tutorial"), None) {
+                self.start_tutorial(ctx);
+            }
+            if menu_item(ui, tr("menu-about"), Some(&SHORTCUT_ABOUT)) {
+                self.about_visible = true;
+            }
+        });
+    }
+
+    fn set_difficulty(&mut self, ctx: &egui::Context, difficulty: GameDifficulty) {
+        self.state.difficulty = difficulty;
+        self.apply_game_mode(ctx);
+    }
+
+    /// Starts a new game with the difficulty level and mode chosen in the menu. Ends any
+    /// hot-seat race, which has to be played out on the same board, or puzzle.
+    fn apply_game_mode(&mut self, ctx: &egui::Context) {
+        self.hotseat = None;
+        self.puzzle = None;
+        self.tutorial = None;
+        self.update_difficulty_settings();
+        if let Err(e) = self.reset_new_game(ctx) {
+            self.report_error(e);
+        }
+    }
+
+    fn open_settings(&mut self) {
+        if self.settings_draft.is_none() {
+            self.settings_draft = Some(self.state.clone());
+        }
+    }
+
+    /// Edits a copy of the settings, which only take effect once applied. Closing the window
+    /// throws away anything that wasn't applied.
+    fn settings_ui(&mut self, ctx: &egui::Context) {
+        let Some(mut draft) = self.settings_draft.take() else {
+            return;
+        };
+        let mut open = true;
+        let mut apply = false;
+        let mut revert = false;
+        egui::Window::new(tr("window-settings"))
+            .open(&mut open)
+            .resizable(false)
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    SettingsTab::ALL.iter().for_each(|tab| {
+                        ui.selectable_value(&mut self.settings_tab, *tab, tab.as_str());
+                    });
+                });
+                ui.separator();
+
+                egui::Grid::new("settings")
+                    .num_columns(2)
+                    .spacing([10.0, 5.0])
+                    .min_row_height(30.0)
+                    .show(ui, |ui| match self.settings_tab {
+                        SettingsTab::Gameplay => {
+                            MinesOfRustApp::gameplay_settings_ui(ui, &mut draft)
+                        }
+                        SettingsTab::Appearance => {
+                            MinesOfRustApp::appearance_settings_ui(ui, &mut draft, &self.progress)
+                        }
+                        SettingsTab::Input => MinesOfRustApp::input_settings_ui(ui, &mut draft),
+                        SettingsTab::Audio => {
+                            ui.label(tr("no-sounds-yet"));
+                        }
+                        SettingsTab::Advanced => {
+                            MinesOfRustApp::advanced_settings_ui(ui, &mut draft)
+                        }
+                    });
+
+                ui.separator();
+                ui.horizontal(|ui| {
+                    let changed = !self.state.has_same_settings(&draft);
+                    apply = ui
+                        .add_enabled(changed, egui::Button::new(tr("apply")))
+                        .clicked();
+                    revert = ui
+                        .add_enabled(changed, egui::Button::new(tr("revert")))
+                        .clicked();
+                });
+            });
+
+        if apply {
+            if draft.notifications && !self.state.notifications {
+                notify::request_permission();
+            }
+            self.state.apply_settings(&draft);
+        } else if revert {
+            draft = self.state.clone();
+        }
+        if open {
+            self.settings_draft = Some(draft);
+        }
+    }
+
+    fn gameplay_settings_ui(ui: &mut egui::Ui, settings: &mut AppState) {
+        ui.label(tr("player-name"));
+        ui.text_edit_singleline(&mut settings.player_name)
+            .on_hover_text(tr("tip-player-name"));
+        ui.end_row();
+
+        ui.label(tr("leaderboard-size"));
+        ui.horizontal(|ui| {
+            toggle_ui(ui, &mut settings.leaderboard_limit)
+                .on_hover_text(tr("tip-leaderboard-size"));
+            ui.add_enabled(
+                settings.leaderboard_limit,
+                egui::DragValue::new(&mut settings.leaderboard_max_entries)
+                    .clamp_range(5..=1000)
+                    .suffix(tr("unit-wins")),
+            );
+        });
+        ui.end_row();
+
+        ui.label(tr("left-click-chords"));
+        toggle_ui(ui, &mut settings.left_click_chord);
+        ui.end_row();
+
+        ui.label(tr("fog-of-war"));
+        toggle_ui(ui, &mut settings.fog_of_war).on_hover_text(tr_with(
+            "tip-fog-of-war",
+            &[("seconds", &constants::SONAR_TIME_PENALTY)],
+        ));
+        ui.end_row();
+
+        ui.label(tr("fog-radius"));
+        ui.add_enabled(
+            settings.fog_of_war,
+            egui::DragValue::new(&mut settings.fog_radius)
+                .clamp_range(1.0..=5.0)
+                .speed(0.1)
+                .suffix(tr("unit-squares")),
+        )
+        .on_hover_text(tr("tip-fog-radius"));
+        ui.end_row();
+
+        ui.label(tr("remember-seen-squares"));
+        ui.add_enabled_ui(settings.fog_of_war, |ui| {
+            toggle_ui(ui, &mut settings.fog_memory).on_hover_text(tr("tip-remember-seen-squares"));
+        });
+        ui.end_row();
+
+        ui.label(tr("auto-pause"));
+        toggle_ui(ui, &mut settings.auto_pause).on_hover_text(tr("tip-auto-pause"));
+        ui.end_row();
+
+        #[cfg(all(feature = "tray", not(target_arch = "wasm32")))]
+        {
+            ui.label(tr("minimize-to-tray"));
+            toggle_ui(ui, &mut settings.minimize_to_tray).on_hover_text(tr("tip-minimize-to-tray"));
+            ui.end_row();
+        }
+
+        #[cfg(all(feature = "hotkeys", not(target_arch = "wasm32")))]
+        {
+            ui.label(tr("global-hotkeys"));
+            toggle_ui(ui, &mut settings.global_hotkeys).on_hover_text(tr("tip-global-hotkeys"));
+            ui.end_row();
+
+            [
+                (tr("new-game-hotkey"), &mut settings.new_game_hotkey),
+                (tr("pause-hotkey"), &mut settings.pause_hotkey),
+            ]
+            .into_iter()
+            .for_each(|(label, hotkey)| {
+                ui.label(label);
+                let valid = parse_hotkey(hotkey).is_ok();
+                ui.add_enabled(
+                    settings.global_hotkeys,
+                    egui::TextEdit::singleline(hotkey)
+                        .text_color_opt((!valid).then_some(constants::COLOR_WARNING)),
+                )
+                .on_hover_text(tr("tip-hotkey"));
+                ui.end_row();
+            });
+        }
+
+        ui.label(tr("confirm-risky-chords"));
+        toggle_ui(ui, &mut settings.safe_chords).on_hover_text(tr("tip-confirm-risky-chords"));
+        ui.end_row();
+
+        ui.label(tr("time-penalties"));
+        toggle_ui(ui, &mut settings.time_penalties).on_hover_text(tr_with(
+            "tip-time-penalties",
+            &[
+                ("flag", &constants::PENALTY_WRONG_FLAG_SECONDS),
+                ("chord", &constants::PENALTY_HELD_CHORD_SECONDS),
+            ],
+        ));
+        ui.end_row();
+
+        ui.label(tr("strict-flags"));
+        toggle_ui(ui, &mut settings.strict_flags).on_hover_text(tr("tip-strict-flags"));
+        ui.end_row();
+
+        ui.label(tr("shake-flag-counter"));
+        toggle_ui(ui, &mut settings.flag_warning_shake).on_hover_text(tr("tip-shake-flag-counter"));
+        ui.end_row();
+
+        ui.label(tr("reveal-animation"));
+        toggle_ui(ui, &mut settings.reveal_animation).on_hover_text(tr("tip-reveal-animation"));
+        ui.end_row();
+
+        ui.label(tr("constraint-assist"));
+        toggle_ui(ui, &mut settings.constraint_assist).on_hover_text(tr("tip-constraint-assist"));
+        ui.end_row();
+
+        ui.label(tr("auto-open"));
+        ui.horizontal(|ui| {
+            toggle_ui(ui, &mut settings.auto_open).on_hover_text(tr("tip-auto-open"));
+            ui.add_enabled(
+                settings.auto_open,
+                egui::Slider::new(&mut settings.auto_open_seconds, 0.0..=2.0)
+                    .suffix(tr("unit-seconds")),
+            );
+        });
+        ui.end_row();
+
+        if notify::SUPPORTED {
+            ui.label(tr("notifications"));
+            toggle_ui(ui, &mut settings.notifications).on_hover_text(tr("tip-notifications"));
+            ui.end_row();
+        }
+
+        ui.label(tr("confirm-abandoning-games"));
+        toggle_ui(ui, &mut settings.confirm_abandon)
+            .on_hover_text(tr("tip-confirm-abandoning-games"));
+        ui.end_row();
+
+        ui.label(tr("restart-after-loss"));
+        ui.horizontal(|ui| {
+            toggle_ui(ui, &mut settings.auto_restart).on_hover_text(tr("tip-restart-after-loss"));
+            ui.add_enabled(
+                settings.auto_restart,
+                egui::DragValue::new(&mut settings.auto_restart_seconds)
+                    .clamp_range(0.5..=10.0)
+                    .speed(0.1)
+                    .suffix(tr("unit-seconds")),
+            );
+        });
+        ui.end_row();
+
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            ui.label(tr("autosave"));
+            ui.horizontal(|ui| {
+                toggle_ui(ui, &mut settings.autosave).on_hover_text(tr("tip-autosave"));
+                ui.add_enabled(
+                    settings.autosave,
+                    egui::DragValue::new(&mut settings.autosave_seconds)
+                        .clamp_range(5.0..=300.0)
+                        .speed(1.0)
+                        .suffix(tr("unit-seconds")),
+                );
+            });
+            ui.end_row();
+
+            ui.label(tr("overlay"));
+            ui.horizontal(|ui| {
+                toggle_ui(ui, &mut settings.overlay).on_hover_text(tr_with(
+                    "tip-overlay",
+                    &[(
+                        "dir",
+                        &config::config_dir()
+                            .map(|d| d.display().to_string())
+                            .unwrap_or_default(),
+                    )],
+                ));
+                ui.add_enabled(
+                    settings.overlay,
+                    egui::DragValue::new(&mut settings.overlay_seconds)
+                        .clamp_range(0.1..=10.0)
+                        .speed(0.1)
+                        .suffix(tr("unit-seconds")),
+                );
+            });
+            ui.end_row();
+        }
+    }
+
+    fn appearance_settings_ui(ui: &mut egui::Ui, settings: &mut AppState, progress: &Progress) {
+        ui.label(tr("language"));
+        egui::ComboBox::from_id_source("language")
+            .selected_text(settings.language.as_str())
+            .show_ui(ui, |ui| {
+                Language::all().into_iter().for_each(|l| {
+                    ui.selectable_value(&mut settings.language, l, l.as_str());
+                });
+            });
+        ui.end_row();
+
+        ui.label(tr("theme"));
+        ui.horizontal(|ui| {
+            [VisualTheme::Dark, VisualTheme::Light]
+                .into_iter()
+                .for_each(|t| {
+                    let text = t.as_str();
+                    ui.radio_value(&mut settings.theme, t, text);
+                });
+        });
+        ui.end_row();
+
+        ui.label(tr("timer"));
+        ui.horizontal(|ui| {
+            [TimerPrecision::Seconds, TimerPrecision::Hundredths]
+                .into_iter()
+                .for_each(|p| {
+                    let text = p.as_str();
+                    ui.radio_value(&mut settings.timer_precision, p, text);
+                });
+        });
+        ui.end_row();
+
+        ui.label(tr("numerals"));
+        ui.horizontal(|ui| {
+            [NumeralStyle::Images, NumeralStyle::Text]
+                .into_iter()
+                .for_each(|s| {
+                    let text = s.as_str();
+                    ui.radio_value(&mut settings.numeral_style, s, text);
+                });
+        })
+        .response
+        .on_hover_text(tr("tip-numerals"));
+        ui.end_row();
+
+        ui.label(tr("guess-indicator"));
+        toggle_ui(ui, &mut settings.show_guess_indicator).on_hover_text(tr("tip-guess-indicator"));
+        ui.end_row();
+
+        ui.label(tr("show-progress"));
+        toggle_ui(ui, &mut settings.show_progress).on_hover_text(tr("tip-show-progress"));
+        ui.end_row();
+
+        ui.label(tr("show-minimap"));
+        toggle_ui(ui, &mut settings.show_minimap).on_hover_text(tr("tip-show-minimap"));
+        ui.end_row();
+
+        ui.label(tr("zen-transparency"));
+        ui.add(
+            egui::Slider::new(&mut settings.zen_transparency, 0.0..=0.9)
+                .custom_formatter(|v, _| format!("{:.0}%", v * 100.0)),
+        )
+        .on_hover_text(tr("tip-zen-transparency"));
+        ui.end_row();
+
+        ui.label(tr("progression"));
+        toggle_ui(ui, &mut settings.progression).on_hover_text(tr("tip-progression"));
+        ui.end_row();
+
+        ui.label(tr("board-skin"));
+        ui.horizontal_wrapped(|ui| {
+            BoardSkin::all().into_iter().for_each(|skin| {
+                let unlocked = !settings.progression || progress.level() >= skin.unlock_level();
+                ui.add_enabled_ui(unlocked, |ui| {
+                    ui.radio_value(&mut settings.board_skin, skin, skin.as_str())
+                        .on_disabled_hover_text(tr_with(
+                            "tip-skin-locked",
+                            &[("level", &skin.unlock_level())],
+                        ));
+                });
+            });
+        });
+        ui.end_row();
+    }
+
+    fn input_settings_ui(ui: &mut egui::Ui, settings: &mut AppState) {
+        ui.label(tr("left-handed-mouse"));
+        toggle_ui(ui, &mut settings.swap_buttons).on_hover_text(tr("tip-left-handed-mouse"));
+        ui.end_row();
+
+        ui.label(tr("paint-flags"));
+        toggle_ui(ui, &mut settings.paint_flags).on_hover_text(tr("tip-paint-flags"));
+        ui.end_row();
+
+        ui.label(tr("touch-gestures"));
+        toggle_ui(ui, &mut settings.touch_gestures).on_hover_text(tr("tip-touch-gestures"));
+        ui.end_row();
+
+        ui.label(tr("long-press"));
+        ui.add_enabled(
+            settings.touch_gestures,
+            egui::DragValue::new(&mut settings.long_press_seconds)
+                .clamp_range(0.2..=2.0)
+                .speed(0.01)
+                .suffix(tr("unit-seconds")),
+        )
+        .on_hover_text(tr("tip-long-press"));
+        ui.end_row();
+
+        ui.label(tr("two-finger-tap"));
+        ui.add_enabled(
+            settings.touch_gestures,
+            egui::DragValue::new(&mut settings.two_finger_tap_seconds)
+                .clamp_range(0.1..=1.0)
+                .speed(0.01)
+                .suffix(tr("unit-seconds")),
+        )
+        .on_hover_text(tr("tip-two-finger-tap"));
+        ui.end_row();
+    }
+
+    fn advanced_settings_ui(ui: &mut egui::Ui, settings: &mut AppState) {
+        #[cfg(feature = "online")]
+        {
+            ui.label(tr("submit-winning-times"));
+            toggle_ui(ui, &mut settings.online_enabled);
+            ui.end_row();
+
+            ui.label(tr("leaderboard-server"));
+            ui.text_edit_singleline(&mut settings.online_endpoint);
+            ui.end_row();
+        }
+
+        #[cfg(feature = "livesplit")]
+        {
+            ui.label(tr("livesplit"));
+            toggle_ui(ui, &mut settings.livesplit_enabled).on_hover_text(tr("tip-livesplit"));
+            ui.end_row();
+
+            ui.label(tr("livesplit-server"));
+            ui.add_enabled(
+                settings.livesplit_enabled,
+                egui::TextEdit::singleline(&mut settings.livesplit_address),
+            );
+            ui.end_row();
+
+            ui.label(tr("livesplit-milestones"));
+            ui.add_enabled(
+                settings.livesplit_enabled,
+                egui::TextEdit::singleline(&mut settings.livesplit_milestones),
+            )
+            .on_hover_text(tr("tip-livesplit-milestones"));
+            ui.end_row();
+        }
+
+        #[cfg(feature = "updates")]
+        {
+            ui.label(tr("check-for-updates"));
+            toggle_ui(ui, &mut settings.check_for_updates)
+                .on_hover_text(tr("tip-check-for-updates"));
+            ui.end_row();
+        }
+
+        ui.label(tr("config-directory"));
+        match config::config_dir() {
+            Ok(dir) => ui.label(dir.display().to_string()),
+            Err(e) => ui.colored_label(constants::COLOR_WARNING, e.to_string()),
+        };
+        ui.end_row();
+
+        ui.label("");
+        if ui.button(tr("restore-defaults")).clicked() {
+            settings.apply_settings(&AppState::default());
+        }
+        ui.end_row();
+    }
+
+    fn hotseat_setup_ui(&mut self, ctx: &egui::Context) {
+        let mut open = self.hotseat_setup_visible;
+        let mut start = false;
+        egui::Window::new(tr("window-hot-seat-race"))
+            .open(&mut open)
+            .resizable(false)
+            .show(ctx, |ui| {
+                ui.label(tr("hotseat-intro"));
+                egui::Grid::new("hotseat_setup")
+                    .num_columns(2)
+                    .spacing([20.0, 5.0])
+                    .show(ui, |ui| {
+                        self.state
+                            .player_names
+                            .iter_mut()
+                            .enumerate()
+                            .for_each(|(i, name)| {
+                                ui.label(tr_with("player-number", &[("number", &(i + 1))]));
+                                ui.text_edit_singleline(name);
+                                ui.end_row();
+                            });
+                    });
+                start = ui.button(tr("start-race")).clicked();
+            });
+        self.hotseat_setup_visible = open && !start;
+        if start {
+            self.start_hotseat(ctx);
+        }
+    }
+
+    fn start_hotseat(&mut self, ctx: &egui::Context) {
+        self.hotseat = Some(HotSeat::new(self.state.player_names.clone()));
+        self.puzzle = None;
+        self.tutorial = None;
+        self.update_difficulty_settings();
+        if let Err(e) = self.reset_new_game(ctx) {
+            self.report_error(e);
+        }
+    }
+
+    /// Hands the board over to the second player of a hot-seat race
+    fn start_next_hotseat_turn(&mut self, ctx: &egui::Context) {
+        if let Some(hotseat) = &mut self.hotseat {
+            hotseat.next_turn();
+        }
+        if let Err(e) = self.reset_new_game(ctx) {
+            self.report_error(e);
+        }
+    }
+
+    /// Each player's result so far, and the winner once both have played
+    fn hotseat_ui(&mut self, ctx: &egui::Context) {
+        let Some(hotseat) = &self.hotseat else {
+            return;
+        };
+        let mut rematch = false;
+        let mut end = false;
+        egui::Window::new(tr("window-race-results"))
+            .resizable(false)
+            .show(ctx, |ui| {
+                egui::Grid::new("hotseat_results")
+                    .num_columns(3)
+                    .spacing([20.0, 5.0])
+                    .striped(true)
+                    .show(ui, |ui| {
+                        ui.label(RichText::new(tr("player")).strong());
+                        ui.label(RichText::new(tr("time")).strong());
+                        ui.label(RichText::new(tr("result")).strong());
+                        ui.end_row();
+
+                        hotseat.names.iter().enumerate().for_each(|(p, name)| {
+                            ui.label(name);
+                            match hotseat.result(p) {
+                                Some(r) => {
+                                    ui.label(format!("{:.2}", r.time));
+                                    if r.won {
+                                        ui.label(tr("cleared"));
+                                    } else {
+                                        ui.label(tr_with("hit-a-mine", &[("cleared", &r.cleared)]));
+                                    }
+                                }
+                                None => {
+                                    ui.label("-");
+                                    ui.label(if p == hotseat.turn() {
+                                        "Playing"
+                                    } else {
+                                        "Waiting"
+                                    });
+                                }
+                            }
+                            ui.end_row();
+                        });
+                    });
+
+                if hotseat.is_finished() {
+                    ui.separator();
+                    ui.heading(match hotseat.winner() {
+                        Some(p) => tr_with("player-wins", &[("name", &hotseat.names[p])]),
+                        None => tr("tie").to_string(),
+                    });
+                    ui.horizontal(|ui| {
+                        rematch = ui.button(tr("rematch")).clicked();
+                        end = ui.button(tr("end-race")).clicked();
+                    });
+                } else {
+                    end = ui.button(tr("quit-race")).clicked();
+                }
+            });
+
+        if rematch {
+            self.start_hotseat(ctx);
+        } else if end {
+            self.apply_game_mode(ctx);
+        }
+    }
+
+    fn current_puzzle(&self) -> Option<&Puzzle> {
+        self.puzzles.get(self.puzzle?)
+    }
+
+    /// The board the puzzle or tutorial being played is always played on, fresh
+    fn fixed_board(&mut self) -> Result<Option<GameBoard>, Error> {
+        if let Some(tutorial) = &mut self.tutorial {
+            *tutorial = Tutorial::default();
+            return Ok(Some(Tutorial::board()));
+        }
+        self.current_puzzle().map(|p| p.board()).transpose()
+    }
+
+    /// How the puzzle being played is going, and every puzzle to pick from
+    fn puzzles_ui(&mut self, ctx: &egui::Context) {
+        let mut open = self.puzzles_visible;
+        let mut play = None;
+        let mut leave = false;
+        egui::Window::new(tr("window-puzzles"))
+            .open(&mut open)
+            .resizable(false)
+            .show(ctx, |ui| {
+                if let (Some(index), Some(puzzle)) = (self.puzzle, self.current_puzzle()) {
+                    ui.heading(&puzzle.name);
+                    ui.label(puzzle.objective.description());
+                    match self.game_state {
+                        GameState::EndedWin if puzzle.objective.is_met(&self.plays) => {
+                            ui.label(tr("puzzle-completed"));
+                        }
+                        GameState::EndedWin => {
+                            ui.colored_label(constants::COLOR_WARNING, tr("objective-missed"));
+                        }
+                        GameState::EndedLoss => {
+                            ui.colored_label(constants::COLOR_WARNING, tr("puzzle-failed"));
+                        }
+                        _ => {}
+                    }
+                    ui.horizontal(|ui| {
+                        if ui.button(tr("try-again")).clicked() {
+                            play = Some(index);
+                        }
+                        if index + 1 < self.puzzles.len() && ui.button(tr("next-puzzle")).clicked()
+                        {
+                            play = Some(index + 1);
+                        }
+                        leave = ui.button(tr("leave")).clicked();
+                    });
+                    ui.separator();
+                } else {
+                    ui.label(tr("puzzles-intro"));
+                }
+
+                egui::ScrollArea::vertical().show(ui, |ui| {
+                    egui::Grid::new("puzzles")
+                        .num_columns(4)
+                        .spacing([20.0, 5.0])
+                        .striped(true)
+                        .show(ui, |ui| {
+                            self.puzzles.iter().enumerate().for_each(|(i, puzzle)| {
+                                ui.label(if self.puzzle_progress.is_completed(puzzle) {
+                                    "✔"
+                                } else {
+                                    ""
+                                });
+                                ui.label(&puzzle.name);
+                                ui.label(puzzle.objective.description());
+                                if ui.button(tr("play")).clicked() {
+                                    play = Some(i);
+                                }
+                                ui.end_row();
+                            });
+                        });
+                });
+            });
+        self.puzzles_visible = open;
+
+        if let Some(index) = play {
+            self.start_puzzle(ctx, index);
+        } else if leave {
+            self.apply_game_mode(ctx);
+        }
+    }
+
+    /// Asks a new player how they'd like to play, so they don't start out with settings
+    /// picked for someone else. Skipping it keeps the defaults.
+    fn onboarding_ui(&mut self, ctx: &egui::Context) {
+        let Some(onboarding) = &mut self.onboarding else {
+            return;
+        };
+        let mut done = false;
+        let mut skip = false;
+        egui::Window::new(tr("window-welcome"))
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_CENTER, [0.0, 0.0])
+            .show(ctx, |ui| {
+                ui.label(tr("welcome-text"));
+                ui.add_space(5.0);
+                egui::Grid::new("onboarding")
+                    .num_columns(2)
+                    .spacing([20.0, 5.0])
+                    .show(ui, |ui| {
+                        ui.label(tr("player-name"));
+                        ui.text_edit_singleline(&mut onboarding.player_name);
+                        ui.end_row();
+
+                        ui.label(tr("difficulty"));
+                        ui.horizontal(|ui| {
+                            [
+                                GameDifficulty::Beginner,
+                                GameDifficulty::Intermediate,
+                                GameDifficulty::Expert,
+                            ]
+                            .into_iter()
+                            .for_each(|d| {
+                                let text = d.name();
+                                ui.radio_value(&mut onboarding.difficulty, d, text);
+                            });
+                        });
+                        ui.end_row();
+
+                        ui.label(tr("theme"));
+                        ui.horizontal(|ui| {
+                            [VisualTheme::Dark, VisualTheme::Light]
+                                .into_iter()
+                                .for_each(|t| {
+                                    let text = t.as_str();
+                                    ui.radio_value(&mut onboarding.theme, t, text);
+                                });
+                        });
+                        ui.end_row();
+
+                        ui.label(tr("input-style"));
+                        ui.vertical(|ui| {
+                            InputStyle::all().into_iter().for_each(|s| {
+                                ui.radio_value(&mut onboarding.input_style, s, s.as_str())
+                                    .on_hover_text(s.description());
+                            });
+                        });
+                        ui.end_row();
+
+                        ui.label(tr("play-tutorial"));
+                        toggle_ui(ui, &mut onboarding.tutorial);
+                        ui.end_row();
+                    });
+                ui.separator();
+                ui.horizontal(|ui| {
+                    done = ui.button(tr("get-started")).clicked();
+                    skip = ui.button(tr("skip")).clicked();
+                });
+            });
+        if skip {
+            self.onboarding = None;
+        } else if done {
+            self.finish_onboarding(ctx);
+        }
+    }
+
+    /// Saves the choices made in the welcome window and starts the first game
+    fn finish_onboarding(&mut self, ctx: &egui::Context) {
+        let Some(onboarding) = self.onboarding.take() else {
+            return;
+        };
+        self.state.difficulty = onboarding.difficulty;
+        self.state.theme = onboarding.theme;
+        if !onboarding.player_name.trim().is_empty() {
+            self.state.player_name = onboarding.player_name.trim().to_string();
+            #[cfg(feature = "net")]
+            {
+                self.state.net_name = self.state.player_name.clone();
+            }
+        }
+        self.state.set_input_style(onboarding.input_style);
+        self.save_to_userhome();
+        if onboarding.tutorial {
+            self.start_tutorial(ctx);
+        } else {
+            self.apply_game_mode(ctx);
+        }
+    }
+
+    fn start_tutorial(&mut self, ctx: &egui::Context) {
+        self.hotseat = None;
+        self.puzzle = None;
+        self.game_settings = Tutorial::settings();
+        self.tutorial = Some(Tutorial::default());
+        if let Err(e) = self.reset_new_game(ctx) {
+            self.report_error(e);
+        }
+    }
+
+    /// Celebrates a new best time above the board, until the next game starts
+    fn personal_best_ui(&mut self, ctx: &egui::Context) {
+        let Some(best) = &self.personal_best else {
+            return;
+        };
+        let text = match best.previous {
+            Some(previous) => tr_with(
+                "new-personal-best",
+                &[
+                    ("category", &self.leaderboard_category().title()),
+                    ("time", &format!("{:.2}", best.time)),
+                    ("delta", &format!("{:.2}", previous - best.time)),
+                ],
+            ),
+            None => tr_with(
+                "first-personal-best",
+                &[
+                    ("category", &self.leaderboard_category().title()),
+                    ("time", &format!("{:.2}", best.time)),
+                ],
+            ),
+        };
+        egui::TopBottomPanel::top("personal_best_panel")
+            .resizable(false)
+            .show(ctx, |ui| {
+                ui.vertical_centered(|ui| {
+                    ui.colored_label(constants::COLOR_PERSONAL_BEST, text);
+                });
+            });
+    }
+
+    /// What to do next in the tutorial, above the board
+    fn tutorial_ui(&mut self, ctx: &egui::Context) {
+        let Some(tutorial) = &self.tutorial else {
+            return;
+        };
+        let mut done = false;
+        egui::TopBottomPanel::top("tutorial_panel")
+            .resizable(false)
+            .show(ctx, |ui| {
+                ui.label(tutorial.instructions());
+                if tutorial.is_blocked() {
+                    ui.colored_label(constants::COLOR_WARNING, tr("tutorial-blocked"));
+                }
+                done = if tutorial.is_finished() {
+                    ui.button(tr("start-playing")).clicked()
+                } else {
+                    ui.button(tr("skip-tutorial")).clicked()
+                };
+            });
+        if done {
+            self.apply_game_mode(ctx);
+        }
+    }
+
+    fn start_puzzle(&mut self, ctx: &egui::Context, index: usize) {
+        let Some(puzzle) = self.puzzles.get(index) else {
+            return;
+        };
+        self.hotseat = None;
+        self.tutorial = None;
+        self.game_settings = puzzle.settings();
+        self.puzzle = Some(index);
+        if let Err(e) = self.reset_new_game(ctx) {
+            self.report_error(e);
+        }
+    }
+
+    #[cfg(feature = "net")]
+    fn net_lobby_ui(&mut self, ctx: &egui::Context) {
+        let mut open = self.net_lobby_visible;
+        let mut host = false;
+        let mut join = false;
+        let mut leave = false;
+        let mut start = false;
+        egui::Window::new(tr("window-network-race"))
+            .open(&mut open)
+            .resizable(false)
+            .show(ctx, |ui| {
+                let Some(net) = &self.net else {
+                    ui.label(tr("net-race-intro"));
+                    egui::Grid::new("net_lobby_setup")
+                        .num_columns(2)
+                        .spacing([20.0, 5.0])
+                        .show(ui, |ui| {
+                            ui.label(tr("name"));
+                            ui.text_edit_singleline(&mut self.state.net_name);
+                            ui.end_row();
+
+                            ui.label(tr("host-address"));
+                            ui.text_edit_singleline(&mut self.state.net_address);
+                            ui.end_row();
+                        });
+                    ui.horizontal(|ui| {
+                        host = ui
+                            .button(tr("host"))
+                            .on_hover_text(tr_with(
+                                "tip-host-race",
+                                &[("port", &constants::DEFAULT_NET_PORT)],
+                            ))
+                            .clicked();
+                        join = ui.button(tr("join")).clicked();
+                    });
+                    return;
+                };
+
+                if net.is_host {
+                    ui.label(tr_with(
+                        "hosting-on-port",
+                        &[("port", &constants::DEFAULT_NET_PORT)],
+                    ));
+                } else if net.is_connected() {
+                    ui.label(tr_with("joined", &[("address", &self.state.net_address)]));
+                } else if net.last_error().is_none() {
+                    ui.horizontal(|ui| {
+                        ui.spinner();
+                        ui.label(tr_with("joining", &[("address", &self.state.net_address)]));
+                    });
+                }
+                if let Some(e) = net.last_error() {
+                    ui.colored_label(constants::COLOR_WARNING, e);
+                }
+
+                ui.separator();
+                ui.label(RichText::new(tr("players")).strong());
+                ui.label(tr_with("player-you", &[("name", &net.name)]));
+                net.opponents().iter().for_each(|o| {
+                    ui.label(o.status());
+                });
+
+                ui.separator();
+                ui.horizontal(|ui| {
+                    if net.is_host {
+                        start = ui
+                            .add_enabled(!net.is_racing(), egui::Button::new(tr("start-race")))
+                            .on_hover_text(tr_with(
+                                "tip-start-race",
+                                &[("difficulty", &self.state.difficulty.name())],
+                            ))
+                            .clicked();
+                    } else if net.is_connected() {
+                        ui.label(tr("waiting-for-host"));
+                    }
+                    leave = ui.button(tr("leave")).clicked();
+                });
+            });
+        self.net_lobby_visible = open;
+
+        if host {
+            match NetSession::host(ctx, &self.state.net_name, constants::DEFAULT_NET_PORT) {
+                Ok(net) => self.net = Some(net),
+                Err(e) => self.report_error(tr_with("unable-to-host-race", &[("error", &e)])),
+            }
+        } else if join {
+            self.net = Some(NetSession::join(
+                ctx,
+                &self.state.net_name,
+                &self.state.net_address,
+            ));
+        } else if leave {
+            self.finish_net_race(false);
+            self.net = None;
+        } else if start {
+            let difficulty = self.state.difficulty.clone();
+            let settings = GameSettings::settings_for_mode(&difficulty, false, 1);
+            // Everyone starts from the middle of the board
+            let opening = Coordinate::from((settings.width / 2, settings.height / 2));
+            let seed = rand::random();
+            if let Some(net) = &mut self.net {
+                net.start_race(difficulty.clone(), seed, opening.clone());
+            }
+            self.start_net_race(ctx, difficulty, seed, opening);
+        }
+    }
+
+    #[cfg(feature = "net")]
+    fn coop_ui(&mut self, ctx: &egui::Context) {
+        let mut open = self.coop_visible;
+        let mut host = false;
+        let mut join = false;
+        let mut leave = false;
+        egui::Window::new(tr("window-co-op"))
+            .open(&mut open)
+            .resizable(false)
+            .show(ctx, |ui| {
+                let Some(coop) = &self.coop else {
+                    ui.label(tr("coop-intro"));
+                    egui::Grid::new("coop_setup")
+                        .num_columns(2)
+                        .spacing([20.0, 5.0])
+                        .show(ui, |ui| {
+                            ui.label(tr("name"));
+                            ui.text_edit_singleline(&mut self.state.net_name);
+                            ui.end_row();
+
+                            ui.label(tr("host-address"));
+                            ui.text_edit_singleline(&mut self.state.coop_address);
+                            ui.end_row();
+                        });
+                    ui.horizontal(|ui| {
+                        host = ui
+                            .button(tr("host"))
+                            .on_hover_text(tr_with(
+                                "tip-host-coop",
+                                &[("port", &constants::DEFAULT_COOP_PORT)],
+                            ))
+                            .clicked();
+                        join = ui.button(tr("join")).clicked();
+                    });
+                    return;
+                };
+
+                match coop.partner_name() {
+                    Some(partner) => {
+                        ui.label(tr_with("playing-with", &[("name", &partner)]));
+                    }
+                    None if coop.is_host() => {
+                        ui.label(tr_with(
+                            "waiting-for-partner",
+                            &[("port", &constants::DEFAULT_COOP_PORT)],
+                        ));
+                    }
+                    None if coop.last_error().is_none() => {
+                        ui.horizontal(|ui| {
+                            ui.spinner();
+                            ui.label(tr_with("joining", &[("address", &self.state.coop_address)]));
+                        });
+                    }
+                    None => {}
+                }
+                if let Some(e) = coop.last_error() {
+                    ui.colored_label(constants::COLOR_WARNING, e);
+                }
+                leave = ui.button(tr("leave")).clicked();
+            });
+        self.coop_visible = open;
+
+        if host {
+            match CoopSession::host(ctx, &self.state.net_name, constants::DEFAULT_COOP_PORT) {
+                Ok(coop) => {
+                    self.hotseat = None;
+                    self.puzzle = None;
+                    self.tutorial = None;
+                    self.coop = Some(coop);
+                    self.update_difficulty_settings();
+                    if let Err(e) = self.reset_new_game(ctx) {
+                        self.report_error(e);
+                    }
+                }
+                Err(e) => self.report_error(tr_with("unable-to-host-coop", &[("error", &e)])),
+            }
+        } else if join {
+            self.hotseat = None;
+            self.puzzle = None;
+            self.tutorial = None;
+            self.coop = Some(CoopSession::join(
+                ctx,
+                &self.state.net_name,
+                &self.state.coop_address,
+            ));
+        } else if leave {
+            self.coop = None;
+            self.apply_game_mode(ctx);
+        }
+    }
+
+    #[cfg(feature = "net")]
+    fn spectate_ui(&mut self, ctx: &egui::Context) {
+        let mut open = self.spectate_visible;
+        let mut broadcasting = self.broadcaster.is_some();
+        let mut watch = false;
+        let mut stop_watching = false;
+        egui::Window::new(tr("window-spectate"))
+            .open(&mut open)
+            .resizable(false)
+            .show(ctx, |ui| {
+                ui.checkbox(&mut broadcasting, tr("let-others-watch-my-games"))
+                    .on_hover_text(tr_with(
+                        "tip-let-others-watch",
+                        &[("port", &constants::DEFAULT_SPECTATE_PORT)],
+                    ));
+                if let Some(broadcaster) = &self.broadcaster {
+                    ui.label(tr_with(
+                        "num-watching",
+                        &[("count", &broadcaster.num_watching())],
+                    ));
+                }
+
+                ui.separator();
+                let Some(spectator) = &mut self.spectator else {
+                    ui.horizontal(|ui| {
+                        ui.label(tr("player-address"));
+                        ui.text_edit_singleline(&mut self.state.spectate_address);
+                        watch = ui.button(tr("watch")).clicked();
+                    });
+                    return;
+                };
+
+                spectator.poll(now());
+                if let Some(e) = spectator.last_error() {
+                    ui.colored_label(constants::COLOR_WARNING, e);
+                } else if !spectator.is_connected() {
+                    ui.horizontal(|ui| {
+                        ui.spinner();
+                        ui.label(tr_with(
+                            "connecting-to",
+                            &[("address", &self.state.spectate_address)],
+                        ));
+                    });
+                }
+                MinesOfRustApp::spectator_view_ui(ui, &spectator.view, &self.state.numeral_style);
+                stop_watching = ui.button(tr("stop-watching")).clicked();
+            });
+        self.spectate_visible = open;
+
+        if broadcasting != self.broadcaster.is_some() {
+            self.broadcaster = None;
+            if broadcasting {
+                match Broadcaster::start(constants::DEFAULT_SPECTATE_PORT) {
+                    Ok(b) => self.broadcaster = Some(b),
+                    Err(e) => self.report_error(tr_with("unable-to-broadcast", &[("error", &e)])),
+                }
+            }
+        }
+        if watch {
+            self.spectator = Some(Spectator::watch(ctx, &self.state.spectate_address));
+        } else if stop_watching || !open {
+            self.spectator = None;
+        }
+    }
+
+    /// Read-only boards of the game being watched
+    #[cfg(feature = "net")]
+    fn spectator_view_ui(ui: &mut egui::Ui, view: &SpectatorView, numeral_style: &NumeralStyle) {
+        let Some(settings) = &view.settings else {
+            ui.label(tr("waiting-for-game"));
+            return;
+        };
+        let game_state = view.game_state();
+        let status = match game_state {
+            GameState::EndedWin => tr("status-won"),
+            GameState::EndedLoss => tr("status-lost"),
+            GameState::NotStarted => tr("status-not-started"),
+            _ => tr("status-playing"),
+        };
+        ui.label(format!(
+            "{}: {} ({:.2})",
+            view.name,
+            status,
+            view.elapsed_time(now())
+        ));
+        if game_state == GameState::Playing {
+            ui.ctx().request_repaint_after(Duration::from_millis(100));
+        }
+
+        let (columns, _) = settings.board_layout();
+        let style = SquareStyle {
+            numeral_style: numeral_style.clone(),
+            skin: BoardSkin::Classic,
+            mines_visible: false,
+        };
+        egui::Grid::new("spectated_boards")
+            .spacing([constants::BOARD_SPACING, constants::BOARD_SPACING])
+            .show(ui, |ui| {
+                view.boards
+                    .iter()
+                    .enumerate()
+                    .for_each(|(board, gameboard)| {
+                        egui::Grid::new(("spectated_board", board))
+                            .spacing([0.0, 0.0])
+                            .show(ui, |ui| {
+                                // All the layers of a layered board, one above the other
+                                iproduct!(0..gameboard.height, 0..settings.width).for_each(
+                                    |(y, x)| {
+                                        let sqr = gameboard
+                                            .player_view(game_state == GameState::EndedLoss)
+                                            .square_at(&Coordinate::from((x, y)))
+                                            .unwrap_or(SquareView::Covered);
+                                        MinesOfRustApp::square_ui(
+                                            ui,
+                                            sqr,
+                                            &game_state,
+                                            &style,
+                                            false,
+                                            false,
+                                            0,
+                                        );
+                                        if x == settings.width - 1 {
+                                            ui.end_row();
+                                        }
+                                    },
+                                );
+                            });
+                        if board as u32 % columns == columns - 1 {
+                            ui.end_row();
+                        }
+                    });
+            });
+    }
+
+    fn about_ui(&mut self, ctx: &egui::Context) {
+        egui::Window::new(tr("window-about"))
+            .open(&mut self.about_visible)
+            .resizable(false)
+            .show(ctx, |ui| {
+                ui.heading("Mines of Rust");
+                ui.label(tr_with(
+                    "version",
+                    &[
+                        ("version", &env!("CARGO_PKG_VERSION")),
+                        ("hash", &env!("MINESOFRUST_GIT_HASH")),
+                    ],
+                ));
+                ui.label(env!("CARGO_PKG_AUTHORS"));
+                ui.hyperlink(env!("CARGO_PKG_REPOSITORY"));
+
+                ui.separator();
+                egui::Grid::new("about_credits")
+                    .num_columns(3)
+                    .spacing([20.0, 5.0])
+                    .striped(true)
+                    .show(ui, |ui| {
+                        CREDITS.iter().for_each(|(what, license, url)| {
+                            ui.label(*what);
+                            ui.label(*license);
+                            ui.hyperlink_to(tr("website"), *url);
+                            ui.end_row();
+                        });
+                    });
+            });
+    }
+
+    fn bottom_panel_ui(&mut self, ctx: &egui::Context) {
+        let panel = egui::TopBottomPanel::bottom("bottom_panel")
+            .resizable(false)
+            .min_height(BOTTOM_PANEL_MIN_HEIGHT)
+            .show(ctx, |ui| {
+                self.status_ui(ui);
+            });
+        self.bottom_panel_height = panel.response.rect.height();
+    }
+
+    /// Shows or hides the bottom panel, shrinking or growing the window to match
+    fn set_compact_mode(&mut self, ctx: &egui::Context, compact: bool) {
+        if compact == self.state.compact_mode {
+            return;
+        }
+        self.state.compact_mode = compact;
+
+        let inner_size = ctx.input(|i| i.viewport().inner_rect.map(|r| r.size()));
+        if let Some(size) = inner_size {
+            let change = if compact {
+                -self.bottom_panel_height
+            } else {
+                self.bottom_panel_height
+            };
+            ctx.send_viewport_cmd(ViewportCommand::InnerSize(Vec2 {
+                x: size.x,
+                y: size.y + change,
+            }));
+        }
+    }
+
+    fn set_always_on_top(&mut self, ctx: &egui::Context, always_on_top: bool) {
+        self.state.always_on_top = always_on_top;
+        ctx.send_viewport_cmd(ViewportCommand::WindowLevel(if always_on_top {
+            WindowLevel::AlwaysOnTop
+        } else {
+            WindowLevel::Normal
+        }));
+    }
+
+    /// Gets the game off the screen in a hurry, pausing it so the clock doesn't run
+    fn hide_window(&mut self, ctx: &egui::Context) {
+        #[cfg(all(feature = "tray", not(target_arch = "wasm32")))]
+        if self.tray.is_some() && self.state.minimize_to_tray {
+            self.hide_in_tray(ctx);
+            return;
+        }
+        if self.game_state == GameState::Playing {
+            self.pause_game();
+        }
+        ctx.send_viewport_cmd(ViewportCommand::Minimized(true));
+    }
+
+    #[cfg(all(feature = "tray", not(target_arch = "wasm32")))]
+    fn hide_in_tray(&mut self, ctx: &egui::Context) {
+        if !self.hidden_in_tray {
+            self.hidden_in_tray = true;
+            ctx.send_viewport_cmd(ViewportCommand::Visible(false));
+            self.events.emit(GameEvent::Hidden);
+        }
+    }
+
+    #[cfg(all(feature = "tray", not(target_arch = "wasm32")))]
+    fn show_from_tray(&mut self, ctx: &egui::Context) {
+        if self.hidden_in_tray {
+            self.hidden_in_tray = false;
+            ctx.send_viewport_cmd(ViewportCommand::Visible(true));
+            ctx.send_viewport_cmd(ViewportCommand::Minimized(false));
+            ctx.send_viewport_cmd(ViewportCommand::Focus);
+            self.events.emit(GameEvent::Shown);
+        }
+    }
+
+    /// Acts on what was picked from the tray icon's menu. Anything that needs the player to
+    /// see the window, like a new game they may have to confirm, brings it back first.
+    #[cfg(all(feature = "tray", not(target_arch = "wasm32")))]
+    fn handle_tray_actions(&mut self, ctx: &egui::Context) {
+        let Some(tray) = self.tray.clone() else {
+            return;
+        };
+        tray.poll().into_iter().for_each(|action| match action {
+            TrayAction::NewGame => {
+                self.show_from_tray(ctx);
+                self.abandon_game(ctx, AbandonAction::NewGame);
+            }
+            TrayAction::Pause => {
+                // The game can't carry on out of sight
+                if self.game_state == GameState::Paused {
+                    self.show_from_tray(ctx);
+                }
+                self.toggle_pause_state();
+            }
+            TrayAction::ShowHide if self.hidden_in_tray => self.show_from_tray(ctx),
+            TrayAction::ShowHide => self.hide_in_tray(ctx),
+            TrayAction::Quit => {
+                self.show_from_tray(ctx);
+                self.abandon_game(ctx, AbandonAction::Exit);
+            }
+        });
+    }
+
+    /// Keeps the global hotkeys in step with the settings, and acts on any pressed
+    #[cfg(all(feature = "hotkeys", not(target_arch = "wasm32")))]
+    fn handle_global_hotkeys(&mut self, ctx: &egui::Context) {
+        let Some(hotkeys) = self.global_hotkeys.clone() else {
+            return;
+        };
+        let registered = if self.state.global_hotkeys {
+            hotkeys.register(&self.state.new_game_hotkey, &self.state.pause_hotkey)
+        } else {
+            hotkeys.register("", "")
+        };
+        if let Err(e) = registered {
+            log::warn!("Failed to register the global hotkeys: {:#}", e);
+        }
+        hotkeys.poll().into_iter().for_each(|action| match action {
+            HotkeyAction::NewGame => {
+                #[cfg(all(feature = "tray", not(target_arch = "wasm32")))]
+                self.show_from_tray(ctx);
+                self.abandon_game(ctx, AbandonAction::NewGame);
+            }
+            HotkeyAction::Pause => {
+                // The game can't carry on out of sight
+                #[cfg(all(feature = "tray", not(target_arch = "wasm32")))]
+                if self.game_state == GameState::Paused {
+                    self.show_from_tray(ctx);
+                }
+                self.toggle_pause_state();
+            }
+        });
+    }
+
+    /// Acts on what's happened in the game since the last frame, before anything is drawn
+    fn tick(&mut self, ctx: &egui::Context) {
+        self.handle_events(ctx);
+        self.auto_open(ctx);
+        self.check_auto_restart(ctx);
+        #[cfg(not(target_arch = "wasm32"))]
+        self.check_autosave(ctx);
+        #[cfg(not(target_arch = "wasm32"))]
+        self.update_overlay(ctx);
+    }
+
+    /// The game as a streaming overlay shows it
+    #[cfg(not(target_arch = "wasm32"))]
+    fn overlay_stats(&self) -> OverlayStats {
+        let time = self.elapsed_time();
+        // Once this game is a new best, it's compared with the one it beat
+        let best = match &self.personal_best {
+            Some(best) => best.previous,
+            None => self.history.personal_best(&self.leaderboard_category()),
+        };
+        OverlayStats {
+            state: format!("{:?}", self.game_state),
+            time,
+            mines_left: self.num_mines() as i64 - self.num_flags() as i64,
+            bbbv_solved: self.boards.iter().map(|b| b.solved_bbbv()).sum(),
+            bbbv: self.bbbv(),
+            best_delta: best.map(|best| time - best),
+        }
+    }
+
+    /// Writes the overlay stats at most every `overlay_seconds`, while the clock's running
+    /// or after anything happens in the game
+    #[cfg(not(target_arch = "wasm32"))]
+    fn update_overlay(&mut self, ctx: &egui::Context) {
+        if !self.state.overlay {
+            self.overlay_written = None;
+            return;
+        }
+        let changing = self.overlay_stale || self.game_state == GameState::Playing;
+        if !changing {
+            return;
+        }
+        let now = ctx.input(|i| i.time);
+        let wait = self
+            .overlay_written
+            .as_ref()
+            .map_or(0.0, |(at, _)| at + self.state.overlay_seconds - now);
+        // Come back when it's due, even if nothing else wakes the app up
+        if wait > 0.0 {
+            ctx.request_repaint_after(Duration::from_secs_f64(wait));
+            return;
+        }
+        self.overlay_stale = false;
+        let stats = self.overlay_stats();
+        if self
+            .overlay_written
+            .as_ref()
+            .is_some_and(|(_, written)| *written == stats)
+        {
+            return;
+        }
+        if let Err(e) = stats.save() {
+            log::warn!("Failed to write the overlay stats: {:#}", e);
+        }
+        self.overlay_written = Some((now, stats));
+        if self.game_state == GameState::Playing {
+            ctx.request_repaint_after(Duration::from_secs_f64(self.state.overlay_seconds));
+        }
+    }
+
+    /// Ends the game once any of the boards has been lost or all of them won. Called after
+    /// every play, so the game is over as soon as the play that ended it is made.
+    fn check_game_end(&mut self) {
+        // The boards only need checking for a win or loss after they've been played on. All
+        // of them are taken so none is left dirty.
+        let changed = self.game_state == GameState::Playing
+            && self
+                .boards
+                .iter_mut()
+                .map(|b| b.take_dirty())
+                .filter(|&dirty| dirty)
+                .count()
+                > 0;
+
+        let mode = self.game_mode();
+        if changed && self.boards.iter().any(|b| mode.is_lost(b)) {
+            self.game_state = GameState::EndedLoss;
+            let time = self.stop_clock();
+            self.events.emit(GameEvent::Lost { time });
+        } else if changed && self.boards.iter().all(|b| mode.is_won(b)) {
+            // You win!
+            self.game_state = GameState::EndedWin;
+            self.boards.iter_mut().for_each(|b| b.flag_all_mines());
+            let time = self.stop_clock();
+            self.events.emit(GameEvent::Won { time });
+        }
+    }
+
+    /// Plays by `mode` instead of the mode the settings are for, from the next game on.
+    /// `None` goes back to the settings.
+    pub fn set_game_mode(&mut self, mode: Option<Rc<dyn GameMode>>) {
+        self.custom_mode = mode;
+    }
+
+    /// The rules the game is played by. A puzzle or the tutorial is played by its own rules,
+    /// whatever the mode.
+    fn game_mode(&self) -> Rc<dyn GameMode> {
+        if let Some(puzzle) = self.current_puzzle() {
+            return Rc::new(PuzzleMode {
+                name: puzzle.name.clone(),
+            });
+        }
+        if self.tutorial.is_some() {
+            return Rc::new(TutorialMode);
+        }
+        self.custom_mode
+            .clone()
+            .unwrap_or_else(|| mode_for(&self.game_settings))
+    }
+
+    /// True if the boards can be made again from their seeds and first clicks, which replays,
+    /// spectators and recovered games all rely on. A mode of your own may place mines in a
+    /// way that can't be.
+    fn is_rebuildable(&self) -> bool {
+        self.custom_mode.is_none()
+    }
+
+    /// Subscribes `observer` to everything that happens in the game from now on
+    pub fn subscribe(&mut self, observer: Rc<RefCell<dyn GameObserver>>) {
+        self.events.subscribe(observer);
+    }
+
+    /// Passes what's happened since the last frame to the observers, then to the parts of
+    /// the app that keep track of the game
+    fn handle_events(&mut self, ctx: &egui::Context) {
+        #[cfg(all(feature = "scripting", not(target_arch = "wasm32")))]
+        let mut played = false;
+        self.events.dispatch().into_iter().for_each(|event| {
+            #[cfg(not(target_arch = "wasm32"))]
+            {
+                self.overlay_stale = true;
+            }
+            #[cfg(all(feature = "scripting", not(target_arch = "wasm32")))]
+            {
+                played |= matches!(
+                    event,
+                    GameEvent::Revealed { .. } | GameEvent::Flagged { .. }
+                );
+            }
+            #[cfg(feature = "livesplit")]
+            self.update_split_timer(&event);
+            match event {
+                GameEvent::Lost { time } => self.on_game_lost(time),
+                GameEvent::Won { time } => self.on_game_won(ctx, time),
+                // Nobody can see the board, so the clock stops whatever auto-pause is set
+                // to
+                GameEvent::Hidden if self.game_state == GameState::Playing => self.pause_game(),
+                GameEvent::Revealed { .. } if self.state.auto_open => {
+                    self.auto_open_due = Some(ctx.input(|i| i.time) + self.state.auto_open_seconds);
+                }
+                _ => {}
+            }
+        });
+        #[cfg(all(feature = "scripting", not(target_arch = "wasm32")))]
+        if played {
+            self.run_script_hook(ctx);
+        }
+    }
+
+    /// Keeps the LiveSplit timer in step with the game, splitting as the 3BV cleared passes
+    /// each milestone
+    #[cfg(feature = "livesplit")]
+    fn update_split_timer(&mut self, event: &GameEvent) {
+        if !self.state.livesplit_enabled {
+            return;
+        }
+        match event {
+            GameEvent::Started => self.split_timer.start(
+                &self.state.livesplit_address,
+                &self.state.livesplit_milestones,
+            ),
+            GameEvent::Revealed { .. } | GameEvent::Won { .. } => {
+                let solved = self.boards.iter().map(|b| b.solved_bbbv()).sum::<u32>();
+                if self.bbbv() > 0 {
+                    self.split_timer
+                        .progress(solved as f64 / self.bbbv() as f64 * 100.0);
+                }
+            }
+            GameEvent::Lost { .. } => self.split_timer.reset(),
+            GameEvent::Paused => self.split_timer.pause(),
+            GameEvent::Resumed => self.split_timer.resume(),
+            _ => {}
+        }
+    }
+
+    #[cfg_attr(not(feature = "net"), allow(unused_variables))]
+    fn on_game_lost(&mut self, time: f64) {
+        let cause = self
+            .post_mortem
+            .as_ref()
+            .map_or(LossCause::Guess, |p| p.cause);
+        if self.game_settings.num_boards == 1 {
+            self.session
+                .for_game_mut(&self.state.difficulty, self.is_practice())
+                .record_loss(cause);
+        }
+        self.post_mortem_visible = self.post_mortem.is_some();
+        let streak = self.history.win_streak(&self.state.difficulty);
+        self.record_game(false);
+        // Only games that counted towards it can end a streak
+        if streak >= constants::MIN_NOTIFIED_WIN_STREAK
+            && self.game_settings.num_boards == 1
+            && !self.is_practice()
+        {
+            self.notify(
+                tr("streak-ended-title"),
+                &tr_with(
+                    "streak-ended",
+                    &[
+                        ("count", &streak),
+                        ("difficulty", &self.state.difficulty.name()),
+                    ],
+                ),
+            );
+        }
+        self.record_hotseat_turn(false);
+        self.puzzles_visible |= self.puzzle.is_some();
+        #[cfg(feature = "net")]
+        self.finish_net_race(false);
+        #[cfg(feature = "net")]
+        self.broadcast(SpectateEvent::End { won: false, time });
+    }
+
+    #[cfg_attr(not(feature = "online"), allow(unused_variables))]
+    fn on_game_won(&mut self, ctx: &egui::Context, time: f64) {
+        let single_board = self.game_settings.num_boards == 1;
+        if single_board {
+            self.session
+                .for_game_mut(&self.state.difficulty, self.is_practice())
+                .record_win();
+        }
+        let category = self.leaderboard_category();
+        // Taken before this win goes into the history
+        let previous_best = self.history.personal_best(&category);
+        self.record_game(true);
+        self.record_hotseat_turn(true);
+        self.complete_puzzle();
+        #[cfg(feature = "net")]
+        self.finish_net_race(true);
+        #[cfg(feature = "net")]
+        self.broadcast(SpectateEvent::End { won: true, time });
+        if self.is_practice() {
+            return;
+        }
+        let time = self.game_mode().score(time);
+        if !previous_best.is_some_and(|best| time >= best) {
+            self.personal_best = Some(PersonalBest {
+                time,
+                previous: previous_best,
+            });
+        }
+        // Multi-board entries keep the first board's seed
+        let mut entry = Entry {
+            flagged: self.plays.flagged() > 0,
+            penalty: self.penalties.map_or(0.0, |p| p.seconds()),
+            // Enough to make the board again, which the seed alone isn't
+            first_click: (self.boards.len() == 1 && self.is_rebuildable())
+                .then(|| self.plays.first_reveal().cloned())
+                .flatten(),
+            replay: (self.boards.len() == 1 && self.is_rebuildable()).then(|| self.plays.clone()),
+            ..Entry::new(
+                &self.state.player_name,
+                time,
+                self.boards[0].seed,
+                self.bbbv(),
+                self.plays.clicks(),
+            )
+        };
+        entry.sign();
+        let max_entries = self.state.max_leaderboard_entries();
+        self.leaderboards.add(&category, entry.clone(), max_entries);
+        // A win without a single flag placed is a no flags win too, wherever flags were
+        // allowed
+        if !entry.flagged && !category.no_flags {
+            let no_flags = LeaderboardCategory {
+                no_flags: true,
+                ..category.clone()
+            };
+            self.leaderboards.add(&no_flags, entry.clone(), max_entries);
+        }
+        if let Some(previous) = previous_best.filter(|&best| time < best) {
+            self.notify(
+                tr("personal-best-title"),
+                &tr_with(
+                    "personal-best",
+                    &[
+                        ("category", &category.title()),
+                        ("time", &format!("{:.2}", time)),
+                        ("previous", &format!("{:.2}", previous)),
+                    ],
+                ),
+            );
+        }
+        // The global leaderboard only has standard single board categories
+        #[cfg(feature = "online")]
+        if self.state.online_enabled && !self.game_settings.no_flags && single_board {
+            let submission = Submission::from_entry(self.state.difficulty.clone(), &entry);
+            self.online
+                .submit(ctx, &self.state.online_endpoint, submission);
+        }
+        // Don't risk losing a new leaderboard entry if the app doesn't exit cleanly
+        self.save_to_userhome();
+    }
+
+    /// Tells the player about a milestone, if they want to know
+    fn notify(&self, title: &str, body: &str) {
+        if self.state.notifications {
+            notify::notify(title, body);
+        }
+    }
+
+    /// The leaderboard a win in the current game goes on
+    fn leaderboard_category(&self) -> LeaderboardCategory {
+        LeaderboardCategory::new(
+            self.state.difficulty.clone(),
+            self.game_settings.no_flags,
+            self.game_settings.num_boards,
+        )
+    }
+
+    fn num_mines(&self) -> u32 {
+        self.game_settings.num_mines * self.game_settings.num_boards
+    }
+
+    fn num_flags(&self) -> u32 {
+        self.boards.iter().map(|b| b.num_flags()).sum()
+    }
+
+    fn num_revealed(&self) -> u32 {
+        self.boards.iter().map(|b| b.num_revealed()).sum()
+    }
+
+    fn num_safe_revealed(&self) -> u32 {
+        self.boards.iter().map(|b| b.num_safe_revealed()).sum()
+    }
+
+    fn bbbv(&self) -> u32 {
+        self.boards.iter().map(|b| b.bbbv()).sum()
+    }
+
+    /// True once mines have been placed on any of the boards
+    fn is_populated(&self) -> bool {
+        self.boards.iter().any(|b| b.is_populated)
+    }
+
+    /// True if any of the boards has been restarted
+    fn is_replayed(&self) -> bool {
+        self.boards.iter().any(|b| b.is_replayed())
+    }
+
+    /// Games that stay off the leaderboard and out of the statistics
+    fn is_practice(&self) -> bool {
+        self.was_seen_before() || self.is_nonstandard_board() || self.had_assistance()
+    }
+
+    /// True if the player could have known the board before playing it: restarted, played
+    /// again from the leaderboard, watched in a hot-seat race, or recovered after a crash
+    fn was_seen_before(&self) -> bool {
+        self.is_replayed() || self.leaderboard_replay || self.hotseat.is_some() || self.recovered
+    }
+
+    /// True if the game isn't the standard one: a mode that isn't ranked, like a shaped or
+    /// layered board, a puzzle or the tutorial, or two players sharing a board
+    fn is_nonstandard_board(&self) -> bool {
+        !self.game_mode().is_ranked() || self.is_coop()
+    }
+
+    /// True if the player had help: the mines shown, the board changed in the debugger,
+    /// plays made by a script or auto-open, a chord held back without costing time, or the
+    /// guess indicator saying whether a safe square can be deduced
+    fn had_assistance(&self) -> bool {
+        self.mines_seen
+            || self.guess_indicator_used
+            || self.board_debugged
+            || self.script_played
+            || self.plays.auto_opens() > 0
+            || (self.chord_warned && self.penalties.is_none())
+    }
+
+    /// True if no square on any board can be deduced to be safe. A board that hasn't been
+    /// played on yet has its first click kept clear, so that's never a guess.
+    fn needs_guess(&mut self) -> bool {
+        self.guess_trackers
+            .resize_with(self.boards.len(), GuessTracker::default);
+        self.boards
+            .iter()
+            .zip(self.guess_trackers.iter_mut())
+            .all(|(board, tracker)| {
+                tracker.update(board);
+                board.is_populated && tracker.needs_guess()
+            })
+    }
+
+    fn num_safe_squares(&self) -> u32 {
+        self.boards.iter().map(|b| b.num_squares()).sum::<u32>() - self.num_mines()
+    }
+
+    fn num_safe_remaining(&self) -> u32 {
+        self.num_safe_squares()
+            .saturating_sub(self.num_safe_revealed())
+    }
+
+    /// Fraction of the safe squares revealed
+    fn fraction_cleared(&self) -> f32 {
+        self.num_safe_revealed() as f32 / self.num_safe_squares() as f32
+    }
+
+    /// A thin bar under the board showing how much of it has been cleared
+    fn progress_bar_ui(&self, ui: &mut egui::Ui) {
+        ui.add(
+            egui::ProgressBar::new(self.fraction_cleared())
+                .desired_width(self.boards_size(ui.spacing().interact_size.x).x)
+                .desired_height(constants::PROGRESS_BAR_HEIGHT),
+        );
+    }
+
+    /// Saves the game in progress every `autosave_seconds` it has new plays, so it can be
+    /// recovered after a crash, and removes the save once the game is over. Only games
+    /// played alone are saved, since nobody else would be there to pick the rest up with,
+    /// and only on boards that can be made again from their plays.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn check_autosave(&mut self, ctx: &egui::Context) {
+        let in_progress = matches!(self.game_state, GameState::Playing | GameState::Paused);
+        let alone = self.hotseat.is_none()
+            && self.puzzle.is_none()
+            && self.tutorial.is_none()
+            && !self.is_coop()
+            && !self.is_net_racing()
+            && self.is_rebuildable();
+        // What a crash would save is kept up to date with every play, autosave or not
+        let num_plays = self.plays.len();
+        let crash_recorded = (in_progress && alone).then_some(num_plays);
+        if crash_recorded != self.crash_recorded {
+            self.crash_recorded = crash_recorded;
+            crash::record(&self.state, crash_recorded.map(|_| self.saved_game()));
+        }
+
+        if !in_progress || !alone || !self.state.autosave {
+            self.remove_autosave();
+            return;
+        }
+        let now = ctx.input(|i| i.time);
+        let due = match self.autosaved {
+            Some((at, plays)) => plays != num_plays && now - at >= self.state.autosave_seconds,
+            None => true,
+        };
+        if !due {
+            return;
+        }
+        self.autosaved = Some((now, num_plays));
+        let saved = self.saved_game();
+        self.autosaver.save(saved);
+    }
+
+    /// The game in progress, as it's saved
+    #[cfg(not(target_arch = "wasm32"))]
+    fn saved_game(&self) -> SavedGame {
+        SavedGame::new(
+            &self.state.difficulty,
+            &self.game_settings,
+            &self.boards,
+            &self.plays,
+            self.elapsed_time(),
+            self.mines_seen,
+        )
+    }
+
+    /// Shows the report of the last time the app crashed, which can be copied or sent on
+    /// as an issue
+    #[cfg(not(target_arch = "wasm32"))]
+    fn crash_report_ui(&mut self, ctx: &egui::Context) {
+        let Some(report) = &self.crash_report else {
+            return;
+        };
+        let mut dismissed = false;
+        egui::Window::new(tr("window-crash-report"))
+            .collapsible(false)
+            .default_width(500.0)
+            .show(ctx, |ui| {
+                ui.label(tr("crash-report-text"));
+                egui::CollapsingHeader::new(tr("crash-report-details")).show(ui, |ui| {
+                    egui::ScrollArea::both().max_height(300.0).show(ui, |ui| {
+                        ui.label(RichText::new(report.as_str()).monospace());
+                    });
+                });
+                ui.horizontal(|ui| {
+                    if ui.button(tr("copy")).clicked() {
+                        ui.output_mut(|o| o.copied_text = report.clone());
+                    }
+                    if ui.button(tr("report-issue")).clicked() {
+                        ui.output_mut(|o| o.copied_text = report.clone());
+                        ui.ctx()
+                            .open_url(egui::OpenUrl::new_tab(crash::issue_url(report)));
+                    }
+                    dismissed = ui.button(tr("dismiss")).clicked();
+                });
+            });
+        if dismissed {
+            self.crash_report = None;
+            if let Err(e) = crash::remove_crash_report() {
+                log::warn!("Failed to remove the crash report: {:#}", e);
+            }
+        }
+    }
+
+    /// Removes the saved game in progress, if this game has been saved
+    #[cfg(not(target_arch = "wasm32"))]
+    fn remove_autosave(&mut self) {
+        if self.autosaved.take().is_some() {
+            self.autosaver.remove();
+        }
+    }
+
+    /// Offers to pick up the game the app closed without finishing last time
+    #[cfg(not(target_arch = "wasm32"))]
+    fn recovered_game_ui(&mut self, ctx: &egui::Context) {
+        let Some(saved) = &self.recovered_game else {
+            return;
+        };
+        let mut resume = false;
+        let mut discard = false;
+        egui::Window::new(tr("window-recovered-game"))
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_CENTER, [0.0, 0.0])
+            .show(ctx, |ui| {
+                ui.label(tr_with(
+                    "recovered-game-text",
+                    &[
+                        ("difficulty", &saved.difficulty.name()),
+                        ("time", &format!("{:.2}", saved.elapsed)),
+                    ],
+                ));
+                ui.label(tr("recovered-game-practice"));
+                ui.horizontal(|ui| {
+                    resume = ui.button(tr("resume")).clicked();
+                    discard = ui.button(tr("discard")).clicked();
+                });
+            });
+        if resume {
+            if let Some(saved) = self.recovered_game.take() {
+                if let Err(e) = self.resume_saved_game(ctx, &saved) {
+                    self.report_error(e);
+                }
+            }
+        } else if discard {
+            self.recovered_game = None;
+            self.autosaver.remove();
+        }
+    }
+
+    /// Brings back a saved game, with the clock going on from where it was saved. It's
+    /// kept off the leaderboard, since the time from the save to the crash is lost.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn resume_saved_game(&mut self, ctx: &egui::Context, saved: &SavedGame) -> Result<(), Error> {
+        let boards = saved.boards()?;
+        self.state.difficulty = saved.difficulty.clone();
+        self.game_settings = saved.settings.clone();
+        self.reset_new_game(ctx)?;
+        self.boards = boards;
+        self.plays = saved.plays.clone();
+        self.snapshot_boards();
+        self.mines_seen |= saved.mines_seen;
+        self.recovered = true;
+        self.game_state = GameState::Playing;
+        self.clock.start_from(saved.elapsed);
+        Ok(())
+    }
+
+    /// Starts a new game once the loss has been on screen for the configured delay
+    /// Opens the squares the numerals around them prove safe on their own, once the delay
+    /// after the last reveal is up. The squares it opens are reveals too, so it goes on
+    /// until there's nothing left that doesn't take some thought.
+    fn auto_open(&mut self, ctx: &egui::Context) {
+        let Some(due) = self.auto_open_due else {
+            return;
+        };
+        // A co-op guest sees the host's auto opens
+        #[cfg(feature = "net")]
+        if self.coop.as_ref().is_some_and(|c| !c.is_host()) {
+            self.auto_open_due = None;
+            return;
+        }
+        if !self.state.auto_open || self.game_state.game_ended() {
+            self.auto_open_due = None;
+            return;
+        }
+        // Held over a pause until the game goes on
+        if self.game_state != GameState::Playing {
+            return;
+        }
+        let remaining = due - ctx.input(|i| i.time);
+        if remaining > 0.0 {
+            ctx.request_repaint_after(Duration::from_secs_f64(remaining));
+            return;
+        }
+        self.auto_open_due = None;
+        let safe = self
+            .boards
+            .iter()
+            .enumerate()
+            .filter(|(_, b)| b.is_populated)
+            .flat_map(|(i, b)| {
+                solver::trivially_safe(
+                    b.width,
+                    b.height,
+                    b.layers(),
+                    &b.revealed_numerals(),
+                    &b.voids(),
+                )
+                .into_iter()
+                .map(move |c| (i, c))
+            })
+            .collect::<Vec<_>>();
+        safe.into_iter().for_each(|(board, c)| {
+            if self.game_state == GameState::Playing {
+                let coord = self.grid_coordinate(board, c.x, c.y);
+                self.apply_play(coord, RevealType::Reveal, PlayKind::AutoOpen);
+            }
+        });
+    }
+
+    fn check_auto_restart(&mut self, ctx: &egui::Context) {
+        // A hot-seat race waits for the players to hand over the board
+        if !self.state.auto_restart
+            || self.game_state != GameState::EndedLoss
+            || self.hotseat.is_some()
+        {
+            return;
+        }
+        // A co-op game restarts when the host's does
+        #[cfg(feature = "net")]
+        if self.coop.as_ref().is_some_and(|c| !c.is_host()) {
+            return;
+        }
+        let remaining =
+            self.state.auto_restart_seconds - self.clock.since_stopped().unwrap_or_default();
+        if remaining > 0.0 {
+            ctx.request_repaint_after(Duration::from_secs_f64(remaining));
+        } else if let Err(e) = self.reset_new_game(ctx) {
+            self.report_error(e);
+        }
+    }
+
+    fn status_ui(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal_centered(|ui| {
+            if !self.game_settings.no_flags
+                && ui
+                    .button(self.state.tap_mode.as_str())
+                    .on_hover_text(tr("tip-tap-mode"))
+                    .clicked()
+            {
+                self.state.tap_mode = self.state.tap_mode.toggled();
+            }
+
+            if self.state.show_progress {
+                ui.label(tr_with(
+                    "safe-squares-left",
+                    &[("count", &self.num_safe_remaining())],
+                ));
+            }
+
+            if self.state.show_guess_indicator && self.game_state == GameState::Playing {
+                self.guess_indicator_used = true;
+                let (color, tip) = if self.needs_guess() {
+                    (constants::COLOR_WARNING, tr("tip-guess-needed"))
+                } else {
+                    (constants::COLOR_NO_GUESS_NEEDED, tr("tip-no-guess-needed"))
+                };
+                ui.label(RichText::new("●").color(color)).on_hover_text(tip);
+            }
+
+            if self.game_state == GameState::EndedWin && self.plays.is_flawless() {
+                ui.label(RichText::new(tr("flawless")).color(constants::COLOR_NO_GUESS_NEEDED))
+                    .on_hover_text(tr("tip-flawless"));
+            }
+
+            if self.game_settings.no_flags {
+                ui.label(tr("no-flags"));
+            }
+
+            if self.game_settings.num_boards > 1 {
+                ui.label(tr_with(
+                    "num-boards",
+                    &[("count", &self.game_settings.num_boards)],
+                ));
+            }
+
+            if let Some(hotseat) = &self.hotseat {
+                ui.label(tr_with(
+                    "players-turn",
+                    &[("name", &hotseat.current_name())],
+                ));
+            }
+
+            #[cfg(feature = "net")]
+            if let Some(partner) = self.coop.as_ref().and_then(|c| c.partner_name()) {
+                ui.label(tr_with("coop-with", &[("name", &partner)]));
+            }
+
+            #[cfg(feature = "net")]
+            if let Some(net) = &self.net {
+                net.opponents().iter().for_each(|o| {
+                    ui.label(o.status());
+                });
+            }
+
+            if let Some(puzzle) = self.current_puzzle() {
+                ui.label(&puzzle.name)
+                    .on_hover_text(puzzle.objective.description());
+            } else if self.is_replayed() {
+                ui.label(tr("practice")).on_hover_text(tr("tip-practice"));
+            }
+
+            if self.state.progression {
+                let next = points_for_level(self.progress.level() + 1);
+                ui.add(
+                    egui::ProgressBar::new(self.progress.level_fraction())
+                        .desired_width(constants::LEVEL_BAR_WIDTH)
+                        .text(tr_with("level", &[("level", &self.progress.level())])),
+                )
+                .on_hover_text(tr_with(
+                    "tip-level",
+                    &[("points", &self.progress.points), ("next", &next)],
+                ));
+            }
+
+            if self.game_state == GameState::Playing && ui.button(tr("pause")).clicked() {
+                self.pause_game();
+            } else if self.game_state == GameState::Paused && ui.button(tr("resume")).clicked() {
+                self.resume_game();
+            }
+        });
+    }
+
+    /// Marks the puzzle being played as completed if the game just won met its objective, and
+    /// shows the player how they did
+    fn complete_puzzle(&mut self) {
+        let Some(puzzle) = self.current_puzzle() else {
+            return;
+        };
+        let met = puzzle.objective.is_met(&self.plays);
+        let id = puzzle.id.clone();
+        if met && self.puzzle_progress.complete(&id) {
+            self.save_to_userhome();
+        }
+        self.puzzles_visible = true;
+    }
+
+    /// Hands the result of the game that just ended to the hot-seat race, if there is one
+    fn record_hotseat_turn(&mut self, won: bool) {
+        let result = TurnResult {
+            won,
+            time: self.clock.elapsed(),
+            cleared: self.num_safe_revealed(),
+        };
+        if let Some(hotseat) = &mut self.hotseat {
+            hotseat.record(result);
+        }
+    }
+
+    /// Starts any race begun by the host and lets the other players know how far through
+    /// the current one this player is
+    #[cfg(feature = "net")]
+    fn update_net_race(&mut self, ctx: &egui::Context) {
+        let Some(net) = &mut self.net else {
+            return;
+        };
+        if let Some((difficulty, seed, opening)) = net.poll() {
+            self.start_net_race(ctx, difficulty, seed, opening);
+        }
+        let percent = (self.fraction_cleared() * 100.0).floor();
+        if let Some(net) = &mut self.net {
+            if self.game_state == GameState::Playing {
+                net.send_progress(percent, None);
+            }
+        }
+    }
+
+    /// Network races are played on a single standard board made from the host's seed
+    #[cfg(feature = "net")]
+    fn start_net_race(
+        &mut self,
+        ctx: &egui::Context,
+        difficulty: GameDifficulty,
+        seed: u64,
+        opening: Coordinate,
+    ) {
+        self.hotseat = None;
+        self.puzzle = None;
+        self.tutorial = None;
+        self.mines_visible = false;
+        self.state.difficulty = difficulty;
+        self.game_settings = GameSettings::settings_for_mode(&self.state.difficulty, false, 1);
+        if let Err(e) = self.reset_new_game(ctx) {
+            self.report_error(e);
+            return;
+        }
+        self.open_seeded_board(seed, opening);
+        if let Some(net) = &mut self.net {
+            net.begin_race();
+        }
+    }
+
+    /// Lets the other players know this player is done with the race, whether by finishing
+    /// the board or by leaving it for another game
+    #[cfg(feature = "net")]
+    fn finish_net_race(&mut self, won: bool) {
+        let percent = (self.fraction_cleared() * 100.0).floor();
+        let time = self.elapsed_time();
+        if let Some(net) = &mut self.net {
+            net.send_progress(percent, Some(Finish { won, time }));
+        }
+    }
+
+    /// Makes a co-op play for `player`, keeping track of who placed each flag. The host
+    /// passes it on to the guest.
+    #[cfg(feature = "net")]
+    fn coop_play(&mut self, player: usize, coord: Coordinate, play_type: RevealType) {
+        if let Some(coop) = self.coop.as_ref().filter(|c| c.is_host()) {
+            coop.send(&CoopMessage::Move {
+                player,
+                coord: coord.clone(),
+                play_type: play_type.clone(),
+            });
+        }
+        let result = self.apply_play(coord.clone(), play_type, PlayKind::Click);
+        if let (Some(coop), Some(flagged)) = (&mut self.coop, result.and_then(|r| r.flagged())) {
+            coop.flags.record(player, coord, flagged);
+        }
+    }
+
+    /// A co-op guest's new games and restarts are made by the host, so the guest asks for
+    /// them instead. Returns true if the host was asked.
+    #[cfg(feature = "net")]
+    fn ask_coop_host(&self, message: CoopMessage) -> bool {
+        match &self.coop {
+            Some(coop) if !coop.is_host() => {
+                coop.send(&message);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Acts on what the co-op partner has sent since the last frame
+    #[cfg(feature = "net")]
+    fn update_coop(&mut self, ctx: &egui::Context) {
+        let Some(coop) = &mut self.coop else {
+            return;
+        };
+        let is_host = coop.is_host();
+        coop.poll().into_iter().for_each(|message| {
+            let result = match message {
+                // A partner has joined the host, who starts the first game together
+                CoopMessage::Join { .. } => self.reset_new_game(ctx),
+                CoopMessage::NewGame { difficulty } if is_host => {
+                    self.state.difficulty = difficulty;
+                    self.update_difficulty_settings();
+                    self.reset_new_game(ctx)
+                }
+                CoopMessage::Restart if is_host => self.reset_existing_game(ctx),
+                CoopMessage::Request { coord, play_type } if is_host => {
+                    self.coop_play(GUEST, coord, play_type);
+                    Ok(())
+                }
+                CoopMessage::Start { difficulty, seed } if !is_host => {
+                    self.state.difficulty = difficulty;
+                    self.update_difficulty_settings();
+                    self.as_coop_guest(|app| {
+                        app.reset_new_game(ctx)?;
+                        app.boards = vec![GameBoard::new_seeded(
+                            app.game_settings.width,
+                            app.game_settings.height,
+                            seed,
+                        )];
+                        Ok(())
+                    })
+                }
+                CoopMessage::Restart => self.as_coop_guest(|app| app.reset_existing_game(ctx)),
+                CoopMessage::Move {
+                    player,
+                    coord,
+                    play_type,
+                } if !is_host => {
+                    self.coop_play(player, coord, play_type);
+                    Ok(())
+                }
+                _ => Ok(()),
+            };
+            if let Err(e) = result {
+                self.report_error(e);
+            }
+        });
+    }
+
+    /// Runs `f` with the co-op session set aside, so the guest can make the new games and
+    /// restarts the host has sent without asking the host for them again
+    #[cfg(feature = "net")]
+    fn as_coop_guest(
+        &mut self,
+        f: impl FnOnce(&mut Self) -> Result<(), Error>,
+    ) -> Result<(), Error> {
+        let mut coop = self.coop.take();
+        let result = f(self);
+        if let Some(coop) = &mut coop {
+            coop.flags.clear();
+        }
+        self.coop = coop;
+        result
+    }
+
+    /// Sends a change to the game to anyone watching it. Spectators make the boards from
+    /// their seeds, so games they can't make the same aren't sent.
+    #[cfg(feature = "net")]
+    fn broadcast(&self, event: SpectateEvent) {
+        if !self.is_rebuildable() {
+            return;
+        }
+        if let Some(broadcaster) = &self.broadcaster {
+            broadcaster.send(&event);
+        }
+    }
+
+    /// Counts a mistake against a game played with time penalties. The cost goes on the
+    /// clock when the game ends.
+    fn add_penalty(&mut self, count: impl FnOnce(&mut Penalties)) {
+        if let Some(penalties) = &mut self.penalties {
+            count(penalties);
+        }
+    }
+
+    /// Stops the clock at the end of the game and adds the penalties to it, returning the
+    /// final time
+    fn stop_clock(&mut self) -> f64 {
+        self.clock.stop();
+        if let Some(penalties) = &self.penalties {
+            self.clock.add_penalty(penalties.seconds());
+        }
+        self.clock.elapsed()
+    }
+
+    /// Adds the game that just ended to the history
+    fn record_game(&mut self, won: bool) {
+        let record = GameRecord {
+            practice: self.is_practice(),
+            num_boards: self.game_settings.num_boards,
+            no_flags: self.game_settings.no_flags,
+            placement: self.game_settings.placement,
+            shape: self.game_settings.shape,
+            layers: self.game_settings.layers,
+            penalties: self.penalties,
+            ..GameRecord::new(
+                self.state.difficulty.clone(),
+                won,
+                self.clock.elapsed(),
+                self.boards[0].seed,
+                self.bbbv(),
+                self.plays.clicks(),
+            )
+        };
+        let level = self.progress.level();
+        self.progress.points += game_points(&record);
+        self.history.add(record);
+        if self.state.progression && self.progress.level() > level {
+            self.notify(
+                tr("level-up-title"),
+                &tr_with("level-up", &[("level", &self.progress.level())]),
+            );
+        }
+    }
+
+    /// The skin picked in the settings, if the player has unlocked it. Everything is unlocked
+    /// for players who don't want to level up.
+    fn board_skin(settings: &AppState, progress: &Progress) -> BoardSkin {
+        let skin = settings.board_skin;
+        if !settings.progression || progress.level() >= skin.unlock_level() {
+            skin
+        } else {
+            BoardSkin::Classic
+        }
+    }
+
+    /// Tracks window focus from the viewport events and, if enabled, pauses the game when
+    /// the window is minimized or loses focus so alt-tabbing doesn't inflate the time.
+    fn check_window_focus(&mut self, ctx: &egui::Context) {
+        let (focus_event, minimized) = ctx.input(|i| {
+            (
+                i.events.iter().rev().find_map(|e| match e {
+                    egui::Event::WindowFocused(f) => Some(*f),
+                    _ => None,
+                }),
+                i.viewport().minimized.unwrap_or(false),
+            )
+        });
+
+        if let Some(focused) = focus_event {
+            self.window_focused = focused;
+        }
+        if minimized {
+            self.window_focused = false;
+        }
+
+        #[cfg(all(feature = "tray", not(target_arch = "wasm32")))]
+        if minimized
+            && self.state.minimize_to_tray
+            && self.tray.is_some()
+            && self.game_state == GameState::Playing
+        {
+            self.hide_in_tray(ctx);
+        }
+
+        if !self.window_focused && self.state.auto_pause && self.game_state == GameState::Playing {
+            log::info!("Window lost focus or was minimized, pausing game");
+            self.pause_game();
+        }
+    }
+
+    /// Moves the window back to where it was when the app last closed
+    #[cfg(not(target_arch = "wasm32"))]
+    fn restore_window_geometry(&mut self, ctx: &egui::Context) {
+        let Some(geometry) = &self.state.window else {
+            return;
+        };
+        let monitor_size = ctx.input(|i| i.viewport().monitor_size);
+        if matches!(monitor_size, Some(size) if !geometry.is_on_monitor(size)) {
+            log::info!(
+                "Monitor has changed since the app last closed, not restoring the window position"
+            );
+        } else {
+            ctx.send_viewport_cmd(ViewportCommand::OuterPosition(Pos2 {
+                x: geometry.x,
+                y: geometry.y,
+            }));
+        }
+        ctx.send_viewport_cmd(ViewportCommand::InnerSize(Vec2 {
+            x: geometry.width,
+            y: geometry.height,
+        }));
+    }
+
+    /// Keeps the window geometry up to date so it's saved along with the other settings
+    #[cfg(not(target_arch = "wasm32"))]
+    fn track_window_geometry(&mut self, ctx: &egui::Context) {
+        let geometry = ctx.input(|i| WindowGeometry::from_viewport(i.viewport()));
+        if geometry.is_some() {
+            self.state.window = geometry;
+        }
+    }
+
+    fn toggle_pause_state(&mut self) {
+        if self.game_state == GameState::Playing {
+            self.pause_game();
+        } else if self.game_state == GameState::Paused {
+            self.resume_game();
+        }
+    }
+
+    fn pause_game(&mut self) {
+        self.game_state = GameState::Paused;
+        self.clock.pause();
+        self.events.emit(GameEvent::Paused);
+    }
+
+    fn resume_game(&mut self) {
+        self.game_state = GameState::Playing;
+        self.clock.resume();
+        self.events.emit(GameEvent::Resumed);
+    }
+
+    /// Analyzes a board as it was just before the play that lost the game
+    fn analyze_loss(
+        &self,
+        board: usize,
+        play_result: &PlayResult,
+        detonated_on: &Coordinate,
+    ) -> PostMortem {
+        let gameboard = &self.boards[board];
+        let mut visible = gameboard.revealed_numerals();
+        play_result.coordinates().iter().for_each(|c| {
+            visible[(c.y * gameboard.width + c.x) as usize] = None;
+        });
+        PostMortem::new(
+            gameboard.width,
+            gameboard.height,
+            gameboard.layers(),
+            &visible,
+            &gameboard.voids(),
+            detonated_on,
+        )
+    }
+
+    /// Covers the boards, drawing `add_contents` over them
+    fn board_cover_ui(
+        &mut self,
+        ui: &mut egui::Ui,
+        add_contents: impl FnOnce(&mut Self, &mut egui::Ui),
+    ) {
+        let desired_size = self.boards_size(ui.spacing().interact_size.x);
+        let (rect, _) = ui.allocate_exact_size(desired_size, egui::Sense::click());
+        ui.painter().rect(
+            rect,
+            1.0,
+            constants::COLOR_REVEALED,
+            Stroke::new(1.0, constants::COLOR_BORDER),
+        );
+
+        ui.allocate_ui_at_rect(rect, |ui| {
+            ui.vertical_centered(|ui| {
+                ui.add_space(rect.height() * 0.3);
+                add_contents(self, ui);
+            });
+        });
+    }
+
+    fn game_board_paused_ui(&mut self, ui: &mut egui::Ui) {
+        // Board is covered, so the paused time and resume button are drawn over it
+        self.board_cover_ui(ui, |app, ui| {
+            ui.label(RichText::new(tr("paused")).heading().color(Color32::WHITE));
+            ui.label(
+                RichText::new(tr_with(
+                    "paused-time",
+                    &[("time", &format!("{:.2}", app.elapsed_time()))],
+                ))
+                .heading()
+                .color(Color32::WHITE),
+            );
+            ui.add_space(20.0);
+            let resume = egui::Button::new(RichText::new(tr("resume")).heading())
+                .min_size(Vec2::new(160.0, 50.0));
+            if ui.add(resume).clicked() {
+                app.resume_game();
+            }
+        });
+    }
+
+    /// Keeps the first player's finished board out of sight of the second player of a
+    /// hot-seat race until they're ready to start
+    fn game_board_handoff_ui(&mut self, ui: &mut egui::Ui) {
+        self.board_cover_ui(ui, |app, ui| {
+            let Some(hotseat) = &app.hotseat else {
+                return;
+            };
+            ui.label(
+                RichText::new(tr_with("pass-to", &[("name", &hotseat.names[1])]))
+                    .heading()
+                    .color(Color32::WHITE),
+            );
+            ui.add_space(20.0);
+            let start = egui::Button::new(RichText::new(tr("start")).heading())
+                .min_size(Vec2::new(160.0, 50.0));
+            if ui.add(start).clicked() {
+                app.start_next_hotseat_turn(&ui.ctx().clone());
+            }
+        });
+    }
+
+    fn game_board_ui(&mut self, ui: &mut egui::Ui, active: bool, pointer_pos: Option<Pos2>) {
+        if ui.input(|i| i.pointer.is_moving()) {
+            self.keyboard_cursor.hide();
+        }
+        if active {
+            self.keyboard_ui(ui);
+        }
+
+        // This determines which square the mouse is over for fog-of-war mode, going by where
+        // the boards were drawn last frame
+        let square_size = ui.spacing().interact_size.x;
+        // Nothing under the mini-map is played on
+        let minimap_rect = self.minimap_rect;
+        let off_minimap = |p: &Pos2| !minimap_rect.is_some_and(|m| m.contains(*p));
+        let pointer_pos = pointer_pos.filter(off_minimap);
+        let mouse_over_coord = pointer_pos.and_then(|p| {
+            let (board, rect) = self
+                .board_rects
+                .iter()
+                .enumerate()
+                .find(|(_, r)| r.contains(p))?;
+            let offset = (p - rect.min) / square_size;
+            Some(self.grid_coordinate(
+                board,
+                (offset.x.floor() as u32).min(self.game_settings.width - 1),
+                (offset.y.floor() as u32).min(self.game_settings.height - 1)
+                    + self.layer * self.game_settings.height,
+            ))
+        });
+        let fog_center = self.keyboard_cursor.coord().or(mouse_over_coord);
+        #[cfg(feature = "net")]
+        if let Some(coop) = &mut self.coop {
+            coop.send_cursor(fog_center.clone());
+        }
+        let fog_radius = self.sonar_ui(ui, fog_center.as_ref());
+        if let Some(c) = &fog_center {
+            if self.state.fog_of_war
+                && self.state.fog_memory
+                && self.game_state == GameState::Playing
+            {
+                let (width, height) = self.grid_size();
+                self.fog_memory.look(c, fog_radius, width, height);
+            }
+        }
+
+        let mut input = BoardInput::read(ui.ctx(), self.state.swap_buttons);
+        input.pointer = input.pointer.filter(off_minimap);
+        let was_pressed = std::mem::take(&mut self.board_pressed);
+        if input.flag_pressed {
+            self.flag_paint.begin();
+        }
+
+        if self.game_settings.layers > 1 {
+            self.layer_maps_ui(ui);
+            ui.add_space(constants::LAYER_MAP_SPACING);
+        }
+
+        // Boards too big for the window scroll, leaving room for the progress bar under them
+        let progress_bar = if self.state.show_progress {
+            constants::PROGRESS_BAR_HEIGHT + ui.spacing().item_spacing.y
+        } else {
+            0.0
+        };
+        let mut scroll_area = egui::ScrollArea::both()
+            .id_source("boards")
+            .drag_to_scroll(false)
+            .max_height(ui.available_height() - progress_bar);
+        if let Some(offset) = self.board_scroll_to.take() {
+            scroll_area = scroll_area.scroll_offset(offset);
+        }
+        let (columns, _) = self.game_settings.board_layout();
+        self.board_rects.clear();
+        let scroll = scroll_area.show(ui, |ui| {
+            egui::Grid::new("boards")
+                .spacing([constants::BOARD_SPACING, constants::BOARD_SPACING])
+                .show(ui, |ui| {
+                    (0..self.boards.len()).for_each(|board| {
+                        let rect = self.board_ui(
+                            ui,
+                            board,
+                            active,
+                            &input,
+                            fog_center.as_ref(),
+                            fog_radius,
+                        );
+                        self.board_rects.push(rect);
+                        if board as u32 % columns == columns - 1 {
+                            ui.end_row();
+                        }
+                    });
+                });
+
+            // Nobody should mistake a game with the mines shown for a real one
+            if self.mines_visible {
+                let boards = self
+                    .board_rects
+                    .iter()
+                    .fold(egui::Rect::NOTHING, |a, r| a.union(*r));
+                ui.painter().text(
+                    boards.center(),
+                    egui::Align2::CENTER_CENTER,
+                    tr("practice-banner"),
+                    egui::FontId::proportional(constants::PRACTICE_BANNER_FONT_SIZE),
+                    constants::COLOR_PRACTICE_BANNER,
+                );
+            }
+        });
+        let overflows = scroll.content_size.x > scroll.inner_rect.width()
+            || scroll.content_size.y > scroll.inner_rect.height();
+        self.minimap_rect = (self.state.show_minimap && overflows).then(|| {
+            self.minimap_ui(
+                ui.ctx(),
+                scroll.inner_rect,
+                scroll.content_size,
+                scroll.state.offset,
+            )
+        });
+
+        // The face was drawn before the board this frame, so it's drawn again to catch up
+        if self.board_pressed != was_pressed {
+            ui.ctx().request_repaint();
+        }
+        // Any tap has been handled above, so a touch that has ended is no longer of interest
+        if !input.reveal_down {
+            self.touch_gestures.cancel();
+        }
+        // Nothing else wakes the UI up while a finger is held still
+        if self.touch_gestures.awaiting_long_press() {
+            ui.ctx()
+                .request_repaint_after(Duration::from_secs_f64(TOUCH_REPAINT_INTERVAL));
+        }
+    }
+
+    /// Draws one of the boards in play and handles the input on it, returning where it was
+    /// drawn. Squares are passed around by where they are on the grid of all the boards.
+    /// Holding the sonar key in fog of war mode sends out a ping from `center` that clears
+    /// the fog further for a moment. Each ping is logged with the plays and adds time to the
+    /// clock, so it's worth saving for when it's needed. Returns how far the fog is clear.
+    fn sonar_ui(&mut self, ui: &egui::Ui, center: Option<&Coordinate>) -> f32 {
+        let radius = self.state.fog_radius;
+        let (held, now) = ui.input(|i| (i.key_down(SONAR_KEY), i.time));
+        if !held
+            || !self.state.fog_of_war
+            || self.game_state != GameState::Playing
+            || ui.ctx().wants_keyboard_input()
+        {
+            self.sonar_since = None;
+            return radius;
+        }
+        let since = match (self.sonar_since, center) {
+            (Some(since), _) => since,
+            (None, Some(center)) => {
+                self.plays
+                    .record_sonar(center.clone(), self.clock.elapsed());
+                self.clock.add_penalty(constants::SONAR_TIME_PENALTY);
+                *self.sonar_since.insert(now)
+            }
+            (None, None) => return radius,
+        };
+        let remaining = constants::SONAR_SECONDS - (now - since);
+        if remaining > 0.0 {
+            ui.ctx()
+                .request_repaint_after(Duration::from_secs_f64(remaining));
+            radius * constants::SONAR_RADIUS_FACTOR
+        } else {
+            radius
+        }
+    }
+
+    fn board_ui(
+        &mut self,
+        ui: &mut egui::Ui,
+        board: usize,
+        active: bool,
+        input: &BoardInput,
+        fog_center: Option<&Coordinate>,
+        fog_radius: f32,
+    ) -> egui::Rect {
+        let (width, height) = (self.game_settings.width, self.game_settings.height);
+        let (grid_width, grid_height) = self.grid_size();
+        let style = SquareStyle {
+            numeral_style: self.state.numeral_style.clone(),
+            skin: Self::board_skin(&self.state, &self.progress),
+            mines_visible: self.mines_visible,
+        };
+        let heatmap = (self.show_heatmap && self.game_state.game_ended())
+            .then(|| self.plays.heatmap(grid_width, grid_height));
+        let max_clicks = heatmap
+            .as_ref()
+            .and_then(|h| h.iter().max().copied())
+            .unwrap_or(0);
+        let time = ui.input(|i| i.time);
+        let rings = self
+            .reveal_rings
+            .as_mut()
+            .filter(|r| r.board == board)
+            .map(|r| (r.shown(time), r.clone()));
+        let reading =
+            self.state.constraint_assist && (input.flag_down || ui.input(|i| i.modifiers.alt));
+        let constraint = self
+            .constraint_focus
+            .as_ref()
+            .filter(|(b, _)| *b == board)
+            .and_then(|(_, c)| Some((c.clone(), NumeralConstraint::of(&self.boards[board], c)?)));
+        let mut focused = None;
+        let rect = egui::Grid::new(("process_grid_outputs", board))
+            .spacing([0.0, 0.0])
+            .striped(false)
+            .show(ui, |ui| {
+                iproduct!(0..height, 0..width).for_each(|(row, x)| {
+                    // Only the layer being shown is drawn
+                    let y = self.layer * height + row;
+                    let sqr = self.boards[board]
+                        .player_view(self.game_state == GameState::EndedLoss || self.mines_visible)
+                        .square_at(&Coordinate::from((x, y)))
+                        .unwrap_or_else(|e| {
+                            self.report_error(e);
+                            SquareView::Covered
+                        });
+                    // Squares the cascade hasn't spread to on screen yet
+                    let sqr = if rings
+                        .as_ref()
+                        .is_some_and(|(shown, r)| r.is_covered(x, y, *shown))
+                    {
+                        SquareView::Covered
+                    } else {
+                        sqr
+                    };
+                    let coord = self.grid_coordinate(board, x, y);
+
+                    let detonated = self.detonated_on.as_ref() == Some(&coord);
+
+                    let (missed_safe, wrong_flag) = self.post_mortem_marks(&coord);
+
+                    let fog = if self.state.fog_of_war {
+                        fog_alpha(
+                            fog_center.map_or(f32::INFINITY, |c| c.distance(&coord)),
+                            fog_radius,
+                            self.state.fog_memory && self.fog_memory.is_seen(&coord),
+                        )
+                    } else {
+                        0
+                    };
+
+                    let resp = MinesOfRustApp::square_ui(
+                        ui,
+                        sqr,
+                        &self.game_state,
+                        &style,
+                        detonated,
+                        missed_safe,
+                        fog,
+                    );
+                    if self.keyboard_cursor.coord().as_ref() == Some(&coord) {
+                        ui.painter().rect_stroke(
+                            resp.rect.shrink(1.0),
+                            0.0,
+                            Stroke::new(2.0, ui.visuals().selection.stroke.color),
+                        );
+                    }
+                    if wrong_flag {
+                        ui.painter().rect_stroke(
+                            resp.rect.shrink(1.0),
+                            0.0,
+                            Stroke::new(2.0, constants::COLOR_WARNING),
+                        );
+                    }
+                    if let Some((numeral, constraint)) = &constraint {
+                        let local = Coordinate::from((x, y));
+                        if constraint.squares.contains(&local) {
+                            ui.painter().rect(
+                                resp.rect.shrink(1.0),
+                                0.0,
+                                constants::COLOR_CONSTRAINT.gamma_multiply(0.3),
+                                Stroke::new(2.0, constants::COLOR_CONSTRAINT),
+                            );
+                        }
+                        if *numeral == local {
+                            egui::show_tooltip_for(
+                                ui.ctx(),
+                                egui::Id::new("numeral_constraint"),
+                                &resp.rect,
+                                |ui| ui.label(Self::constraint_text(constraint)),
+                            );
+                        }
+                    }
+                    if reading
+                        && input.pointer.is_some_and(|p| resp.rect.contains(p))
+                        && matches!(sqr, SquareView::Revealed(n) if n > 0)
+                    {
+                        focused = Some(Coordinate::from((x, y)));
+                    }
+                    if self.tutorial.as_ref().and_then(|t| t.target()).as_ref() == Some(&coord) {
+                        ui.painter().rect_stroke(
+                            resp.rect.shrink(1.0),
+                            0.0,
+                            Stroke::new(3.0, constants::COLOR_TUTORIAL_TARGET),
+                        );
+                    }
+                    let clicks = heatmap
+                        .as_ref()
+                        .map_or(0, |h| h[(coord.y * grid_width + coord.x) as usize]);
+                    if clicks > 0 {
+                        let alpha = egui::lerp(
+                            constants::HEATMAP_MIN_ALPHA..=constants::HEATMAP_MAX_ALPHA,
+                            clicks as f32 / max_clicks as f32,
+                        );
+                        ui.painter().rect_filled(
+                            resp.rect,
+                            0.0,
+                            constants::COLOR_HEATMAP.gamma_multiply(alpha),
+                        );
+                    }
+                    if self.active_chord_warning() == Some(&coord) {
+                        ui.painter().rect_stroke(
+                            resp.rect.shrink(1.0),
+                            0.0,
+                            Stroke::new(2.0, constants::COLOR_WARNING),
+                        );
+                        egui::show_tooltip_for(
+                            ui.ctx(),
+                            egui::Id::new("chord_warning"),
+                            &resp.rect,
+                            |ui| ui.label(tr("chord-warning")),
+                        );
+                    }
+                    #[cfg(feature = "net")]
+                    if self
+                        .coop
+                        .as_ref()
+                        .is_some_and(|c| c.partner_cursor.as_ref() == Some(&coord))
+                    {
+                        ui.painter().rect_stroke(
+                            resp.rect.shrink(1.0),
+                            0.0,
+                            Stroke::new(2.0, constants::COLOR_PARTNER_CURSOR),
+                        );
+                    }
+
+                    let pointer_over = input.pointer.is_some_and(|p| resp.rect.contains(p));
+                    self.board_pressed |= active && pointer_over && input.reveal_down;
+                    if self.state.touch_gestures && active && pointer_over {
+                        if input.reveal_pressed && input.is_touch {
+                            self.touch_gestures.begin(coord.clone(), input.time);
+                        } else if input.reveal_down && self.game_state == GameState::Playing {
+                            if let Some(c) = self.touch_gestures.update(
+                                &coord,
+                                input.time,
+                                input.num_touches,
+                                self.state.long_press_seconds,
+                            ) {
+                                self.play(c, RevealType::Flag);
+                            }
+                        }
+                    }
 
-        egui::TopBottomPanel::top("top_panel")
-            .resizable(false)
-            .min_height(50.0)
-            .show(ctx, |ui| {
-                // self.state.dark_mode = ui.visuals().dark_mode; // I don't like having this here.
-
-                if ui.input_mut(|i| {
-                    i.consume_shortcut(&KeyboardShortcut::new(Modifiers::COMMAND, Key::N))
-                }) {
-                    println!("ctrl+n is pressed, resetting game");
-                    self.reset_new_game(ctx).expect("Error building new game");
-                }
-                if ui.input_mut(|i| {
-                    i.consume_shortcut(&KeyboardShortcut::new(Modifiers::COMMAND, Key::R))
-                }) {
-                    println!("ctrl+r is pressed, resetting existing game");
-                    self.reset_existing_game(ctx)
-                        .expect("Error rebuilding game");
-                }
-                if ui.input_mut(|i| {
-                    i.consume_shortcut(&KeyboardShortcut::new(Modifiers::COMMAND, Key::Q))
-                }) {
-                    println!("Boss can see screen. Ctrl+q is pressed, exiting");
-                    process::exit(0);
-                }
-                if ui.input_mut(|i| {
-                    i.consume_shortcut(&KeyboardShortcut::new(Modifiers::COMMAND, Key::P))
-                }) {
-                    println!("Ctrl+q is pressed, toggling pause status");
-                    self.toggle_pause_state();
-                }
+                    let tap = if active && resp.clicked_by(input.reveal_button) {
+                        Some(
+                            self.touch_gestures
+                                .tap(input.time, self.state.two_finger_tap_seconds),
+                        )
+                    } else {
+                        None
+                    };
 
-                ui.vertical_centered(|ui| {
-                    let resp = self.face_ui(ui);
-                    if resp.clicked_by(egui::PointerButton::Primary) {
-                        self.reset_new_game(ctx).expect("Error building new game");
-                    } else if resp.clicked_by(egui::PointerButton::Secondary) {
-                        self.reset_existing_game(ctx)
-                            .expect("Error building new game");
+                    // The first tap on a board always reveals, since there's nothing yet to flag
+                    let play_type = match tap {
+                        Some(TapAction::Reveal)
+                            if self.state.tap_mode == TapMode::Flag
+                                && !self.game_settings.no_flags
+                                && self.game_state == GameState::Playing
+                                && self.boards[board].is_populated =>
+                        {
+                            Some(RevealType::Flag)
+                        }
+                        Some(TapAction::Reveal) if self.state.left_click_chord => {
+                            Some(RevealType::RevealChord)
+                        }
+                        Some(TapAction::Reveal) => Some(RevealType::Reveal),
+                        Some(TapAction::Chord) => Some(RevealType::Chord),
+                        Some(TapAction::Ignore) => None,
+                        None if active && resp.clicked_by(egui::PointerButton::Middle) => {
+                            Some(RevealType::Chord)
+                        }
+                        None if resp.clicked_by(input.flag_button)
+                            && active
+                            && !self.flag_paint.is_painting() =>
+                        {
+                            Some(RevealType::Flag)
+                        }
+                        None => None,
+                    };
+
+                    if self.state.paint_flags && active && input.flag_down && pointer_over {
+                        self.flag_paint
+                            .drag_over(coord.clone())
+                            .into_iter()
+                            .for_each(|c| self.paint_flag(c));
+                    }
+
+                    if let Some(p) = play_type {
+                        self.play(coord, p);
+                    }
+
+                    if x == width - 1 {
+                        ui.end_row();
                     }
                 });
-            });
+            })
+            .response
+            .rect;
+        // Read again next frame, once the squares around the numeral can be marked
+        let focused = focused.map(|c| (board, c));
+        let was_here = self
+            .constraint_focus
+            .as_ref()
+            .is_some_and(|(b, _)| *b == board);
+        if (focused.is_some() || was_here) && self.constraint_focus != focused {
+            self.constraint_focus = focused;
+            ui.ctx().request_repaint();
+        }
+        if let Some((shown, r)) = rings {
+            if shown >= r.last {
+                self.reveal_rings = None;
+            } else {
+                ui.ctx().request_repaint();
+            }
+        }
+        rect
+    }
 
-        egui::CentralPanel::default().show(ctx, |ui| {
-            ui.vertical_centered(|ui| {
-                if self.game_state != GameState::Paused {
-                    self.game_board_ui(ui, !self.game_state.game_ended(), ctx.pointer_latest_pos());
-                } else {
-                    self.game_board_paused_ui(ui);
-                }
-            });
-        });
+    /// Where a square of one of the boards is on the grid of all of them, laid out as they
+    /// are on screen. The layers of a layered board are stacked on the grid.
+    fn grid_coordinate(&self, board: usize, x: u32, y: u32) -> Coordinate {
+        let (columns, _) = self.game_settings.board_layout();
+        Coordinate {
+            x: board as u32 % columns * self.game_settings.width + x,
+            y: board as u32 / columns * self.board_height() + y,
+        }
+    }
 
-        egui::TopBottomPanel::bottom("bottom_panel")
-            .resizable(false)
-            .min_height(165.0)
-            .show(ctx, |ui| {
-                ui.vertical_centered(|ui| {
-                    egui::Grid::new("app_options")
-                        .num_columns(3)
-                        .spacing([10.0, 50.0])
-                        .striped(false)
-                        .show(ui, |ui| {
-                            //egui::CollapsingHeader::new("Options")
-                            //    .default_open(false)
-                            //    .show(ui, |ui| {
-                            self.options_ui(ctx, ui);
-                            //    });
-                            self.status_ui(ui);
-                        });
+    /// Rows of each board, across all its layers
+    fn board_height(&self) -> u32 {
+        self.game_settings.height * self.game_settings.layers
+    }
+
+    /// The board a square on the grid of all the boards belongs to, and where it is on that
+    /// board
+    fn board_coordinate(&self, coord: &Coordinate) -> Option<(usize, Coordinate)> {
+        self.game_settings
+            .board_coordinate(coord)
+            .filter(|(board, _)| *board < self.boards.len())
+    }
+
+    /// Size of the grid of all the boards, in squares
+    fn grid_size(&self) -> (u32, u32) {
+        let (columns, rows) = self.game_settings.board_layout();
+        (
+            columns * self.game_settings.width,
+            rows * self.board_height(),
+        )
+    }
+
+    /// Size of all the boards as they're laid out on screen, with the layer maps of a
+    /// layered board above it
+    fn boards_size(&self, square_size: f32) -> Vec2 {
+        let (columns, rows) = self.game_settings.board_layout();
+        let (width, _) = self.grid_size();
+        let height = rows * self.game_settings.height;
+        let layer_maps = if self.game_settings.layers > 1 {
+            self.game_settings.height as f32 * constants::LAYER_MAP_SQUARE_SIZE
+                + constants::LAYER_MAP_SPACING
+        } else {
+            0.0
+        };
+        Vec2::new(
+            width as f32 * square_size + (columns - 1) as f32 * constants::BOARD_SPACING,
+            height as f32 * square_size + (rows - 1) as f32 * constants::BOARD_SPACING + layer_maps,
+        )
+    }
 
-                    ui.horizontal_centered(|ui| {
-                        if ui.button("Leaderboard").clicked() {
-                            self.leaderboard_visible = true;
+    /// Maps of the layers of a layered board, showing what's been cleared and flagged on
+    /// each. Clicking one shows that layer.
+    fn layer_maps_ui(&mut self, ui: &mut egui::Ui) {
+        let (width, height) = (self.game_settings.width, self.game_settings.height);
+        let size = constants::LAYER_MAP_SQUARE_SIZE;
+        let view = self.boards[0].player_view(self.game_state == GameState::EndedLoss);
+        let mut clicked = None;
+        ui.horizontal(|ui| {
+            ui.spacing_mut().item_spacing.x = constants::LAYER_MAP_SPACING;
+            (0..self.game_settings.layers).for_each(|layer| {
+                let (rect, response) = ui.allocate_exact_size(
+                    Vec2::new(width as f32, height as f32) * size,
+                    egui::Sense::click(),
+                );
+                iproduct!(0..height, 0..width).for_each(|(y, x)| {
+                    let color = match view.square_at(&Coordinate::from((x, layer * height + y))) {
+                        Ok(SquareView::Covered | SquareView::HiddenMine) => {
+                            constants::COLOR_UNREVEALED
                         }
-                        if ui.button("Game Stats").clicked() {
-                            self.gamestats_visible = true;
+                        Ok(SquareView::Flagged | SquareView::Misflagged) => {
+                            constants::COLOR_LAYER_MAP_FLAG
                         }
-                    });
+                        Ok(SquareView::Detonated) => constants::COLOR_DETONATED,
+                        Ok(SquareView::Revealed(_)) => constants::COLOR_REVEALED,
+                        Ok(SquareView::Void) | Err(_) => return,
+                    };
+                    let min = rect.min + Vec2::new(x as f32, y as f32) * size;
+                    ui.painter().rect_filled(
+                        egui::Rect::from_min_size(min, Vec2::splat(size)),
+                        0.0,
+                        color,
+                    );
                 });
+                if layer == self.layer {
+                    ui.painter().rect_stroke(
+                        rect.expand(1.0),
+                        0.0,
+                        Stroke::new(1.0, ui.visuals().selection.stroke.color),
+                    );
+                }
+                let response =
+                    response.on_hover_text(tr_with("layer-number", &[("layer", &(layer + 1))]));
+                if response.clicked() {
+                    clicked = Some(layer);
+                }
             });
-        if self.game_state == GameState::Playing {
-            ctx.request_repaint();
+        });
+        if let Some(layer) = clicked {
+            self.show_layer(layer);
         }
-        Ok(())
     }
 
-    fn status_ui(&mut self, ui: &mut egui::Ui) {
-        ui.vertical_centered(|ui| {
-            ui.heading("");
-            let s = format!(
-                "{} of {}",
-                self.gameboard.num_flags(),
-                self.gameboard.num_mines
-            );
-            ui.add(egui::Label::new(<String as Into<RichText>>::into(s).heading()).wrap(false));
+    /// A map in the corner of boards too big for the window, showing how much has been
+    /// cleared and flagged where, with the part in the window outlined. Clicking or dragging
+    /// on it scrolls the boards there. `viewport` is where the boards are shown, `content_size`
+    /// how big they are and `offset` how far they've been scrolled. Returns where the map was
+    /// drawn.
+    fn minimap_ui(
+        &mut self,
+        ctx: &egui::Context,
+        viewport: egui::Rect,
+        content_size: Vec2,
+        offset: Vec2,
+    ) -> egui::Rect {
+        let square_size = ctx.style().spacing.interact_size.x;
+        let scale = constants::MINIMAP_SIZE / content_size.max_elem();
+        let size = content_size * scale;
+        // Squares are lumped into cells big enough to see, each shaded by how much of it has
+        // been cleared and flagged
+        let per_cell =
+            ((constants::MINIMAP_CELL_SIZE / (square_size * scale)).ceil() as u32).max(1);
+        let cell_size = per_cell as f32 * square_size * scale;
+        let (width, height) = (self.game_settings.width, self.game_settings.height);
+        let cells_x = (width as f32 / per_cell as f32).ceil() as u32;
+        let cells_y = (height as f32 / per_cell as f32).ceil() as u32;
 
-            let s = if self.game_state == GameState::Playing
-                && self.gameboard.is_loss_configuration()
-            {
-                self.game_state = GameState::EndedLoss;
-                self.game_finished = now();
-                self.losses += 1;
-                "".to_string()
-            } else if self.game_state == GameState::Playing && self.gameboard.is_win_configuration()
-            {
-                // You win!
-                self.game_state = GameState::EndedWin;
-                self.gameboard.flag_all_mines();
-                self.game_finished = now();
-                self.wins += 1;
-                self.leaderboards.add(
-                    self.state.difficulty.clone(),
-                    &whoami::realname(), // Do this until I write a dialog asking for the real name
-                    self.game_finished - self.game_started,
+        egui::Area::new(egui::Id::new("minimap"))
+            .order(egui::Order::Foreground)
+            .fixed_pos(viewport.right_bottom() - size - Vec2::splat(constants::MINIMAP_MARGIN))
+            .show(ctx, |ui| {
+                let (rect, response) = ui.allocate_exact_size(size, egui::Sense::click_and_drag());
+                ui.painter()
+                    .rect_filled(rect, 0.0, ui.visuals().extreme_bg_color);
+                self.board_rects
+                    .iter()
+                    .enumerate()
+                    .for_each(|(board, board_rect)| {
+                        let origin = rect.min + (board_rect.min - viewport.min + offset) * scale;
+                        let board_area =
+                            egui::Rect::from_min_size(origin, board_rect.size() * scale);
+                        let view =
+                            self.boards[board].player_view(self.game_state == GameState::EndedLoss);
+                        // Revealed, flagged and all the squares of each cell
+                        let mut cells = vec![(0, 0, 0); (cells_x * cells_y) as usize];
+                        iproduct!(0..height, 0..width).for_each(|(y, x)| {
+                            let cell = &mut cells[(y / per_cell * cells_x + x / per_cell) as usize];
+                            match view.square_at(&Coordinate::from((x, self.layer * height + y))) {
+                                Ok(SquareView::Revealed(_) | SquareView::Detonated) => {
+                                    cell.0 += 1;
+                                    cell.2 += 1;
+                                }
+                                Ok(SquareView::Flagged | SquareView::Misflagged) => {
+                                    cell.1 += 1;
+                                    cell.2 += 1;
+                                }
+                                Ok(SquareView::Covered | SquareView::HiddenMine) => cell.2 += 1,
+                                Ok(SquareView::Void) | Err(_) => {}
+                            }
+                        });
+                        cells.iter().enumerate().filter(|(_, c)| c.2 > 0).for_each(
+                            |(i, &(revealed, flagged, total))| {
+                                let color = mix_colors(
+                                    mix_colors(
+                                        constants::COLOR_UNREVEALED,
+                                        constants::COLOR_REVEALED,
+                                        revealed as f32 / total as f32,
+                                    ),
+                                    constants::COLOR_LAYER_MAP_FLAG,
+                                    flagged as f32 / total as f32,
+                                );
+                                let (cx, cy) = (i as u32 % cells_x, i as u32 / cells_x);
+                                let min = origin + Vec2::new(cx as f32, cy as f32) * cell_size;
+                                ui.painter().rect_filled(
+                                    egui::Rect::from_min_size(min, Vec2::splat(cell_size))
+                                        .intersect(board_area),
+                                    0.0,
+                                    color,
+                                );
+                            },
+                        );
+                    });
+                let shown =
+                    egui::Rect::from_min_size(rect.min + offset * scale, viewport.size() * scale)
+                        .intersect(rect);
+                ui.painter().rect_stroke(
+                    shown,
+                    0.0,
+                    Stroke::new(1.0, ui.visuals().selection.stroke.color),
                 );
-                "".to_string()
-            } else if self.game_state == GameState::Playing {
-                format!("Time: {:.2}", now() - self.game_started)
-            } else if self.game_state == GameState::Paused {
-                format!("Time: {:.2}", self.game_started)
-            } else if self.game_state.game_ended() {
-                format!("Time: {:.2}", self.game_finished - self.game_started)
-            } else {
-                "".to_string()
-            };
+                ui.painter()
+                    .rect_stroke(rect, 0.0, ui.visuals().widgets.noninteractive.bg_stroke);
+
+                // The window is centered on where the map is clicked or dragged to
+                if let Some(p) = response.interact_pointer_pos() {
+                    self.board_scroll_to =
+                        Some(((p - rect.min) / scale - viewport.size() / 2.0).max(Vec2::ZERO));
+                    ctx.request_repaint();
+                }
+                rect
+            })
+            .inner
+    }
 
-            ui.add(egui::Label::new(<String as Into<RichText>>::into(s).heading()).wrap(false));
+    /// Shows another layer of a layered board, taking the keyboard cursor along
+    fn show_layer(&mut self, layer: u32) {
+        let layer = layer.min(self.game_settings.layers - 1);
+        if self.keyboard_cursor.coord().is_some() {
+            let (width, height) = self.grid_size();
+            let dy = (layer as i32 - self.layer as i32) * self.game_settings.height as i32;
+            self.keyboard_cursor.move_by(0, dy, width, height);
+        }
+        self.layer = layer;
+    }
 
-            if self.game_state == GameState::Playing && ui.button("Pause").clicked() {
-                self.pause_game();
-            } else if self.game_state == GameState::Paused && ui.button("Resume").clicked() {
-                self.resume_game();
+    /// Moves the keyboard cursor with the arrow keys. Space reveals the square under it and
+    /// F flags it. Page Up and Page Down go through the layers of a layered board.
+    fn keyboard_ui(&mut self, ui: &mut egui::Ui) {
+        if ui.ctx().wants_keyboard_input() {
+            return;
+        }
+        if ui.input_mut(|i| i.consume_key(Modifiers::NONE, LAYER_UP_KEY)) {
+            self.show_layer(self.layer.saturating_sub(1));
+        }
+        if ui.input_mut(|i| i.consume_key(Modifiers::NONE, LAYER_DOWN_KEY)) {
+            self.show_layer(self.layer + 1);
+        }
+        let (width, height) = self.grid_size();
+        [
+            (Key::ArrowLeft, -1, 0),
+            (Key::ArrowRight, 1, 0),
+            (Key::ArrowUp, 0, -1),
+            (Key::ArrowDown, 0, 1),
+        ]
+        .into_iter()
+        .filter(|(key, _, _)| ui.input_mut(|i| i.consume_key(Modifiers::NONE, *key)))
+        .for_each(|(_, dx, dy)| self.keyboard_cursor.move_by(dx, dy, width, height));
+
+        let Some(coord) = self.keyboard_cursor.coord() else {
+            return;
+        };
+        // The cursor can be moved off the layer shown onto the next, which is shown instead
+        if self.game_settings.layers > 1 {
+            self.layer = coord.y / self.game_settings.height;
+        }
+
+        if ui.input_mut(|i| i.consume_key(Modifiers::NONE, Key::Space)) {
+            if self.state.left_click_chord {
+                self.play(coord, RevealType::RevealChord);
+            } else {
+                self.play(coord, RevealType::Reveal);
             }
-        });
+        } else if ui.input_mut(|i| i.consume_key(Modifiers::NONE, Key::F))
+            && self.game_state == GameState::Playing
+        {
+            self.play(coord, RevealType::Flag);
+        }
     }
 
-    fn toggle_pause_state(&mut self) {
-        if self.game_state == GameState::Playing {
-            self.pause_game();
-        } else if self.game_state == GameState::Paused {
-            self.resume_game();
+    /// Makes a play on the boards and records it, noting where the game was lost if it was.
+    /// Reveals start the game, or the board, if it hasn't been started yet.
+    fn play(&mut self, coord: Coordinate, play_type: RevealType) {
+        if let Some(tutorial) = &mut self.tutorial {
+            if !tutorial.accept(&coord, &play_type) {
+                return;
+            }
+        }
+        // Co-op plays are all made by the host, in the order it gets them, so both players'
+        // boards stay the same
+        #[cfg(feature = "net")]
+        if let Some(coop) = &self.coop {
+            if coop.is_host() {
+                self.coop_play(HOST, coord, play_type);
+            } else {
+                coop.send(&CoopMessage::Request { coord, play_type });
+            }
+            return;
         }
+        self.apply_play(coord, play_type, PlayKind::Click);
     }
 
-    fn pause_game(&mut self) {
-        self.game_state = GameState::Paused;
-        self.game_started = now() - self.game_started;
+    /// Whether `play_type` at `local` on `board` is a chord that sets off a mine, which only
+    /// happens when a flag around it is wrong
+    fn chord_would_detonate(
+        &self,
+        board: usize,
+        local: &Coordinate,
+        play_type: &RevealType,
+    ) -> bool {
+        let chord = matches!(play_type, RevealType::Chord | RevealType::RevealChord);
+        let revealed = self.boards[board]
+            .get_square(local.x, local.y)
+            .is_ok_and(|s| s.is_revealed);
+        // Tried out on a copy of the board, so nothing is given away unless it goes off
+        chord
+            && revealed
+            && self.boards[board]
+                .clone()
+                .play(local.x, local.y, play_type.clone())
+                .is_ok_and(|r| r.explosion().is_some())
     }
 
-    fn resume_game(&mut self) {
-        self.game_state = GameState::Playing;
-        self.game_started = now() - self.game_started;
+    /// The square of a chord that was held back and can still be made again to go ahead
+    fn active_chord_warning(&self) -> Option<&Coordinate> {
+        self.chord_warning
+            .as_ref()
+            .filter(|w| self.clock.elapsed() - w.time <= constants::CHORD_WARNING_SECONDS)
+            .map(|w| &w.coord)
     }
 
-    fn options_ui(&mut self, ctx: &egui::Context, ui: &mut egui::Ui) {
-        egui::Grid::new("app_options")
-            .num_columns(2)
-            .spacing([5.0, 5.0])
-            .min_row_height(30.0)
-            .striped(false)
-            .show(ui, |ui| {
-                ui.label("Difficulty:");
-
-                let cb = egui::ComboBox::new("GameDifficulty", "")
-                    .width(0_f32)
-                    .selected_text(self.state.difficulty.as_str());
-                cb.show_ui(ui, |ui| {
-                    let b = ui.selectable_value(
-                        &mut self.state.difficulty,
-                        GameDifficulty::Beginner,
-                        "Beginner",
-                    );
-                    let i = ui.selectable_value(
-                        &mut self.state.difficulty,
-                        GameDifficulty::Intermediate,
-                        "Intermediate",
-                    );
-                    let e = ui.selectable_value(
-                        &mut self.state.difficulty,
-                        GameDifficulty::Expert,
-                        "Expert",
-                    );
-                    // I don't like this pattern:
-                    if b.changed() || i.changed() || e.changed() {
-                        self.update_difficulty_settings();
-                        self.reset_new_game(ctx).expect("Failed to reset game");
-                    }
+    /// Makes a play on the boards, returning its result
+    fn apply_play(
+        &mut self,
+        coord: Coordinate,
+        play_type: RevealType,
+        kind: PlayKind,
+    ) -> Option<PlayResult> {
+        let (board, local) = self.board_coordinate(&coord)?;
+        // Plays the mode doesn't allow, like flag clicks in a no flag game, don't count
+        // against the game
+        if !self
+            .game_mode()
+            .allows_play(&self.boards[board], &local, &play_type)
+        {
+            return None;
+        }
+        // Voids are outside the board's shape, so there's nothing to play
+        if self.boards[board]
+            .get_square_by_coordinate(&local)
+            .is_ok_and(|s| s.is_void())
+        {
+            return None;
+        }
+        let adds_flag = play_type == RevealType::Flag
+            && self.boards[board]
+                .get_square(local.x, local.y)
+                .is_ok_and(|s| !s.is_flagged && !s.is_revealed);
+        if adds_flag && self.num_flags() >= self.num_mines() {
+            self.flag_warnings += 1;
+            if self.state.strict_flags {
+                return None;
+            }
+        }
+        if self.state.safe_chords && self.chord_would_detonate(board, &local, &play_type) {
+            let now = self.clock.elapsed();
+            let confirmed = self.chord_warning.take().is_some_and(|w| {
+                w.coord == coord && now - w.time <= constants::CHORD_WARNING_SECONDS
+            });
+            if !confirmed {
+                self.chord_warning = Some(ChordWarning { coord, time: now });
+                self.chord_warned = true;
+                self.add_penalty(|p| p.held_chords += 1);
+                return None;
+            }
+        }
+        let reveal = matches!(play_type, RevealType::Reveal | RevealType::RevealChord);
+        // Worked out before the first click places the mines, so that's never a guess
+        self.guess_trackers
+            .resize_with(self.boards.len(), GuessTracker::default);
+        let deduction = reveal
+            .then(|| self.guess_trackers[board].deduction(&self.boards[board], &local))
+            .flatten();
+        if reveal {
+            if let Err(e) = self.start_game(board, local.clone()) {
+                self.report_error(e);
+                return None;
+            }
+        }
+        #[cfg(not(target_arch = "wasm32"))]
+        let counts = BoardCounts::of(&self.boards[board]);
+        let outcome = self.boards[board].play(local.x, local.y, play_type.clone());
+        #[cfg(not(target_arch = "wasm32"))]
+        let play_report = self.save_play_report(counts, board, &local, &play_type, &outcome);
+        let played = match outcome {
+            Ok(r) => {
+                let wrong_flag = r.flagged() == Some(true)
+                    && self.boards[board]
+                        .get_square_by_coordinate(&local)
+                        .is_ok_and(|s| !s.is_mine());
+                // Taking a wrong flag off and putting it back is still one mistake
+                let square = (board, local.clone());
+                if wrong_flag
+                    && self.boards[board].is_populated
+                    && !self.penalized_flags.contains(&square)
+                {
+                    self.penalized_flags.push(square);
+                    self.add_penalty(|p| p.wrong_flags += 1);
+                }
+                #[cfg(feature = "net")]
+                self.broadcast(SpectateEvent::Play {
+                    board,
+                    entry: PlayEntry {
+                        kind,
+                        coord: local,
+                        play_type: play_type.clone(),
+                        changed: r.squares_changed(),
+                        time: self.elapsed_time(),
+                        deduction,
+                    },
+                    time: self.elapsed_time(),
                 });
-                ui.end_row();
+                if self.state.reveal_animation {
+                    if let Some(rings) = RevealRings::new(board, &self.boards[board], &r) {
+                        self.reveal_rings = Some(rings);
+                    }
+                }
+                if let Some(event) = GameEvent::from_play(coord.clone(), &r) {
+                    self.events.emit(event);
+                }
+                if kind == PlayKind::AutoOpen {
+                    self.plays.record_auto_open(coord, &r, self.elapsed_time());
+                } else {
+                    self.plays
+                        .record(coord, play_type, &r, self.elapsed_time(), deduction);
+                }
+                if let Some(c) = r.explosion().cloned() {
+                    log::info!("Detonated on {:?} of board {}", c, board + 1);
+                    let mut post_mortem = self.analyze_loss(board, &r, &c);
+                    post_mortem.missed_safe = post_mortem
+                        .missed_safe
+                        .iter()
+                        .map(|m| self.grid_coordinate(board, m.x, m.y))
+                        .collect();
+                    post_mortem.wrong_flags = r
+                        .skipped()
+                        .filter(|s| {
+                            self.boards[board]
+                                .get_square_by_coordinate(s)
+                                .is_ok_and(|s| !s.is_mine())
+                        })
+                        .map(|s| self.grid_coordinate(board, s.x, s.y))
+                        .collect();
+                    self.post_mortem = Some(post_mortem);
+                    self.detonated_on = Some(self.grid_coordinate(board, c.x, c.y));
+                }
+                self.check_game_end();
+                Some(r)
+            }
+            Err(e) => {
+                self.report_error(e);
+                None
+            }
+        };
+        #[cfg(not(target_arch = "wasm32"))]
+        if let Some(message) = play_report {
+            self.report_error(message);
+        }
+        played
+    }
 
-                ui.label("Left Click Chords:");
-                toggle_ui(ui, &mut self.state.left_click_chord);
-                ui.end_row();
+    /// Saves a report of a play that failed or gave a result that doesn't match the board,
+    /// for the player to attach to a bug report. Returns what to tell the player. The board
+    /// before the play, which the report needs, is only made again once a result looks wrong
+    /// by the board's counts from before it.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn save_play_report(
+        &self,
+        counts: BoardCounts,
+        board: usize,
+        coord: &Coordinate,
+        play_type: &RevealType,
+        outcome: &Result<PlayResult, Error>,
+    ) -> Option<String> {
+        let after = &self.boards[board];
+        if let Ok(r) = outcome {
+            if !bugreport::is_suspect_result(counts, after, r) {
+                return None;
+            }
+        }
+        let Some(before) = self.board_before_play(board) else {
+            log::warn!("Unable to make board {} again for a play report", board + 1);
+            return outcome.as_ref().err().map(|e| e.to_string());
+        };
+        let problem = match outcome {
+            Ok(r) => bugreport::unexpected_result(&before, after, r)?,
+            Err(e) => e.to_string(),
+        };
+        let report = bugreport::play_report(&before, after, coord, play_type, outcome, &problem);
+        match bugreport::save_play_report(&report) {
+            Ok(path) => Some(tr_with(
+                "play-report-saved",
+                &[("problem", &problem), ("path", &path.display().to_string())],
+            )),
+            Err(e) => {
+                log::warn!("Failed to save the play report: {:#}", e);
+                Some(problem)
+            }
+        }
+    }
 
-                ui.label("Fog of War:");
-                toggle_ui(ui, &mut self.state.fog_of_war);
-                ui.end_row();
+    /// Takes a copy of every board as it is now, for play reports to make a board from
+    #[cfg(not(target_arch = "wasm32"))]
+    fn snapshot_boards(&mut self) {
+        self.board_snapshots = self
+            .boards
+            .iter()
+            .map(|b| Some((b.clone(), self.plays.len())))
+            .collect();
+    }
 
-                ui.label("Theme:");
-                let cb = egui::ComboBox::new("VisualTheme", "")
-                    .width(0_f32)
-                    .selected_text(self.state.theme.as_str());
-                cb.show_ui(ui, |ui| {
-                    ui.selectable_value(&mut self.state.theme, VisualTheme::Dark, "Dark");
-                    ui.selectable_value(&mut self.state.theme, VisualTheme::Light, "Light");
-                });
-            });
+    /// Takes a copy of `board` as it is now, once its mines have been placed
+    #[cfg(not(target_arch = "wasm32"))]
+    fn snapshot_board(&mut self, board: usize) {
+        self.board_snapshots.resize(self.boards.len(), None);
+        self.board_snapshots[board] = Some((self.boards[board].clone(), self.plays.len()));
     }
 
-    /// Returns the first found Explosion in a list of cascaded play results
-    fn first_losing_square_of_vec(play_result: &[PlayResult]) -> Option<Coordinate> {
-        for r in play_result {
-            if let PlayResult::Explosion(c) = r {
-                return Some(c.clone());
-            }
-        }
-        None
+    /// The board as it was before the play being made now, made again by playing the plays
+    /// since its last snapshot on it
+    #[cfg(not(target_arch = "wasm32"))]
+    fn board_before_play(&self, board: usize) -> Option<GameBoard> {
+        let (snapshot, since) = self.board_snapshots.get(board)?.as_ref()?;
+        let mut before = snapshot.clone();
+        self.plays
+            .list
+            .get(*since..)?
+            .iter()
+            .filter(|e| e.changes_board())
+            .filter_map(|e| Some((self.board_coordinate(&e.coord)?, &e.play_type)))
+            .filter(|((b, _), _)| *b == board)
+            .try_for_each(|((_, local), play_type)| {
+                before.play(local.x, local.y, play_type.clone()).map(|_| ())
+            })
+            .ok()?;
+        Some(before)
     }
 
-    /// Returns the first found Explosion in either an explicit explosion or a cascaded play result
-    fn first_losing_square(play_result: &PlayResult) -> Option<Coordinate> {
-        match play_result {
-            PlayResult::Explosion(c) => Some(c.clone()),
-            PlayResult::CascadedReveal(r) => MinesOfRustApp::first_losing_square_of_vec(r),
-            _ => None,
+    /// Flags a square passed over by a paint flags drag, leaving squares that are already
+    /// flagged or revealed alone
+    fn paint_flag(&mut self, c: Coordinate) {
+        let Some((board, local)) = self.board_coordinate(&c) else {
+            return;
+        };
+        match self.boards[board].player_view(false).square_at(&local) {
+            Ok(SquareView::Covered) => self.play(c, RevealType::Flag),
+            Ok(_) => {}
+            Err(e) => self.report_error(e),
         }
     }
 
-    fn game_board_paused_ui(&mut self, ui: &mut egui::Ui) {
-        let desired_size = ui.spacing().interact_size.x
-            * egui::vec2(
-                self.game_settings.width as f32,
-                self.game_settings.height as f32,
-            );
-        let (rect, _) = ui.allocate_exact_size(desired_size, egui::Sense::click());
-        ui.painter().rect(
-            rect,
-            1.0,
-            constants::COLOR_REVEALED,
-            Stroke::new(1.0, constants::COLOR_BORDER),
-        );
+    /// Seconds elapsed in the current game, excluding time spent paused
+    fn elapsed_time(&self) -> f64 {
+        self.clock.elapsed()
     }
 
-    fn game_board_ui(&mut self, ui: &mut egui::Ui, active: bool, pointer_pos: Option<Pos2>) {
-        // This determines which square the mouse is over for fog-of-war mode
-        let mouse_over_coord = if let Some(p) = pointer_pos {
-            let n = ui.next_widget_position();
-            let x = (p.x - ui.spacing().button_padding.x * 2.0) / ui.spacing().interact_size.x;
-            let y = (p.y - n.y) / ui.spacing().interact_size.x;
-            Coordinate {
-                x: x.floor() as u32,
-                y: y.floor() as u32,
-            }
-        } else {
-            Coordinate { x: 9999, y: 9999 }
-        };
+    /// Time until the timer display next changes at the selected precision
+    fn timer_repaint_interval(&self) -> Duration {
+        let resolution = self.state.timer_precision.resolution();
+        let until_next = resolution - self.elapsed_time().rem_euclid(resolution);
+        Duration::from_secs_f64(until_next.max(0.001))
+    }
 
-        egui::Grid::new("process_grid_outputs")
-            .spacing([0.0, 0.0])
-            .striped(false)
-            .show(ui, |ui| {
-                iproduct!(0..self.gameboard.height, 0..self.gameboard.width).for_each(|(y, x)| {
-                    let sqr = self
-                        .gameboard
-                        .get_square(x, y)
-                        .expect("Error retrieving square");
-
-                    let detonated = if let Some(c) = &self.detonated_on {
-                        c.matches(x, y)
-                    } else {
-                        false
-                    };
+    fn mine_counter_ui(&self, ui: &mut egui::Ui) -> egui::Response {
+        let remaining = self.num_mines() as i64 - self.num_flags() as i64;
+        let shake = self.flag_warning_shake(ui.ctx());
+        ui.add_space(constants::SEGMENT_TOP_PADDING);
 
-                    let resp = self.square_ui(
-                        ui,
-                        &sqr,
-                        detonated,
-                        mouse_over_coord.distance(&Coordinate { x, y }),
-                    );
-                    if resp.clicked() && self.game_state == GameState::NotStarted {
-                        self.start_game(Coordinate { x, y })
-                            .expect("Error starting game");
-                    }
+        // Drawn shifted sideways by the shake, while taking up its usual place
+        let rect = ui.available_rect_before_wrap();
+        let mut shaken = ui.child_ui(rect.translate(Vec2::new(shake, 0.0)), *ui.layout());
+        let response = segment_display_ui(
+            &mut shaken,
+            &format!("{:03}", remaining.clamp(-99, 999)),
+            constants::SEGMENT_DIGIT_HEIGHT,
+            remaining < 0,
+        );
+        ui.advance_cursor_after_rect(response.rect.translate(Vec2::new(-shake, 0.0)));
+        response
+    }
 
-                    let play_type = if active
-                        && resp.clicked_by(egui::PointerButton::Primary)
-                        && !self.state.left_click_chord
-                    {
-                        Some(RevealType::Reveal)
-                    } else if active
-                        && resp.clicked_by(egui::PointerButton::Primary)
-                        && self.state.left_click_chord
-                    {
-                        Some(RevealType::RevealChord)
-                    } else if active && resp.clicked_by(egui::PointerButton::Middle) {
-                        Some(RevealType::Chord)
-                    } else if resp.clicked_by(egui::PointerButton::Secondary) && active {
-                        Some(RevealType::Flag)
-                    } else {
-                        None
-                    };
+    /// How far the mine counter is pushed sideways by the shake after the last flag warning
+    fn flag_warning_shake(&self, ctx: &egui::Context) -> f32 {
+        let shown = ctx.animate_value_with_time(
+            egui::Id::new("flag_warning_shake"),
+            self.flag_warnings as f32,
+            constants::FLAG_WARNING_SHAKE_SECONDS,
+        );
+        let left = (self.flag_warnings as f32 - shown).clamp(0.0, 1.0);
+        if !self.state.flag_warning_shake || left == 0.0 {
+            return 0.0;
+        }
+        let swing = (left * constants::FLAG_WARNING_SHAKE_SWINGS * std::f32::consts::TAU).sin();
+        constants::FLAG_WARNING_SHAKE_DISTANCE * left * swing
+    }
 
-                    if let Some(p) = play_type {
-                        self.plays.push(PlayEntry {
-                            play_type: p.clone(),
-                            coord: Coordinate { x, y },
-                        });
+    /// What a numeral says about the covered squares around it, as the player reads it
+    fn constraint_text(constraint: &NumeralConstraint) -> String {
+        if constraint.mines < 0 {
+            return tr("constraint-too-many-flags").to_string();
+        }
+        tr_with(
+            "constraint-mines",
+            &[
+                ("mines", &constraint.mines.to_string()),
+                ("squares", &constraint.squares.len().to_string()),
+            ],
+        )
+    }
 
-                        if let Some(c) = MinesOfRustApp::first_losing_square(
-                            &self
-                                .gameboard
-                                .play(x, y, p)
-                                .expect("Failed to play desired move"),
-                        ) {
-                            println!("Detonated on {:?}", c);
-                            self.detonated_on = Some(c.clone());
-                        }
-                    }
+    /// The time on the clock, as the timer shows it
+    fn timer_text(&self) -> String {
+        let elapsed = self.elapsed_time().clamp(0.0, 999.99);
+        match self.state.timer_precision {
+            TimerPrecision::Seconds => format!("{:03}", elapsed.floor() as u32),
+            TimerPrecision::Hundredths => format!("{:06.2}", elapsed),
+        }
+    }
 
-                    if x == self.gameboard.width - 1 {
-                        ui.end_row();
-                    }
-                });
-            });
+    fn timer_ui(&self, ui: &mut egui::Ui) -> egui::Response {
+        let s = self.timer_text();
+        ui.add_space(constants::SEGMENT_TOP_PADDING);
+        segment_display_ui(ui, &s, constants::SEGMENT_DIGIT_HEIGHT, false)
+    }
+
+    /// The only thing shown above the board in zen mode. Clicking it brings everything back.
+    fn zen_timer_ui(&mut self, ui: &mut egui::Ui) {
+        let text = RichText::new(self.timer_text()).monospace().small().weak();
+        let shortcut = ui.ctx().format_shortcut(&SHORTCUT_ZEN_MODE);
+        if ui
+            .add(egui::Label::new(text).sense(egui::Sense::click()))
+            .on_hover_text(tr_with("tip-zen-timer", &[("shortcut", &shortcut)]))
+            .clicked()
+        {
+            self.state.zen_mode = false;
+        }
     }
 
     fn face_ui(&self, ui: &mut egui::Ui) -> egui::Response {
         let desired_size = ui.spacing().interact_size.x * egui::vec2(1.4, 1.4);
         let (rect, response) = ui.allocate_exact_size(desired_size, egui::Sense::click());
 
-        if self.game_state == GameState::EndedLoss {
-            egui::Image::new(egui::include_image!("../assets/loss.png")).paint_at(ui, rect);
-        } else if self.game_state == GameState::EndedWin {
-            egui::Image::new(egui::include_image!("../assets/win.png")).paint_at(ui, rect);
+        // Looks like a button being pushed in when hovered and clicked
+        let rect = if response.is_pointer_button_down_on() {
+            let visuals = ui.visuals().widgets.active;
+            ui.painter()
+                .rect(rect, visuals.rounding, visuals.bg_fill, visuals.bg_stroke);
+            rect.shrink(2.0)
+        } else if response.hovered() {
+            let visuals = ui.visuals().widgets.hovered;
+            ui.painter().rect(
+                rect,
+                visuals.rounding,
+                visuals.weak_bg_fill,
+                visuals.bg_stroke,
+            );
+            rect
         } else {
-            egui::Image::new(egui::include_image!("../assets/happy.png")).paint_at(ui, rect);
-        }
+            rect
+        };
+
+        let face = match self.game_state {
+            GameState::EndedLoss => egui::include_image!("../assets/loss.png"),
+            // The cool face is only for wins on a board the player hadn't seen before, with
+            // no help along the way
+            GameState::EndedWin if !self.was_seen_before() && !self.had_assistance() => {
+                egui::include_image!("../assets/win.png")
+            }
+            GameState::Paused => egui::include_image!("../assets/sleeping.png"),
+            GameState::NotStarted | GameState::Playing if self.board_pressed => {
+                egui::include_image!("../assets/surprised.png")
+            }
+            _ => egui::include_image!("../assets/happy.png"),
+        };
+        egui::Image::new(face).paint_at(ui, rect);
 
         response
     }
 
+    /// Draws `numeral` as text filling `rect`, in its color for the current theme
+    fn paint_numeral_text(ui: &egui::Ui, rect: egui::Rect, numeral: u32) {
+        let colors = if ui.visuals().dark_mode {
+            constants::COLORS_NUMERAL_DARK
+        } else {
+            constants::COLORS_NUMERAL_LIGHT
+        };
+        let color = colors[(numeral as usize).clamp(1, colors.len()) - 1];
+        ui.painter().text(
+            rect.center(),
+            egui::Align2::CENTER_CENTER,
+            numeral.to_string(),
+            egui::FontId::monospace(rect.height() * constants::NUMERAL_TEXT_SCALE),
+            color,
+        );
+    }
+
     fn square_ui(
-        &self,
         ui: &mut egui::Ui,
-        sqr: &Square,
+        sqr: SquareView,
+        game_state: &GameState,
+        style: &SquareStyle,
         is_detonated: bool,
-        mouse_distance: f32,
+        is_missed_safe: bool,
+        fog_alpha: u8,
     ) -> egui::Response {
-        let opaque = mouse_distance > 1.5 && self.state.fog_of_war;
+        let numeral_style = &style.numeral_style;
+        let opaque = fog_alpha > 0;
 
         let desired_size = (ui.spacing().interact_size.x) * egui::vec2(1.0, 1.0);
+        // Voids are left as empty space that can't be clicked
+        if sqr == SquareView::Void {
+            return ui.allocate_exact_size(desired_size, egui::Sense::hover()).1;
+        }
         let (rect, response) = ui.allocate_exact_size(desired_size, egui::Sense::click());
         let visuals_off = ui.style().interact_selectable(&response, false);
         let visuals_on = ui.style().interact_selectable(&response, true);
 
-        let unrevealed_color = visuals_on.bg_fill; //constants::COLOR_UNREVEALED;
+        let unrevealed_color = style.skin.covered_color().unwrap_or(visuals_on.bg_fill);
         let revealed_color = if is_detonated {
             constants::COLOR_DETONATED
+        } else if sqr == SquareView::Detonated {
+            // Set off along with the detonated mine by the same chord
+            constants::COLOR_CHORD_DETONATED
         } else {
             visuals_off.bg_fill
         };
@@ -744,13 +7055,7 @@ impl MinesOfRustApp {
             unrevealed_color.r(),
             unrevealed_color.g(),
             unrevealed_color.b(),
-            if mouse_distance < 1.0 {
-                0
-            } else if mouse_distance < 3.0 {
-                140
-            } else {
-                255
-            },
+            fog_alpha,
         );
 
         ui.painter()
@@ -766,10 +7071,11 @@ impl MinesOfRustApp {
         //      Unrevealed, Mouse down, chord
         // Loss
         //      Unrevealed
-        //      Unrevealed non-mined flagged
+        //      Unrevealed non-mined flagged (crossed out)
         //      Unrevealed mined flagged
+        //      Unrevealed mined unflagged (dimmed)
         //      Revealed mined (losing play)
-        //      Revealed mined (adjacent to losing play)
+        //      Revealed mined (set off by the same chord as the losing play)
         //      Revealed numeral
         //      Revealed blank
         // Win
@@ -777,37 +7083,120 @@ impl MinesOfRustApp {
         //      Unrevealed flagged
         //      Revealed numeral
         //      Revealed blank
-        if sqr.is_mine() && !sqr.is_flagged && self.game_state == GameState::EndedLoss {
-            egui::Image::new(egui::include_image!("../assets/mine.png")).paint_at(ui, rect);
-        } else if sqr.is_flagged && !sqr.is_mine() && self.game_state == GameState::EndedLoss {
-            ui.painter()
-                .rect(rect, 0.0, misflagged_color, Stroke::new(0.5, border_color));
-            egui::Image::new(egui::include_image!("../assets/flag.png")).paint_at(ui, rect);
-        } else if sqr.is_flagged {
-            ui.painter()
-                .rect(rect, 0.0, unrevealed_color, Stroke::new(0.5, border_color));
-            egui::Image::new(egui::include_image!("../assets/flag.png")).paint_at(ui, rect);
-        } else if sqr.is_revealed {
-            match sqr.numeral {
-                1 => egui::Image::new(egui::include_image!("../assets/1.png")).paint_at(ui, rect),
-                2 => egui::Image::new(egui::include_image!("../assets/2.png")).paint_at(ui, rect),
-                3 => egui::Image::new(egui::include_image!("../assets/3.png")).paint_at(ui, rect),
-                4 => egui::Image::new(egui::include_image!("../assets/4.png")).paint_at(ui, rect),
-                5 => egui::Image::new(egui::include_image!("../assets/5.png")).paint_at(ui, rect),
-                6 => egui::Image::new(egui::include_image!("../assets/6.png")).paint_at(ui, rect),
-                7 => egui::Image::new(egui::include_image!("../assets/7.png")).paint_at(ui, rect),
-                8 => egui::Image::new(egui::include_image!("../assets/8.png")).paint_at(ui, rect),
-                _ => {}
-            };
-        } else {
-            ui.painter()
-                .rect(rect, 0.0, unrevealed_color, Stroke::new(0.5, border_color));
+        match sqr {
+            SquareView::Detonated => {
+                paint_tile(
+                    ui,
+                    egui::Image::new(egui::include_image!("../assets/mine.svg")),
+                    rect,
+                );
+            }
+            SquareView::HiddenMine
+                if style.mines_visible && *game_state != GameState::EndedLoss =>
+            {
+                // Practicing with the mines shown, so they're kept faint enough to read the
+                // board around them
+                ui.painter()
+                    .rect(rect, 0.0, unrevealed_color, Stroke::new(0.5, border_color));
+                paint_tile(
+                    ui,
+                    egui::Image::new(egui::include_image!("../assets/mine.svg"))
+                        .tint(constants::COLOR_PRACTICE_MINE_TINT),
+                    rect,
+                );
+            }
+            SquareView::HiddenMine => {
+                // Mines the player never found are shown dimmed on an unrevealed square
+                ui.painter()
+                    .rect(rect, 0.0, unrevealed_color, Stroke::new(0.5, border_color));
+                paint_tile(
+                    ui,
+                    egui::Image::new(egui::include_image!("../assets/mine.svg"))
+                        .tint(constants::COLOR_UNFOUND_MINE_TINT),
+                    rect,
+                );
+            }
+            SquareView::Misflagged => {
+                ui.painter()
+                    .rect(rect, 0.0, misflagged_color, Stroke::new(0.5, border_color));
+                paint_tile(
+                    ui,
+                    egui::Image::new(egui::include_image!("../assets/flag.svg")),
+                    rect,
+                );
+                paint_tile(
+                    ui,
+                    egui::Image::new(egui::include_image!("../assets/x.svg")),
+                    rect,
+                );
+            }
+            SquareView::Flagged => {
+                ui.painter()
+                    .rect(rect, 0.0, unrevealed_color, Stroke::new(0.5, border_color));
+                paint_tile(
+                    ui,
+                    egui::Image::new(egui::include_image!("../assets/flag.svg")),
+                    rect,
+                );
+            }
+            SquareView::Revealed(0) | SquareView::Void => {}
+            SquareView::Revealed(numeral) if *numeral_style == NumeralStyle::Text => {
+                MinesOfRustApp::paint_numeral_text(ui, rect, numeral);
+            }
+            SquareView::Revealed(numeral) => match numeral_image(numeral) {
+                Some(source) => paint_tile(ui, egui::Image::new(source), rect),
+                // There's no artwork past 8
+                None => MinesOfRustApp::paint_numeral_text(ui, rect, numeral),
+            },
+            SquareView::Covered => {
+                ui.painter()
+                    .rect(rect, 0.0, unrevealed_color, Stroke::new(0.5, border_color));
+            }
         }
 
-        if opaque && self.game_state == GameState::Playing {
+        if opaque && *game_state == GameState::Playing {
             ui.painter()
                 .rect(rect, 0.0, opaque_color, Stroke::new(0.5, border_color));
         }
+
+        if is_missed_safe {
+            ui.painter().rect_stroke(
+                rect.shrink(1.0),
+                0.0,
+                Stroke::new(2.0, constants::COLOR_MISSED_SAFE),
+            );
+        }
         response
     }
 }
+
+#[cfg(not(target_arch = "wasm32"))]
+#[test]
+fn test_restart_after_loss_outlines_nothing() -> Result<(), Error> {
+    crate::config::set_config_dir(
+        std::env::temp_dir().join(format!("minesofrust-test-{}", std::process::id())),
+    );
+    let ctx = egui::Context::default();
+    let mut app = MinesOfRustApp::load_from_persistence();
+    app.game_settings = GameSettings::for_board(3, 2, 2);
+    app.boards = vec![GameBoard::new_with_mines(
+        3,
+        2,
+        &[Coordinate::from((0, 0)), Coordinate::from((2, 0))],
+    )?];
+
+    // With the 1-2-1 revealed, the middle of the top row is known to be safe
+    (0..3).for_each(|x| app.play(Coordinate::from((x, 1)), RevealType::Reveal));
+    app.play(Coordinate::from((0, 0)), RevealType::Reveal);
+    app.handle_events(&ctx);
+    assert_eq!(app.game_state, GameState::EndedLoss);
+    assert_eq!(
+        app.post_mortem_marks(&Coordinate::from((1, 0))),
+        (true, false)
+    );
+
+    app.reset_existing_game(&ctx)?;
+    assert!(!app.post_mortem_visible);
+    assert!((0..3).all(|x| app.post_mortem_marks(&Coordinate::from((x, 0))) == (false, false)));
+    Ok(())
+}
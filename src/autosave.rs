@@ -0,0 +1,181 @@
+use std::cell::RefCell;
+use std::sync::mpsc::{self, Sender};
+use std::thread::{self, JoinHandle};
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+use crate::config::{self, Versioned};
+use crate::enums::GameDifficulty;
+use crate::minesweeper::{Error, GameBoard};
+use crate::plays::PlayList;
+use crate::state::GameSettings;
+
+const CONFIG_FILE_NAME: &str = "minesofrust-autosave.toml";
+
+/// A game in progress, saved every so often while it's played so a crash or power cut
+/// doesn't lose it. The boards are kept as the seeds they were made from, and brought back
+/// by making the plays again.
+#[derive(Clone, Deserialize, Serialize)]
+pub struct SavedGame {
+    #[serde(default)]
+    pub version: i64,
+    pub difficulty: GameDifficulty,
+    pub settings: GameSettings,
+    /// Each board's seed, in the order they're laid out
+    pub seeds: Vec<u64>,
+    pub plays: PlayList,
+    /// Seconds on the clock when the game was saved
+    pub elapsed: f64,
+    /// The mines had been shown at some point in the game
+    #[serde(default)]
+    pub mines_seen: bool,
+}
+
+impl Versioned for SavedGame {
+    // 1: Initial version
+    const VERSION: i64 = 1;
+}
+
+impl SavedGame {
+    pub fn new(
+        difficulty: &GameDifficulty,
+        settings: &GameSettings,
+        boards: &[GameBoard],
+        plays: &PlayList,
+        elapsed: f64,
+        mines_seen: bool,
+    ) -> Self {
+        SavedGame {
+            version: <SavedGame as Versioned>::VERSION,
+            difficulty: difficulty.clone(),
+            settings: settings.clone(),
+            seeds: boards.iter().map(|b| b.seed).collect(),
+            plays: plays.clone(),
+            elapsed,
+            mines_seen,
+        }
+    }
+
+    pub fn load_from_userhome() -> Result<Option<Self>> {
+        config::load_toml(CONFIG_FILE_NAME)
+    }
+
+    pub fn save_to_userhome(&self) -> Result<()> {
+        config::save_toml(self, CONFIG_FILE_NAME)
+    }
+
+    /// Removes the saved game, once it's over or has been thrown away
+    pub fn remove_from_userhome() -> Result<()> {
+        config::remove_file(CONFIG_FILE_NAME)
+    }
+
+    /// The boards as they were when the game was saved. Each board's mines are placed
+    /// around the first square revealed on it, the same as when the game was played.
+    pub fn boards(&self) -> Result<Vec<GameBoard>, Error> {
+        let mut boards: Vec<GameBoard> = self
+            .seeds
+            .iter()
+            .map(|s| self.settings.new_board(*s))
+            .collect();
+        self.plays.play_on(&self.settings, &mut boards)?;
+        Ok(boards)
+    }
+}
+
+enum Job {
+    Save(Box<SavedGame>),
+    Remove,
+}
+
+/// Saves and removes the game in progress on a thread of its own, so the game doesn't wait
+/// on the disk. They're done one at a time in the order they were asked for, so a save that
+/// was slow to write can't bring back a game removed after it.
+#[derive(Default)]
+pub struct Autosaver {
+    jobs: RefCell<Option<Sender<Job>>>,
+    worker: RefCell<Option<JoinHandle<()>>>,
+}
+
+impl Autosaver {
+    pub fn save(&self, game: SavedGame) {
+        self.send(Job::Save(Box::new(game)));
+    }
+
+    pub fn remove(&self) {
+        self.send(Job::Remove);
+    }
+
+    /// Waits for everything asked for so far to be done, for when the app is closing
+    pub fn finish(&self) {
+        self.jobs.take();
+        if let Some(worker) = self.worker.take() {
+            let _ = worker.join();
+        }
+    }
+
+    fn send(&self, job: Job) {
+        let mut jobs = self.jobs.borrow_mut();
+        let jobs = jobs.get_or_insert_with(|| {
+            let (jobs, received) = mpsc::channel();
+            *self.worker.borrow_mut() = Some(thread::spawn(move || {
+                for job in received {
+                    match job {
+                        Job::Save(game) => {
+                            if let Err(e) = game.save_to_userhome() {
+                                log::warn!("Failed to save the game in progress: {:#}", e);
+                            }
+                        }
+                        Job::Remove => {
+                            if let Err(e) = SavedGame::remove_from_userhome() {
+                                log::warn!("Failed to remove the saved game: {:#}", e);
+                            }
+                        }
+                    }
+                }
+            }));
+            jobs
+        });
+        let _ = jobs.send(job);
+    }
+}
+
+#[test]
+fn test_saved_game() -> Result<(), Error> {
+    use crate::minesweeper::{Coordinate, RevealType};
+
+    let settings = GameSettings::beginner();
+    let mut board = settings.new_board(7);
+    let mut plays = PlayList::default();
+    let first_click = Coordinate::from((4, 4));
+    board.populate_mines_around(settings.num_mines, Some(first_click.clone()))?;
+    board.populate_numerals()?;
+    let result = board.play(4, 4, RevealType::Reveal)?;
+    plays.record(first_click, RevealType::Reveal, &result, 0.0, None);
+    let mine = (0..81)
+        .map(|i| Coordinate::from((i % 9, i / 9)))
+        .find(|c| board.get_square_by_coordinate(c).is_ok_and(|s| s.is_mine()))
+        .expect("The board has mines");
+    let result = board.play(mine.x, mine.y, RevealType::Flag)?;
+    plays.record(mine.clone(), RevealType::Flag, &result, 1.5, None);
+
+    let saved = SavedGame::new(
+        &GameDifficulty::Beginner,
+        &settings,
+        &[board.clone()],
+        &plays,
+        12.5,
+        false,
+    );
+    let text = toml::to_string(&saved).expect("A saved game can be written");
+    let loaded: SavedGame = config::parse_versioned(&text).expect("A saved game can be read");
+    assert_eq!(loaded.elapsed, 12.5);
+
+    // Making the plays again on the seeded board brings it back as it was
+    let boards = loaded.boards()?;
+    assert_eq!(boards.len(), 1);
+    assert_eq!(boards[0].num_revealed(), board.num_revealed());
+    assert!(boards[0].get_square_by_coordinate(&mine)?.is_flagged);
+    assert_eq!(boards[0].bbbv(), board.bbbv());
+    Ok(())
+}
@@ -0,0 +1,11 @@
+#![warn(clippy::all, rust_2018_idioms)]
+
+// Plays and analyzes boards with the minesweeper engine, without the GUI. Run with
+// `cargo run --bin minesweeper-cli -- help` for the commands.
+fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if let Err(e) = minesofrust_web::cli::run(&args) {
+        eprintln!("Error: {}", e);
+        std::process::exit(1);
+    }
+}
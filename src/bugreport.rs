@@ -0,0 +1,248 @@
+use std::fs;
+use std::path::PathBuf;
+
+use anyhow::{Context, Result};
+
+use crate::config;
+use crate::minesweeper::{
+    CellChange, ChangeKind, Coordinate, Error, GameBoard, PlayResult, RevealType,
+};
+
+/// How many squares of a board were revealed and flagged, which is all that's kept of the
+/// board before each play for the quick check of its result
+#[derive(Debug, Clone, Copy)]
+pub struct BoardCounts {
+    revealed: u32,
+    flags: u32,
+}
+
+impl BoardCounts {
+    pub fn of(board: &GameBoard) -> Self {
+        BoardCounts {
+            revealed: board.num_revealed(),
+            flags: board.num_flags(),
+        }
+    }
+}
+
+/// Whether a play's result looks wrong going by the board's counts from before it and the
+/// board after it. It's quick enough to check every play with, and catches most of what
+/// `unexpected_result` does without the whole board from before the play.
+pub fn is_suspect_result(before: BoardCounts, after: &GameBoard, result: &PlayResult) -> bool {
+    unexpected_counts(before, after, result).is_some()
+        || result.changes.iter().any(|change| {
+            after
+                .get_square_by_coordinate(&change.coord)
+                .map_or(true, |now| match change.kind {
+                    ChangeKind::Revealed => !now.is_revealed || now.is_mine(),
+                    ChangeKind::Exploded => !now.is_revealed || !now.is_mine(),
+                    ChangeKind::Flagged => !now.is_flagged,
+                    ChangeKind::Unflagged => now.is_flagged,
+                    ChangeKind::Skipped => !now.is_flagged || now.is_revealed,
+                })
+        })
+}
+
+/// What's wrong with the result the engine gave for a play, if anything: squares it says
+/// it revealed that the play didn't reveal, explosions on squares that aren't mines, and
+/// the like
+pub fn unexpected_result(
+    before: &GameBoard,
+    after: &GameBoard,
+    result: &PlayResult,
+) -> Option<String> {
+    if let Some(problem) = unexpected_counts(BoardCounts::of(before), after, result) {
+        return Some(problem);
+    }
+    result.changes.iter().find_map(|change| {
+        let CellChange { coord, kind, .. } = change;
+        let (Ok(was), Ok(now)) = (
+            before.get_square_by_coordinate(coord),
+            after.get_square_by_coordinate(coord),
+        ) else {
+            return Some(format!("{:?} is outside the board", change));
+        };
+        let expected = match kind {
+            ChangeKind::Revealed => !was.is_revealed && now.is_revealed && !now.is_mine(),
+            ChangeKind::Exploded => !was.is_revealed && now.is_revealed && now.is_mine(),
+            ChangeKind::Flagged => !was.is_flagged && now.is_flagged,
+            ChangeKind::Unflagged => was.is_flagged && !now.is_flagged,
+            ChangeKind::Skipped => now.is_flagged && !now.is_revealed,
+        };
+        (!expected).then(|| format!("{:?} doesn't match the square, {:?}", change, now))
+    })
+}
+
+/// What's wrong with how many squares a result lists as revealed and flags as changed, if
+/// they aren't as many as the board's counts changed by
+fn unexpected_counts(
+    before: BoardCounts,
+    after: &GameBoard,
+    result: &PlayResult,
+) -> Option<String> {
+    let revealed = after.num_revealed().saturating_sub(before.revealed) as usize;
+    if revealed != result.coordinates().len() {
+        return Some(format!(
+            "{} squares were revealed, but the result lists {}",
+            revealed,
+            result.coordinates().len()
+        ));
+    }
+    let flags = after.num_flags() as i64 - before.flags as i64;
+    let listed_flags = result
+        .changes
+        .iter()
+        .map(|c| match c.kind {
+            ChangeKind::Flagged => 1,
+            ChangeKind::Unflagged => -1,
+            _ => 0,
+        })
+        .sum::<i64>();
+    (flags != listed_flags).then(|| {
+        format!(
+            "The flags changed by {}, but the result lists {}",
+            flags, listed_flags
+        )
+    })
+}
+
+/// A report of a play the engine got wrong, for the player to attach to a bug report: the
+/// play, what went wrong, the result it gave in full, and the board before and after it
+/// side by side with the squares that changed marked
+pub fn play_report(
+    before: &GameBoard,
+    after: &GameBoard,
+    coord: &Coordinate,
+    play_type: &RevealType,
+    outcome: &Result<PlayResult, Error>,
+    problem: &str,
+) -> String {
+    let result = match outcome {
+        Ok(r) => format!("{:#?}", r),
+        Err(e) => format!("Error: {}", e),
+    };
+    let player_views = before
+        .to_ascii(false)
+        .lines()
+        .zip(after.to_ascii(false).lines())
+        .map(|(b, a)| {
+            let changed = b
+                .chars()
+                .zip(a.chars())
+                .map(|(b, a)| if b == a { '.' } else { '#' })
+                .collect::<String>();
+            format!("{}  {}  {}\n", b, a, changed)
+        })
+        .collect::<String>();
+    format!(
+        "Mines of Rust {} ({})\n\
+         {:?} at ({}, {}) on a {}x{} board with {} mines, seed {:016x}\n\
+         {}\n\n\
+         Result:\n{}\n\n\
+         Before, after and the squares that changed:\n{}\n\
+         The board after the play:\n{}",
+        env!("CARGO_PKG_VERSION"),
+        env!("MINESOFRUST_GIT_HASH"),
+        play_type,
+        coord.x,
+        coord.y,
+        after.width,
+        after.height,
+        after.num_mines,
+        after.seed,
+        problem,
+        result,
+        player_views,
+        after.to_ascii(true)
+    )
+}
+
+/// Saves a play report in the config directory, returning where it went. Each report gets
+/// a file of its own so none is lost.
+pub fn save_play_report(report: &str) -> Result<PathBuf> {
+    let config_dir = config::config_dir()?;
+    fs::create_dir_all(&config_dir)?;
+    let path = config_dir.join(format!(
+        "minesofrust-play-report-{}.txt",
+        chrono::Local::now().format("%Y%m%d-%H%M%S")
+    ));
+    fs::write(&path, report).with_context(|| format!("Failed to write {:?}", path))?;
+    Ok(path)
+}
+
+#[test]
+fn test_play_report() -> Result<(), Error> {
+    let before = GameBoard::new_with_mines(5, 1, &[Coordinate::from((3, 0))])?;
+    let mut after = before.clone();
+    let result = after.play(0, 0, RevealType::Reveal)?;
+    assert_eq!(unexpected_result(&before, &after, &result), None);
+
+    // A result that doesn't match what happened to the board
+    let mut wrong = result.clone();
+    wrong.changes[2].kind = ChangeKind::Exploded;
+    assert!(unexpected_result(&before, &after, &wrong).is_some_and(|p| p.contains("Exploded")));
+    let mut twice = result.clone();
+    twice.changes.push(result.changes[0].clone());
+    assert!(unexpected_result(&before, &after, &twice).is_some());
+    assert!(unexpected_result(&before, &after, &PlayResult::default()).is_some());
+    let flagged = PlayResult::single(Coordinate::from((3, 0)), ChangeKind::Flagged);
+    assert!(unexpected_result(&after, &after, &flagged).is_some());
+
+    let report = play_report(
+        &before,
+        &after,
+        &Coordinate::from((0, 0)),
+        &RevealType::Reveal,
+        &Ok(wrong),
+        "Something went wrong",
+    );
+    assert!(report.contains("Reveal at (0, 0) on a 5x1 board with 1 mines"));
+    assert!(report.contains("Something went wrong"));
+    assert!(report.contains("-----  ..1--  ###..\n"));
+    assert!(report.contains("The board after the play:\n..1*1\n"));
+    Ok(())
+}
+
+#[test]
+fn test_engine_results_are_expected() -> Result<(), Error> {
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+
+    // Random plays on random boards never give a result that doesn't match the board
+    let mut rng = StdRng::seed_from_u64(7);
+    let play_types = [
+        RevealType::Reveal,
+        RevealType::RevealChord,
+        RevealType::Chord,
+        RevealType::Flag,
+    ];
+    (0..200).try_for_each(|seed| {
+        let mut board = GameBoard::new_seeded(9, 9, seed);
+        board.populate_mines_around(10, Some(Coordinate::from((4, 4))))?;
+        board.populate_numerals()?;
+        (0..60).try_for_each(|_| {
+            let (x, y) = (rng.gen_range(0..9), rng.gen_range(0..9));
+            let play_type = play_types[rng.gen_range(0..play_types.len())].clone();
+            let before = board.clone();
+            let result = board.play(x, y, play_type.clone())?;
+            assert!(!is_suspect_result(
+                BoardCounts::of(&before),
+                &board,
+                &result
+            ));
+            assert_eq!(
+                unexpected_result(&before, &board, &result),
+                None,
+                "{}",
+                play_report(
+                    &before,
+                    &board,
+                    &Coordinate::from((x, y)),
+                    &play_type,
+                    &Ok(result),
+                    ""
+                )
+            );
+            Ok(())
+        })
+    })
+}
@@ -0,0 +1,265 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+
+use crate::minesweeper::*;
+use crate::solver;
+use crate::state::GameSettings;
+
+/// Boards generated between looks at whether a background analysis has been cancelled,
+/// and on the web, each frame
+const BOARDS_PER_STEP: u32 = 20;
+
+/// The values of one measurement across a batch of boards, kept sorted
+#[derive(Debug, Clone, Default)]
+pub struct Distribution {
+    values: Vec<u32>,
+}
+
+impl Distribution {
+    fn new(mut values: Vec<u32>) -> Self {
+        values.sort_unstable();
+        Distribution { values }
+    }
+
+    pub fn min(&self) -> u32 {
+        self.values.first().copied().unwrap_or(0)
+    }
+
+    pub fn max(&self) -> u32 {
+        self.values.last().copied().unwrap_or(0)
+    }
+
+    pub fn mean(&self) -> f64 {
+        if self.values.is_empty() {
+            return 0.0;
+        }
+        self.values.iter().map(|v| *v as f64).sum::<f64>() / self.values.len() as f64
+    }
+
+    /// The value `p` percent of the way through the batch, 50 being the median
+    pub fn percentile(&self, p: f64) -> u32 {
+        if self.values.is_empty() {
+            return 0;
+        }
+        let idx = (p.clamp(0.0, 100.0) / 100.0 * (self.values.len() - 1) as f64).round();
+        self.values[idx as usize]
+    }
+
+    /// How many values fall into each of `num_bins` equal ranges from the smallest value to
+    /// the largest, as (start of the range, count)
+    pub fn histogram(&self, num_bins: u32) -> Vec<(u32, u32)> {
+        let num_bins = num_bins.max(1);
+        let bin_width = ((self.max() - self.min()) / num_bins + 1).max(1);
+        let mut bins: Vec<(u32, u32)> = (0..num_bins)
+            .map(|i| (self.min() + i * bin_width, 0))
+            .collect();
+        self.values.iter().for_each(|v| {
+            let bin = (((v - self.min()) / bin_width) as usize).min(bins.len() - 1);
+            bins[bin].1 += 1;
+        });
+        bins
+    }
+}
+
+/// What a batch of generated boards looks like, for tuning the difficulty presets
+#[derive(Debug, Clone)]
+pub struct BoardStats {
+    pub num_boards: u32,
+    /// Boards generated from `seed` on, one each
+    pub seed: u64,
+    pub bbbv: Distribution,
+    /// Squares revealed by the first click
+    pub opening: Distribution,
+    /// Boards that can't be cleared from the first click without guessing
+    pub guess_required: u32,
+}
+
+impl BoardStats {
+    /// Percentage of the boards that need a guess
+    pub fn guess_rate(&self) -> f64 {
+        if self.num_boards == 0 {
+            0.0
+        } else {
+            self.guess_required as f64 / self.num_boards as f64 * 100.0
+        }
+    }
+}
+
+/// Generates `n` boards with `settings` from `seed` on, each opened in the middle the way
+/// the game keeps the first click clear, and measures them
+pub fn analyze_seeded_boards(
+    settings: &GameSettings,
+    n: u32,
+    seed: u64,
+) -> Result<BoardStats, Error> {
+    let mut analysis = BoardAnalysis::new(settings, n, seed);
+    analysis.step(n)?;
+    Ok(analysis.stats())
+}
+
+/// Boards being generated and measured a few at a time, so a big batch can be shown
+/// progressing and stopped part way
+#[derive(Clone)]
+pub struct BoardAnalysis {
+    settings: GameSettings,
+    num_boards: u32,
+    seed: u64,
+    bbbv: Vec<u32>,
+    opening: Vec<u32>,
+    guess_required: u32,
+}
+
+impl BoardAnalysis {
+    pub fn new(settings: &GameSettings, num_boards: u32, seed: u64) -> Self {
+        BoardAnalysis {
+            settings: settings.clone(),
+            num_boards,
+            seed,
+            bbbv: vec![],
+            opening: vec![],
+            guess_required: 0,
+        }
+    }
+
+    pub fn num_done(&self) -> u32 {
+        self.bbbv.len() as u32
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.num_done() >= self.num_boards
+    }
+
+    /// How far through the boards the analysis is, from 0 to 1
+    pub fn fraction_done(&self) -> f32 {
+        self.num_done() as f32 / self.num_boards.max(1) as f32
+    }
+
+    /// Generates and measures the next `count` boards, or as many as are left
+    pub fn step(&mut self, count: u32) -> Result<(), Error> {
+        let settings = &self.settings;
+        let first_click = Coordinate::from((settings.width / 2, settings.height / 2));
+        let start = self.num_done();
+        (start..(start + count).min(self.num_boards)).try_for_each(|i| {
+            let mut board = settings.new_board(self.seed.wrapping_add(i as u64));
+            board.populate_mines_around(settings.num_mines, Some(first_click.clone()))?;
+            board.populate_numerals()?;
+            self.bbbv.push(board.bbbv());
+
+            board.play(first_click.x, first_click.y, RevealType::Reveal)?;
+            self.opening.push(board.num_revealed());
+            if !solver::solve(&mut board, &first_click)?.won {
+                self.guess_required += 1;
+            }
+            Ok(())
+        })
+    }
+
+    /// The boards measured so far
+    pub fn stats(&self) -> BoardStats {
+        BoardStats {
+            num_boards: self.num_done(),
+            seed: self.seed,
+            bbbv: Distribution::new(self.bbbv.clone()),
+            opening: Distribution::new(self.opening.clone()),
+            guess_required: self.guess_required,
+        }
+    }
+}
+
+/// A board analysis run without holding up the UI: on a background thread, or on the web,
+/// where there are no threads, a few boards each time it's polled
+#[derive(Clone)]
+pub struct AnalysisJob {
+    analysis: Arc<Mutex<BoardAnalysis>>,
+    result: Arc<Mutex<Option<Result<BoardStats, String>>>>,
+    cancelled: Arc<AtomicBool>,
+}
+
+impl AnalysisJob {
+    /// Starts analyzing `n` boards with `settings`, from a random seed
+    pub fn start(settings: &GameSettings, n: u32) -> Self {
+        let job = AnalysisJob {
+            analysis: Arc::new(Mutex::new(BoardAnalysis::new(settings, n, rand::random()))),
+            result: Arc::new(Mutex::new(None)),
+            cancelled: Arc::new(AtomicBool::new(false)),
+        };
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            let job = job.clone();
+            std::thread::spawn(move || while job.step() {});
+        }
+        job
+    }
+
+    /// Analyzes the next few boards. Returns whether there are more to do.
+    fn step(&self) -> bool {
+        if self.cancelled.load(Ordering::Relaxed) {
+            return false;
+        }
+        let Ok(mut analysis) = self.analysis.lock() else {
+            return false;
+        };
+        let result = match analysis.step(BOARDS_PER_STEP) {
+            Ok(()) if !analysis.is_done() => return true,
+            Ok(()) => Ok(analysis.stats()),
+            Err(e) => Err(e.to_string()),
+        };
+        if let Ok(mut r) = self.result.lock() {
+            *r = Some(result);
+        }
+        false
+    }
+
+    /// How far through the boards the analysis is, from 0 to 1
+    pub fn fraction_done(&self) -> f32 {
+        self.analysis.lock().map_or(0.0, |a| a.fraction_done())
+    }
+
+    /// Stops the analysis. Nothing is reported for it after this.
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    /// The stats once the analysis has finished. Call once per frame.
+    pub fn poll(&self) -> Option<Result<BoardStats, String>> {
+        #[cfg(target_arch = "wasm32")]
+        self.step();
+        self.result.lock().ok()?.take()
+    }
+}
+
+#[test]
+fn test_distribution() {
+    let d = Distribution::new(vec![5, 1, 3, 2, 4]);
+    assert_eq!((d.min(), d.max()), (1, 5));
+    assert_eq!(d.mean(), 3.0);
+    assert_eq!(d.percentile(50.0), 3);
+    assert_eq!(d.percentile(100.0), 5);
+    assert_eq!(d.histogram(2), [(1, 3), (4, 2)]);
+    assert_eq!(Distribution::default().percentile(50.0), 0);
+}
+
+#[test]
+fn test_analyze_boards() -> Result<(), Error> {
+    let settings = GameSettings::beginner();
+    let stats = analyze_seeded_boards(&settings, 20, 7)?;
+    assert_eq!(stats.num_boards, 20);
+    // The first click is always kept clear, so it opens at least its own 3x3 block
+    assert!(stats.opening.min() >= 9);
+    assert!(stats.bbbv.min() >= 1);
+    assert!(stats.guess_required <= 20);
+
+    // The same seed gives the same boards
+    let again = analyze_seeded_boards(&settings, 20, 7)?;
+    assert_eq!(again.bbbv.mean(), stats.bbbv.mean());
+    assert_eq!(again.guess_required, stats.guess_required);
+
+    // Analyzing them a few at a time makes no difference
+    let mut analysis = BoardAnalysis::new(&settings, 20, 7);
+    analysis.step(15)?;
+    assert_eq!(analysis.fraction_done(), 0.75);
+    analysis.step(15)?;
+    assert!(analysis.is_done());
+    assert_eq!(analysis.stats().bbbv.mean(), stats.bbbv.mean());
+    Ok(())
+}
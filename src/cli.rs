@@ -0,0 +1,361 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::time::Instant;
+
+use anyhow::{anyhow, Result};
+
+use crate::calibrate::{self, Distribution};
+use crate::enums::GameDifficulty;
+use crate::minesweeper::{Coordinate, GameBoard};
+use crate::placement::MinePlacement;
+use crate::solver;
+use crate::state::GameSettings;
+
+const USAGE: &str = "\
+Usage: minesweeper-cli <command> [options]
+
+Commands:
+    generate <size> [--count N] [--seed N] [--first-click X,Y] [--placement P] [--out DIR]
+        Generates boards, printing them or writing each to DIR as board-<seed>.txt
+    bbbv <file>...
+        Prints the 3BV of each board
+    solve <file> [--first-click X,Y]
+        Plays a board without guessing and prints how far it got
+    simulate <size> [--games N] [--seed N] [--placement P]
+        Solves N generated boards and prints how many could be won without guessing
+    analyze <size> [--boards N] [--seed N] [--placement P]
+        Prints the spread of 3BV and opening sizes over N generated boards, and how
+        many of them need a guess
+    print <file>
+        Prints a board along with its size, mines and 3BV
+
+<size> is beginner, intermediate, expert or WIDTHxHEIGHTxMINES, as in 30x16x80. Boards
+are text files with a line per row: `*` for a mine, and a numeral or `.` for a safe
+square. Lines starting with `#` are comments. The first click defaults to the middle of
+the board. Mines are spread by the placement: uniform (the default), clustered, gradient
+or radial.";
+
+/// Command line arguments: the positional ones in order, and the value of each
+/// `--name value` option
+struct Args {
+    positional: Vec<String>,
+    options: HashMap<String, String>,
+}
+
+impl Args {
+    fn parse(args: &[String]) -> Result<Self> {
+        let mut positional = vec![];
+        let mut options = HashMap::new();
+        let mut iter = args.iter();
+        while let Some(arg) = iter.next() {
+            match arg.strip_prefix("--") {
+                Some(name) => {
+                    let value = iter
+                        .next()
+                        .ok_or_else(|| anyhow!("--{} needs a value", name))?;
+                    options.insert(name.to_string(), value.clone());
+                }
+                None => positional.push(arg.clone()),
+            }
+        }
+        Ok(Args {
+            positional,
+            options,
+        })
+    }
+
+    fn positional(&self, index: usize, what: &str) -> Result<&str> {
+        self.positional
+            .get(index)
+            .map(|s| s.as_str())
+            .ok_or_else(|| anyhow!("Missing {}\n\n{}", what, USAGE))
+    }
+
+    fn option<T: FromStr>(&self, name: &str) -> Result<Option<T>> {
+        self.options
+            .get(name)
+            .map(|v| {
+                v.parse()
+                    .map_err(|_| anyhow!("Invalid value for --{}: {}", name, v))
+            })
+            .transpose()
+    }
+
+    fn placement(&self) -> Result<MinePlacement> {
+        Ok(self.option("placement")?.unwrap_or_default())
+    }
+
+    fn first_click(&self, width: u32, height: u32) -> Result<Coordinate> {
+        match self.options.get("first-click") {
+            Some(v) => parse_coordinate(v),
+            None => Ok(Coordinate::from((width / 2, height / 2))),
+        }
+    }
+}
+
+/// Width, height and number of mines of a board size given by name or as
+/// WIDTHxHEIGHTxMINES
+fn parse_size(size: &str) -> Result<(u32, u32, u32)> {
+    let difficulty = match size.to_lowercase().as_str() {
+        "beginner" => Some(GameDifficulty::Beginner),
+        "intermediate" => Some(GameDifficulty::Intermediate),
+        "expert" => Some(GameDifficulty::Expert),
+        _ => None,
+    };
+    if let Some(difficulty) = difficulty {
+        let settings = GameSettings::settings_for_difficulty(&difficulty);
+        return Ok((settings.width, settings.height, settings.num_mines));
+    }
+    let parts = size
+        .split('x')
+        .map(|p| p.parse())
+        .collect::<Result<Vec<u32>, _>>()
+        .map_err(|_| anyhow!("Invalid board size: {}", size))?;
+    match parts[..] {
+        [width, height, num_mines] if width > 0 && height > 0 => Ok((width, height, num_mines)),
+        _ => Err(anyhow!("Invalid board size: {}", size)),
+    }
+}
+
+pub(crate) fn parse_coordinate(coord: &str) -> Result<Coordinate> {
+    coord
+        .split_once(',')
+        .and_then(|(x, y)| {
+            Some(Coordinate::from((
+                x.trim().parse().ok()?,
+                y.trim().parse().ok()?,
+            )))
+        })
+        .ok_or_else(|| anyhow!("Invalid coordinate, expected X,Y: {}", coord))
+}
+
+fn load_board(path: &Path) -> Result<GameBoard> {
+    let text = std::fs::read_to_string(path)
+        .map_err(|e| anyhow!("Unable to read {}: {}", path.display(), e))?;
+    GameBoard::from_ascii(&text).map_err(|e| anyhow!("{}: {}", path.display(), e))
+}
+
+/// Builds a populated board whose mines are reproducible from `seed`
+fn generate_board(
+    (width, height, num_mines): (u32, u32, u32),
+    seed: u64,
+    first_click: &Coordinate,
+    placement: MinePlacement,
+) -> Result<GameBoard> {
+    let mut board = GameBoard::new_seeded(width, height, seed);
+    board.placement = placement;
+    board.populate_mines_around(num_mines, Some(first_click.clone()))?;
+    board.populate_numerals()?;
+    Ok(board)
+}
+
+fn generate(args: &Args) -> Result<()> {
+    let size = parse_size(args.positional(1, "board size")?)?;
+    let first_click = args.first_click(size.0, size.1)?;
+    let count: u64 = args.option("count")?.unwrap_or(1);
+    let seed: u64 = args.option("seed")?.unwrap_or_else(rand::random);
+    let placement = args.placement()?;
+    let out: Option<PathBuf> = args.option("out")?;
+    if let Some(dir) = &out {
+        std::fs::create_dir_all(dir)?;
+    }
+
+    (seed..seed.saturating_add(count)).try_for_each(|seed| {
+        let text = generate_board(size, seed, &first_click, placement)?.to_ascii(true);
+        match &out {
+            Some(dir) => {
+                let path = dir.join(format!("board-{}.txt", seed));
+                std::fs::write(&path, text)?;
+                println!("{}", path.display());
+            }
+            None if placement == MinePlacement::Uniform => println!("# seed {}\n{}", seed, text),
+            None => println!(
+                "# seed {}, {} placement\n{}",
+                seed,
+                placement.as_str(),
+                text
+            ),
+        }
+        Ok(())
+    })
+}
+
+fn bbbv(args: &Args) -> Result<()> {
+    args.positional(1, "board file")?;
+    args.positional[1..].iter().try_for_each(|path| {
+        let board = load_board(Path::new(path))?;
+        println!("{}: {}", path, board.bbbv());
+        Ok(())
+    })
+}
+
+fn solve(args: &Args) -> Result<()> {
+    let mut board = load_board(Path::new(args.positional(1, "board file")?))?;
+    let first_click = args.first_click(board.width, board.height)?;
+    if board.get_square_by_coordinate(&first_click)?.is_mine() {
+        return Err(anyhow!(
+            "The first click ({}, {}) is a mine, choose another with --first-click",
+            first_click.x,
+            first_click.y
+        ));
+    }
+    let solution = solver::solve(&mut board, &first_click)?;
+    print!("{}", board.to_ascii(false));
+    if solution.won {
+        println!("Solved without guessing in {} steps", solution.steps);
+    } else {
+        println!(
+            "Stuck after {} steps with {} safe squares left, a guess is needed",
+            solution.steps, solution.remaining
+        );
+    }
+    Ok(())
+}
+
+fn simulate(args: &Args) -> Result<()> {
+    let size = parse_size(args.positional(1, "board size")?)?;
+    let first_click = args.first_click(size.0, size.1)?;
+    let games: u64 = args.option("games")?.unwrap_or(1000);
+    let seed: u64 = args.option("seed")?.unwrap_or_else(rand::random);
+    let placement = args.placement()?;
+    if games == 0 {
+        return Err(anyhow!("--games must be at least 1"));
+    }
+
+    let started = Instant::now();
+    let mut won = 0;
+    let mut total_bbbv = 0;
+    let mut total_cleared = 0.0;
+    (seed..seed.saturating_add(games)).try_for_each(|seed| {
+        let mut board = generate_board(size, seed, &first_click, placement)?;
+        let solution = solver::solve(&mut board, &first_click)?;
+        let num_safe = size.0 * size.1 - size.2;
+        won += solution.won as u64;
+        total_bbbv += board.bbbv() as u64;
+        total_cleared += 1.0 - solution.remaining as f64 / num_safe.max(1) as f64;
+        Ok::<(), anyhow::Error>(())
+    })?;
+
+    println!("Games:                   {} (seeds {} on)", games, seed);
+    println!(
+        "Won without guessing:    {} ({:.1}%)",
+        won,
+        won as f64 / games as f64 * 100.0
+    );
+    println!(
+        "Average 3BV:             {:.1}",
+        total_bbbv as f64 / games as f64
+    );
+    println!(
+        "Average cleared:         {:.1}%",
+        total_cleared / games as f64 * 100.0
+    );
+    println!(
+        "Time:                    {:.2}s",
+        started.elapsed().as_secs_f64()
+    );
+    Ok(())
+}
+
+fn analyze(args: &Args) -> Result<()> {
+    let (width, height, num_mines) = parse_size(args.positional(1, "board size")?)?;
+    let settings = GameSettings {
+        width,
+        height,
+        num_mines,
+        placement: args.placement()?,
+        ..GameSettings::beginner()
+    };
+    let boards: u32 = args.option("boards")?.unwrap_or(1000);
+    let seed: u64 = args.option("seed")?.unwrap_or_else(rand::random);
+    let stats = calibrate::analyze_seeded_boards(&settings, boards, seed)?;
+
+    let row = |name: &str, d: &Distribution| {
+        println!(
+            "{:<10}{:>8}{:>8}{:>8}{:>8}{:>8}{:>8.1}",
+            name,
+            d.min(),
+            d.percentile(10.0),
+            d.percentile(50.0),
+            d.percentile(90.0),
+            d.max(),
+            d.mean()
+        );
+    };
+    println!("Boards:   {} (seeds {} on)", stats.num_boards, stats.seed);
+    println!(
+        "Need a guess: {} ({:.1}%)\n",
+        stats.guess_required,
+        stats.guess_rate()
+    );
+    println!(
+        "{:<10}{:>8}{:>8}{:>8}{:>8}{:>8}{:>8}",
+        "", "min", "10%", "median", "90%", "max", "mean"
+    );
+    row("3BV", &stats.bbbv);
+    row("Opening", &stats.opening);
+    Ok(())
+}
+
+fn print(args: &Args) -> Result<()> {
+    let board = load_board(Path::new(args.positional(1, "board file")?))?;
+    println!(
+        "{}x{}, {} mines, 3BV {}",
+        board.width,
+        board.height,
+        board.num_mines,
+        board.bbbv()
+    );
+    print!("{}", board.to_ascii(true));
+    Ok(())
+}
+
+/// Runs `minesweeper-cli` with its arguments, less the program name
+pub fn run(args: &[String]) -> Result<()> {
+    let args = Args::parse(args)?;
+    match args.positional.first().map(|s| s.as_str()) {
+        Some("generate") => generate(&args),
+        Some("bbbv") => bbbv(&args),
+        Some("solve") => solve(&args),
+        Some("simulate") => simulate(&args),
+        Some("analyze") => analyze(&args),
+        Some("print") => print(&args),
+        Some("help") | None => {
+            println!("{}", USAGE);
+            Ok(())
+        }
+        Some(command) => Err(anyhow!("Unknown command: {}\n\n{}", command, USAGE)),
+    }
+}
+
+#[test]
+fn test_parse_args() -> Result<()> {
+    assert_eq!(parse_size("expert")?, (30, 16, 80));
+    assert_eq!(parse_size("Beginner")?, (9, 9, 10));
+    assert_eq!(parse_size("40x20x150")?, (40, 20, 150));
+    assert!(parse_size("40x20").is_err());
+    assert!(parse_size("0x20x1").is_err());
+    assert_eq!(parse_coordinate("3, 4")?, Coordinate::from((3, 4)));
+    assert!(parse_coordinate("3").is_err());
+
+    let args: Vec<String> = [
+        "simulate",
+        "expert",
+        "--games",
+        "50",
+        "--placement",
+        "radial",
+    ]
+    .iter()
+    .map(|s| s.to_string())
+    .collect();
+    let args = Args::parse(&args)?;
+    assert_eq!(args.positional(1, "board size")?, "expert");
+    assert_eq!(args.option::<u64>("games")?, Some(50));
+    assert_eq!(args.option::<u64>("seed")?, None);
+    assert_eq!(args.first_click(30, 16)?, Coordinate::from((15, 8)));
+    assert_eq!(args.placement()?, MinePlacement::Radial);
+    assert!(Args::parse(&["--seed".to_string()]).is_err());
+    Ok(())
+}
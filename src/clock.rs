@@ -0,0 +1,131 @@
+use std::time::{Duration, Instant};
+
+/// Times a game, leaving out the time spent paused. Runs off the monotonic clock, so
+/// changes to the system clock don't affect it.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct GameClock {
+    /// When the clock was last started or resumed, while it's running
+    running_since: Option<Instant>,
+    /// Time counted up to the last pause or stop
+    counted: Duration,
+    /// When the clock was stopped at the end of the game
+    stopped_at: Option<Instant>,
+}
+
+impl GameClock {
+    /// Sets the clock back to zero, without starting it
+    pub fn reset(&mut self) {
+        *self = GameClock::default();
+    }
+
+    /// Sets the clock back to zero and starts it
+    pub fn start(&mut self) {
+        self.start_at(Instant::now());
+    }
+
+    /// Starts the clock with `seconds` already counted, for a game picked up part way through
+    pub fn start_from(&mut self, seconds: f64) {
+        self.start();
+        self.counted = Duration::from_secs_f64(seconds);
+    }
+
+    pub fn pause(&mut self) {
+        self.pause_at(Instant::now());
+    }
+
+    pub fn resume(&mut self) {
+        self.resume_at(Instant::now());
+    }
+
+    /// Stops the clock at the end of the game, returning the seconds counted
+    pub fn stop(&mut self) -> f64 {
+        self.stop_at(Instant::now())
+    }
+
+    /// Seconds counted so far
+    pub fn elapsed(&self) -> f64 {
+        self.elapsed_at(Instant::now())
+    }
+
+    /// Adds `seconds` to the time counted, as the cost of something done during the game
+    pub fn add_penalty(&mut self, seconds: f64) {
+        self.counted += Duration::from_secs_f64(seconds);
+    }
+
+    /// Seconds since the clock was stopped, if it has been
+    pub fn since_stopped(&self) -> Option<f64> {
+        self.stopped_at.map(|s| s.elapsed().as_secs_f64())
+    }
+
+    fn start_at(&mut self, now: Instant) {
+        *self = GameClock {
+            running_since: Some(now),
+            ..Default::default()
+        };
+    }
+
+    fn pause_at(&mut self, now: Instant) {
+        if let Some(since) = self.running_since.take() {
+            self.counted += now.saturating_duration_since(since);
+        }
+    }
+
+    fn resume_at(&mut self, now: Instant) {
+        if self.running_since.is_none() && self.stopped_at.is_none() {
+            self.running_since = Some(now);
+        }
+    }
+
+    fn stop_at(&mut self, now: Instant) -> f64 {
+        self.pause_at(now);
+        self.stopped_at = Some(now);
+        self.counted.as_secs_f64()
+    }
+
+    fn elapsed_at(&self, now: Instant) -> f64 {
+        let running = self
+            .running_since
+            .map_or(Duration::ZERO, |s| now.saturating_duration_since(s));
+        (self.counted + running).as_secs_f64()
+    }
+}
+
+#[test]
+fn test_game_clock() {
+    let t0 = Instant::now();
+    let at = |secs: u64| t0 + Duration::from_secs(secs);
+
+    let mut clock = GameClock::default();
+    assert_eq!(clock.elapsed_at(at(5)), 0.0);
+
+    clock.start_at(at(0));
+    assert_eq!(clock.elapsed_at(at(3)), 3.0);
+
+    // Time spent paused isn't counted, and pausing or resuming twice changes nothing
+    clock.pause_at(at(4));
+    clock.pause_at(at(6));
+    assert_eq!(clock.elapsed_at(at(10)), 4.0);
+    clock.resume_at(at(10));
+    clock.resume_at(at(11));
+    assert_eq!(clock.elapsed_at(at(12)), 6.0);
+    // Penalties count as time played
+    clock.add_penalty(2.0);
+    assert_eq!(clock.elapsed_at(at(12)), 8.0);
+
+    assert_eq!(clock.stop_at(at(13)), 9.0);
+    assert_eq!(clock.elapsed_at(at(20)), 9.0);
+    // A stopped clock stays stopped until it's started again
+    clock.resume_at(at(21));
+    assert_eq!(clock.elapsed_at(at(30)), 9.0);
+    assert!(clock.since_stopped().is_some());
+
+    clock.start_at(at(40));
+    assert_eq!(clock.elapsed_at(at(41)), 1.0);
+    assert!(clock.since_stopped().is_none());
+    clock.reset();
+    assert_eq!(clock.elapsed_at(at(50)), 0.0);
+
+    // A game picked up part way through goes on from the time it had
+    clock.start_from(100.0);
+    assert!(clock.elapsed() >= 100.0);
+}
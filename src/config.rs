@@ -0,0 +1,243 @@
+use std::fs;
+use std::fs::File;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use anyhow::{anyhow, Context, Result};
+use serde::{de::DeserializeOwned, Serialize};
+
+/// Environment variable that overrides the default config directory
+pub const CONFIG_DIR_ENV_VAR: &str = "MINESOFRUST_CONFIG_DIR";
+
+/// Directory chosen by the user to use in place of the default config directory
+static CONFIG_DIR_OVERRIDE: Mutex<Option<PathBuf>> = Mutex::new(None);
+
+/// Config files written by a newer build, which are left as they are rather than saved over
+static NEWER_FILES: Mutex<Vec<PathBuf>> = Mutex::new(Vec::new());
+
+/// Use `dir` for reading and writing all config files from now on
+pub fn set_config_dir(dir: PathBuf) {
+    if let Ok(mut d) = CONFIG_DIR_OVERRIDE.lock() {
+        *d = Some(dir);
+    }
+}
+
+/// Config directory explicitly requested by the user, either from the UI, the
+/// `--config-dir` command line option, or the environment
+fn config_dir_override() -> Option<PathBuf> {
+    CONFIG_DIR_OVERRIDE
+        .lock()
+        .ok()
+        .and_then(|d| d.clone())
+        .or_else(|| std::env::var_os(CONFIG_DIR_ENV_VAR).map(PathBuf::from))
+}
+
+/// Where config files were kept by earlier versions
+fn legacy_config_dir() -> Option<PathBuf> {
+    dirs::home_dir().map(|h| h.join(".apoapsys/"))
+}
+
+/// Directory that config files are read from and written to. Defaults to the platform's
+/// config location (e.g. `$XDG_CONFIG_HOME/minesofrust` on Linux).
+pub fn config_dir() -> Result<PathBuf> {
+    if let Some(dir) = config_dir_override() {
+        return Ok(dir);
+    }
+    dirs::config_dir()
+        .map(|c| c.join("minesofrust"))
+        .ok_or_else(|| anyhow!("Unable to determine the config directory"))
+}
+
+/// Copies a config file written by an earlier version into the current config directory.
+/// The legacy file is left in place for older versions. If it can't be copied, its path is
+/// returned so that it can still be read.
+fn migrate_legacy_file(file_name: &str, config_file_path: &Path) -> Option<PathBuf> {
+    if config_dir_override().is_some() {
+        return None;
+    }
+    let legacy_path = legacy_config_dir()?.join(file_name);
+    if !legacy_path.exists() {
+        return None;
+    }
+
+    log::info!(
+        "Migrating config file {:?} to {:?}",
+        legacy_path,
+        config_file_path
+    );
+    let copied = config_file_path
+        .parent()
+        .map_or(Ok(()), fs::create_dir_all)
+        .and_then(|_| fs::copy(&legacy_path, config_file_path));
+    match copied {
+        Ok(_) => Some(config_file_path.to_path_buf()),
+        Err(e) => {
+            log::warn!("Failed to migrate config file, reading in place: {}", e);
+            Some(legacy_path)
+        }
+    }
+}
+
+/// A config file was written by a newer build than this one
+#[derive(Debug, thiserror::Error)]
+#[error("File version {version} is newer than the supported version {supported}")]
+pub struct NewerVersionError {
+    pub version: i64,
+    pub supported: i64,
+}
+
+/// A config file with a schema `version` field, able to upgrade files written by older versions
+pub trait Versioned: DeserializeOwned + Serialize {
+    /// Schema version written by this build
+    const VERSION: i64;
+
+    /// Upgrades the raw contents of a file from `from_version` to `from_version + 1`
+    fn migrate(_table: &mut toml::Table, _from_version: i64) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// Parses the contents of a versioned config file, migrating it to the current version.
+/// Files from before versioning was introduced are treated as version 0.
+pub fn parse_versioned<T: Versioned>(t: &str) -> Result<T> {
+    from_versioned_table(toml::from_str(t)?)
+}
+
+/// Like `parse_versioned`, for a file's contents that have already been parsed, or a table
+/// holding one within a larger file
+pub fn from_versioned_table<T: Versioned>(mut table: toml::Table) -> Result<T> {
+    let mut version = table
+        .get("version")
+        .and_then(|v| v.as_integer())
+        .unwrap_or(0);
+    if version > T::VERSION {
+        return Err(NewerVersionError {
+            version,
+            supported: T::VERSION,
+        }
+        .into());
+    }
+
+    while version < T::VERSION {
+        T::migrate(&mut table, version)?;
+        version += 1;
+    }
+    table.insert("version".to_string(), toml::Value::Integer(version));
+    Ok(toml::Value::Table(table).try_into()?)
+}
+
+/// Reads a TOML config file. Returns `None` if the file doesn't exist yet.
+///
+/// A file that can't be parsed is renamed with a timestamped `.bak` extension so it isn't
+/// overwritten on exit, and an error describing where it went is returned. A file written by
+/// a newer build is left where it is, and isn't saved over until the app is restarted.
+pub fn load_toml<T: Versioned>(file_name: &str) -> Result<Option<T>> {
+    let saved_path = config_dir()?.join(file_name);
+    let mut config_file_path = saved_path.clone();
+    if !config_file_path.exists() {
+        if let Some(p) = migrate_legacy_file(file_name, &config_file_path) {
+            config_file_path = p;
+        }
+    }
+    if !config_file_path.exists() {
+        log::info!(
+            "Config file {:?} does not exist. Will be created on exit",
+            config_file_path
+        );
+        return Ok(None);
+    }
+
+    log::debug!("Config file exists at path: {:?}", config_file_path);
+    let t = fs::read_to_string(&config_file_path)
+        .with_context(|| format!("Failed to read {:?}", config_file_path))?;
+    match parse_versioned(&t) {
+        Ok(v) => Ok(Some(v)),
+        Err(e) if e.is::<NewerVersionError>() => {
+            if let Ok(mut newer) = NEWER_FILES.lock() {
+                newer.push(saved_path);
+            }
+            Err(anyhow!(
+                "{:?} is from a newer version and won't be changed: {}",
+                config_file_path,
+                e
+            ))
+        }
+        Err(e) => {
+            let backup_path = config_file_path.with_extension(format!(
+                "toml.{}.bak",
+                chrono::Local::now().format("%Y%m%d-%H%M%S")
+            ));
+            fs::rename(&config_file_path, &backup_path).with_context(|| {
+                format!("Failed to back up corrupt file {:?}", config_file_path)
+            })?;
+            Err(anyhow!(
+                "{:?} is corrupt and was moved to {:?}: {}",
+                config_file_path,
+                backup_path,
+                e
+            ))
+        }
+    }
+}
+
+/// Removes a config file, if it's there
+#[cfg(not(target_arch = "wasm32"))]
+pub fn remove_file(file_name: &str) -> Result<()> {
+    let path = config_dir()?.join(file_name);
+    match fs::remove_file(&path) {
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+            Err(e).with_context(|| format!("Failed to remove {:?}", path))
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Writes a value to a TOML config file, creating the config directory if needed.
+///
+/// The file is written to a temporary file and then renamed over the original, so a crash
+/// mid-save leaves either the old or the new contents in place, never a partial file.
+pub fn save_toml<T: Versioned>(value: &T, file_name: &str) -> Result<()> {
+    let toml_str = toml::to_string(value)?;
+    let config_dir = config_dir()?;
+    let config_file_path = config_dir.join(file_name);
+    if NEWER_FILES
+        .lock()
+        .is_ok_and(|newer| newer.contains(&config_file_path))
+    {
+        log::info!(
+            "Not saving over {:?}, from a newer version",
+            config_file_path
+        );
+        return Ok(());
+    }
+    if !config_dir.exists() {
+        fs::create_dir_all(&config_dir)
+            .with_context(|| format!("Failed to create config directory {:?}", config_dir))?;
+    }
+    let temp_file_path = config_file_path.with_extension("toml.tmp");
+
+    let mut f = File::create(&temp_file_path)
+        .with_context(|| format!("Failed to create {:?}", temp_file_path))?;
+    f.write_all(toml_str.as_bytes())
+        .and_then(|_| f.sync_all())
+        .with_context(|| format!("Failed to write {:?}", temp_file_path))?;
+    fs::rename(&temp_file_path, &config_file_path)
+        .with_context(|| format!("Failed to replace {:?}", config_file_path))?;
+    Ok(())
+}
+
+#[test]
+fn test_newer_version_is_distinct() {
+    #[derive(serde::Deserialize, Serialize)]
+    struct Settings {
+        version: i64,
+    }
+    impl Versioned for Settings {
+        const VERSION: i64 = 2;
+    }
+
+    assert!(parse_versioned::<Settings>("version = 1").is_ok_and(|s| s.version == 2));
+    assert!(parse_versioned::<Settings>("version = 3").is_err_and(|e| e.is::<NewerVersionError>()));
+    assert!(parse_versioned::<Settings>("version = ").is_err_and(|e| !e.is::<NewerVersionError>()));
+}
@@ -4,23 +4,166 @@ pub const DEFAULT_BEGINNER_WIDTH: u32 = 9;
 pub const DEFAULT_BEGINNER_HEIGHT: u32 = 9;
 pub const DEFAULT_BEGINNER_NUM_MINES: u32 = 10;
 pub const DEFAULT_BEGINNER_UI_WIDTH: f32 = 376.0;
-pub const DEFAULT_BEGINNER_UI_HEIGHT: f32 = 605.0;
+pub const DEFAULT_BEGINNER_UI_HEIGHT: f32 = 505.0;
 
 pub const DEFAULT_INTERMEDIATE_WIDTH: u32 = 16;
 pub const DEFAULT_INTERMEDIATE_HEIGHT: u32 = 16;
 pub const DEFAULT_INTERMEDIATE_NUM_MINES: u32 = 40;
 pub const DEFAULT_INTERMEDIATE_UI_WIDTH: f32 = 655.0;
-pub const DEFAULT_INTERMEDIATE_UI_HEIGHT: f32 = 885.0;
+pub const DEFAULT_INTERMEDIATE_UI_HEIGHT: f32 = 785.0;
 
 pub const DEFAULT_EXPERT_WIDTH: u32 = 30;
 pub const DEFAULT_EXPERT_HEIGHT: u32 = 16;
 pub const DEFAULT_EXPERT_NUM_MINES: u32 = 80;
 pub const DEFAULT_EXPERT_UI_WIDTH: f32 = 1215.0;
-pub const DEFAULT_EXPERT_UI_HEIGHT: f32 = 885.0;
+pub const DEFAULT_EXPERT_UI_HEIGHT: f32 = 785.0;
+
+/// Window size for a board of any other size: this much for each square, plus room for the
+/// menu, status and controls
+pub const SQUARE_UI_SIZE: f32 = 40.0;
+pub const BOARD_UI_EXTRA_WIDTH: f32 = 15.0;
+pub const BOARD_UI_EXTRA_HEIGHT: f32 = 145.0;
+/// Extra window height for the tutorial's instructions
+pub const TUTORIAL_UI_HEIGHT: f32 = 90.0;
+/// Size of the banner across the boards while the mines are shown
+pub const PRACTICE_BANNER_FONT_SIZE: f32 = 48.0;
+
+pub const DEFAULT_AUTO_RESTART_SECONDS: f64 = 2.0;
+/// Wins kept on each leaderboard unless the player changes it
+pub const DEFAULT_LEADERBOARD_MAX_ENTRIES: u32 = 25;
+/// Leaderboard entries shown at a time
+pub const LEADERBOARD_PAGE_SIZE: usize = 20;
+/// Handicaps a player starts with when they're given one
+pub const DEFAULT_HANDICAP_PERCENT: f64 = 10.0;
+pub const DEFAULT_HANDICAP_SECONDS: f64 = 5.0;
+/// Seconds after a reveal before the squares it proved safe are opened
+pub const DEFAULT_AUTO_OPEN_SECONDS: f64 = 0.3;
+/// Seconds of play between saves of the game in progress
+pub const DEFAULT_AUTOSAVE_SECONDS: f64 = 30.0;
+pub const DEFAULT_OVERLAY_SECONDS: f64 = 0.5;
+pub const DEFAULT_FOG_RADIUS: f32 = 1.5;
+/// A sonar ping clears the fog this many times further out, for this many seconds, at the
+/// cost of this many seconds on the clock
+pub const SONAR_RADIUS_FACTOR: f32 = 3.0;
+pub const SONAR_SECONDS: f64 = 1.0;
+pub const SONAR_TIME_PENALTY: f64 = 5.0;
+/// Seconds added to the time for each mistake when playing with time penalties
+pub const PENALTY_WRONG_FLAG_SECONDS: f64 = 5.0;
+pub const PENALTY_HELD_CHORD_SECONDS: f64 = 10.0;
+pub const DEFAULT_LONG_PRESS_SECONDS: f64 = 0.5;
+pub const DEFAULT_TWO_FINGER_TAP_SECONDS: f64 = 0.3;
+pub const DEFAULT_REPLAY_CELL_SIZE: u32 = 24;
+pub const DEFAULT_REPLAY_SECONDS_PER_PLAY: f64 = 0.3;
+/// How long the finished board is shown at the end of an exported replay
+pub const REPLAY_END_SECONDS: f64 = 3.0;
+/// Trades the colors of an exported replay for encoding time, from 1 (best) to 30
+pub const REPLAY_GIF_SPEED: i32 = 10;
+/// How long a chord held back for setting off a mine waits to be made again
+pub const CHORD_WARNING_SECONDS: f64 = 2.0;
+#[cfg(feature = "net")]
+pub const DEFAULT_NET_PORT: u16 = 7878;
+#[cfg(feature = "net")]
+pub const DEFAULT_SPECTATE_PORT: u16 = 7879;
+#[cfg(feature = "net")]
+pub const DEFAULT_COOP_PORT: u16 = 7880;
+/// The port LiveSplit's server component listens on unless it's changed
+#[cfg(feature = "livesplit")]
+pub const DEFAULT_LIVESPLIT_PORT: u16 = 16834;
 
-#[allow(dead_code)]
 pub const COLOR_UNREVEALED: Color32 = Color32::from_rgb(0, 92, 128);
 pub const COLOR_BORDER: Color32 = Color32::from_rgb(27, 27, 27);
-pub const COLOR_DETONATED: Color32 = Color32::GOLD;
+pub const COLOR_DETONATED: Color32 = Color32::RED;
+pub const COLOR_CHORD_DETONATED: Color32 = Color32::GOLD;
+pub const COLOR_UNFOUND_MINE_TINT: Color32 = Color32::from_rgba_premultiplied(128, 128, 128, 128);
 pub const COLOR_REVEALED: Color32 = Color32::DARK_GRAY;
 pub const COLOR_MISFLAGGED: Color32 = Color32::from_rgb(255, 188, 188);
+pub const COLOR_MISSED_SAFE: Color32 = Color32::from_rgb(0, 200, 0);
+#[cfg(feature = "net")]
+pub const COLOR_PARTNER_CURSOR: Color32 = Color32::from_rgb(255, 140, 0);
+/// Squares of the click heatmap, more opaque the more they were clicked
+pub const COLOR_HEATMAP: Color32 = Color32::from_rgb(255, 48, 0);
+pub const HEATMAP_MIN_ALPHA: f32 = 0.25;
+pub const HEATMAP_MAX_ALPHA: f32 = 0.75;
+/// Text numerals 1 to 8, in the classic colors, then lightened to read on the dark theme.
+/// Anything higher uses the color for 8.
+pub const COLORS_NUMERAL_LIGHT: [Color32; 8] = [
+    Color32::from_rgb(0, 0, 255),
+    Color32::from_rgb(0, 128, 0),
+    Color32::from_rgb(255, 0, 0),
+    Color32::from_rgb(0, 0, 128),
+    Color32::from_rgb(128, 0, 0),
+    Color32::from_rgb(0, 128, 128),
+    Color32::from_rgb(0, 0, 0),
+    Color32::from_rgb(128, 128, 128),
+];
+pub const COLORS_NUMERAL_DARK: [Color32; 8] = [
+    Color32::from_rgb(96, 150, 255),
+    Color32::from_rgb(80, 200, 80),
+    Color32::from_rgb(255, 96, 96),
+    Color32::from_rgb(170, 120, 255),
+    Color32::from_rgb(255, 160, 64),
+    Color32::from_rgb(64, 210, 210),
+    Color32::from_rgb(230, 230, 230),
+    Color32::from_rgb(160, 160, 160),
+];
+/// Height of a text numeral as a fraction of the square's
+pub const NUMERAL_TEXT_SCALE: f32 = 0.8;
+pub const COLOR_SEGMENT_BACKGROUND: Color32 = Color32::from_rgb(16, 0, 0);
+pub const COLOR_SEGMENT_ON: Color32 = Color32::from_rgb(255, 32, 16);
+pub const COLOR_SEGMENT_OFF: Color32 = Color32::from_rgb(56, 8, 4);
+pub const COLOR_SEGMENT_WARNING_BACKGROUND: Color32 = Color32::from_rgb(176, 8, 0);
+pub const COLOR_SEGMENT_WARNING_ON: Color32 = Color32::from_rgb(255, 236, 200);
+pub const COLOR_SEGMENT_WARNING_OFF: Color32 = Color32::from_rgb(136, 16, 8);
+
+pub const PROGRESS_BAR_HEIGHT: f32 = 4.0;
+/// Gap between boards in multi-board games
+pub const BOARD_SPACING: f32 = 16.0;
+
+/// Size of a square on the maps of a layered board's layers
+pub const LAYER_MAP_SQUARE_SIZE: f32 = 3.0;
+/// Gap between the layer maps, and between them and the board
+pub const LAYER_MAP_SPACING: f32 = 8.0;
+
+/// Longest side of the mini-map shown when the boards don't fit in the window
+pub const MINIMAP_SIZE: f32 = 160.0;
+/// Smallest cell of the mini-map, with squares lumped together to fill it
+pub const MINIMAP_CELL_SIZE: f32 = 3.0;
+/// Gap between the mini-map and the corner of the boards' view
+pub const MINIMAP_MARGIN: f32 = 8.0;
+/// Mines on a layered board, as a fraction of those on as many flat boards. Squares there
+/// have up to 26 neighbors, so the same density would leave few openings.
+pub const LAYERED_MINES_FRACTION: f64 = 0.5;
+
+pub const SEGMENT_DIGIT_HEIGHT: f32 = 28.0;
+pub const SEGMENT_TOP_PADDING: f32 = 10.0;
+/// How far and for how long the mine counter shakes when there are more flags than mines
+pub const FLAG_WARNING_SHAKE_DISTANCE: f32 = 4.0;
+pub const FLAG_WARNING_SHAKE_SECONDS: f32 = 0.4;
+/// Times the counter swings from side to side in one shake
+pub const FLAG_WARNING_SHAKE_SWINGS: f32 = 3.0;
+/// How long each ring of a cascade takes to be shown after the one inside it
+pub const REVEAL_RING_SECONDS: f64 = 0.02;
+pub const COLOR_WARNING: Color32 = Color32::from_rgb(255, 200, 0);
+/// The guess indicator when a safe square can be deduced
+pub const COLOR_NO_GUESS_NEEDED: Color32 = Color32::from_rgb(64, 200, 64);
+/// Outline of the square the tutorial wants played next
+/// The covered squares around a numeral being read
+pub const COLOR_CONSTRAINT: Color32 = Color32::from_rgb(120, 200, 80);
+pub const COLOR_TUTORIAL_TARGET: Color32 = Color32::from_rgb(0, 170, 255);
+/// The banner for a new personal best
+pub const COLOR_PERSONAL_BEST: Color32 = Color32::from_rgb(255, 215, 0);
+pub const COLOR_PRACTICE_BANNER: Color32 = Color32::from_rgba_premultiplied(200, 40, 40, 200);
+pub const COLOR_LAYER_MAP_FLAG: Color32 = Color32::from_rgb(255, 200, 0);
+pub const COLOR_PRACTICE_MINE_TINT: Color32 = Color32::from_rgba_premultiplied(64, 64, 64, 64);
+
+/// Win streaks at least this long get a notification when they end
+pub const MIN_NOTIFIED_WIN_STREAK: u32 = 3;
+
+/// Covered squares with the board skins unlocked by levelling up
+pub const COLOR_SKIN_OCEAN: Color32 = Color32::from_rgb(70, 130, 180);
+pub const COLOR_SKIN_FOREST: Color32 = Color32::from_rgb(60, 120, 70);
+pub const COLOR_SKIN_SUNSET: Color32 = Color32::from_rgb(200, 110, 60);
+pub const COLOR_SKIN_MIDNIGHT: Color32 = Color32::from_rgb(60, 60, 110);
+
+/// Width of the level progress bar in the status area
+pub const LEVEL_BAR_WIDTH: f32 = 80.0;
@@ -0,0 +1,329 @@
+use std::io::Write;
+use std::net::TcpStream;
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::constants::DEFAULT_COOP_PORT;
+use crate::enums::GameDifficulty;
+use crate::minesweeper::{Coordinate, RevealType};
+use crate::net::{accept_connections, connect, encode_line, read_lines, Closer, Peers};
+
+/// The host is always player 0 and the player who joined them player 1
+pub const HOST: usize = 0;
+pub const GUEST: usize = 1;
+
+/// Sent between the two players, one JSON object per line. Plays are only ever made by the
+/// host, in the order it receives them, so both copies of the board stay the same.
+#[derive(Clone, Deserialize, Serialize)]
+#[serde(tag = "type")]
+pub enum CoopMessage {
+    Join {
+        name: String,
+    },
+    /// From the host: a new game on the board made from `seed`
+    Start {
+        difficulty: GameDifficulty,
+        seed: u64,
+    },
+    /// From the guest: asks the host for a new game
+    NewGame {
+        difficulty: GameDifficulty,
+    },
+    /// Asks the host to restart the board, or from the host, restarts it
+    Restart,
+    /// From the guest: a play the host should make for them
+    Request {
+        coord: Coordinate,
+        play_type: RevealType,
+    },
+    /// From the host: a play made by `player`, to be made on the guest's board
+    Move {
+        player: usize,
+        coord: Coordinate,
+        play_type: RevealType,
+    },
+    /// The square the player is pointing at
+    Cursor {
+        coord: Option<Coordinate>,
+    },
+}
+
+impl CoopMessage {
+    /// Whether the host takes the message from the guest. Only the host starts games and
+    /// makes plays.
+    fn allowed_from_guest(&self) -> bool {
+        !matches!(self, CoopMessage::Start { .. } | CoopMessage::Move { .. })
+    }
+}
+
+/// Who placed each of the flags on the board
+#[derive(Clone, Default)]
+pub struct FlagOwners {
+    owners: Vec<(Coordinate, usize)>,
+}
+
+impl FlagOwners {
+    /// Records a flag being placed or taken away by `player`
+    pub fn record(&mut self, player: usize, coord: Coordinate, flagged: bool) {
+        self.owners.retain(|(c, _)| *c != coord);
+        if flagged {
+            self.owners.push((coord, player));
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.owners.clear();
+    }
+
+    /// The flags on the board placed by `player`
+    pub fn placed_by(&self, player: usize) -> impl Iterator<Item = &Coordinate> {
+        self.owners
+            .iter()
+            .filter(move |(_, p)| *p == player)
+            .map(|(c, _)| c)
+    }
+}
+
+/// State shared with the connection threads
+#[derive(Default)]
+struct Shared {
+    peers: Peers,
+    inbox: Vec<CoopMessage>,
+    error: Option<String>,
+}
+
+/// Two players clearing the same board at the same time over the network
+#[derive(Clone)]
+pub struct CoopSession {
+    /// The host's name first
+    pub names: [String; 2],
+    /// Which of the two players this is
+    pub player: usize,
+    pub partner_cursor: Option<Coordinate>,
+    pub flags: FlagOwners,
+    cursor_sent: Option<Coordinate>,
+    shared: Arc<Mutex<Shared>>,
+    closer: Arc<Closer>,
+}
+
+impl CoopSession {
+    fn new(name: &str, player: usize) -> Self {
+        let shared = Arc::new(Mutex::new(Shared::default()));
+        let closing = shared.clone();
+        let mut names = [String::new(), String::new()];
+        names[player] = name.to_string();
+        CoopSession {
+            names,
+            player,
+            partner_cursor: None,
+            flags: FlagOwners::default(),
+            cursor_sent: None,
+            shared,
+            closer: Arc::new(Closer::new(move || {
+                if let Ok(s) = closing.lock() {
+                    s.peers.shutdown();
+                }
+            })),
+        }
+    }
+
+    /// Waits for a partner to join on `port`
+    pub fn host(ctx: &egui::Context, name: &str, port: u16) -> Result<Self> {
+        let session = CoopSession::new(name, HOST);
+        let (shared, ctx) = (session.shared.clone(), ctx.clone());
+        accept_connections(
+            port,
+            session.closer.closed.clone(),
+            move |stream| {
+                let id = {
+                    let mut s = shared
+                        .lock()
+                        .map_err(|_| anyhow!("Connection state poisoned"))?;
+                    // There's only room for one partner
+                    if !s.peers.is_empty() {
+                        return Err(anyhow!("A partner has already joined"));
+                    }
+                    s.peers.add(&stream)?
+                };
+                CoopSession::read(shared.clone(), ctx.clone(), id, stream, true);
+                Ok(())
+            },
+            |e| log::warn!("Stopped accepting partners: {}", e),
+        )?;
+        Ok(session)
+    }
+
+    /// Connects to the host at `address`, which takes the default port if it doesn't
+    /// include one
+    pub fn join(ctx: &egui::Context, name: &str, address: &str) -> Self {
+        let session = CoopSession::new(name, GUEST);
+        let (shared, ctx) = (session.shared.clone(), ctx.clone());
+        let (name, address) = (name.to_string(), address.to_string());
+        thread::spawn(move || {
+            let connect = || -> Result<(usize, TcpStream)> {
+                let mut stream = connect(&address, DEFAULT_COOP_PORT)?;
+                stream.write_all(encode_line(&CoopMessage::Join { name })?.as_bytes())?;
+                let id = shared
+                    .lock()
+                    .map_err(|_| anyhow!("Connection state poisoned"))?
+                    .peers
+                    .add(&stream)?;
+                Ok((id, stream))
+            };
+            match connect() {
+                Ok((id, stream)) => CoopSession::read(shared, ctx, id, stream, false),
+                Err(e) => {
+                    if let Ok(mut s) = shared.lock() {
+                        s.error = Some(format!("Unable to join {}: {}", address, e));
+                    }
+                    ctx.request_repaint();
+                }
+            }
+        });
+        session
+    }
+
+    /// Reads messages from the partner until they leave. The host drops any the guest isn't
+    /// allowed to send.
+    fn read(
+        shared: Arc<Mutex<Shared>>,
+        ctx: egui::Context,
+        id: usize,
+        stream: TcpStream,
+        from_guest: bool,
+    ) {
+        thread::spawn(move || {
+            for line in read_lines(stream) {
+                match serde_json::from_str::<CoopMessage>(&line) {
+                    Ok(message) if from_guest && !message.allowed_from_guest() => {
+                        log::warn!("Ignoring a message the guest can't send: {}", line)
+                    }
+                    Ok(message) => {
+                        if let Ok(mut s) = shared.lock() {
+                            s.inbox.push(message);
+                        }
+                        ctx.request_repaint();
+                    }
+                    Err(e) => log::warn!("Ignoring invalid message: {}", e),
+                }
+            }
+            if let Ok(mut s) = shared.lock() {
+                s.peers.remove(id);
+                s.error = Some("Your partner has left".to_string());
+            }
+            ctx.request_repaint();
+        });
+    }
+
+    pub fn is_host(&self) -> bool {
+        self.player == HOST
+    }
+
+    /// The other player's name, once they've joined
+    pub fn partner_name(&self) -> Option<&str> {
+        Some(self.names[1 - self.player].as_str()).filter(|n| !n.is_empty())
+    }
+
+    pub fn last_error(&self) -> Option<String> {
+        self.shared.lock().ok().and_then(|s| s.error.clone())
+    }
+
+    pub fn send(&self, message: &CoopMessage) {
+        let Ok(line) = encode_line(message) else {
+            return;
+        };
+        if let Ok(mut s) = self.shared.lock() {
+            s.peers.broadcast(&line, None);
+        }
+    }
+
+    /// Lets the partner know where this player is pointing, if that's changed
+    pub fn send_cursor(&mut self, coord: Option<Coordinate>) {
+        if coord != self.cursor_sent {
+            self.cursor_sent = coord.clone();
+            self.send(&CoopMessage::Cursor { coord });
+        }
+    }
+
+    /// Takes the messages received since the last call that need the game to act on them.
+    /// Call once per frame.
+    pub fn poll(&mut self) -> Vec<CoopMessage> {
+        let inbox = match self.shared.lock() {
+            Ok(mut s) => std::mem::take(&mut s.inbox),
+            Err(_) => return vec![],
+        };
+        inbox
+            .into_iter()
+            .filter_map(|message| match message {
+                CoopMessage::Join { name } => {
+                    self.names[1 - self.player] = name;
+                    if let Ok(mut s) = self.shared.lock() {
+                        s.error = None;
+                    }
+                    if self.is_host() {
+                        // Introduce ourselves back, then start the first game together
+                        self.send(&CoopMessage::Join {
+                            name: self.names[HOST].clone(),
+                        });
+                        Some(CoopMessage::Join {
+                            name: self.names[GUEST].clone(),
+                        })
+                    } else {
+                        None
+                    }
+                }
+                CoopMessage::Cursor { coord } => {
+                    self.partner_cursor = coord;
+                    None
+                }
+                _ => Some(message),
+            })
+            .collect()
+    }
+}
+
+#[test]
+fn test_flag_owners() {
+    let mut flags = FlagOwners::default();
+    flags.record(HOST, Coordinate::from((0, 0)), true);
+    flags.record(GUEST, Coordinate::from((1, 0)), true);
+    flags.record(GUEST, Coordinate::from((2, 0)), true);
+    flags.record(HOST, Coordinate::from((2, 0)), false);
+    // Taken away and placed again by the other player
+    flags.record(HOST, Coordinate::from((1, 0)), false);
+    flags.record(HOST, Coordinate::from((1, 0)), true);
+
+    assert_eq!(flags.placed_by(HOST).count(), 2);
+    assert_eq!(flags.placed_by(GUEST).count(), 0);
+    flags.clear();
+    assert_eq!(flags.placed_by(HOST).count(), 0);
+}
+
+#[test]
+fn test_guest_messages() {
+    // Guests ask the host for plays and games, but never make them
+    let coord = Coordinate::from((1, 1));
+    assert!(CoopMessage::Request {
+        coord: coord.clone(),
+        play_type: RevealType::Reveal,
+    }
+    .allowed_from_guest());
+    assert!(CoopMessage::NewGame {
+        difficulty: GameDifficulty::Beginner,
+    }
+    .allowed_from_guest());
+    assert!(!CoopMessage::Move {
+        player: GUEST,
+        coord,
+        play_type: RevealType::Reveal,
+    }
+    .allowed_from_guest());
+    assert!(!CoopMessage::Start {
+        difficulty: GameDifficulty::Beginner,
+        seed: 42,
+    }
+    .allowed_from_guest());
+}
@@ -0,0 +1,180 @@
+use std::backtrace::Backtrace;
+use std::fmt::Write;
+use std::fs;
+use std::sync::Mutex;
+
+use anyhow::{Context, Result};
+
+use crate::autosave::SavedGame;
+use crate::config;
+use crate::state::AppState;
+
+const CRASH_REPORT_FILE_NAME: &str = "minesofrust-crash.txt";
+
+/// The last plays of the game in progress that go in a crash report
+const REPORT_PLAYS: usize = 20;
+
+/// What the app was doing, kept up to date so a panic can save it
+struct CrashContext {
+    state: Option<AppState>,
+    game: Option<SavedGame>,
+}
+
+static CONTEXT: Mutex<CrashContext> = Mutex::new(CrashContext {
+    state: None,
+    game: None,
+});
+
+/// Notes the settings and the game in progress, if there is one, to be saved if the app
+/// crashes
+pub fn record(state: &AppState, game: Option<SavedGame>) {
+    if let Ok(mut context) = CONTEXT.lock() {
+        context.state = Some(state.clone());
+        context.game = game;
+    }
+}
+
+/// Saves the settings and the game in progress when the app panics, and writes a report
+/// of what went wrong for the next launch to offer to the player. The panic is then
+/// reported as it would have been anyway.
+pub fn install_panic_hook() {
+    let default_hook = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        let report = crash_report(
+            &info.to_string(),
+            &Backtrace::force_capture().to_string(),
+            // Whatever was holding the lock may be what panicked, so don't wait on it
+            CONTEXT
+                .try_lock()
+                .ok()
+                .and_then(|c| c.game.clone())
+                .as_ref(),
+        );
+        if let Err(e) = save_crash_report(&report) {
+            eprintln!("Failed to save the crash report: {:#}", e);
+        }
+        if let Ok(context) = CONTEXT.try_lock() {
+            if let Some(Err(e)) = context.state.as_ref().map(|s| s.save_to_userhome()) {
+                eprintln!("Failed to save settings: {:#}", e);
+            }
+            if let Some(Err(e)) = context.game.as_ref().map(|g| g.save_to_userhome()) {
+                eprintln!("Failed to save the game in progress: {:#}", e);
+            }
+        }
+        default_hook(info);
+    }));
+}
+
+/// The report of a crash: what panicked and where, the backtrace, and the last plays of
+/// the game being played
+fn crash_report(panic: &str, backtrace: &str, game: Option<&SavedGame>) -> String {
+    let mut report = format!(
+        "Mines of Rust {} ({})\n{}\n\n{}\n",
+        env!("CARGO_PKG_VERSION"),
+        env!("MINESOFRUST_GIT_HASH"),
+        panic,
+        backtrace
+    );
+    if let Some(game) = game {
+        let plays = &game.plays.list;
+        let _ = writeln!(
+            report,
+            "\n{} game, {:.2} seconds in, {} plays. The last ones:",
+            game.difficulty.name(),
+            game.elapsed,
+            plays.len()
+        );
+        plays
+            .iter()
+            .skip(plays.len().saturating_sub(REPORT_PLAYS))
+            .for_each(|p| {
+                let _ = writeln!(
+                    report,
+                    "{:?} {:?} at ({}, {})",
+                    p.kind, p.play_type, p.coord.x, p.coord.y
+                );
+            });
+    }
+    report
+}
+
+fn save_crash_report(report: &str) -> Result<()> {
+    let config_dir = config::config_dir()?;
+    fs::create_dir_all(&config_dir)?;
+    let path = config_dir.join(CRASH_REPORT_FILE_NAME);
+    fs::write(&path, report).with_context(|| format!("Failed to write {:?}", path))
+}
+
+/// The report of the last crash, if the app crashed and the report hasn't been dismissed
+pub fn load_crash_report() -> Option<String> {
+    fs::read_to_string(config::config_dir().ok()?.join(CRASH_REPORT_FILE_NAME)).ok()
+}
+
+pub fn remove_crash_report() -> Result<()> {
+    config::remove_file(CRASH_REPORT_FILE_NAME)
+}
+
+/// Where to open an issue about a crash, with the first line of the report as its title.
+/// The report itself is too long for a link, so it's left for the player to paste in.
+pub fn issue_url(report: &str) -> String {
+    let panic = report.lines().nth(1).unwrap_or_default();
+    format!(
+        "{}/issues/new?title={}&body={}",
+        env!("CARGO_PKG_REPOSITORY"),
+        url_encode(&format!("Crash: {}", panic)),
+        url_encode("Paste the crash report here.")
+    )
+}
+
+/// Percent-encodes everything but the characters that can go in a URL as they are
+fn url_encode(s: &str) -> String {
+    s.bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => {
+                (b as char).to_string()
+            }
+            _ => format!("%{:02X}", b),
+        })
+        .collect()
+}
+
+#[test]
+fn test_crash_report() {
+    use crate::enums::GameDifficulty;
+    use crate::minesweeper::{ChangeKind, Coordinate, PlayResult, RevealType};
+    use crate::plays::PlayList;
+    use crate::state::GameSettings;
+
+    let mut plays = PlayList::default();
+    (0..30).for_each(|x| {
+        plays.record(
+            Coordinate::from((x, 0)),
+            RevealType::Reveal,
+            &PlayResult::single(Coordinate::from((x, 0)), ChangeKind::Flagged),
+            x as f64,
+            None,
+        )
+    });
+    let game = SavedGame::new(
+        &GameDifficulty::Expert,
+        &GameSettings::expert(),
+        &[],
+        &plays,
+        42.0,
+        false,
+    );
+    let report = crash_report(
+        "panicked at src/app.rs:1:1:\noops",
+        "backtrace",
+        Some(&game),
+    );
+    assert!(report.contains("oops"));
+    assert!(report.contains("30 plays"));
+    // Only the last plays are listed
+    assert!(report.contains("at (29, 0)"));
+    assert!(!report.contains("at (9, 0)"));
+    assert!(!crash_report("oops", "", None).contains("plays"));
+
+    assert_eq!(url_encode("a b/c?"), "a%20b%2Fc%3F");
+    assert!(issue_url(&report).contains("title=Crash%3A%20panicked%20at"));
+}
@@ -1,5 +1,9 @@
+use egui::Color32;
 use serde::{Deserialize, Serialize};
 
+use crate::constants;
+use crate::i18n::tr;
+
 #[derive(Debug, Eq, PartialEq, Deserialize, Serialize, Clone)]
 pub enum VisualTheme {
     Light,
@@ -9,8 +13,210 @@ pub enum VisualTheme {
 impl VisualTheme {
     pub fn as_str(&self) -> &'static str {
         match *self {
-            VisualTheme::Dark => "Dark",
-            VisualTheme::Light => "Light",
+            VisualTheme::Dark => tr("theme-dark"),
+            VisualTheme::Light => tr("theme-light"),
+        }
+    }
+}
+
+/// Language the UI is shown in
+#[derive(Debug, Eq, PartialEq, Deserialize, Serialize, Clone, Copy)]
+pub enum Language {
+    English,
+    Spanish,
+}
+
+impl Language {
+    pub fn all() -> [Language; 2] {
+        [Language::English, Language::Spanish]
+    }
+
+    /// The language's own name for itself, so players can find it whatever the UI is in
+    pub fn as_str(&self) -> &'static str {
+        match *self {
+            Language::English => "English",
+            Language::Spanish => "Español",
+        }
+    }
+}
+
+/// Kind of server the profile is synced with
+#[cfg(all(feature = "sync", not(target_arch = "wasm32")))]
+#[derive(Debug, Eq, PartialEq, Deserialize, Serialize, Clone, Copy, Default)]
+pub enum SyncService {
+    #[default]
+    WebDav,
+    S3,
+}
+
+#[cfg(all(feature = "sync", not(target_arch = "wasm32")))]
+impl SyncService {
+    pub fn all() -> [SyncService; 2] {
+        [SyncService::WebDav, SyncService::S3]
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match *self {
+            SyncService::WebDav => "WebDAV",
+            SyncService::S3 => "S3",
+        }
+    }
+}
+
+#[derive(Debug, Eq, PartialEq, Deserialize, Serialize, Clone)]
+pub enum TimerPrecision {
+    Seconds,
+    Hundredths,
+}
+
+impl TimerPrecision {
+    pub fn as_str(&self) -> &'static str {
+        match *self {
+            TimerPrecision::Seconds => tr("timer-seconds"),
+            TimerPrecision::Hundredths => tr("timer-hundredths"),
+        }
+    }
+
+    /// Smallest change in seconds visible on the timer display
+    pub fn resolution(&self) -> f64 {
+        match *self {
+            TimerPrecision::Seconds => 1.0,
+            TimerPrecision::Hundredths => 0.01,
+        }
+    }
+}
+
+/// How the numerals on revealed squares are drawn
+#[derive(Debug, Eq, PartialEq, Deserialize, Serialize, Clone)]
+pub enum NumeralStyle {
+    /// The numeral artwork, which only goes up to 8
+    Images,
+    /// Text sized to the square, for any numeral
+    Text,
+}
+
+impl NumeralStyle {
+    pub fn as_str(&self) -> &'static str {
+        match *self {
+            NumeralStyle::Images => tr("numerals-images"),
+            NumeralStyle::Text => tr("numerals-text"),
+        }
+    }
+}
+
+/// Color of the covered squares, unlocked by levelling up
+#[derive(Debug, Eq, PartialEq, Deserialize, Serialize, Clone, Copy, Default)]
+pub enum BoardSkin {
+    /// The theme's own color
+    #[default]
+    Classic,
+    Ocean,
+    Forest,
+    Sunset,
+    Midnight,
+}
+
+impl BoardSkin {
+    pub fn all() -> [BoardSkin; 5] {
+        [
+            BoardSkin::Classic,
+            BoardSkin::Ocean,
+            BoardSkin::Forest,
+            BoardSkin::Sunset,
+            BoardSkin::Midnight,
+        ]
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match *self {
+            BoardSkin::Classic => tr("skin-classic"),
+            BoardSkin::Ocean => tr("skin-ocean"),
+            BoardSkin::Forest => tr("skin-forest"),
+            BoardSkin::Sunset => tr("skin-sunset"),
+            BoardSkin::Midnight => tr("skin-midnight"),
+        }
+    }
+
+    /// Level the player has to reach to use the skin
+    pub fn unlock_level(&self) -> u32 {
+        match *self {
+            BoardSkin::Classic => 1,
+            BoardSkin::Ocean => 3,
+            BoardSkin::Forest => 5,
+            BoardSkin::Sunset => 8,
+            BoardSkin::Midnight => 12,
+        }
+    }
+
+    /// Color of the covered squares, or `None` for the theme's
+    pub fn covered_color(&self) -> Option<Color32> {
+        match *self {
+            BoardSkin::Classic => None,
+            BoardSkin::Ocean => Some(constants::COLOR_SKIN_OCEAN),
+            BoardSkin::Forest => Some(constants::COLOR_SKIN_FOREST),
+            BoardSkin::Sunset => Some(constants::COLOR_SKIN_SUNSET),
+            BoardSkin::Midnight => Some(constants::COLOR_SKIN_MIDNIGHT),
+        }
+    }
+}
+
+/// What a plain tap or left click on an unrevealed square does
+#[derive(Debug, Eq, PartialEq, Deserialize, Serialize, Clone)]
+pub enum TapMode {
+    Reveal,
+    Flag,
+}
+
+impl TapMode {
+    pub fn as_str(&self) -> &'static str {
+        match *self {
+            TapMode::Reveal => tr("tap-reveals"),
+            TapMode::Flag => tr("tap-flags"),
+        }
+    }
+
+    pub fn toggled(&self) -> Self {
+        match *self {
+            TapMode::Reveal => TapMode::Flag,
+            TapMode::Flag => TapMode::Reveal,
+        }
+    }
+}
+
+/// How the player likes to play, picked when the app is first run to set up the input
+/// settings to suit it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InputStyle {
+    /// Left click reveals, right click flags, and both buttons or the middle one chord
+    Classic,
+    /// As classic, but left clicking a numeral chords it too
+    LeftClickChord,
+    /// Tap to reveal or chord, long press to flag
+    Touch,
+}
+
+impl InputStyle {
+    pub fn all() -> [InputStyle; 3] {
+        [
+            InputStyle::Classic,
+            InputStyle::LeftClickChord,
+            InputStyle::Touch,
+        ]
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match *self {
+            InputStyle::Classic => tr("input-style-classic"),
+            InputStyle::LeftClickChord => tr("input-style-left-click-chord"),
+            InputStyle::Touch => tr("input-style-touch"),
+        }
+    }
+
+    pub fn description(&self) -> &'static str {
+        match *self {
+            InputStyle::Classic => tr("tip-input-style-classic"),
+            InputStyle::LeftClickChord => tr("tip-input-style-left-click-chord"),
+            InputStyle::Touch => tr("tip-input-style-touch"),
         }
     }
 }
@@ -47,4 +253,14 @@ impl GameDifficulty {
             // GameDifficulty::Custom => "Custom",
         }
     }
+
+    /// The name shown to the player, in the UI's language. `as_str` is the one saved to
+    /// files and sent over the network.
+    pub fn name(&self) -> &'static str {
+        match *self {
+            GameDifficulty::Beginner => tr("difficulty-beginner"),
+            GameDifficulty::Intermediate => tr("difficulty-intermediate"),
+            GameDifficulty::Expert => tr("difficulty-expert"),
+        }
+    }
 }
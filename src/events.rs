@@ -0,0 +1,129 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use crate::minesweeper::{Coordinate, PlayResult};
+
+/// Something that happened to the game. Squares are given by where they are on the grid of
+/// all the boards.
+#[derive(Debug, Clone, PartialEq)]
+pub enum GameEvent {
+    /// The first square of the game was revealed
+    Started,
+    /// A reveal, chord or explosion uncovered `squares` squares
+    Revealed {
+        coord: Coordinate,
+        squares: u32,
+    },
+    Flagged {
+        coord: Coordinate,
+        flagged: bool,
+    },
+    /// `time` seconds into the game
+    Won {
+        time: f64,
+    },
+    Lost {
+        time: f64,
+    },
+    Paused,
+    Resumed,
+    /// The window was hidden in the system tray
+    Hidden,
+    /// The window was brought back from the system tray
+    Shown,
+}
+
+impl GameEvent {
+    /// The event for a play made at `coord`, if it changed anything
+    pub fn from_play(coord: Coordinate, result: &PlayResult) -> Option<GameEvent> {
+        if result.is_no_change() {
+            return None;
+        }
+        Some(match result.flagged() {
+            Some(flagged) => GameEvent::Flagged { coord, flagged },
+            None => GameEvent::Revealed {
+                coord,
+                squares: result.squares_changed(),
+            },
+        })
+    }
+}
+
+/// Something that wants to know what's happening in the game, like sounds, animations or
+/// a network connection
+pub trait GameObserver {
+    fn on_event(&mut self, event: &GameEvent);
+}
+
+/// Events waiting to be handled, and the observers they're passed to
+#[derive(Clone, Default)]
+pub struct GameEvents {
+    pending: Vec<GameEvent>,
+    observers: Vec<Rc<RefCell<dyn GameObserver>>>,
+}
+
+impl GameEvents {
+    pub fn emit(&mut self, event: GameEvent) {
+        self.pending.push(event);
+    }
+
+    pub fn has_pending(&self) -> bool {
+        !self.pending.is_empty()
+    }
+
+    pub fn subscribe(&mut self, observer: Rc<RefCell<dyn GameObserver>>) {
+        self.observers.push(observer);
+    }
+
+    /// Passes the events emitted since the last call to each observer, in order, then
+    /// returns them for the app to act on too
+    pub fn dispatch(&mut self) -> Vec<GameEvent> {
+        let events = std::mem::take(&mut self.pending);
+        events.iter().for_each(|e| {
+            self.observers
+                .iter()
+                .for_each(|o| o.borrow_mut().on_event(e))
+        });
+        events
+    }
+}
+
+#[test]
+fn test_dispatch() {
+    use crate::minesweeper::ChangeKind;
+
+    #[derive(Default)]
+    struct Recorder(Vec<GameEvent>);
+    impl GameObserver for Recorder {
+        fn on_event(&mut self, event: &GameEvent) {
+            self.0.push(event.clone());
+        }
+    }
+
+    let recorder = Rc::new(RefCell::new(Recorder::default()));
+    let mut events = GameEvents::default();
+    events.subscribe(recorder.clone());
+    events.emit(GameEvent::Started);
+    [
+        PlayResult::default(),
+        PlayResult::single(Coordinate::from((1, 2)), ChangeKind::Flagged),
+    ]
+    .iter()
+    .filter_map(|r| GameEvent::from_play(Coordinate::from((1, 2)), r))
+    .for_each(|e| events.emit(e));
+    events.emit(GameEvent::Won { time: 3.0 });
+
+    let expected = [
+        GameEvent::Started,
+        GameEvent::Flagged {
+            coord: Coordinate::from((1, 2)),
+            flagged: true,
+        },
+        GameEvent::Won { time: 3.0 },
+    ];
+    assert_eq!(events.dispatch(), expected);
+    assert_eq!(recorder.borrow().0, expected);
+    // Each event is only passed on once
+    assert!(events.dispatch().is_empty());
+    assert_eq!(recorder.borrow().0.len(), 3);
+}
@@ -0,0 +1,118 @@
+use std::io::Write;
+
+use anyhow::Result;
+use image::codecs::gif::{GifEncoder, Repeat};
+use image::{Delay, Frame};
+
+use crate::constants;
+use crate::minesweeper::*;
+use crate::plays::PlayList;
+use crate::render::render_boards;
+
+/// How a replay is drawn and how fast it plays
+#[derive(Debug, Clone, Copy)]
+pub struct ReplayOptions {
+    /// Width of a square, in pixels
+    pub cell_size: u32,
+    pub seconds_per_play: f64,
+}
+
+fn delay(seconds: f64) -> Delay {
+    Delay::from_numer_denom_ms((seconds * 1000.0).round().max(20.0) as u32, 1)
+}
+
+/// Plays `plays` back on fresh copies of `boards`, the finished boards laid out `columns` to
+/// a row, and writes it to `writer` as a looping animated GIF. There's a frame for each play
+/// that changed something, and the last one holds the finished boards for a while.
+pub fn write_replay_gif(
+    boards: &[GameBoard],
+    columns: u32,
+    plays: &PlayList,
+    options: &ReplayOptions,
+    writer: impl Write,
+) -> Result<()> {
+    let Some(first) = boards.first() else {
+        return Ok(());
+    };
+    let (width, height) = (first.width, first.height);
+    let mut replay: Vec<GameBoard> = boards.to_vec();
+    replay.iter_mut().for_each(|b| b.reset_existing());
+
+    let render = |boards: &[GameBoard], reveal_mines| {
+        render_boards(boards, columns, options.cell_size, reveal_mines)
+    };
+    let mut frames = vec![Frame::from_parts(
+        render(&replay, false),
+        0,
+        0,
+        delay(options.seconds_per_play),
+    )];
+    plays
+        .list
+        .iter()
+        .filter(|e| e.changed > 0)
+        .try_for_each(|e| {
+            // Plays are recorded where they are on the grid of all the boards
+            let board = (e.coord.y / height * columns + e.coord.x / width) as usize;
+            if let Some(b) = replay.get_mut(board) {
+                b.play(e.coord.x % width, e.coord.y % height, e.play_type.clone())?;
+            }
+            frames.push(Frame::from_parts(
+                render(&replay, false),
+                0,
+                0,
+                delay(options.seconds_per_play),
+            ));
+            Ok::<(), Error>(())
+        })?;
+
+    let lost = boards.iter().any(|b| b.is_loss_configuration());
+    frames.push(Frame::from_parts(
+        render(boards, lost),
+        0,
+        0,
+        delay(constants::REPLAY_END_SECONDS),
+    ));
+
+    let mut encoder = GifEncoder::new_with_speed(writer, constants::REPLAY_GIF_SPEED);
+    encoder.set_repeat(Repeat::Infinite)?;
+    encoder.encode_frames(frames)?;
+    Ok(())
+}
+
+#[test]
+fn test_write_replay_gif() -> Result<()> {
+    use image::codecs::gif::GifDecoder;
+    use image::AnimationDecoder;
+
+    // [0 0 1 * 1], won in two reveals with a wasted click between them
+    let mut gb = GameBoard::new_with_mines(5, 1, &[Coordinate::from((3, 0))])?;
+    let mut plays = PlayList::default();
+    [
+        (0, RevealType::Reveal),
+        (1, RevealType::Reveal),
+        (4, RevealType::Reveal),
+    ]
+    .into_iter()
+    .try_for_each(|(x, play_type)| {
+        let result = gb.play(x, 0, play_type.clone())?;
+        plays.record(Coordinate::from((x, 0)), play_type, &result, x as f64, None);
+        Ok::<(), Error>(())
+    })?;
+
+    let options = ReplayOptions {
+        cell_size: 8,
+        seconds_per_play: 0.5,
+    };
+    let mut gif = vec![];
+    write_replay_gif(&[gb], 1, &plays, &options, &mut gif)?;
+
+    let frames = GifDecoder::new(std::io::Cursor::new(gif))?
+        .into_frames()
+        .collect_frames()?;
+    // The empty board, the two plays that changed it, then the finished board
+    assert_eq!(frames.len(), 4);
+    assert_eq!(frames[0].buffer().dimensions(), (41, 9));
+    assert_eq!(frames[1].delay().numer_denom_ms(), (500, 1));
+    Ok(())
+}
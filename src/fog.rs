@@ -0,0 +1,73 @@
+use crate::minesweeper::Coordinate;
+
+/// Opacity of the fog over squares at the edge of the clear area, or remembered
+const FOG_DIM_ALPHA: u8 = 140;
+
+/// Remembers which squares the player has had a clear look at in fog of war mode
+#[derive(Debug, Clone, Default)]
+pub struct FogMemory {
+    width: u32,
+    seen: Vec<bool>,
+}
+
+impl FogMemory {
+    pub fn reset(&mut self) {
+        self.seen.clear();
+    }
+
+    /// Marks every square within `radius` of `center` as seen on a board of the given size
+    pub fn look(&mut self, center: &Coordinate, radius: f32, width: u32, height: u32) {
+        if self.width != width || self.seen.len() != (width * height) as usize {
+            self.width = width;
+            self.seen = vec![false; (width * height) as usize];
+        }
+        let reach = radius.ceil() as i64;
+        (-reach..=reach)
+            .flat_map(|dy| (-reach..=reach).map(move |dx| (dx, dy)))
+            .map(|(dx, dy)| (center.x as i64 + dx, center.y as i64 + dy))
+            .filter(|&(x, y)| x >= 0 && y >= 0 && x < width as i64 && y < height as i64)
+            .map(|(x, y)| Coordinate::from((x as u32, y as u32)))
+            .filter(|c| c.distance(center) <= radius)
+            .for_each(|c| self.seen[(c.y * width + c.x) as usize] = true);
+    }
+
+    pub fn is_seen(&self, coord: &Coordinate) -> bool {
+        self.seen
+            .get((coord.y * self.width + coord.x) as usize)
+            .copied()
+            .unwrap_or(false)
+    }
+}
+
+/// How opaque the fog is over a square `distance` squares from the player's focus, from
+/// clear (0) to hidden (255). Squares within `radius` are clear, the ring beyond is dimmed,
+/// and so are squares further away that are `remembered`.
+pub fn fog_alpha(distance: f32, radius: f32, remembered: bool) -> u8 {
+    if distance <= radius {
+        0
+    } else if distance < radius * 2.0 || remembered {
+        FOG_DIM_ALPHA
+    } else {
+        255
+    }
+}
+
+#[test]
+fn test_fog() {
+    assert_eq!(fog_alpha(1.4, 1.5, false), 0);
+    assert_eq!(fog_alpha(2.0, 1.5, false), FOG_DIM_ALPHA);
+    assert_eq!(fog_alpha(3.0, 1.5, false), 255);
+    assert_eq!(fog_alpha(3.0, 1.5, true), FOG_DIM_ALPHA);
+
+    let mut memory = FogMemory::default();
+    memory.look(&Coordinate::from((0, 0)), 1.5, 9, 9);
+    assert!(memory.is_seen(&Coordinate::from((1, 1))));
+    assert!(!memory.is_seen(&Coordinate::from((2, 0))));
+    memory.look(&Coordinate::from((8, 8)), 1.0, 9, 9);
+    assert!(memory.is_seen(&Coordinate::from((8, 7))));
+    assert!(!memory.is_seen(&Coordinate::from((7, 7))));
+    assert!(memory.is_seen(&Coordinate::from((0, 0))));
+
+    memory.reset();
+    assert!(!memory.is_seen(&Coordinate::from((0, 0))));
+}
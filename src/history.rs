@@ -0,0 +1,443 @@
+use anyhow::Result;
+use chrono::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::config::{self, Versioned};
+use crate::constants::{PENALTY_HELD_CHORD_SECONDS, PENALTY_WRONG_FLAG_SECONDS};
+use crate::enums::GameDifficulty;
+use crate::leader::{as_df_date, as_df_date_opt, as_hex_seed, LeaderboardCategory};
+use crate::placement::MinePlacement;
+use crate::shape::BoardShape;
+
+const CONFIG_FILE_NAME: &str = "minesofrust-history.toml";
+
+/// The outcome of a single finished game
+#[derive(Clone, Deserialize, Serialize)]
+pub struct GameRecord {
+    pub difficulty: GameDifficulty,
+
+    #[serde(with = "as_df_date")]
+    pub date: DateTime<FixedOffset>,
+    pub won: bool,
+    pub time: f64,
+
+    #[serde(with = "as_hex_seed")]
+    pub seed: u64,
+    pub bbbv: u32,
+    pub clicks: u32,
+
+    /// Played on a board that was restarted, so the player may have known where the mines
+    /// were. Left out of the statistics.
+    #[serde(default)]
+    pub practice: bool,
+
+    /// Boards played at once. Games on more than one board are left out of the statistics.
+    #[serde(default = "default_num_boards")]
+    pub num_boards: u32,
+
+    /// Played without flags
+    #[serde(default)]
+    pub no_flags: bool,
+
+    /// How the mines were spread, which together with the seed and first click gives the
+    /// board
+    #[serde(default)]
+    pub placement: MinePlacement,
+
+    /// The outline of the board
+    #[serde(default)]
+    pub shape: BoardShape,
+
+    /// Layers stacked into a 3D board
+    #[serde(default = "default_layers")]
+    pub layers: u32,
+
+    /// What was added to `time` for mistakes, for games played with time penalties
+    #[serde(default)]
+    pub penalties: Option<Penalties>,
+}
+
+fn default_num_boards() -> u32 {
+    1
+}
+
+fn default_layers() -> u32 {
+    1
+}
+
+impl GameRecord {
+    pub fn new(
+        difficulty: GameDifficulty,
+        won: bool,
+        time: f64,
+        seed: u64,
+        bbbv: u32,
+        clicks: u32,
+    ) -> Self {
+        GameRecord {
+            difficulty,
+            date: Local::now().fixed_offset(),
+            won,
+            time,
+            seed,
+            bbbv,
+            clicks,
+            practice: false,
+            num_boards: 1,
+            no_flags: false,
+            placement: MinePlacement::Uniform,
+            shape: BoardShape::Rectangle,
+            layers: 1,
+            penalties: None,
+        }
+    }
+
+    /// 3BV cleared per second. Only meaningful for won games, where the whole board was
+    /// cleared.
+    pub fn bbbv_per_second(&self) -> Option<f64> {
+        (self.won && self.bbbv > 0 && self.time > 0.0).then(|| self.bbbv as f64 / self.time)
+    }
+}
+
+/// Mistakes made in a game played with time penalties, each of which adds to its time
+/// instead of making it a practice game
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize, Serialize)]
+pub struct Penalties {
+    /// Flags placed on squares without a mine
+    #[serde(default)]
+    pub wrong_flags: u32,
+    /// Chords held back because they would have set off a mine
+    #[serde(default)]
+    pub held_chords: u32,
+}
+
+impl Penalties {
+    /// Seconds added to the game's time
+    pub fn seconds(&self) -> f64 {
+        self.wrong_flags as f64 * PENALTY_WRONG_FLAG_SECONDS
+            + self.held_chords as f64 * PENALTY_HELD_CHORD_SECONDS
+    }
+}
+
+/// Every game played, oldest first
+#[derive(Clone, Deserialize, Serialize)]
+pub struct GameHistory {
+    #[serde(default)]
+    pub version: i64,
+    #[serde(default)]
+    pub games: Vec<GameRecord>,
+    /// When the history was last cleared. Games played by then are left out of a merge,
+    /// so another copy of the history that still has them doesn't bring them back.
+    #[serde(
+        default,
+        with = "as_df_date_opt",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub cleared: Option<DateTime<FixedOffset>>,
+}
+
+impl Default for GameHistory {
+    fn default() -> Self {
+        Self {
+            version: <GameHistory as Versioned>::VERSION,
+            games: vec![],
+            cleared: None,
+        }
+    }
+}
+
+impl Versioned for GameHistory {
+    // 1: Initial version
+    // 2: Added the time the history was cleared
+    const VERSION: i64 = 2;
+}
+
+impl GameHistory {
+    pub fn add(&mut self, record: GameRecord) {
+        self.games.push(record);
+    }
+
+    /// Deletes every game, including from other copies of the history this is merged with
+    pub fn clear(&mut self) {
+        self.games.clear();
+        self.cleared = Some(Local::now().fixed_offset());
+    }
+
+    /// Adds the games in `other` that aren't already in this history, keeping them in the
+    /// order they were played, then drops those played by the time either was last cleared.
+    /// Dates are only stored to the millisecond.
+    pub fn merge(&mut self, other: &GameHistory) {
+        self.cleared = self.cleared.max(other.cleared);
+        other.games.iter().for_each(|g| {
+            let known = self.games.iter().any(|mine| {
+                mine.date.timestamp_millis() == g.date.timestamp_millis()
+                    && mine.seed == g.seed
+                    && mine.time == g.time
+            });
+            if !known {
+                self.games.push(g.clone());
+            }
+        });
+        // Compared to the millisecond, as that's all the file keeps
+        if let Some(cleared) = self.cleared {
+            self.games
+                .retain(|g| g.date.timestamp_millis() > cleared.timestamp_millis());
+        }
+        self.games.sort_by_key(|g| g.date);
+    }
+
+    /// Single board games won in a row at a difficulty level, up to the last one played,
+    /// leaving out practice games
+    pub fn win_streak(&self, difficulty: &GameDifficulty) -> u32 {
+        self.games
+            .iter()
+            .rev()
+            .filter(|g| g.difficulty == *difficulty && !g.practice && g.num_boards == 1)
+            .take_while(|g| g.won)
+            .count() as u32
+    }
+
+    /// The most recent `n` single board games played at a difficulty level, oldest first,
+    /// leaving out practice games
+    pub fn last_n(&self, difficulty: &GameDifficulty, n: usize) -> Vec<&GameRecord> {
+        let mut games: Vec<&GameRecord> = self
+            .games
+            .iter()
+            .rev()
+            .filter(|g| g.difficulty == *difficulty && !g.practice && g.num_boards == 1)
+            .take(n)
+            .collect();
+        games.reverse();
+        games
+    }
+
+    /// The fastest win in a leaderboard category, leaving out practice games
+    pub fn personal_best(&self, category: &LeaderboardCategory) -> Option<f64> {
+        self.games
+            .iter()
+            .filter(|g| {
+                g.won
+                    && !g.practice
+                    && g.difficulty == category.difficulty
+                    && g.no_flags == category.no_flags
+                    && g.num_boards == category.num_boards
+            })
+            .map(|g| g.time)
+            .min_by(f64::total_cmp)
+    }
+
+    pub fn load_from_userhome() -> Result<Option<Self>> {
+        config::load_toml(CONFIG_FILE_NAME)
+    }
+
+    pub fn save_to_userhome(&self) -> Result<()> {
+        config::save_toml(self, CONFIG_FILE_NAME)
+    }
+}
+
+/// Why a game was lost
+#[derive(Debug, Eq, PartialEq, Clone, Copy)]
+pub enum LossCause {
+    /// The square wasn't known to be a mine from what was revealed
+    Guess,
+    /// The square could have been deduced to be a mine
+    Misclick,
+}
+
+/// Wins and losses at one difficulty level since the session was started or reset
+#[derive(Clone, Default)]
+pub struct SessionRecord {
+    pub wins: u32,
+    pub guess_losses: u32,
+    pub misclick_losses: u32,
+}
+
+impl SessionRecord {
+    pub fn record_win(&mut self) {
+        self.wins += 1;
+    }
+
+    pub fn record_loss(&mut self, cause: LossCause) {
+        match cause {
+            LossCause::Guess => self.guess_losses += 1,
+            LossCause::Misclick => self.misclick_losses += 1,
+        }
+    }
+
+    pub fn losses(&self) -> u32 {
+        self.guess_losses + self.misclick_losses
+    }
+
+    pub fn games(&self) -> u32 {
+        self.wins + self.losses()
+    }
+}
+
+/// Results of the games played since the app was started or the session was reset
+#[derive(Clone, Default)]
+pub struct SessionStats {
+    pub beginner: SessionRecord,
+    pub intermediate: SessionRecord,
+    pub expert: SessionRecord,
+    /// Games on restarted boards, at any difficulty level
+    pub practice: SessionRecord,
+}
+
+impl SessionStats {
+    pub fn for_difficulty(&self, difficulty: &GameDifficulty) -> &SessionRecord {
+        match difficulty {
+            GameDifficulty::Beginner => &self.beginner,
+            GameDifficulty::Intermediate => &self.intermediate,
+            GameDifficulty::Expert => &self.expert,
+        }
+    }
+
+    /// Where to record the result of a game
+    pub fn for_game_mut(
+        &mut self,
+        difficulty: &GameDifficulty,
+        practice: bool,
+    ) -> &mut SessionRecord {
+        match difficulty {
+            _ if practice => &mut self.practice,
+            GameDifficulty::Beginner => &mut self.beginner,
+            GameDifficulty::Intermediate => &mut self.intermediate,
+            GameDifficulty::Expert => &mut self.expert,
+        }
+    }
+
+    pub fn reset(&mut self) {
+        *self = SessionStats::default();
+    }
+}
+
+/// Win rate as a percentage over a sliding window of up to `window` games, as plot points
+/// of (game number, win rate)
+pub fn rolling_win_rate(games: &[&GameRecord], window: usize) -> Vec<[f64; 2]> {
+    (0..games.len())
+        .map(|i| {
+            let recent = &games[(i + 1).saturating_sub(window)..=i];
+            let wins = recent.iter().filter(|g| g.won).count();
+            [(i + 1) as f64, wins as f64 / recent.len() as f64 * 100.0]
+        })
+        .collect()
+}
+
+#[test]
+fn test_history() {
+    let mut history = GameHistory::default();
+    [true, false, false, true].iter().for_each(|&won| {
+        history.add(GameRecord::new(
+            GameDifficulty::Beginner,
+            won,
+            10.0,
+            0,
+            20,
+            25,
+        ));
+        history.add(GameRecord::new(
+            GameDifficulty::Expert,
+            false,
+            10.0,
+            0,
+            20,
+            25,
+        ));
+    });
+
+    let games = history.last_n(&GameDifficulty::Beginner, 3);
+    assert_eq!(games.len(), 3);
+    assert!(!games[0].won && !games[1].won && games[2].won);
+    assert_eq!(games[2].bbbv_per_second(), Some(2.0));
+    assert_eq!(games[0].bbbv_per_second(), None);
+
+    history.add(GameRecord {
+        practice: true,
+        ..GameRecord::new(GameDifficulty::Beginner, true, 1.0, 0, 20, 20)
+    });
+    history.add(GameRecord {
+        num_boards: 2,
+        ..GameRecord::new(GameDifficulty::Beginner, true, 1.0, 0, 40, 40)
+    });
+    let games = history.last_n(&GameDifficulty::Beginner, 10);
+    assert_eq!(
+        rolling_win_rate(&games, 2),
+        [[1.0, 100.0], [2.0, 50.0], [3.0, 0.0], [4.0, 50.0]]
+    );
+    // The practice and two board wins don't add to the streak
+    assert_eq!(history.win_streak(&GameDifficulty::Beginner), 1);
+    assert_eq!(history.win_streak(&GameDifficulty::Expert), 0);
+
+    // Nor do they count as a best time for single board games
+    let category = |no_flags, num_boards| {
+        LeaderboardCategory::new(GameDifficulty::Beginner, no_flags, num_boards)
+    };
+    assert_eq!(history.personal_best(&category(false, 1)), Some(10.0));
+    assert_eq!(history.personal_best(&category(false, 2)), Some(1.0));
+    assert_eq!(history.personal_best(&category(true, 1)), None);
+    history.add(GameRecord {
+        no_flags: true,
+        ..GameRecord::new(GameDifficulty::Beginner, true, 12.0, 0, 20, 20)
+    });
+    assert_eq!(history.personal_best(&category(true, 1)), Some(12.0));
+    assert_eq!(
+        history.personal_best(&LeaderboardCategory::new(GameDifficulty::Expert, false, 1)),
+        None
+    );
+}
+
+#[test]
+fn test_merge_history() -> Result<()> {
+    let mut here = GameHistory::default();
+    here.add(GameRecord::new(
+        GameDifficulty::Beginner,
+        true,
+        10.0,
+        1,
+        20,
+        25,
+    ));
+    here.add(GameRecord {
+        penalties: Some(Penalties {
+            wrong_flags: 1,
+            held_chords: 2,
+        }),
+        placement: MinePlacement::Clustered,
+        shape: BoardShape::Donut,
+        layers: 3,
+        ..GameRecord::new(GameDifficulty::Beginner, true, 30.0, 3, 20, 25)
+    });
+    let mut there: GameHistory = toml::from_str(&toml::to_string(&here)?)?;
+    assert_eq!(there.games[1].penalties, here.games[1].penalties);
+    assert_eq!(
+        there.games[1].penalties.map(|p| p.seconds()),
+        Some(PENALTY_WRONG_FLAG_SECONDS + 2.0 * PENALTY_HELD_CHORD_SECONDS)
+    );
+    assert_eq!(there.games[0].penalties, None);
+    assert_eq!(there.games[1].placement, MinePlacement::Clustered);
+    assert_eq!(there.games[1].shape, BoardShape::Donut);
+    assert_eq!(there.games[0].shape, BoardShape::Rectangle);
+    assert_eq!((there.games[0].layers, there.games[1].layers), (1, 3));
+    let mut earlier = GameRecord::new(GameDifficulty::Expert, false, 50.0, 2, 100, 30);
+    earlier.date -= chrono::Duration::days(1);
+    there.add(earlier);
+
+    here.merge(&there);
+    here.merge(&there);
+    assert_eq!(here.games.len(), 3);
+    // Oldest first
+    assert!(here.games[0].difficulty == GameDifficulty::Expert);
+
+    // Once cleared, a copy that still has the games doesn't bring them back, but games
+    // played since are kept
+    here.clear();
+    let mut since = GameRecord::new(GameDifficulty::Beginner, true, 40.0, 4, 20, 25);
+    since.date += chrono::Duration::days(1);
+    there.add(since);
+    let here_saved: GameHistory = toml::from_str(&toml::to_string(&here)?)?;
+    there.merge(&here_saved);
+    assert_eq!(there.games.len(), 1);
+    here.merge(&there);
+    assert_eq!(here.games.len(), 1);
+    Ok(())
+}
@@ -0,0 +1,129 @@
+use std::str::FromStr;
+use std::sync::mpsc::{self, Receiver};
+use std::sync::{Arc, Mutex};
+
+use anyhow::{anyhow, Result};
+use global_hotkey::hotkey::HotKey;
+use global_hotkey::{GlobalHotKeyEvent, GlobalHotKeyManager, HotKeyState};
+
+/// Something a global hotkey was pressed for
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HotkeyAction {
+    NewGame,
+    Pause,
+}
+
+/// Parses a hotkey like `Ctrl+Alt+N`. An empty one is no hotkey at all.
+pub fn parse_hotkey(hotkey: &str) -> Result<Option<HotKey>> {
+    if hotkey.trim().is_empty() {
+        return Ok(None);
+    }
+    HotKey::from_str(hotkey.trim())
+        .map(Some)
+        .map_err(|e| anyhow!("Invalid hotkey {:?}: {}", hotkey, e))
+}
+
+/// Hotkeys that work while the window doesn't have focus, or is hidden. Presses wake the
+/// app up and are picked up by `poll()`.
+pub struct GlobalHotkeys {
+    manager: GlobalHotKeyManager,
+    /// The hotkeys registered, shared with the thread the presses come in on
+    bindings: Arc<Mutex<Vec<(HotKey, HotkeyAction)>>>,
+    /// The settings the hotkeys were last registered from
+    registered_from: Mutex<Option<(String, String)>>,
+    actions: Receiver<HotkeyAction>,
+}
+
+impl GlobalHotkeys {
+    /// Starts listening for hotkeys, with none registered yet. Only one can be made, as the
+    /// presses can only be sent to one place.
+    pub fn new(ctx: &egui::Context) -> Result<Self> {
+        let manager = GlobalHotKeyManager::new()?;
+        let bindings: Arc<Mutex<Vec<(HotKey, HotkeyAction)>>> = Arc::default();
+        let (sender, actions) = mpsc::channel();
+        let pressed = bindings.clone();
+        let ctx = ctx.clone();
+        GlobalHotKeyEvent::set_event_handler(Some(move |event: GlobalHotKeyEvent| {
+            if event.state() != HotKeyState::Pressed {
+                return;
+            }
+            let action = pressed.lock().ok().and_then(|b| {
+                b.iter()
+                    .find(|(hotkey, _)| hotkey.id() == event.id())
+                    .map(|(_, action)| *action)
+            });
+            if let Some(action) = action {
+                let _ = sender.send(action);
+                ctx.request_repaint();
+            }
+        }));
+        Ok(GlobalHotkeys {
+            manager,
+            bindings,
+            registered_from: Mutex::new(None),
+            actions,
+        })
+    }
+
+    /// Registers the hotkeys for a new game and pausing, replacing the ones registered
+    /// before. Nothing is done if they haven't changed since the last call, so it can be
+    /// called every frame. Empty ones are left out, so two empty ones let go of them all.
+    pub fn register(&self, new_game: &str, pause: &str) -> Result<()> {
+        let settings = Some((new_game.to_string(), pause.to_string()));
+        let Ok(mut registered_from) = self.registered_from.lock() else {
+            return Ok(());
+        };
+        if *registered_from == settings {
+            return Ok(());
+        }
+        // Whatever happens, don't try again until the settings change
+        *registered_from = settings;
+        self.unregister();
+        let hotkeys = [
+            (parse_hotkey(new_game)?, HotkeyAction::NewGame),
+            (parse_hotkey(pause)?, HotkeyAction::Pause),
+        ];
+        hotkeys.into_iter().try_for_each(|(hotkey, action)| {
+            let Some(hotkey) = hotkey else {
+                return Ok(());
+            };
+            self.manager.register(hotkey)?;
+            if let Ok(mut bindings) = self.bindings.lock() {
+                bindings.push((hotkey, action));
+            }
+            Ok(())
+        })
+    }
+
+    /// Lets go of all the hotkeys, for other apps to use
+    fn unregister(&self) {
+        let Ok(mut bindings) = self.bindings.lock() else {
+            return;
+        };
+        let hotkeys: Vec<HotKey> = bindings.drain(..).map(|(hotkey, _)| hotkey).collect();
+        if let Err(e) = self.manager.unregister_all(&hotkeys) {
+            log::warn!("Failed to unregister hotkeys: {}", e);
+        }
+    }
+
+    /// Hotkeys pressed since the last call. Call once per frame.
+    pub fn poll(&self) -> Vec<HotkeyAction> {
+        self.actions.try_iter().collect()
+    }
+}
+
+#[test]
+fn test_parse_hotkey() -> Result<()> {
+    use global_hotkey::hotkey::{Code, Modifiers};
+
+    assert_eq!(
+        parse_hotkey("Ctrl+Alt+N")?,
+        Some(HotKey::new(
+            Some(Modifiers::CONTROL | Modifiers::ALT),
+            Code::KeyN
+        ))
+    );
+    assert_eq!(parse_hotkey(" ")?, None);
+    assert!(parse_hotkey("Ctrl+Nope").is_err());
+    Ok(())
+}
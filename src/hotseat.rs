@@ -0,0 +1,124 @@
+use std::cmp::Ordering;
+
+use crate::minesweeper::Coordinate;
+
+/// How one player's turn of a hot-seat match went
+#[derive(Debug, Clone, PartialEq)]
+pub struct TurnResult {
+    pub won: bool,
+    pub time: f64,
+    /// Safe squares revealed
+    pub cleared: u32,
+}
+
+/// A two player race on identical boards, played one after the other on the same machine.
+/// The second player's board is built from the first player's seed and first click, so it
+/// has the same mines and opening.
+#[derive(Debug, Clone)]
+pub struct HotSeat {
+    pub names: [String; 2],
+    turn: usize,
+    opening: Option<(u64, Coordinate)>,
+    results: [Option<TurnResult>; 2],
+}
+
+impl HotSeat {
+    pub fn new(names: [String; 2]) -> Self {
+        HotSeat {
+            names,
+            turn: 0,
+            opening: None,
+            results: [None, None],
+        }
+    }
+
+    /// Index of the player whose turn it is
+    pub fn turn(&self) -> usize {
+        self.turn
+    }
+
+    pub fn current_name(&self) -> &str {
+        &self.names[self.turn]
+    }
+
+    pub fn result(&self, player: usize) -> Option<&TurnResult> {
+        self.results[player].as_ref()
+    }
+
+    /// Notes the board the first player is starting on. Ignored on the second turn.
+    pub fn set_opening(&mut self, seed: u64, first_click: Coordinate) {
+        if self.turn == 0 {
+            self.opening = Some((seed, first_click));
+        }
+    }
+
+    /// Seed and first click the second player's board is built from, once it's their turn
+    pub fn opening(&self) -> Option<(u64, Coordinate)> {
+        self.opening.clone().filter(|_| self.turn == 1)
+    }
+
+    pub fn record(&mut self, result: TurnResult) {
+        self.results[self.turn] = Some(result);
+    }
+
+    /// The first player is done and the board has to be handed over
+    pub fn is_waiting_for_next_turn(&self) -> bool {
+        self.turn == 0 && self.results[0].is_some()
+    }
+
+    pub fn next_turn(&mut self) {
+        if self.is_waiting_for_next_turn() {
+            self.turn = 1;
+        }
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.results[1].is_some()
+    }
+
+    /// Index of the player who won the match, or `None` for a tie or a match still being
+    /// played. Clearing the board beats not clearing it, then the faster clear wins. If
+    /// neither cleared it, whoever revealed more safe squares wins.
+    pub fn winner(&self) -> Option<usize> {
+        let (Some(a), Some(b)) = (&self.results[0], &self.results[1]) else {
+            return None;
+        };
+        let ordering = match (a.won, b.won) {
+            (true, false) => Ordering::Less,
+            (false, true) => Ordering::Greater,
+            (true, true) => a.time.total_cmp(&b.time),
+            (false, false) => b.cleared.cmp(&a.cleared),
+        };
+        match ordering {
+            Ordering::Less => Some(0),
+            Ordering::Greater => Some(1),
+            Ordering::Equal => None,
+        }
+    }
+}
+
+#[test]
+fn test_hotseat() {
+    let result = |won, time, cleared| TurnResult { won, time, cleared };
+    let mut hotseat = HotSeat::new(["Alice".to_string(), "Bob".to_string()]);
+    hotseat.set_opening(42, Coordinate::from((3, 4)));
+    assert_eq!(hotseat.opening(), None);
+    assert!(!hotseat.is_waiting_for_next_turn());
+
+    hotseat.record(result(true, 20.0, 71));
+    assert!(hotseat.is_waiting_for_next_turn());
+    hotseat.next_turn();
+    assert_eq!(hotseat.current_name(), "Bob");
+    hotseat.set_opening(7, Coordinate::from((0, 0)));
+    assert_eq!(hotseat.opening(), Some((42, Coordinate::from((3, 4)))));
+    assert_eq!(hotseat.winner(), None);
+
+    hotseat.record(result(true, 15.0, 71));
+    assert!(hotseat.is_finished());
+    assert_eq!(hotseat.winner(), Some(1));
+
+    hotseat.results[1] = Some(result(false, 5.0, 60));
+    assert_eq!(hotseat.winner(), Some(0));
+    hotseat.results[0] = Some(result(false, 5.0, 60));
+    assert_eq!(hotseat.winner(), None);
+}
@@ -0,0 +1,170 @@
+use std::collections::HashMap;
+use std::fmt::Display;
+use std::sync::{OnceLock, RwLock};
+
+use chrono::{DateTime, TimeZone};
+
+use crate::enums::Language;
+
+/// UI text by key, one table per language. English is the template the others are
+/// translated from: every key has to be there, and other languages fall back to it.
+type Table = HashMap<String, String>;
+
+static LANGUAGE: RwLock<Language> = RwLock::new(Language::English);
+
+fn parse_table(text: &str) -> Table {
+    toml::from_str(text).expect("Invalid translation table")
+}
+
+fn table(language: Language) -> &'static Table {
+    static ENGLISH: OnceLock<Table> = OnceLock::new();
+    static SPANISH: OnceLock<Table> = OnceLock::new();
+    match language {
+        Language::English => {
+            ENGLISH.get_or_init(|| parse_table(include_str!("../assets/i18n/en.toml")))
+        }
+        Language::Spanish => {
+            SPANISH.get_or_init(|| parse_table(include_str!("../assets/i18n/es.toml")))
+        }
+    }
+}
+
+/// Text for `key` in `language`, in English if it hasn't been translated yet
+fn lookup(language: Language, key: &'static str) -> &'static str {
+    table(language)
+        .get(key)
+        .or_else(|| table(Language::English).get(key))
+        .map_or(key, |s| s.as_str())
+}
+
+/// Fills in the `{name}` placeholders in `text`
+fn fill(text: &str, args: &[(&str, &dyn Display)]) -> String {
+    args.iter().fold(text.to_string(), |text, (name, value)| {
+        text.replace(&format!("{{{}}}", name), &value.to_string())
+    })
+}
+
+/// Sets the language the UI is shown in
+pub fn set_language(language: Language) {
+    *LANGUAGE.write().unwrap() = language;
+}
+
+fn language() -> Language {
+    *LANGUAGE.read().unwrap()
+}
+
+/// The UI text for `key`
+pub fn tr(key: &'static str) -> &'static str {
+    lookup(language(), key)
+}
+
+/// The UI text for `key`, with its `{name}` placeholders filled in from `args`
+pub fn tr_with(key: &'static str, args: &[(&str, &dyn Display)]) -> String {
+    fill(tr(key), args)
+}
+
+/// A date and time the way the UI's language writes them
+pub fn format_date_time<Tz: TimeZone>(date: &DateTime<Tz>) -> String
+where
+    Tz::Offset: Display,
+{
+    date.format(tr("date-time-format")).to_string()
+}
+
+/// The `{name}` placeholders in `text`, sorted
+#[cfg(test)]
+fn placeholders(text: &str) -> Vec<&str> {
+    let mut names: Vec<&str> = text
+        .split('{')
+        .skip(1)
+        .filter_map(|s| s.split_once('}').map(|(name, _)| name))
+        .collect();
+    names.sort_unstable();
+    names
+}
+
+#[test]
+fn test_translations_match_template() {
+    let english = table(Language::English);
+    Language::all().into_iter().for_each(|language| {
+        let table = table(language);
+        let mut missing: Vec<&String> =
+            english.keys().filter(|k| !table.contains_key(*k)).collect();
+        let mut extra: Vec<&String> = table.keys().filter(|k| !english.contains_key(*k)).collect();
+        missing.sort();
+        extra.sort();
+        assert!(
+            missing.is_empty(),
+            "{:?} is missing {:?}",
+            language,
+            missing
+        );
+        assert!(
+            extra.is_empty(),
+            "{:?} has unknown keys {:?}",
+            language,
+            extra
+        );
+        table.iter().for_each(|(key, text)| {
+            assert_eq!(
+                placeholders(text),
+                placeholders(&english[key]),
+                "{:?} {}",
+                language,
+                key
+            );
+        });
+    });
+}
+
+#[test]
+fn test_keys_used_are_in_template() {
+    let english = table(Language::English);
+    [
+        include_str!("app.rs"),
+        include_str!("enums.rs"),
+        include_str!("leader.rs"),
+    ]
+    .iter()
+    .for_each(|source| {
+        ["tr(", "tr_with("].iter().for_each(|call| {
+            source.match_indices(call).for_each(|(i, _)| {
+                let word_start = !source[..i].ends_with(|c: char| c.is_alphanumeric() || c == '_');
+                let key = source[i + call.len()..]
+                    .trim_start()
+                    .strip_prefix('"')
+                    .and_then(|s| s.split_once('"'))
+                    .map(|(key, _)| key);
+                if let (true, Some(key)) = (word_start, key) {
+                    assert!(english.contains_key(key), "{} isn't in en.toml", key);
+                }
+            });
+        });
+    });
+}
+
+#[test]
+fn test_lookup() {
+    assert_eq!(lookup(Language::English, "menu-game"), "Game");
+    assert_eq!(lookup(Language::Spanish, "menu-game"), "Juego");
+    // Unknown keys are shown as they are, so they stand out
+    assert_eq!(lookup(Language::Spanish, "no-such-key"), "no-such-key");
+    assert_eq!(
+        fill(
+            lookup(Language::English, "joining"),
+            &[("address", &"127.0.0.1:7878")]
+        ),
+        "Joining 127.0.0.1:7878..."
+    );
+
+    let date = chrono::FixedOffset::east_opt(0)
+        .unwrap()
+        .with_ymd_and_hms(2024, 3, 9, 14, 5, 0)
+        .unwrap();
+    let format = |language| {
+        date.format(lookup(language, "date-time-format"))
+            .to_string()
+    };
+    assert_eq!(format(Language::English), "2024-03-09 14:05");
+    assert_eq!(format(Language::Spanish), "09/03/2024 14:05");
+}
@@ -0,0 +1,306 @@
+use egui::{PointerButton, Pos2};
+
+use crate::minesweeper::Coordinate;
+
+/// The mouse buttons that reveal and flag squares on the board, which are swapped for left
+/// handed players. Touches always act as the primary button, so are never swapped.
+pub fn board_buttons(swap_buttons: bool, is_touch: bool) -> (PointerButton, PointerButton) {
+    if swap_buttons && !is_touch {
+        (PointerButton::Secondary, PointerButton::Primary)
+    } else {
+        (PointerButton::Primary, PointerButton::Secondary)
+    }
+}
+
+/// Pointer state for the board's gestures, read once per frame
+#[derive(Debug, Clone)]
+pub struct BoardInput {
+    pub pointer: Option<Pos2>,
+    pub time: f64,
+    pub reveal_button: PointerButton,
+    pub flag_button: PointerButton,
+    pub reveal_pressed: bool,
+    pub reveal_down: bool,
+    pub flag_pressed: bool,
+    pub flag_down: bool,
+    /// The pointer is being driven by a touchscreen rather than a mouse
+    pub is_touch: bool,
+    pub num_touches: usize,
+}
+
+impl BoardInput {
+    pub fn read(ctx: &egui::Context, swap_buttons: bool) -> Self {
+        ctx.input(|i| {
+            let is_touch = i.any_touches();
+            let (reveal_button, flag_button) = board_buttons(swap_buttons, is_touch);
+            BoardInput {
+                pointer: i.pointer.interact_pos(),
+                time: i.time,
+                reveal_button,
+                flag_button,
+                reveal_pressed: i.pointer.button_pressed(reveal_button),
+                reveal_down: i.pointer.button_down(reveal_button),
+                flag_pressed: i.pointer.button_pressed(flag_button),
+                flag_down: i.pointer.button_down(flag_button),
+                is_touch,
+                num_touches: i.multi_touch().map_or(0, |m| m.num_touches),
+            }
+        })
+    }
+}
+
+/// Tracks a right button drag across the board in "paint flags" mode, where every
+/// unrevealed square the drag passes over is flagged
+#[derive(Debug, Clone, Default)]
+pub struct FlagPaint {
+    /// Square the drag started on
+    start: Option<Coordinate>,
+    /// Squares already passed over by the drag, so none is toggled twice
+    painted: Vec<Coordinate>,
+}
+
+impl FlagPaint {
+    /// Forgets the previous drag. Call when the right button is pressed.
+    pub fn begin(&mut self) {
+        *self = FlagPaint::default();
+    }
+
+    /// True once the drag has left the square it started on. The right click that ends a
+    /// drag should then be ignored, or it would toggle the square it ended on again.
+    pub fn is_painting(&self) -> bool {
+        !self.painted.is_empty()
+    }
+
+    /// Moves the drag over `coord`, returning the squares that should now be flagged. The
+    /// start square is only flagged once the drag leaves it, so a plain right click still
+    /// toggles a flag as usual.
+    pub fn drag_over(&mut self, coord: Coordinate) -> Vec<Coordinate> {
+        let Some(start) = &self.start else {
+            self.start = Some(coord);
+            return vec![];
+        };
+        if *start == coord || self.painted.contains(&coord) {
+            return vec![];
+        }
+
+        let mut newly_painted = vec![];
+        if self.painted.is_empty() {
+            newly_painted.push(start.clone());
+        }
+        newly_painted.push(coord);
+        self.painted.extend(newly_painted.iter().cloned());
+        newly_painted
+    }
+}
+
+/// What a tap on the board should do once touch gestures have been accounted for
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TapAction {
+    /// A plain tap or click
+    Reveal,
+    /// A quick tap with two fingers
+    Chord,
+    /// The end of a long press or a gesture that wasn't a tap
+    Ignore,
+}
+
+#[derive(Debug, Clone)]
+struct TouchPress {
+    coord: Coordinate,
+    started: f64,
+    multi_touch: bool,
+    long_pressed: bool,
+    moved: bool,
+}
+
+/// Recognizes the touchscreen gestures that stand in for the other mouse buttons: a long
+/// press flags a square and a two finger tap chords it
+#[derive(Debug, Clone, Default)]
+pub struct TouchGestures {
+    press: Option<TouchPress>,
+}
+
+impl TouchGestures {
+    /// Starts tracking a touch that went down on `coord`
+    pub fn begin(&mut self, coord: Coordinate, time: f64) {
+        self.press = Some(TouchPress {
+            coord,
+            started: time,
+            multi_touch: false,
+            long_pressed: false,
+            moved: false,
+        });
+    }
+
+    /// Follows a touch that is still down over `over`. Returns the square to flag once the
+    /// touch has been held still on it for `long_press_seconds`.
+    pub fn update(
+        &mut self,
+        over: &Coordinate,
+        time: f64,
+        num_touches: usize,
+        long_press_seconds: f64,
+    ) -> Option<Coordinate> {
+        let press = self.press.as_mut()?;
+        press.multi_touch |= num_touches > 1;
+        press.moved |= *over != press.coord;
+        if press.multi_touch || press.moved || press.long_pressed {
+            return None;
+        }
+        if time - press.started >= long_press_seconds {
+            press.long_pressed = true;
+            return Some(press.coord.clone());
+        }
+        None
+    }
+
+    /// Stops tracking the touch, for when it ended without a tap
+    pub fn cancel(&mut self) {
+        self.press = None;
+    }
+
+    /// True while a touch is being held still, so it could still become a long press
+    pub fn awaiting_long_press(&self) -> bool {
+        matches!(&self.press, Some(p) if !(p.multi_touch || p.moved || p.long_pressed))
+    }
+
+    /// Decides what the tap or click that just ended should do. Clicks that didn't start
+    /// as a tracked touch, such as those from a mouse, are always reveals.
+    pub fn tap(&mut self, time: f64, two_finger_tap_seconds: f64) -> TapAction {
+        match self.press.take() {
+            None => TapAction::Reveal,
+            Some(p) if p.long_pressed || p.moved => TapAction::Ignore,
+            Some(p) if p.multi_touch && time - p.started <= two_finger_tap_seconds => {
+                TapAction::Chord
+            }
+            Some(p) if p.multi_touch => TapAction::Ignore,
+            Some(_) => TapAction::Reveal,
+        }
+    }
+}
+
+/// A square selected with the arrow keys, for playing without the mouse. Hidden until an
+/// arrow key is pressed, and again when the mouse moves.
+#[derive(Debug, Clone, Default)]
+pub struct KeyboardCursor {
+    coord: Option<Coordinate>,
+    visible: bool,
+}
+
+impl KeyboardCursor {
+    pub fn coord(&self) -> Option<Coordinate> {
+        self.coord.clone().filter(|_| self.visible)
+    }
+
+    pub fn hide(&mut self) {
+        self.visible = false;
+    }
+
+    /// Moves the cursor by (`dx`, `dy`), stopping at the edges of the board. Showing the
+    /// cursor puts it where it was left, or in the middle of the board the first time.
+    pub fn move_by(&mut self, dx: i32, dy: i32, width: u32, height: u32) {
+        let (x, y) = match &self.coord {
+            Some(c) if self.visible => (c.x as i32 + dx, c.y as i32 + dy),
+            Some(c) => (c.x as i32, c.y as i32),
+            None => ((width / 2) as i32, (height / 2) as i32),
+        };
+        self.visible = true;
+        self.coord = Some(Coordinate::from((
+            x.clamp(0, width as i32 - 1) as u32,
+            y.clamp(0, height as i32 - 1) as u32,
+        )));
+    }
+}
+
+#[test]
+fn test_board_buttons() {
+    assert_eq!(
+        board_buttons(false, false),
+        (PointerButton::Primary, PointerButton::Secondary)
+    );
+    assert_eq!(
+        board_buttons(true, false),
+        (PointerButton::Secondary, PointerButton::Primary)
+    );
+    assert_eq!(
+        board_buttons(true, true),
+        (PointerButton::Primary, PointerButton::Secondary)
+    );
+}
+
+#[test]
+fn test_touch_gestures() {
+    let square = Coordinate::from((2, 3));
+    let mut touch = TouchGestures::default();
+    assert_eq!(touch.tap(0.0, 0.3), TapAction::Reveal);
+
+    // Long press flags once, and the release doesn't reveal
+    touch.begin(square.clone(), 1.0);
+    assert_eq!(touch.update(&square, 1.2, 1, 0.5), None);
+    assert!(touch.awaiting_long_press());
+    assert_eq!(touch.update(&square, 1.5, 1, 0.5), Some(square.clone()));
+    assert_eq!(touch.update(&square, 1.7, 1, 0.5), None);
+    assert!(!touch.awaiting_long_press());
+    assert_eq!(touch.tap(1.8, 0.3), TapAction::Ignore);
+
+    // Sliding off the square cancels the long press
+    touch.begin(square.clone(), 2.0);
+    assert_eq!(touch.update(&Coordinate::from((2, 4)), 2.6, 1, 0.5), None);
+    assert_eq!(touch.tap(2.7, 0.3), TapAction::Ignore);
+
+    // Two finger taps chord unless held too long
+    touch.begin(square.clone(), 3.0);
+    assert_eq!(touch.update(&square, 3.1, 2, 0.5), None);
+    assert_eq!(touch.tap(3.2, 0.3), TapAction::Chord);
+    touch.begin(square.clone(), 4.0);
+    assert_eq!(touch.update(&square, 4.6, 2, 0.5), None);
+    assert_eq!(touch.tap(4.7, 0.3), TapAction::Ignore);
+
+    // A quick single finger tap reveals
+    touch.begin(square.clone(), 5.0);
+    assert_eq!(touch.update(&square, 5.1, 1, 0.5), None);
+    assert_eq!(touch.tap(5.1, 0.3), TapAction::Reveal);
+}
+
+#[test]
+fn test_flag_paint() {
+    let mut paint = FlagPaint::default();
+    paint.begin();
+    assert!(paint.drag_over(Coordinate::from((0, 0))).is_empty());
+    assert!(paint.drag_over(Coordinate::from((0, 0))).is_empty());
+    assert!(!paint.is_painting());
+
+    assert_eq!(
+        paint.drag_over(Coordinate::from((1, 0))),
+        [Coordinate::from((0, 0)), Coordinate::from((1, 0))]
+    );
+    assert_eq!(
+        paint.drag_over(Coordinate::from((2, 0))),
+        [Coordinate::from((2, 0))]
+    );
+    // Going back over squares already painted doesn't unflag them
+    assert!(paint.drag_over(Coordinate::from((1, 0))).is_empty());
+    assert!(paint.drag_over(Coordinate::from((0, 0))).is_empty());
+    assert!(paint.is_painting());
+
+    paint.begin();
+    assert!(!paint.is_painting());
+}
+
+#[test]
+fn test_keyboard_cursor() {
+    let mut cursor = KeyboardCursor::default();
+    assert_eq!(cursor.coord(), None);
+    cursor.move_by(1, 0, 9, 9);
+    assert_eq!(cursor.coord(), Some(Coordinate::from((4, 4))));
+    cursor.move_by(-1, -1, 9, 9);
+    assert_eq!(cursor.coord(), Some(Coordinate::from((3, 3))));
+    (0..10).for_each(|_| cursor.move_by(1, -1, 9, 9));
+    assert_eq!(cursor.coord(), Some(Coordinate::from((8, 0))));
+    cursor.hide();
+    assert_eq!(cursor.coord(), None);
+    cursor.move_by(0, 1, 9, 9);
+    assert_eq!(cursor.coord(), Some(Coordinate::from((8, 0))));
+    cursor.move_by(0, 0, 5, 5);
+    assert_eq!(cursor.coord(), Some(Coordinate::from((4, 0))));
+}
@@ -1,14 +1,39 @@
-use std::fs;
-use std::fs::File;
-use std::io::Write;
+use std::collections::BTreeMap;
 
-use anyhow::anyhow;
 use chrono::prelude::*;
+use hmac::{Hmac, Mac};
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
 
+use crate::config::{self, Versioned};
 use crate::enums::GameDifficulty;
+use crate::i18n::tr;
+use crate::minesweeper::Coordinate;
+use crate::plays::PlayList;
+use crate::state::GameSettings;
 
-const MAX_ENTRIES_PER_BOARD: usize = 25;
+const CONFIG_FILE_NAME: &str = "minesofrust-leaderboard.toml";
+
+/// Key used to sign leaderboard entries. It ships with the app, so signing only catches
+/// casual edits to the leaderboard file, not a determined cheat.
+const ENTRY_SIGNING_KEY: &[u8] = b"minesofrust-leaderboard-entry-v1";
+
+/// How far the time of a replay's last play can be from the time recorded for the win. The
+/// clock stops just after the winning play is logged.
+const REPLAY_TIME_TOLERANCE: f64 = 0.05;
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// Whether a leaderboard entry matches its signature
+#[derive(Debug, Eq, PartialEq, Clone, Copy)]
+pub enum EntryIntegrity {
+    Valid,
+    /// Written before entries were signed, or before the replay was covered by the
+    /// signature
+    Unsigned,
+    /// Edited since it was recorded
+    Tampered,
+}
 
 #[derive(Clone, Deserialize, Serialize)]
 pub struct Entry {
@@ -17,92 +42,647 @@ pub struct Entry {
     #[serde(with = "as_df_date")]
     pub date: DateTime<FixedOffset>,
     pub time: f64,
+
+    /// Seed of the board the game was played on. Stored as hex since TOML integers can't
+    /// hold the full range of a u64.
+    #[serde(default, with = "as_hex_seed")]
+    pub seed: u64,
+
+    /// Hex encoded HMAC-SHA256 of the entry's fields
+    #[serde(default)]
+    pub signature: String,
+
+    /// 3BV of the board and the clicks taken to clear it. Zero for entries recorded before
+    /// they were tracked.
+    #[serde(default)]
+    pub bbbv: u32,
+    #[serde(default)]
+    pub clicks: u32,
+
+    /// Flags were placed during the game. Entries recorded before it was tracked count as
+    /// flagged.
+    #[serde(default = "default_flagged")]
+    pub flagged: bool,
+
+    /// The first square revealed, which together with the seed gives the board, so it can
+    /// be played again. Only kept for single board wins.
+    #[serde(default)]
+    pub first_click: Option<Coordinate>,
+
+    /// The plays that won the game, timed, for checking the win was real. Only kept for
+    /// single board wins.
+    #[serde(default)]
+    pub replay: Option<PlayList>,
+
+    /// Seconds of `time` added at the end of a game played with time penalties. The
+    /// replay's plays are timed without them.
+    #[serde(default)]
+    pub penalty: f64,
+
+    /// Whether the replay was played back to the same win, once `verify_replay()` has
+    /// checked it. Never saved, so it can't be written into the file.
+    #[serde(skip)]
+    pub verified: Option<bool>,
+}
+
+fn default_flagged() -> bool {
+    true
+}
+
+impl Entry {
+    pub fn new(player_name: &str, time: f64, seed: u64, bbbv: u32, clicks: u32) -> Self {
+        let mut entry = Entry {
+            player_name: player_name.to_string(),
+            date: Local::now().fixed_offset(),
+            time,
+            seed,
+            signature: String::new(),
+            bbbv,
+            clicks,
+            flagged: true,
+            first_click: None,
+            replay: None,
+            penalty: 0.0,
+            verified: None,
+        };
+        entry.sign();
+        entry
+    }
+
+    /// Signs the entry as it is. Call again after setting any of the fields after `new()`.
+    pub fn sign(&mut self) {
+        self.signature = hex::encode(self.mac().finalize().into_bytes());
+    }
+
+    /// MAC over the signed fields. The date is formatted the same way it is stored so the
+    /// signature survives a round trip through the leaderboard file, and the replay is
+    /// covered by a hash of it.
+    fn mac(&self) -> HmacSha256 {
+        let first_click = self
+            .first_click
+            .as_ref()
+            .map_or(String::new(), |c| format!("{},{}", c.x, c.y));
+        let replay = self.replay.as_ref().map_or(String::new(), |r| {
+            let json = serde_json::to_vec(r).unwrap_or_default();
+            hex::encode(Sha256::digest(json))
+        });
+        let mut mac = self.legacy_mac();
+        mac.update(
+            format!(
+                "|{}|{}|{}|{}|{}",
+                self.bbbv, self.clicks, self.flagged, first_click, replay
+            )
+            .as_bytes(),
+        );
+        // Only signed when there is one, so entries signed before penalties were kept still
+        // check out
+        if self.penalty != 0.0 {
+            mac.update(format!("|{}", self.penalty).as_bytes());
+        }
+        mac
+    }
+
+    /// MAC over the fields signed before the replay was, which older entries are signed with
+    fn legacy_mac(&self) -> HmacSha256 {
+        let mut mac =
+            HmacSha256::new_from_slice(ENTRY_SIGNING_KEY).expect("HMAC accepts keys of any size");
+        mac.update(
+            format!(
+                "{}|{}|{}|{}",
+                self.player_name,
+                self.seed,
+                self.time,
+                self.date.format(as_df_date::FORMAT)
+            )
+            .as_bytes(),
+        );
+        mac
+    }
+
+    /// 3BV cleared per second, if the 3BV was recorded
+    pub fn bbbv_per_second(&self) -> Option<f64> {
+        (self.bbbv > 0 && self.time > 0.0).then(|| self.bbbv as f64 / self.time)
+    }
+
+    /// 3BV as a percentage of the clicks taken, if recorded
+    pub fn efficiency(&self) -> Option<f64> {
+        (self.bbbv > 0 && self.clicks > 0).then(|| self.bbbv as f64 / self.clicks as f64 * 100.0)
+    }
+
+    /// True if `other` records the same win, such as a copy of this entry brought in from
+    /// another machine. Dates are only stored to the millisecond.
+    pub fn is_same_win(&self, other: &Entry) -> bool {
+        self.player_name == other.player_name
+            && self.date.timestamp_millis() == other.date.timestamp_millis()
+            && self.time == other.time
+            && self.seed == other.seed
+    }
+
+    /// Plays the replay back on the board made from the seed, checking that it wins the game
+    /// with the 3BV, clicks and time, less any penalty, that were recorded. The error says
+    /// what doesn't match.
+    pub fn verify_replay(&self, category: &LeaderboardCategory) -> Result<(), String> {
+        let replay = self.replay.as_ref().ok_or("No replay was recorded")?;
+        let settings = GameSettings::settings_for_mode(&category.difficulty, category.no_flags, 1);
+        let mut boards = [settings.new_board(self.seed)];
+        replay
+            .play_on(&settings, &mut boards)
+            .map_err(|e| format!("The replay can't be played: {}", e))?;
+        let board = &boards[0];
+        if !board.is_win_configuration() {
+            return Err("The replay doesn't clear the board".to_string());
+        }
+        if board.bbbv() != self.bbbv || replay.clicks() != self.clicks {
+            return Err(format!(
+                "The replay clears {} 3BV in {} clicks, not {} in {}",
+                board.bbbv(),
+                replay.clicks(),
+                self.bbbv,
+                self.clicks
+            ));
+        }
+        let elapsed = replay
+            .list
+            .iter()
+            .try_fold(0.0, |last, e| (e.time >= last).then_some(e.time))
+            .ok_or("The replay's plays are out of order")?;
+        if (elapsed + self.penalty - self.time).abs() > REPLAY_TIME_TOLERANCE {
+            return Err(format!(
+                "The replay takes {:.2} seconds, not {:.2}",
+                elapsed,
+                self.time - self.penalty
+            ));
+        }
+        Ok(())
+    }
+
+    pub fn integrity(&self) -> EntryIntegrity {
+        if self.signature.is_empty() {
+            return EntryIntegrity::Unsigned;
+        }
+        let Ok(sig) = hex::decode(&self.signature) else {
+            return EntryIntegrity::Tampered;
+        };
+        if self.mac().verify_slice(&sig).is_ok() {
+            return EntryIntegrity::Valid;
+        }
+        // Older entries with nothing to replay are as good as they were, but a replay that
+        // isn't signed can't be trusted
+        match self.legacy_mac().verify_slice(&sig) {
+            Ok(()) if self.replay.is_none() && self.first_click.is_none() => EntryIntegrity::Valid,
+            Ok(()) => EntryIntegrity::Unsigned,
+            Err(_) => EntryIntegrity::Tampered,
+        }
+    }
+}
+
+/// Column that leaderboard entries can be ordered by
+#[derive(Debug, Eq, PartialEq, Clone, Copy)]
+pub enum SortColumn {
+    PlayerName,
+    Time,
+    /// The time with the player's handicap taken off
+    AdjustedTime,
+    Date,
+}
+
+/// Time taken off a player's wins, so players of different skill, like a household's, can
+/// race each other on the same leaderboard
+#[derive(Debug, PartialEq, Clone, Copy, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Handicap {
+    /// A percentage of the time
+    Percent(f64),
+    /// A number of seconds, never going below zero
+    Seconds(f64),
+}
+
+impl Handicap {
+    pub fn apply(&self, time: f64) -> f64 {
+        match self {
+            Handicap::Percent(p) => time * (1.0 - p.clamp(0.0, 100.0) / 100.0),
+            Handicap::Seconds(s) => (time - s).max(0.0),
+        }
+    }
+}
+
+/// The handicaps of the players that have one, by player name
+pub type Handicaps = BTreeMap<String, Handicap>;
+
+/// `entry`'s time with its player's handicap taken off, the same as its time if they don't
+/// have one
+pub fn adjusted_time(handicaps: &Handicaps, entry: &Entry) -> f64 {
+    handicaps
+        .get(&entry.player_name)
+        .map_or(entry.time, |h| h.apply(entry.time))
+}
+
+/// Criteria for which leaderboard entries are shown. Empty criteria match everything.
+#[derive(Clone, Default)]
+pub struct EntryFilter {
+    /// Case insensitive substring of the player name, the date or the time
+    pub search: String,
+    /// Case insensitive substring of the player name
+    pub player_name: String,
+    /// Only the wins of this player, matched exactly
+    pub player: Option<String>,
+    /// First and last days to include
+    pub from: Option<NaiveDate>,
+    pub to: Option<NaiveDate>,
+    /// Only wins played with flags, or only those played without
+    pub flagged: Option<bool>,
+    /// Only the fastest of the wins matching the rest of the filter, by their times less any
+    /// handicap
+    pub top: Option<usize>,
+}
+
+impl EntryFilter {
+    /// Whether `entry` matches everything but `top`, which depends on the other entries
+    pub fn matches(&self, entry: &Entry) -> bool {
+        let date = entry.date.date_naive();
+        let search = self.search.trim().to_lowercase();
+        let found = search.is_empty()
+            || entry.player_name.to_lowercase().contains(&search)
+            || date.format("%Y-%m-%d").to_string().contains(&search)
+            || format!("{:.2}", entry.time).contains(&search);
+        found
+            && entry
+                .player_name
+                .to_lowercase()
+                .contains(&self.player_name.trim().to_lowercase())
+            && !matches!(&self.player, Some(player) if entry.player_name != *player)
+            && !matches!(self.from, Some(from) if date < from)
+            && !matches!(self.to, Some(to) if date > to)
+            && !matches!(self.flagged, Some(flagged) if entry.flagged != flagged)
+    }
+}
+
+/// A win deleted from a leaderboard, kept so that merging in another copy of the
+/// leaderboard that still has the win doesn't bring it back
+#[derive(Clone, Deserialize, Serialize)]
+pub struct DeletedEntry {
+    pub player_name: String,
+    #[serde(with = "as_df_date")]
+    pub date: DateTime<FixedOffset>,
+    pub time: f64,
+    #[serde(with = "as_hex_seed")]
+    pub seed: u64,
+    /// When the win was deleted
+    #[serde(with = "as_df_date")]
+    pub deleted: DateTime<FixedOffset>,
+}
+
+impl DeletedEntry {
+    fn of(entry: &Entry) -> Self {
+        DeletedEntry {
+            player_name: entry.player_name.clone(),
+            date: entry.date,
+            time: entry.time,
+            seed: entry.seed,
+            deleted: Local::now().fixed_offset(),
+        }
+    }
+
+    /// The win, as `Entry::is_same_win()` tells wins apart
+    fn win(&self) -> (&str, i64, f64, u64) {
+        (
+            &self.player_name,
+            self.date.timestamp_millis(),
+            self.time,
+            self.seed,
+        )
+    }
+
+    /// True if this deletion takes `entry` off the leaderboard. Wins aren't changed once
+    /// they're recorded, so a deletion of one is always newer than any copy of it. Dates are
+    /// compared to the millisecond, as that's all the file keeps.
+    fn removes(&self, entry: &Entry) -> bool {
+        self.deleted.timestamp_millis() >= entry.date.timestamp_millis()
+            && self.win()
+                == (
+                    entry.player_name.as_str(),
+                    entry.date.timestamp_millis(),
+                    entry.time,
+                    entry.seed,
+                )
+    }
 }
 
 #[derive(Clone, Deserialize, Serialize, Default)]
 pub struct LeaderBoard {
     pub entries: Vec<Entry>,
+    /// Wins deleted from the leaderboard, which merging leaves out
+    #[serde(default)]
+    pub deleted: Vec<DeletedEntry>,
+}
+
+/// Stands in for multi-board leaderboards nobody has won a game on yet
+static EMPTY_BOARD: LeaderBoard = LeaderBoard {
+    entries: Vec::new(),
+    deleted: Vec::new(),
+};
+
+/// The kind of game a leaderboard holds wins for
+#[derive(Eq, PartialEq, Clone)]
+pub struct LeaderboardCategory {
+    pub difficulty: GameDifficulty,
+    pub no_flags: bool,
+    /// Boards played at once
+    pub num_boards: u32,
+}
+
+impl LeaderboardCategory {
+    pub fn new(difficulty: GameDifficulty, no_flags: bool, num_boards: u32) -> Self {
+        LeaderboardCategory {
+            difficulty,
+            no_flags,
+            num_boards,
+        }
+    }
+
+    /// Every category, single board ones first
+    pub fn all() -> impl Iterator<Item = LeaderboardCategory> {
+        [1, 2, 4].into_iter().flat_map(|num_boards| {
+            [false, true].into_iter().flat_map(move |no_flags| {
+                [
+                    GameDifficulty::Beginner,
+                    GameDifficulty::Intermediate,
+                    GameDifficulty::Expert,
+                ]
+                .into_iter()
+                .map(move |d| LeaderboardCategory::new(d, no_flags, num_boards))
+            })
+        })
+    }
+
+    pub fn title(&self) -> String {
+        let mut title = self.difficulty.name().to_string();
+        if self.num_boards > 1 {
+            title.push_str(&format!(" x{}", self.num_boards));
+        }
+        if self.no_flags {
+            title.push_str(&format!(" ({})", tr("no-flags")));
+        }
+        title
+    }
+
+    /// Key of a multi-board category in the leaderboard file
+    fn key(&self) -> String {
+        format!(
+            "{}-x{}{}",
+            self.difficulty.as_str().to_lowercase(),
+            self.num_boards,
+            if self.no_flags { "-nf" } else { "" }
+        )
+    }
 }
 
 impl LeaderBoard {
-    pub fn add(&mut self, player_name: &str, time: f64) {
-        self.entries.push(Entry {
-            player_name: player_name.to_string(),
-            date: Local::now().fixed_offset(),
-            time,
+    /// Records a new win, keeping no more than the best `max_entries` if there's a limit
+    pub fn add(&mut self, entry: Entry, max_entries: Option<usize>, handicaps: &Handicaps) {
+        self.entries.push(entry);
+        self.sort_and_trim(max_entries, handicaps);
+    }
+
+    /// Indices of the entries matching `filter`, in the requested order. Adjusted times take
+    /// off the players' `handicaps`.
+    pub fn filtered_sorted(
+        &self,
+        filter: &EntryFilter,
+        handicaps: &Handicaps,
+        sort: SortColumn,
+        ascending: bool,
+    ) -> Vec<usize> {
+        let mut indices: Vec<usize> = (0..self.entries.len())
+            .filter(|&i| filter.matches(&self.entries[i]))
+            .collect();
+        if let Some(top) = filter.top {
+            indices.sort_by(|&a, &b| {
+                adjusted_time(handicaps, &self.entries[a])
+                    .total_cmp(&adjusted_time(handicaps, &self.entries[b]))
+            });
+            indices.truncate(top);
+        }
+        indices.sort_by(|&a, &b| {
+            let (a, b) = (&self.entries[a], &self.entries[b]);
+            let ord = match sort {
+                SortColumn::PlayerName => a
+                    .player_name
+                    .to_lowercase()
+                    .cmp(&b.player_name.to_lowercase()),
+                SortColumn::Time => a.time.total_cmp(&b.time),
+                SortColumn::AdjustedTime => {
+                    adjusted_time(handicaps, a).total_cmp(&adjusted_time(handicaps, b))
+                }
+                SortColumn::Date => a.date.cmp(&b.date),
+            };
+            if ascending {
+                ord
+            } else {
+                ord.reverse()
+            }
+        });
+        indices
+    }
+
+    pub fn remove(&mut self, index: usize) -> Option<Entry> {
+        if index < self.entries.len() {
+            let entry = self.entries.remove(index);
+            self.deleted.push(DeletedEntry::of(&entry));
+            Some(entry)
+        } else {
+            None
+        }
+    }
+
+    pub fn clear(&mut self) {
+        let entries = std::mem::take(&mut self.entries);
+        self.deleted.extend(entries.iter().map(DeletedEntry::of));
+    }
+
+    /// Adds the entries of `other` that aren't already on this leaderboard, then takes off
+    /// any win deleted from either of them
+    pub fn merge(
+        &mut self,
+        other: &LeaderBoard,
+        max_entries: Option<usize>,
+        handicaps: &Handicaps,
+    ) {
+        other.deleted.iter().for_each(|d| {
+            if !self.deleted.iter().any(|mine| mine.win() == d.win()) {
+                self.deleted.push(d.clone());
+            }
         });
-        self.sort_and_trim();
+        other.entries.iter().for_each(|e| {
+            if !self.entries.iter().any(|mine| mine.is_same_win(e)) {
+                self.entries.push(e.clone());
+            }
+        });
+        let deleted = &self.deleted;
+        self.entries
+            .retain(|e| !deleted.iter().any(|d| d.removes(e)));
+        self.sort_and_trim(max_entries, handicaps);
     }
 
-    pub fn sort_and_trim(&mut self) {
+    /// Sorts the wins by time, keeping no more than `max_entries` of them if there's a limit.
+    /// A win stays if it's among the best by either its time or its time after `handicaps`,
+    /// so trimming never takes a win off the adjusted view.
+    pub fn sort_and_trim(&mut self, max_entries: Option<usize>, handicaps: &Handicaps) {
         self.entries.sort_by(|a, b| a.time.total_cmp(&b.time));
-        if self.entries.len() > MAX_ENTRIES_PER_BOARD {
-            self.entries = self.entries[0..MAX_ENTRIES_PER_BOARD].to_vec();
+        if let Some(max_entries) = max_entries {
+            let mut by_adjusted: Vec<usize> = (0..self.entries.len()).collect();
+            by_adjusted.sort_by(|&a, &b| {
+                adjusted_time(handicaps, &self.entries[a])
+                    .total_cmp(&adjusted_time(handicaps, &self.entries[b]))
+            });
+            let mut keep = vec![false; self.entries.len()];
+            keep.iter_mut().take(max_entries).for_each(|k| *k = true);
+            by_adjusted
+                .iter()
+                .take(max_entries)
+                .for_each(|&i| keep[i] = true);
+            let mut keep = keep.into_iter();
+            self.entries.retain(|_| keep.next().unwrap_or(false));
         }
     }
 }
 
-#[derive(Clone, Deserialize, Serialize, Default)]
+#[derive(Clone, Deserialize, Serialize)]
 pub struct LeaderBoards {
+    #[serde(default)]
+    pub version: i64,
     pub beginner: LeaderBoard,
     pub intermediate: LeaderBoard,
     pub expert: LeaderBoard,
+
+    /// Wins played without flags
+    #[serde(default)]
+    pub beginner_nf: LeaderBoard,
+    #[serde(default)]
+    pub intermediate_nf: LeaderBoard,
+    #[serde(default)]
+    pub expert_nf: LeaderBoard,
+
+    /// Wins played on several boards at once, by category key
+    #[serde(default)]
+    pub multi_board: BTreeMap<String, LeaderBoard>,
+
+    #[serde(default)]
+    pub handicaps: Handicaps,
+}
+
+impl Default for LeaderBoards {
+    fn default() -> Self {
+        Self {
+            version: <LeaderBoards as Versioned>::VERSION,
+            beginner: LeaderBoard::default(),
+            intermediate: LeaderBoard::default(),
+            expert: LeaderBoard::default(),
+            beginner_nf: LeaderBoard::default(),
+            intermediate_nf: LeaderBoard::default(),
+            expert_nf: LeaderBoard::default(),
+            multi_board: BTreeMap::new(),
+            handicaps: Handicaps::new(),
+        }
+    }
+}
+
+impl Versioned for LeaderBoards {
+    // 1: Added the version field
+    // 2: Added entry seeds and signatures
+    // 3: Added the no flag boards
+    // 4: Added the multi-board boards
+    // 5: Added player handicaps
+    // 6: Added 3BV, clicks, flags, first click and signed replays
+    // 7: Added deleted entries
+    // 8: Added time penalties
+    const VERSION: i64 = 8;
 }
 
 impl LeaderBoards {
     #[allow(dead_code)]
     pub fn leaderboard_for_level(&self, level: GameDifficulty) -> LeaderBoard {
-        match level {
-            GameDifficulty::Beginner => &self.beginner,
-            GameDifficulty::Intermediate => &self.intermediate,
-            GameDifficulty::Expert => &self.expert,
-        }
-        .clone()
-    }
-
-    pub fn add(&mut self, level: GameDifficulty, player_name: &str, time: f64) {
-        match level {
-            GameDifficulty::Beginner => &mut self.beginner,
-            GameDifficulty::Intermediate => &mut self.intermediate,
-            GameDifficulty::Expert => &mut self.expert,
-        }
-        .add(player_name, time);
-    }
-
-    pub fn load_from_userhome() -> anyhow::Result<Self> {
-        let config_file_path = dirs::home_dir()
-            .unwrap()
-            .join(".apoapsys/minesofrust-leaderboard.toml");
-        if config_file_path.exists() {
-            println!(
-                "Window state config file exists at path: {:?}",
-                config_file_path
-            );
-            let t = std::fs::read_to_string(config_file_path)?;
-            Ok(toml::from_str(&t)?)
-        } else {
-            println!("Window state config file does not exist. Will be created on exit");
-            Err(anyhow!("Config file does not exist"))
+        self.board(&LeaderboardCategory::new(level, false, 1))
+            .clone()
+    }
+
+    pub fn board(&self, category: &LeaderboardCategory) -> &LeaderBoard {
+        if category.num_boards > 1 {
+            return self
+                .multi_board
+                .get(&category.key())
+                .unwrap_or(&EMPTY_BOARD);
+        }
+        match (&category.difficulty, category.no_flags) {
+            (GameDifficulty::Beginner, false) => &self.beginner,
+            (GameDifficulty::Intermediate, false) => &self.intermediate,
+            (GameDifficulty::Expert, false) => &self.expert,
+            (GameDifficulty::Beginner, true) => &self.beginner_nf,
+            (GameDifficulty::Intermediate, true) => &self.intermediate_nf,
+            (GameDifficulty::Expert, true) => &self.expert_nf,
         }
     }
 
-    pub fn save_to_userhome(&self) {
-        let toml_str = toml::to_string(&self).unwrap();
-        let apoapsys_config_dir = dirs::home_dir().unwrap().join(".apoapsys/");
-        if !apoapsys_config_dir.exists() {
-            fs::create_dir(&apoapsys_config_dir).expect("Failed to create config directory");
+    pub fn board_mut(&mut self, category: &LeaderboardCategory) -> &mut LeaderBoard {
+        if category.num_boards > 1 {
+            return self.multi_board.entry(category.key()).or_default();
         }
-        let config_file_path = apoapsys_config_dir.join("minesofrust-leaderboard.toml");
-        let mut f = File::create(config_file_path).expect("Failed to create config file");
-        f.write_all(toml_str.as_bytes())
-            .expect("Failed to write to config file");
-        println!("{}", toml_str);
+        match (&category.difficulty, category.no_flags) {
+            (GameDifficulty::Beginner, false) => &mut self.beginner,
+            (GameDifficulty::Intermediate, false) => &mut self.intermediate,
+            (GameDifficulty::Expert, false) => &mut self.expert,
+            (GameDifficulty::Beginner, true) => &mut self.beginner_nf,
+            (GameDifficulty::Intermediate, true) => &mut self.intermediate_nf,
+            (GameDifficulty::Expert, true) => &mut self.expert_nf,
+        }
+    }
+
+    pub fn add(
+        &mut self,
+        category: &LeaderboardCategory,
+        entry: Entry,
+        max_entries: Option<usize>,
+    ) {
+        let handicaps = self.handicaps.clone();
+        self.board_mut(category).add(entry, max_entries, &handicaps)
+    }
+
+    /// Adds the wins on `other` that aren't already on these leaderboards, keeping the best
+    /// of both, and the handicaps of players that don't have one here
+    pub fn merge(&mut self, other: &LeaderBoards, max_entries: Option<usize>) {
+        other.handicaps.iter().for_each(|(player, handicap)| {
+            self.handicaps.entry(player.clone()).or_insert(*handicap);
+        });
+        let handicaps = self.handicaps.clone();
+        LeaderboardCategory::all()
+            .filter(|c| c.num_boards == 1)
+            .for_each(|c| {
+                self.board_mut(&c)
+                    .merge(other.board(&c), max_entries, &handicaps)
+            });
+        other.multi_board.iter().for_each(|(key, board)| {
+            self.multi_board
+                .entry(key.clone())
+                .or_default()
+                .merge(board, max_entries, &handicaps);
+        });
+    }
+
+    pub fn load_from_userhome() -> anyhow::Result<Option<Self>> {
+        config::load_toml(CONFIG_FILE_NAME)
+    }
+
+    pub fn save_to_userhome(&self) -> anyhow::Result<()> {
+        config::save_toml(self, CONFIG_FILE_NAME)
     }
 }
 
 #[test]
 fn test_leaderboards() -> Result<(), anyhow::Error> {
     let mut leaderboard = LeaderBoards::default();
+    let beginner = LeaderboardCategory::new(GameDifficulty::Beginner, false, 1);
+    let beginner_nf = LeaderboardCategory::new(GameDifficulty::Beginner, true, 1);
+    let beginner_x2 = LeaderboardCategory::new(GameDifficulty::Beginner, false, 2);
     assert_eq!(
         leaderboard
             .leaderboard_for_level(GameDifficulty::Beginner)
@@ -111,7 +691,7 @@ fn test_leaderboards() -> Result<(), anyhow::Error> {
         0
     );
 
-    leaderboard.add(GameDifficulty::Beginner, "Player 1", 100.0);
+    leaderboard.add(&beginner, Entry::new("Player 1", 100.0, 1, 0, 0), None);
     assert_eq!(
         leaderboard
             .leaderboard_for_level(GameDifficulty::Beginner)
@@ -119,8 +699,11 @@ fn test_leaderboards() -> Result<(), anyhow::Error> {
             .len(),
         1
     );
-    leaderboard.add(GameDifficulty::Beginner, "Player 2", 300.0);
-    leaderboard.add(GameDifficulty::Beginner, "Player 3", 200.0);
+    leaderboard.add(&beginner, Entry::new("Player 2", 300.0, 2, 0, 0), None);
+    leaderboard.add(&beginner, Entry::new("Player 3", 200.0, 3, 0, 0), None);
+    leaderboard.add(&beginner_nf, Entry::new("Player 4", 50.0, 4, 0, 0), None);
+    assert!(leaderboard.board(&beginner_x2).entries.is_empty());
+    leaderboard.add(&beginner_x2, Entry::new("Player 5", 150.0, 5, 0, 0), None);
     assert_eq!(
         leaderboard
             .leaderboard_for_level(GameDifficulty::Beginner)
@@ -129,17 +712,24 @@ fn test_leaderboards() -> Result<(), anyhow::Error> {
         3
     );
     assert_eq!(leaderboard.beginner.entries[1].player_name, "Player 3");
-
-    (0..MAX_ENTRIES_PER_BOARD + 10).for_each(|_| {
-        leaderboard.add(GameDifficulty::Beginner, "Player 2", 300.0);
-    });
     assert_eq!(
-        leaderboard
-            .leaderboard_for_level(GameDifficulty::Beginner)
-            .entries
-            .len(),
-        MAX_ENTRIES_PER_BOARD
+        leaderboard.board(&beginner_nf).entries[0].player_name,
+        "Player 4"
+    );
+    assert_eq!(
+        leaderboard.board(&beginner_x2).entries[0].player_name,
+        "Player 5"
     );
+    assert_eq!(beginner_x2.title(), "Beginner x2");
+
+    // With no limit, every win is kept
+    (0..10).for_each(|_| {
+        leaderboard.add(&beginner, Entry::new("Player 2", 300.0, 2, 0, 0), None);
+    });
+    assert_eq!(leaderboard.beginner.entries.len(), 13);
+    leaderboard.add(&beginner, Entry::new("Player 6", 10.0, 6, 0, 0), Some(5));
+    assert_eq!(leaderboard.beginner.entries.len(), 5);
+    assert_eq!(leaderboard.beginner.entries[0].player_name, "Player 6");
 
     // leaderboard.save_to_userhome();
     // let lb_reloaded = LeaderBoards::load_from_userhome()?;
@@ -157,11 +747,366 @@ fn test_leaderboards() -> Result<(), anyhow::Error> {
     Ok(())
 }
 
+#[test]
+fn test_trim_keeps_handicapped_wins() {
+    let beginner = LeaderboardCategory::new(GameDifficulty::Beginner, false, 1);
+    let mut leaderboard = LeaderBoards::default();
+    leaderboard
+        .handicaps
+        .insert("Kid".to_string(), Handicap::Percent(80.0));
+    leaderboard.add(&beginner, Entry::new("Kid", 100.0, 1, 0, 0), Some(2));
+    leaderboard.add(&beginner, Entry::new("Parent", 30.0, 2, 0, 0), Some(2));
+    leaderboard.add(&beginner, Entry::new("Parent", 40.0, 3, 0, 0), Some(2));
+    leaderboard.add(&beginner, Entry::new("Parent", 50.0, 4, 0, 0), Some(2));
+
+    // 100 seconds is the slowest, but 20 after the handicap is the best adjusted time
+    let times: Vec<f64> = leaderboard
+        .beginner
+        .entries
+        .iter()
+        .map(|e| e.time)
+        .collect();
+    assert_eq!(times, [30.0, 40.0, 100.0]);
+
+    let mut there = LeaderBoards::default();
+    there.add(&beginner, Entry::new("Parent", 10.0, 5, 0, 0), None);
+    there.add(&beginner, Entry::new("Parent", 25.0, 6, 0, 0), None);
+    leaderboard.merge(&there, Some(2));
+    let times: Vec<f64> = leaderboard
+        .beginner
+        .entries
+        .iter()
+        .map(|e| e.time)
+        .collect();
+    assert_eq!(times, [10.0, 25.0, 100.0]);
+}
+
+#[test]
+fn test_entry_integrity() -> Result<(), anyhow::Error> {
+    let entry = Entry::new("Player 1", 12.345, 42, 0, 0);
+    assert_eq!(entry.integrity(), EntryIntegrity::Valid);
+
+    // Signatures have to survive being written to and read back from the file
+    let mut leaderboard = LeaderBoard::default();
+    leaderboard.entries.push(entry);
+    let reloaded: LeaderBoard = toml::from_str(&toml::to_string(&leaderboard)?)?;
+    assert_eq!(reloaded.entries[0].integrity(), EntryIntegrity::Valid);
+
+    let mut tampered = reloaded.entries[0].clone();
+    tampered.time = 1.0;
+    assert_eq!(tampered.integrity(), EntryIntegrity::Tampered);
+
+    tampered.signature = String::new();
+    assert_eq!(tampered.integrity(), EntryIntegrity::Unsigned);
+
+    // The replay is signed along with everything else, but whether it was verified isn't
+    // saved at all
+    let mut replay = PlayList::default();
+    replay.record_sonar(Coordinate::from((1, 1)), 3.0);
+    let mut entry = Entry {
+        first_click: Some(Coordinate::from((1, 1))),
+        replay: Some(replay),
+        verified: Some(true),
+        ..Entry::new("Player 1", 12.345, 42, 10, 12)
+    };
+    entry.sign();
+    let reloaded: Entry = toml::from_str(&toml::to_string(&entry)?)?;
+    assert_eq!(reloaded.integrity(), EntryIntegrity::Valid);
+    assert_eq!(reloaded.verified, None);
+    let mut swapped = reloaded.clone();
+    swapped.replay = Some(PlayList::default());
+    assert_eq!(swapped.integrity(), EntryIntegrity::Tampered);
+    let mut edited = reloaded.clone();
+    edited.bbbv = 1;
+    assert_eq!(edited.integrity(), EntryIntegrity::Tampered);
+
+    // Signed before replays were, which only holds for entries without one
+    let mut legacy = reloaded;
+    legacy.signature = hex::encode(legacy.legacy_mac().finalize().into_bytes());
+    assert_eq!(legacy.integrity(), EntryIntegrity::Unsigned);
+    legacy.replay = None;
+    legacy.first_click = None;
+    assert_eq!(legacy.integrity(), EntryIntegrity::Valid);
+    Ok(())
+}
+
+#[test]
+fn test_merge_leaderboards() -> Result<(), anyhow::Error> {
+    let beginner = LeaderboardCategory::new(GameDifficulty::Beginner, false, 1);
+    let expert_x2 = LeaderboardCategory::new(GameDifficulty::Expert, false, 2);
+    let mut here = LeaderBoards::default();
+    here.add(&beginner, Entry::new("Player 1", 20.0, 1, 0, 0), None);
+    here.handicaps
+        .insert("Player 1".to_string(), Handicap::Percent(10.0));
+
+    // The copy from the other machine has been through its file, and has a win of its own
+    let mut there: LeaderBoards = toml::from_str(&toml::to_string(&here)?)?;
+    there.add(&beginner, Entry::new("Player 2", 10.0, 2, 0, 0), None);
+    there.add(&expert_x2, Entry::new("Player 2", 300.0, 3, 0, 0), None);
+    there
+        .handicaps
+        .insert("Player 1".to_string(), Handicap::Seconds(3.0));
+    there
+        .handicaps
+        .insert("Player 2".to_string(), Handicap::Seconds(3.0));
+
+    here.merge(&there, None);
+    let names: Vec<&str> = here
+        .board(&beginner)
+        .entries
+        .iter()
+        .map(|e| e.player_name.as_str())
+        .collect();
+    assert_eq!(names, ["Player 2", "Player 1"]);
+    assert_eq!(here.board(&expert_x2).entries.len(), 1);
+    // The handicaps set here stay as they are
+    assert_eq!(here.handicaps["Player 1"], Handicap::Percent(10.0));
+    assert_eq!(here.handicaps["Player 2"], Handicap::Seconds(3.0));
+
+    // Merging again changes nothing
+    here.merge(&there, None);
+    assert_eq!(here.board(&beginner).entries.len(), 2);
+
+    // A win deleted here stays deleted when the other copy, which still has it, is merged
+    // in, and the deletion takes it off the other copy too
+    here.board_mut(&beginner).remove(0);
+    here.merge(&there, None);
+    assert_eq!(here.board(&beginner).entries.len(), 1);
+    let here_saved: LeaderBoards = toml::from_str(&toml::to_string(&here)?)?;
+    there.merge(&here_saved, None);
+    assert_eq!(there.board(&beginner).entries.len(), 1);
+    assert_eq!(there.board(&beginner).entries[0].player_name, "Player 1");
+    // Clearing a board deletes each of its wins the same way
+    there.board_mut(&expert_x2).clear();
+    here.merge(&there, None);
+    assert!(here.board(&expert_x2).entries.is_empty());
+    Ok(())
+}
+
+#[test]
+fn test_filter_and_sort() {
+    let date = |s: &str| DateTime::parse_from_str(s, as_df_date::FORMAT).unwrap();
+    let mut leaderboard = LeaderBoard::default();
+    leaderboard.add(Entry::new("Alice", 30.0, 1, 0, 0), None, &Handicaps::new());
+    leaderboard.add(Entry::new("bob", 10.0, 2, 0, 0), None, &Handicaps::new());
+    leaderboard.add(Entry::new("Carol", 20.0, 3, 0, 0), None, &Handicaps::new());
+    leaderboard.entries[0].date = date("2024-03-01T12:00:00.000 +0000"); // bob
+    leaderboard.entries[1].date = date("2024-02-01T12:00:00.000 +0000"); // Carol
+    leaderboard.entries[2].date = date("2024-01-01T12:00:00.000 +0000"); // Alice
+    leaderboard.entries[1].flagged = false; // Carol
+
+    let all = EntryFilter::default();
+    let mut handicaps = Handicaps::new();
+    let names = |indices: Vec<usize>| -> Vec<String> {
+        indices
+            .iter()
+            .map(|&i| leaderboard.entries[i].player_name.clone())
+            .collect()
+    };
+    assert_eq!(
+        names(leaderboard.filtered_sorted(&all, &handicaps, SortColumn::Time, true)),
+        ["bob", "Carol", "Alice"]
+    );
+    assert_eq!(
+        names(leaderboard.filtered_sorted(&all, &handicaps, SortColumn::PlayerName, false)),
+        ["Carol", "bob", "Alice"]
+    );
+    assert_eq!(
+        names(leaderboard.filtered_sorted(&all, &handicaps, SortColumn::Date, true)),
+        ["Alice", "Carol", "bob"]
+    );
+
+    let by_name = EntryFilter {
+        player_name: "B".to_string(),
+        ..Default::default()
+    };
+    assert_eq!(
+        names(leaderboard.filtered_sorted(&by_name, &handicaps, SortColumn::Time, true)),
+        ["bob"]
+    );
+
+    // Searches look at the names, dates and times
+    let search = |text: &str| EntryFilter {
+        search: text.to_string(),
+        ..Default::default()
+    };
+    assert_eq!(
+        names(leaderboard.filtered_sorted(&search("CAR"), &handicaps, SortColumn::Time, true)),
+        ["Carol"]
+    );
+    assert_eq!(
+        names(leaderboard.filtered_sorted(&search("2024-01"), &handicaps, SortColumn::Time, true)),
+        ["Alice"]
+    );
+    assert_eq!(
+        names(leaderboard.filtered_sorted(&search("10.00"), &handicaps, SortColumn::Time, true)),
+        ["bob"]
+    );
+
+    // A player's own entries are theirs by name alone
+    let mine = EntryFilter {
+        player: Some("bob".to_string()),
+        ..Default::default()
+    };
+    assert_eq!(
+        names(leaderboard.filtered_sorted(&mine, &handicaps, SortColumn::Time, true)),
+        ["bob"]
+    );
+    let mine = EntryFilter {
+        player: Some("Bob".to_string()),
+        ..Default::default()
+    };
+    assert!(leaderboard
+        .filtered_sorted(&mine, &handicaps, SortColumn::Time, true)
+        .is_empty());
+
+    // The fastest two, in the order asked for
+    let top = EntryFilter {
+        top: Some(2),
+        ..Default::default()
+    };
+    assert_eq!(
+        names(leaderboard.filtered_sorted(&top, &handicaps, SortColumn::Date, true)),
+        ["Carol", "bob"]
+    );
+
+    let no_flags = EntryFilter {
+        flagged: Some(false),
+        ..Default::default()
+    };
+    assert_eq!(
+        names(leaderboard.filtered_sorted(&no_flags, &handicaps, SortColumn::Time, true)),
+        ["Carol"]
+    );
+
+    let by_date = EntryFilter {
+        from: NaiveDate::from_ymd_opt(2024, 2, 1),
+        to: NaiveDate::from_ymd_opt(2024, 3, 1),
+        ..Default::default()
+    };
+    assert_eq!(
+        names(leaderboard.filtered_sorted(&by_date, &handicaps, SortColumn::Time, true)),
+        ["bob", "Carol"]
+    );
+
+    // Alice's 30 seconds count as 12, and Carol's 20 as 5
+    handicaps.insert("Alice".to_string(), Handicap::Percent(60.0));
+    handicaps.insert("Carol".to_string(), Handicap::Seconds(15.0));
+    assert_eq!(
+        names(leaderboard.filtered_sorted(&all, &handicaps, SortColumn::AdjustedTime, true)),
+        ["Carol", "bob", "Alice"]
+    );
+    assert_eq!(
+        names(leaderboard.filtered_sorted(&all, &handicaps, SortColumn::Time, true)),
+        ["bob", "Carol", "Alice"]
+    );
+    // The fastest is picked by adjusted time, whichever column it's sorted by
+    let fastest = EntryFilter {
+        top: Some(1),
+        ..Default::default()
+    };
+    assert_eq!(
+        names(leaderboard.filtered_sorted(&fastest, &handicaps, SortColumn::Time, true)),
+        ["Carol"]
+    );
+    assert_eq!(adjusted_time(&handicaps, &leaderboard.entries[0]), 10.0);
+    assert_eq!(Handicap::Seconds(50.0).apply(20.0), 0.0);
+
+    assert_eq!(leaderboard.remove(0).unwrap().player_name, "bob");
+    assert!(leaderboard.remove(5).is_none());
+    assert_eq!(leaderboard.entries.len(), 2);
+    leaderboard.clear();
+    assert!(leaderboard.entries.is_empty());
+}
+
+#[test]
+fn test_entry_seed_round_trip() -> Result<(), anyhow::Error> {
+    let mut leaderboard = LeaderBoard::default();
+    leaderboard.add(
+        Entry::new("Player 1", 12.345, u64::MAX, 30, 40),
+        None,
+        &Handicaps::new(),
+    );
+    let reloaded: LeaderBoard = toml::from_str(&toml::to_string(&leaderboard)?)?;
+    assert_eq!(reloaded.entries[0].seed, u64::MAX);
+    assert_eq!(reloaded.entries[0].efficiency(), Some(75.0));
+    assert_eq!(reloaded.entries[0].integrity(), EntryIntegrity::Valid);
+
+    // Entries from before flags were tracked count as flagged
+    let old: Entry = toml::from_str(
+        r#"
+        player_name = "Player 1"
+        date = "2024-01-01T12:00:00.000 +0000"
+        time = 10.0
+        "#,
+    )?;
+    assert!(old.flagged);
+    assert!(old.first_click.is_none());
+
+    let entry = Entry {
+        first_click: Some(Coordinate::from((3, 4))),
+        ..Entry::new("Player 1", 10.0, 7, 0, 0)
+    };
+    let reloaded: Entry = toml::from_str(&toml::to_string(&entry)?)?;
+    assert_eq!(reloaded.first_click, Some(Coordinate::from((3, 4))));
+    Ok(())
+}
+
+pub mod as_hex_seed {
+    use serde::{self, Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S>(seed: &u64, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_str(&format!("{:016x}", seed))
+    }
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<u64, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let s = String::deserialize(deserializer)?;
+        u64::from_str_radix(&s, 16).map_err(serde::de::Error::custom)
+    }
+}
+
+/// Like `as_df_date`, for a date that isn't always set
+pub mod as_df_date_opt {
+    use chrono::{DateTime, FixedOffset};
+    use serde::{self, Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S>(
+        date: &Option<DateTime<FixedOffset>>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        match date {
+            Some(date) => super::as_df_date::serialize(date, serializer),
+            None => serializer.serialize_none(),
+        }
+    }
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<Option<DateTime<FixedOffset>>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        Option::<String>::deserialize(deserializer)?
+            .map(|s| {
+                DateTime::parse_from_str(&s, super::as_df_date::FORMAT)
+                    .map_err(serde::de::Error::custom)
+            })
+            .transpose()
+    }
+}
+
 pub mod as_df_date {
     use chrono::{DateTime, FixedOffset, Local};
     use serde::{self, Deserialize, Deserializer, Serializer};
 
-    const FORMAT: &str = "%Y-%m-%dT%H:%M:%S%.3f %z";
+    pub const FORMAT: &str = "%Y-%m-%dT%H:%M:%S%.3f %z";
 
     pub fn serialize<S>(date: &DateTime<FixedOffset>, serializer: S) -> Result<S::Ok, S::Error>
     where
@@ -183,3 +1128,71 @@ pub mod as_df_date {
         }
     }
 }
+
+#[test]
+fn test_verify_replay() -> Result<(), anyhow::Error> {
+    use crate::minesweeper::RevealType;
+
+    let category = LeaderboardCategory::new(GameDifficulty::Beginner, false, 1);
+    let settings = GameSettings::settings_for_mode(&category.difficulty, false, 1);
+    let first_click = Coordinate::from((4, 4));
+    let mut board = settings.new_board(7);
+    board.populate_mines_around(settings.num_mines, Some(first_click.clone()))?;
+    board.populate_numerals()?;
+    let mut plays = PlayList::default();
+    let mut time = 0.0;
+    for coord in
+        std::iter::once(first_click).chain((0..81).map(|i| Coordinate::from((i % 9, i / 9))))
+    {
+        let square = board.get_square_by_coordinate(&coord)?;
+        if square.is_mine() || square.is_revealed {
+            continue;
+        }
+        let result = board.play(coord.x, coord.y, RevealType::Reveal)?;
+        time += 0.5;
+        plays.record(coord, RevealType::Reveal, &result, time, None);
+    }
+    assert!(board.is_win_configuration());
+
+    let clicks = plays.clicks();
+    let entry = Entry {
+        replay: Some(plays),
+        ..Entry::new("Player 1", time, 7, board.bbbv(), clicks)
+    };
+    assert_eq!(entry.verify_replay(&category), Ok(()));
+    // The replay survives the leaderboard file
+    let reloaded: Entry = toml::from_str(&toml::to_string(&entry)?)?;
+    assert_eq!(reloaded.verify_replay(&category), Ok(()));
+
+    // A faster time, another board or another difficulty don't match the replay
+    let faster = Entry {
+        time: time - 1.0,
+        ..entry.clone()
+    };
+    assert!(faster.verify_replay(&category).is_err());
+    let other_seed = Entry {
+        seed: 8,
+        ..entry.clone()
+    };
+    assert!(other_seed.verify_replay(&category).is_err());
+    let expert = LeaderboardCategory::new(GameDifficulty::Expert, false, 1);
+    assert!(entry.verify_replay(&expert).is_err());
+    assert!(Entry::new("Player 1", time, 7, 0, 0)
+        .verify_replay(&category)
+        .is_err());
+
+    // Time penalties are added to the time at the end, after the last play
+    let mut penalized = Entry {
+        time: time + 5.0,
+        penalty: 5.0,
+        ..entry.clone()
+    };
+    assert_eq!(penalized.verify_replay(&category), Ok(()));
+    penalized.sign();
+    let reloaded: Entry = toml::from_str(&toml::to_string(&penalized)?)?;
+    assert_eq!(reloaded.integrity(), EntryIntegrity::Valid);
+    let mut waived = reloaded;
+    waived.penalty = 0.0;
+    assert_eq!(waived.integrity(), EntryIntegrity::Tampered);
+    Ok(())
+}
@@ -1,10 +1,71 @@
 #![warn(clippy::all, rust_2018_idioms)]
 
 mod app;
+#[cfg(not(target_arch = "wasm32"))]
+mod autosave;
+#[cfg(not(target_arch = "wasm32"))]
+mod bugreport;
+mod calibrate;
+pub mod cli;
+mod clock;
+mod config;
 mod constants;
+#[cfg(feature = "net")]
+mod coop;
+#[cfg(not(target_arch = "wasm32"))]
+mod crash;
 mod enums;
+pub mod events;
+#[cfg(not(target_arch = "wasm32"))]
+mod export;
+mod fog;
+mod history;
+#[cfg(all(feature = "hotkeys", not(target_arch = "wasm32")))]
+mod hotkeys;
+mod hotseat;
+mod i18n;
+mod input;
 mod leader;
-mod minesweeper;
+#[cfg(not(target_arch = "wasm32"))]
+mod logging;
+pub mod minesweeper;
+pub mod mode;
+#[cfg(feature = "net")]
+mod net;
+mod notify;
+#[cfg(feature = "online")]
+mod online;
+#[cfg(not(target_arch = "wasm32"))]
+mod overlay;
+pub mod placement;
+pub mod plays;
+#[cfg(not(target_arch = "wasm32"))]
+mod profile;
+mod progress;
+mod puzzle;
+#[cfg(not(target_arch = "wasm32"))]
+mod render;
+#[cfg(all(feature = "scripting", not(target_arch = "wasm32")))]
+mod script;
+mod segment;
+mod shape;
+mod solver;
+#[cfg(feature = "net")]
+mod spectate;
+#[cfg(feature = "livesplit")]
+mod splits;
 mod state;
+#[cfg(all(feature = "sync", not(target_arch = "wasm32")))]
+mod sync;
 mod toggle;
+#[cfg(all(feature = "tray", not(target_arch = "wasm32")))]
+mod tray;
+mod tutorial;
+#[cfg(feature = "updates")]
+mod update;
 pub use app::MinesOfRustApp;
+pub use config::set_config_dir;
+#[cfg(not(target_arch = "wasm32"))]
+pub use crash::install_panic_hook;
+#[cfg(not(target_arch = "wasm32"))]
+pub use logging::init_logging;
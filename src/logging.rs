@@ -0,0 +1,172 @@
+use std::collections::VecDeque;
+use std::fs::{self, File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use anyhow::{Context, Result};
+use env_logger::filter::{Builder, Filter};
+use log::{LevelFilter, Log, Metadata, Record};
+
+use crate::config;
+
+const LOG_FILE_NAME: &str = "minesofrust.log";
+
+/// How big the log file gets before it's moved aside for a new one. Only the one before it
+/// is kept.
+const MAX_LOG_BYTES: u64 = 1024 * 1024;
+
+/// How many of the latest log lines are kept for the debug console
+const RECENT_LINES: usize = 500;
+
+static RECENT: Mutex<VecDeque<String>> = Mutex::new(VecDeque::new());
+
+/// The latest lines logged, oldest first
+pub fn recent() -> Vec<String> {
+    RECENT
+        .lock()
+        .map(|r| r.iter().cloned().collect())
+        .unwrap_or_default()
+}
+
+struct LogFile {
+    path: PathBuf,
+    file: File,
+    size: u64,
+}
+
+impl LogFile {
+    /// Opens the log file to add to, moving it aside first if it's already full
+    fn open(path: PathBuf) -> Result<Self> {
+        if fs::metadata(&path).is_ok_and(|m| m.len() >= MAX_LOG_BYTES) {
+            rotate(&path)?;
+        }
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)
+            .with_context(|| format!("Failed to open {:?}", path))?;
+        let size = file.metadata().map(|m| m.len()).unwrap_or(0);
+        Ok(LogFile { path, file, size })
+    }
+
+    fn write_line(&mut self, line: &str) -> Result<()> {
+        if self.size >= MAX_LOG_BYTES {
+            rotate(&self.path)?;
+            *self = LogFile::open(self.path.clone())?;
+        }
+        writeln!(self.file, "{}", line)?;
+        self.size += line.len() as u64 + 1;
+        Ok(())
+    }
+}
+
+/// Moves a full log file to `<name>.1`, replacing the one there
+fn rotate(path: &Path) -> Result<()> {
+    let mut rotated = path.as_os_str().to_owned();
+    rotated.push(".1");
+    fs::rename(path, &rotated).with_context(|| format!("Failed to rotate {:?}", path))
+}
+
+/// Sends each record to stderr, the log file in the config directory, and the debug console
+struct Logger {
+    filter: Filter,
+    file: Option<Mutex<LogFile>>,
+}
+
+impl Log for Logger {
+    fn enabled(&self, metadata: &Metadata<'_>) -> bool {
+        self.filter.enabled(metadata)
+    }
+
+    fn log(&self, record: &Record<'_>) {
+        if !self.filter.matches(record) {
+            return;
+        }
+        let line = format!(
+            "{} {:<5} {}: {}",
+            chrono::Local::now().format("%Y-%m-%d %H:%M:%S%.3f"),
+            record.level(),
+            record.target(),
+            record.args()
+        );
+        eprintln!("{}", line);
+        if let Some(Err(e)) = self
+            .file
+            .as_ref()
+            .and_then(|f| f.lock().ok())
+            .map(|mut f| f.write_line(&line))
+        {
+            eprintln!("Failed to write to the log file: {:#}", e);
+        }
+        if let Ok(mut recent) = RECENT.lock() {
+            if recent.len() >= RECENT_LINES {
+                recent.pop_front();
+            }
+            recent.push_back(line);
+        }
+    }
+
+    fn flush(&self) {
+        if let Some(Ok(mut f)) = self.file.as_ref().map(|f| f.lock()) {
+            let _ = f.file.flush();
+        }
+    }
+}
+
+/// Starts logging the app's messages at info level, or debug if `verbose`, and only warnings
+/// from the libraries it uses. `RUST_LOG` still overrides either, the same as it does for
+/// `env_logger`. Call it once the config directory is settled, as that's where the log file
+/// goes.
+pub fn init_logging(verbose: bool) {
+    let mut builder = Builder::new();
+    builder.filter_level(LevelFilter::Warn).filter_module(
+        module_path!().split("::").next().unwrap_or_default(),
+        if verbose {
+            LevelFilter::Debug
+        } else {
+            LevelFilter::Info
+        },
+    );
+    if let Ok(filters) = std::env::var("RUST_LOG") {
+        builder.parse(&filters);
+    }
+    let filter = builder.build();
+    let file = config::config_dir()
+        .and_then(|dir| {
+            fs::create_dir_all(&dir)?;
+            LogFile::open(dir.join(LOG_FILE_NAME))
+        })
+        .map_err(|e| eprintln!("Logging to stderr only: {:#}", e))
+        .ok();
+    log::set_max_level(filter.filter());
+    if let Err(e) = log::set_boxed_logger(Box::new(Logger {
+        filter,
+        file: file.map(Mutex::new),
+    })) {
+        eprintln!("Failed to start logging: {}", e);
+    }
+}
+
+#[test]
+fn test_log_file() -> Result<()> {
+    let dir = std::env::temp_dir().join(format!("minesofrust-log-{}", std::process::id()));
+    fs::create_dir_all(&dir)?;
+    let path = dir.join(LOG_FILE_NAME);
+    fs::write(&path, vec![b'x'; MAX_LOG_BYTES as usize])?;
+
+    // A full log is moved aside when it's opened, and again once the new one fills up
+    let mut log = LogFile::open(path.clone())?;
+    assert_eq!(log.size, 0);
+    log.write_line("first")?;
+    log.size = MAX_LOG_BYTES;
+    log.write_line("second")?;
+    assert_eq!(fs::read_to_string(&path)?, "second\n");
+    assert_eq!(
+        fs::read_to_string(dir.join("minesofrust.log.1"))?,
+        "first\n"
+    );
+
+    fs::remove_dir_all(&dir)?;
+    Ok(())
+}
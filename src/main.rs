@@ -4,12 +4,27 @@
 // When compiling natively:
 #[cfg(not(target_arch = "wasm32"))]
 fn main() -> eframe::Result<()> {
-    env_logger::init(); // Log to stderr (if you run with `RUST_LOG=debug`).
+    // --config-dir <path> overrides where settings and leaderboards are stored
+    let args: Vec<String> = std::env::args().collect();
+    if let Some(dir) = args
+        .iter()
+        .position(|a| a == "--config-dir")
+        .and_then(|i| args.get(i + 1))
+    {
+        minesofrust_web::set_config_dir(dir.into());
+    }
+
+    // Log to stderr and the config directory, with debug messages too if run with
+    // --verbose (or `RUST_LOG=debug`)
+    minesofrust_web::init_logging(args.iter().any(|a| a == "--verbose"));
+    minesofrust_web::install_panic_hook();
 
     let native_options = eframe::NativeOptions {
         viewport: egui::ViewportBuilder::default()
             .with_inner_size([400.0, 300.0])
             .with_min_inner_size([300.0, 220.0])
+            // Zen mode can make the window see-through
+            .with_transparent(true)
             .with_icon(
                 // NOTE: Adding an icon is optional
                 eframe::icon_data::from_png_bytes(&include_bytes!("../assets/icon-256.png")[..])
@@ -20,7 +35,7 @@ fn main() -> eframe::Result<()> {
     eframe::run_native(
         "eframe template",
         native_options,
-        Box::new(|cc| Box::new(minesofrust_web::MinesOfRustApp::load_from_persistence())),
+        Box::new(|_cc| Box::new(minesofrust_web::MinesOfRustApp::load_from_persistence())),
     )
 }
 
@@ -37,7 +52,7 @@ fn main() {
             .start(
                 "the_canvas_id", // hardcode it
                 web_options,
-                Box::new(|cc| Box::new(minesofrust_web::MinesOfRustApp::load_from_persistence())),
+                Box::new(|_cc| Box::new(minesofrust_web::MinesOfRustApp::load_from_persistence())),
             )
             .await
             .expect("failed to start eframe");
@@ -1,17 +1,41 @@
+use std::collections::{HashSet, VecDeque};
+
 use anyhow::Result;
 use itertools::iproduct;
 use rand::prelude::*;
 use serde::{Deserialize, Serialize};
 
+use crate::placement::{MinePlacement, MinePlacementStrategy, TemplatePlacement};
+
 /// Indicates some sort of error related to initialization and play on the gameboard
-#[derive(Debug)]
+#[derive(Debug, thiserror::Error)]
 #[allow(dead_code)]
 pub enum Error {
-    ExcessiveMines,
-    InvalidCoordinates,
-    IndexOutOfBounds,
-    InvalidCascade,
+    #[error("Cannot place {num_mines} mines on a {width}x{height} board")]
+    ExcessiveMines {
+        num_mines: u32,
+        width: u32,
+        height: u32,
+    },
+    #[error("Coordinates ({x}, {y}) are outside of the {width}x{height} board")]
+    InvalidCoordinates {
+        x: u32,
+        y: u32,
+        width: u32,
+        height: u32,
+    },
+    #[error("Square index {idx} is out of bounds for a board of {len} squares")]
+    IndexOutOfBounds { idx: u32, len: usize },
+    #[error("Cannot cascade from ({x}, {y}), the square is mined, flagged or numbered")]
+    InvalidCascade { x: u32, y: u32 },
+    #[error("Unexpected play result")]
     UnexpectedResult,
+    #[error("Invalid board text on line {line}: {reason}")]
+    InvalidBoardText { line: usize, reason: String },
+    #[error("Invalid board data: {reason}")]
+    InvalidBoardData { reason: String },
+    #[error("Cannot place a mine at ({x}, {y}), the square has to be kept clear")]
+    IneligibleMine { x: u32, y: u32 },
 }
 
 /// Represents the type of a square as to the presence of a mine
@@ -19,6 +43,9 @@ pub enum Error {
 pub enum SquareType {
     Empty,
     Mine,
+    /// Outside the board's shape, so it's neither a mine nor played. Voids don't count as
+    /// anyone's neighbors.
+    Void,
 }
 
 /// Representation of a single minesweeper square.
@@ -55,9 +82,15 @@ impl Square {
         self.square_type == SquareType::Mine
     }
 
+    pub fn is_void(&self) -> bool {
+        self.square_type == SquareType::Void
+    }
+
     #[allow(dead_code)]
     pub fn print(&self) {
-        if self.is_flagged {
+        if self.is_void() {
+            print!("   ");
+        } else if self.is_flagged {
             print!(" > ");
         } else if !self.is_revealed {
             print!(" - ");
@@ -108,34 +141,184 @@ pub enum RevealType {
     Flag,
 }
 
-#[allow(dead_code)]
-#[derive(Debug, PartialEq, Eq, Deserialize, Serialize)]
-pub enum PlayResult {
-    Flagged(bool),
-    Explosion(Coordinate), // Loss
-    NoChange,
-    Revealed(Coordinate),
-    CascadedReveal(Vec<PlayResult>),
+/// What a play did to a square
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+pub enum ChangeKind {
+    Revealed,
+    /// A mine was revealed and went off. The game is lost.
+    Exploded,
+    Flagged,
+    Unflagged,
+    /// A flagged square a chord went around, trusting the flag. The square itself is left
+    /// as it was.
+    Skipped,
+}
+
+/// Something a play did to one square
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct CellChange {
+    pub coord: Coordinate,
+    pub kind: ChangeKind,
+    /// How many squares a cascade spread through to reach the square, counted from the
+    /// square played
+    #[serde(default)]
+    pub depth: u32,
+}
+
+/// Everything a play did, a square at a time in the order it happened. Empty if the play
+/// didn't do anything.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct PlayResult {
+    pub changes: Vec<CellChange>,
+}
+
+impl PlayResult {
+    /// A play that did one thing to one square
+    pub fn single(coord: Coordinate, kind: ChangeKind) -> Self {
+        PlayResult {
+            changes: vec![CellChange {
+                coord,
+                kind,
+                depth: 0,
+            }],
+        }
+    }
+
+    /// True if the play didn't change the board
+    pub fn is_no_change(&self) -> bool {
+        self.squares_changed() == 0
+    }
+
+    /// Number of squares the play revealed, exploded or (un)flagged
+    pub fn squares_changed(&self) -> u32 {
+        self.changes
+            .iter()
+            .filter(|c| c.kind != ChangeKind::Skipped)
+            .count() as u32
+    }
+
+    /// Squares revealed or exploded by the play
+    pub fn coordinates(&self) -> Vec<Coordinate> {
+        self.changes
+            .iter()
+            .filter(|c| matches!(c.kind, ChangeKind::Revealed | ChangeKind::Exploded))
+            .map(|c| c.coord.clone())
+            .collect()
+    }
+
+    /// The first mine the play set off, if it set one off
+    pub fn explosion(&self) -> Option<&Coordinate> {
+        self.of_kind(ChangeKind::Exploded).next()
+    }
+
+    /// Whether the square is flagged now, if the play flagged or unflagged it
+    pub fn flagged(&self) -> Option<bool> {
+        self.changes.iter().find_map(|c| match c.kind {
+            ChangeKind::Flagged => Some(true),
+            ChangeKind::Unflagged => Some(false),
+            _ => None,
+        })
+    }
+
+    /// Flagged squares a chord went around
+    pub fn skipped(&self) -> impl Iterator<Item = &Coordinate> {
+        self.of_kind(ChangeKind::Skipped)
+    }
+
+    fn of_kind(&self, kind: ChangeKind) -> impl Iterator<Item = &Coordinate> {
+        self.changes
+            .iter()
+            .filter(move |c| c.kind == kind)
+            .map(|c| &c.coord)
+    }
+
+    fn push(&mut self, coord: Coordinate, kind: ChangeKind, depth: u32) {
+        self.changes.push(CellChange { coord, kind, depth });
+    }
+}
+
+/// The squares around the one at `idx` on a `width` by `height` board, given by their
+/// index. A board of more than one layer has them stacked a layer's rows at a time, and
+/// the squares above and below on the next layers count as neighbors too.
+pub fn neighbors(width: u32, height: u32, layers: u32, idx: u32) -> impl Iterator<Item = u32> {
+    let layers = layers.max(1);
+    let layer_height = height / layers;
+    let (x, y) = (idx % width, idx / width);
+    let (z, y) = (y / layer_height, y % layer_height);
+    // The ranges are kept on the board so nothing needs filtering out but the square itself
+    let around = |i: u32, len: u32| i.saturating_sub(1)..=(i + 1).min(len - 1);
+    iproduct!(around(z, layers), around(y, layer_height), around(x, width))
+        .filter(move |&n| n != (z, y, x))
+        .map(move |(nz, ny, nx)| (nz * layer_height + ny) * width + nx)
 }
 
 #[derive(Debug, Clone)]
 /// Representation of a minesweeper game board
+///
+/// Squares should only be modified through the board's methods so that the cached
+/// counters stay in sync.
 pub struct GameBoard {
     pub width: u32,
+    /// Rows of all the layers together
     pub height: u32,
+    /// 2D layers the board is made of, stacked one on top of the other
+    layers: u32,
     pub num_mines: u32,
-    pub squares: Vec<Square>,
+    /// Only read through the board's queries. The UI reads them through `player_view` so
+    /// it can't see where the mines are.
+    squares: Vec<Square>,
     pub is_populated: bool,
+    /// Seed for the board's random number generator. Boards built with the same seed,
+    /// dimensions, number of mines and first click are identical.
+    pub seed: u64,
+    /// Flag plays are ignored
+    pub no_flags: bool,
+    /// How the mines are spread when the board is populated
+    pub placement: MinePlacement,
+    rng: StdRng,
+    flag_count: u32,
+    void_count: u32,
+    revealed_count: u32,
+    detonated_count: u32,
+    is_dirty: bool,
+    bbbv: u32,
+    replayed: bool,
 }
 
 impl GameBoard {
     pub fn new(width: u32, height: u32) -> Self {
+        Self::new_seeded(width, height, rand::thread_rng().gen())
+    }
+
+    /// Creates an empty board whose mine placement is reproducible from `seed`
+    pub fn new_seeded(width: u32, height: u32, seed: u64) -> Self {
         GameBoard {
             width,
             height,
+            layers: 1,
             num_mines: 0,
             squares: (0..width * height).map(|_| Square::default()).collect(),
             is_populated: false,
+            seed,
+            no_flags: false,
+            placement: MinePlacement::Uniform,
+            rng: StdRng::seed_from_u64(seed),
+            flag_count: 0,
+            void_count: 0,
+            revealed_count: 0,
+            detonated_count: 0,
+            is_dirty: false,
+            bbbv: 0,
+            replayed: false,
+        }
+    }
+
+    /// Creates an empty board of `layers` layers, each `width` by `layer_height`, whose mine
+    /// placement is reproducible from `seed`
+    pub fn new_layered(width: u32, layer_height: u32, layers: u32, seed: u64) -> Self {
+        GameBoard {
+            layers: layers.max(1),
+            ..Self::new_seeded(width, layer_height * layers.max(1), seed)
         }
     }
 
@@ -149,9 +332,22 @@ impl GameBoard {
 
     #[allow(dead_code)]
     pub fn reset(&mut self) {
-        self.squares = (0..self.width * self.height)
-            .map(|_| Square::default())
+        // The board keeps its shape
+        self.squares = self
+            .squares
+            .iter()
+            .map(|s| Square {
+                square_type: if s.is_void() {
+                    SquareType::Void
+                } else {
+                    SquareType::Empty
+                },
+                ..Square::default()
+            })
             .collect();
+        self.bbbv = 0;
+        self.replayed = false;
+        self.recount();
     }
 
     #[allow(dead_code)]
@@ -167,11 +363,121 @@ impl GameBoard {
         Ok(gb)
     }
 
+    /// Builds a fully populated board with mines on exactly the given squares
+    #[allow(dead_code)]
+    pub fn new_with_mines(
+        width: u32,
+        height: u32,
+        mines: &[Coordinate],
+    ) -> Result<GameBoard, Error> {
+        let mut gb = Self::new(width, height);
+        let template = TemplatePlacement {
+            mines: mines.to_vec(),
+        };
+        gb.populate_mines_with(&template, mines.len() as u32, None)?;
+        gb.populate_numerals()?;
+        Ok(gb)
+    }
+
+    /// Builds a fully populated board with a mine layout determined entirely by `seed`,
+    /// giving benchmarks identical inputs from run to run.
+    #[allow(dead_code)]
+    pub fn new_benchmark_board(
+        width: u32,
+        height: u32,
+        num_mines: u32,
+        seed: u64,
+    ) -> Result<GameBoard, Error> {
+        let mut gb = Self::new_seeded(width, height, seed);
+        gb.populate_mines(num_mines)?;
+        gb.populate_numerals()?;
+        Ok(gb)
+    }
+
+    /// Gives the board a shape by making the squares `is_void` picks out into voids. Meant
+    /// for a board that hasn't been populated yet, as anything on those squares is lost.
+    pub fn set_voids(&mut self, is_void: impl Fn(u32, u32) -> bool) {
+        iproduct!(0..self.width, 0..self.height)
+            .filter(|&(x, y)| is_void(x, y))
+            .for_each(|(x, y)| {
+                let idx = self.xy_to_idx(x, y);
+                self.squares[idx as usize] = Square {
+                    square_type: SquareType::Void,
+                    ..Square::default()
+                };
+            });
+        self.recount();
+    }
+
+    /// Recomputes the cached counters from scratch. Used after bulk changes to the squares.
+    fn recount(&mut self) {
+        self.num_mines = 0;
+        self.flag_count = 0;
+        self.void_count = 0;
+        self.revealed_count = 0;
+        self.detonated_count = 0;
+        for sqr in self.squares.iter() {
+            self.num_mines += sqr.is_mine() as u32;
+            self.flag_count += sqr.is_flagged as u32;
+            self.void_count += sqr.is_void() as u32;
+            self.revealed_count += sqr.is_revealed as u32;
+            self.detonated_count += (sqr.is_revealed && sqr.is_mine()) as u32;
+        }
+        self.is_dirty = true;
+    }
+
+    /// Marks a square as revealed, keeping the cached counters in sync.
+    fn mark_revealed(&mut self, idx: u32) {
+        let sqr = &mut self.squares[idx as usize];
+        if !sqr.is_revealed {
+            sqr.is_revealed = true;
+            self.revealed_count += 1;
+            if sqr.is_mine() {
+                self.detonated_count += 1;
+            }
+            self.is_dirty = true;
+        }
+    }
+
+    /// Turns an empty square into a mine, or a mine back into an empty square, for setting up
+    /// a board by hand. Numerals aren't updated until `populate_numerals` is run again, and
+    /// voids are left as they are.
+    pub fn toggle_mine(&mut self, x: u32, y: u32) -> Result<(), Error> {
+        if x >= self.width || y >= self.height {
+            return Err(self.invalid_coordinates(x, y));
+        }
+        let idx = self.xy_to_idx(x, y);
+        let sqr = &mut self.squares[idx as usize];
+        sqr.square_type = match sqr.square_type {
+            SquareType::Empty => SquareType::Mine,
+            SquareType::Mine => SquareType::Empty,
+            SquareType::Void => return Ok(()),
+        };
+        self.is_populated = true;
+        self.recount();
+        Ok(())
+    }
+
+    /// Returns whether the board has changed since the last call, then clears the flag.
+    pub fn take_dirty(&mut self) -> bool {
+        std::mem::replace(&mut self.is_dirty, false)
+    }
+
+    fn invalid_coordinates(&self, x: u32, y: u32) -> Error {
+        Error::InvalidCoordinates {
+            x,
+            y,
+            width: self.width,
+            height: self.height,
+        }
+    }
+
     /// Convert x, y coordinate to vector index
     fn xy_to_idx(&self, x: u32, y: u32) -> u32 {
         y * self.width + x
     }
 
+    #[allow(dead_code)]
     fn coordinate_to_idx(&self, coord: &Coordinate) -> u32 {
         self.xy_to_idx(coord.x, coord.y)
     }
@@ -179,7 +485,10 @@ impl GameBoard {
     #[allow(dead_code)]
     fn idx_to_xy(&self, idx: u32) -> Result<Coordinate, Error> {
         if idx as usize > self.squares.len() - 1 {
-            return Err(Error::IndexOutOfBounds);
+            return Err(Error::IndexOutOfBounds {
+                idx,
+                len: self.squares.len(),
+            });
         }
 
         Ok(Coordinate {
@@ -190,7 +499,10 @@ impl GameBoard {
 
     fn get_square_by_idx(&self, idx: u32) -> Result<Square, Error> {
         if idx as usize >= self.squares.len() {
-            Err(Error::InvalidCoordinates)
+            Err(Error::IndexOutOfBounds {
+                idx,
+                len: self.squares.len(),
+            })
         } else {
             Ok(self.squares[idx as usize])
         }
@@ -198,7 +510,7 @@ impl GameBoard {
 
     pub fn get_square(&self, x: u32, y: u32) -> Result<Square, Error> {
         if x >= self.width || y >= self.height {
-            Err(Error::InvalidCoordinates)
+            Err(self.invalid_coordinates(x, y))
         } else {
             self.get_square_by_idx(self.xy_to_idx(x, y))
         }
@@ -208,107 +520,81 @@ impl GameBoard {
         self.get_square(coord.x, coord.y)
     }
 
-    /// Determines whether a square contains a mine, allowing for negative
-    /// and invalid coordinates.
-    fn is_mine_protected(&self, x: i32, y: i32) -> bool {
-        if x < 0 {
-            return false;
-        }
-        if y < 0 {
-            return false;
-        }
-
-        match self.get_square(x as u32, y as u32) {
-            Ok(sqr) => sqr.is_mine(),
-            _ => false,
-        }
-    }
-
-    fn is_flagged_protected(&self, x: i32, y: i32) -> bool {
-        if x < 0 {
-            return false;
-        }
-        if y < 0 {
-            return false;
-        }
-
-        match self.get_square(x as u32, y as u32) {
-            Ok(sqr) => sqr.is_flagged,
-            _ => false,
-        }
+    /// The squares around one, given by their index
+    fn neighbors_of(&self, x: u32, y: u32) -> impl Iterator<Item = u32> {
+        neighbors(self.width, self.height, self.layers, self.xy_to_idx(x, y))
     }
 
-    fn flagged_neighbor_count(&self, x: u32, y: u32) -> Result<u32, Error> {
+    /// Determine how many flags a given square touches.
+    pub fn flagged_neighbor_count(&self, x: u32, y: u32) -> Result<u32, Error> {
         if x >= self.width || y >= self.height {
-            Err(Error::InvalidCoordinates)
+            Err(self.invalid_coordinates(x, y))
         } else {
-            Ok(iproduct!(-1_i32..2_i32, -1_i32..2_i32)
-                .map(|(dx, dy)| {
-                    if self.is_flagged_protected(x as i32 + dx, y as i32 + dy) {
-                        1
-                    } else {
-                        0
-                    }
-                })
-                .collect::<Vec<u32>>()
-                .into_iter()
-                .sum())
+            Ok(self
+                .neighbors_of(x, y)
+                .filter(|&i| self.squares[i as usize].is_flagged)
+                .count() as u32)
         }
     }
 
     /// Determine how many mines a given square touches.
-    fn mined_neighbor_count(&self, x: u32, y: u32) -> Result<u32, Error> {
+    pub fn mined_neighbor_count(&self, x: u32, y: u32) -> Result<u32, Error> {
         if x >= self.width || y >= self.height {
-            Err(Error::InvalidCoordinates)
+            Err(self.invalid_coordinates(x, y))
         } else {
-            Ok(iproduct!(-1_i32..2_i32, -1_i32..2_i32)
-                .map(|(dx, dy)| {
-                    if self.is_mine_protected(x as i32 + dx, y as i32 + dy) {
-                        1
-                    } else {
-                        0
-                    }
-                })
-                .collect::<Vec<u32>>()
-                .into_iter()
-                .sum())
+            Ok(self
+                .neighbors_of(x, y)
+                .filter(|&i| self.squares[i as usize].is_mine())
+                .count() as u32)
         }
     }
 
-    fn gen_random_square_coordinates(&self) -> Coordinate {
-        Coordinate {
-            x: rand::thread_rng().gen_range(0..self.width),
-            y: rand::thread_rng().gen_range(0..self.height),
-        }
+    /// Places mines at random as the board's `placement` spreads them, never on
+    /// `keep_clear` or its neighbors.
+    pub fn populate_mines_around(
+        &mut self,
+        num_mines: u32,
+        keep_clear: Option<Coordinate>,
+    ) -> Result<(), Error> {
+        let strategy = self.placement.strategy();
+        self.populate_mines_with(strategy.as_ref(), num_mines, keep_clear)
     }
 
-    pub fn populate_mines_around(
+    /// Places mines on the squares `strategy` picks, never on `keep_clear` or its
+    /// neighbors.
+    ///
+    /// Mines are picked without replacement from the eligible squares, so this always
+    /// terminates regardless of the mine density.
+    pub fn populate_mines_with(
         &mut self,
+        strategy: &dyn MinePlacementStrategy,
         num_mines: u32,
         keep_clear: Option<Coordinate>,
     ) -> Result<(), Error> {
-        if num_mines > self.width * self.height {
-            Err(Error::ExcessiveMines)
+        let clear: Vec<u32> = keep_clear
+            .iter()
+            .filter(|kc| kc.x < self.width && kc.y < self.height)
+            .flat_map(|kc| {
+                std::iter::once(self.xy_to_idx(kc.x, kc.y)).chain(self.neighbors_of(kc.x, kc.y))
+            })
+            .collect();
+        let eligible = (0..self.width * self.height)
+            .filter(|&idx| !self.squares[idx as usize].is_void() && !clear.contains(&idx))
+            .collect::<Vec<u32>>();
+
+        if num_mines as usize > eligible.len() {
+            Err(Error::ExcessiveMines {
+                num_mines,
+                width: self.width,
+                height: self.height,
+            })
         } else {
-            self.num_mines = num_mines;
-
-            let mut mines_placed = 0;
-            while mines_placed < num_mines {
-                let random_coord = self.gen_random_square_coordinates();
-
-                if let Some(kc) = &keep_clear {
-                    let sqr = self.get_square_by_coordinate(&random_coord)?;
-                    if !kc.near(&random_coord) && !sqr.is_mine() {
-                        let idx = self.coordinate_to_idx(&random_coord);
-                        self.squares[idx as usize] = Square::default_mine();
-                        mines_placed += 1;
-                    }
-                } else {
-                    let idx = self.coordinate_to_idx(&random_coord);
-                    self.squares[idx as usize] = Square::default_mine();
-                    mines_placed += 1;
-                }
-            }
+            strategy
+                .place(&mut self.rng, self.width, self.height, &eligible, num_mines)?
+                .into_iter()
+                .for_each(|idx| self.squares[idx as usize] = Square::default_mine());
+
+            self.recount();
             self.is_populated = true;
             Ok(())
         }
@@ -323,10 +609,85 @@ impl GameBoard {
             let idx = self.xy_to_idx(x, y);
             self.squares[idx as usize].numeral = self.mined_neighbor_count(x, y).unwrap_or(0);
         });
+        self.bbbv = self.compute_bbbv();
 
         Ok(())
     }
 
+    /// Computes the board's 3BV: the minimum number of clicks needed to clear it without
+    /// flagging. Each opening (connected region of blank squares) takes one click, which
+    /// also reveals the numbered squares around its edge. Every other numbered square
+    /// takes a click of its own.
+    fn compute_bbbv(&self) -> u32 {
+        self.count_bbbv(false)
+    }
+
+    /// How much of the board's 3BV has been cleared so far: openings that have been
+    /// revealed, and numbered squares outside them that have been revealed on their own
+    pub fn solved_bbbv(&self) -> u32 {
+        self.count_bbbv(true)
+    }
+
+    /// Counts the clicks making up the board's 3BV, or only those already made if
+    /// `solved_only`. Revealing any blank of an opening reveals all of it.
+    fn count_bbbv(&self, solved_only: bool) -> u32 {
+        let counts = |sqr: &Square| !solved_only || sqr.is_revealed;
+        let mut marked = vec![false; self.squares.len()];
+        let mut bbbv = 0;
+
+        for idx in 0..self.squares.len() {
+            let sqr = &self.squares[idx];
+            if marked[idx] || sqr.is_mine() || sqr.is_void() || sqr.numeral > 0 {
+                continue;
+            }
+
+            if counts(sqr) {
+                bbbv += 1;
+            }
+            marked[idx] = true;
+            let mut worklist = vec![idx as u32];
+            while let Some(i) = worklist.pop() {
+                neighbors(self.width, self.height, self.layers, i).for_each(|nidx| {
+                    let nsqr = &self.squares[nidx as usize];
+                    if marked[nidx as usize] || nsqr.is_mine() || nsqr.is_void() {
+                        return;
+                    }
+                    marked[nidx as usize] = true;
+                    if nsqr.numeral == 0 {
+                        worklist.push(nidx);
+                    }
+                });
+            }
+        }
+
+        bbbv + self
+            .squares
+            .iter()
+            .zip(marked)
+            .filter(|(sqr, marked)| !marked && !sqr.is_mine() && !sqr.is_void() && counts(sqr))
+            .count() as u32
+    }
+
+    /// The numeral of each square as the player sees it, or `None` if it hasn't been
+    /// revealed. Revealed mines are also `None`, as they don't tell the player anything, and
+    /// so are voids, which `voids` picks out.
+    pub fn revealed_numerals(&self) -> Vec<Option<u32>> {
+        self.squares
+            .iter()
+            .map(|s| (s.is_revealed && !s.is_mine()).then_some(s.numeral))
+            .collect()
+    }
+
+    /// Whether each square is a void, in the same order as `revealed_numerals`
+    pub fn voids(&self) -> Vec<bool> {
+        self.squares.iter().map(|s| s.is_void()).collect()
+    }
+
+    /// The board's 3BV, once mines and numerals have been populated
+    pub fn bbbv(&self) -> u32 {
+        self.bbbv
+    }
+
     #[allow(dead_code)]
     pub fn print(&self) {
         for y in 0..self.height {
@@ -337,89 +698,236 @@ impl GameBoard {
         }
     }
 
+    /// The board as text, one line per row. With `show_all`, every square is shown: `*` for
+    /// a mine, its numeral, or `.` for a blank. Otherwise it's the player's view: `-` for a
+    /// covered square, `>` for a flag and `X` for a detonated mine. Voids are `_` either way.
+    pub fn to_ascii(&self, show_all: bool) -> String {
+        self.squares
+            .chunks(self.width as usize)
+            .map(|row| {
+                row.iter()
+                    .map(|sqr| match sqr {
+                        s if s.is_void() => '_',
+                        s if !show_all && s.is_flagged => '>',
+                        s if !show_all && !s.is_revealed => '-',
+                        s if s.is_mine() && s.is_revealed => 'X',
+                        s if s.is_mine() => '*',
+                        // Layered boards go up to 26, written `a` to `q` past 9
+                        s if s.numeral > 0 => char::from_digit(s.numeral, 36).unwrap_or('?'),
+                        _ => '.',
+                    })
+                    .collect::<String>()
+                    + "\n"
+            })
+            .collect()
+    }
+
+    /// Builds a fully populated board from text in the format written by
+    /// `to_ascii(true)`. Only the mines are read; the numerals are worked out again. Lines
+    /// starting with `#` are comments.
+    pub fn from_ascii(text: &str) -> Result<GameBoard, Error> {
+        let rows: Vec<(usize, &str)> = text
+            .lines()
+            .enumerate()
+            .map(|(i, l)| (i + 1, l.trim()))
+            .filter(|(_, l)| !l.is_empty() && !l.starts_with('#'))
+            .collect();
+        let width = rows.first().map_or(0, |(_, l)| l.chars().count());
+        let mut mines = vec![];
+        let mut voids = vec![];
+        for (y, (line, row)) in rows.iter().enumerate() {
+            if row.chars().count() != width {
+                return Err(Error::InvalidBoardText {
+                    line: *line,
+                    reason: format!("expected {} squares", width),
+                });
+            }
+            for (x, c) in row.chars().enumerate() {
+                match c {
+                    '*' => mines.push(Coordinate::from((x as u32, y as u32))),
+                    '_' => voids.push(Coordinate::from((x as u32, y as u32))),
+                    '.' | '1'..='9' | 'a'..='q' => {}
+                    c => {
+                        return Err(Error::InvalidBoardText {
+                            line: *line,
+                            reason: format!("unknown square '{}'", c),
+                        })
+                    }
+                }
+            }
+        }
+        if rows.is_empty() {
+            return Err(Error::InvalidBoardText {
+                line: 1,
+                reason: "the board is empty".to_string(),
+            });
+        }
+        let mut gb = GameBoard::new(width as u32, rows.len() as u32);
+        gb.set_voids(|x, y| voids.contains(&Coordinate::from((x, y))));
+        let template = TemplatePlacement { mines };
+        gb.populate_mines_with(&template, template.mines.len() as u32, None)?;
+        gb.populate_numerals()?;
+        Ok(gb)
+    }
+
+    /// The board in the Minesweeper Board Format other clones share boards in: a byte each
+    /// for the width and height, the number of mines as two big endian bytes, then a byte
+    /// each for the column and row of every mine. Boards with voids or more than 255
+    /// columns or rows can't be written.
+    pub fn to_mbf(&self) -> Result<Vec<u8>, Error> {
+        let invalid = |reason: &str| Error::InvalidBoardData {
+            reason: reason.to_string(),
+        };
+        let (Ok(width), Ok(height)) = (u8::try_from(self.width), u8::try_from(self.height)) else {
+            return Err(invalid("the board is too big"));
+        };
+        if self.squares.iter().any(|s| s.is_void()) {
+            return Err(invalid("the board has voids"));
+        }
+        let mines: Vec<u8> = iproduct!(0..height, 0..width)
+            .filter(|&(y, x)| self.squares[self.xy_to_idx(x as u32, y as u32) as usize].is_mine())
+            .flat_map(|(y, x)| [x, y])
+            .collect();
+        let num_mines = u16::try_from(mines.len() / 2).map_err(|_| invalid("too many mines"))?;
+        Ok([&[width, height][..], &num_mines.to_be_bytes(), &mines].concat())
+    }
+
+    /// Builds a fully populated board from the Minesweeper Board Format written by
+    /// `to_mbf()`
+    pub fn from_mbf(data: &[u8]) -> Result<GameBoard, Error> {
+        let invalid = |reason: String| Error::InvalidBoardData { reason };
+        let [width, height, count_high, count_low, mines @ ..] = data else {
+            return Err(invalid("the header is cut short".to_string()));
+        };
+        let (width, height) = (*width as u32, *height as u32);
+        if width == 0 || height == 0 {
+            return Err(invalid(format!("the board is {}x{}", width, height)));
+        }
+        let num_mines = u16::from_be_bytes([*count_high, *count_low]) as usize;
+        if mines.len() != num_mines * 2 {
+            return Err(invalid(format!(
+                "{} mines are listed, but {} bytes follow",
+                num_mines,
+                mines.len()
+            )));
+        }
+        let mines: Vec<Coordinate> = mines
+            .chunks(2)
+            .map(|m| Coordinate::from((m[0] as u32, m[1] as u32)))
+            .collect();
+        let mut listed = HashSet::new();
+        if let Some(twice) = mines.iter().find(|m| !listed.insert((m.x, m.y))) {
+            return Err(invalid(format!("the mine at {:?} is listed twice", twice)));
+        }
+        GameBoard::new_with_mines(width, height, &mines)
+    }
+
     /// Toggles the flagged state of a square.
-    /// Returns the updated flagged state of the square.
     ///
     /// A revealed square cannot be flagged
     ///
     pub fn flag(&mut self, x: u32, y: u32) -> Result<PlayResult, Error> {
         if x >= self.width || y >= self.height {
-            Err(Error::InvalidCoordinates)
+            Err(self.invalid_coordinates(x, y))
         } else {
             let idx = self.xy_to_idx(x, y);
             let sqr = self.get_square_by_idx(idx)?;
-            if !sqr.is_revealed {
+            if !sqr.is_revealed && !sqr.is_void() {
                 self.squares[idx as usize].is_flagged = !sqr.is_flagged;
-                Ok(PlayResult::Flagged(self.squares[idx as usize].is_flagged))
+                if sqr.is_flagged {
+                    self.flag_count -= 1;
+                } else {
+                    self.flag_count += 1;
+                }
+                self.is_dirty = true;
+                let kind = if sqr.is_flagged {
+                    ChangeKind::Unflagged
+                } else {
+                    ChangeKind::Flagged
+                };
+                Ok(PlayResult::single(Coordinate::from((x, y)), kind))
             } else {
-                Ok(PlayResult::NoChange) // Maybe return false instead?
+                Ok(PlayResult::default())
             }
         }
     }
 
     pub fn cascade_from(&mut self, x: u32, y: u32) -> Result<PlayResult, Error> {
         if x >= self.width || y >= self.height {
-            return Err(Error::InvalidCoordinates);
+            return Err(self.invalid_coordinates(x, y));
         }
 
         let idx = self.xy_to_idx(x, y);
 
         if self.squares[idx as usize].is_mine()
+            || self.squares[idx as usize].is_void()
             || self.squares[idx as usize].is_flagged
             || self.squares[idx as usize].numeral > 0
         {
-            return Err(Error::InvalidCascade);
+            return Err(Error::InvalidCascade { x, y });
         }
-        self.squares[idx as usize].is_revealed = true;
+        self.mark_revealed(idx);
 
-        let results = iproduct!(-1_i32..2_i32, -1_i32..2_i32)
-            .map(|(dx, dy)| self.reveal_protected(x as i32 + dx, y as i32 + dy))
-            .collect::<Vec<PlayResult>>();
+        // Breadth first flood fill using an explicit queue so large empty regions can't
+        // overflow the stack, and the squares come out in rings around the one played. Every
+        // square on the queue is a revealed blank whose neighbors are all safe.
+        let mut result = PlayResult::single(Coordinate::from((x, y)), ChangeKind::Revealed);
+        let mut queue = VecDeque::from([(Coordinate::from((x, y)), 0)]);
+        while let Some((c, depth)) = queue.pop_front() {
+            self.neighbors_of(c.x, c.y).for_each(|nidx| {
+                let sqr = self.squares[nidx as usize];
+                if sqr.is_revealed || sqr.is_flagged || sqr.is_mine() || sqr.is_void() {
+                    return;
+                }
 
-        Ok(PlayResult::CascadedReveal(results))
+                self.mark_revealed(nidx);
+                let coord = Coordinate::from((nidx % self.width, nidx / self.width));
+                if sqr.numeral == 0 {
+                    queue.push_back((coord.clone(), depth + 1));
+                }
+                result.push(coord, ChangeKind::Revealed, depth + 1);
+            });
+        }
+
+        Ok(result)
     }
 
     // Defines a single square reveal
     pub fn reveal(&mut self, x: u32, y: u32) -> Result<PlayResult, Error> {
         if x >= self.width || y >= self.height {
-            Err(Error::InvalidCoordinates)
+            Err(self.invalid_coordinates(x, y))
         } else {
             let idx = self.xy_to_idx(x, y);
             let sqr = self.get_square_by_idx(idx)?;
 
-            if sqr.is_mine() && !sqr.is_flagged {
-                // If the square is a mine and it's not flagged (unprotected)
-                self.squares[idx as usize].is_revealed = true;
-                Ok(PlayResult::Explosion(Coordinate::from((x, y))))
-            } else if !sqr.is_mine() && !sqr.is_flagged && !sqr.is_revealed {
+            if sqr.is_mine() && !sqr.is_flagged && !sqr.is_revealed {
+                // If the square is a mine and it's not flagged (unprotected), and hasn't
+                // already gone off
+                self.mark_revealed(idx);
+                Ok(PlayResult::single(
+                    Coordinate::from((x, y)),
+                    ChangeKind::Exploded,
+                ))
+            } else if !sqr.is_mine() && !sqr.is_void() && !sqr.is_flagged && !sqr.is_revealed {
                 // if the square is not a mine, is unflagged, and is unrevealed
                 if self.squares[idx as usize].numeral == 0 {
                     // If it's a non-numeral square, we can auto-chord it
                     self.cascade_from(x, y)
                 } else {
                     // Otherwise, reveal the single square, and set it as so
-                    self.squares[idx as usize].is_revealed = true;
-                    Ok(PlayResult::Revealed(Coordinate::from((x, y))))
+                    self.mark_revealed(idx);
+                    Ok(PlayResult::single(
+                        Coordinate::from((x, y)),
+                        ChangeKind::Revealed,
+                    ))
                 }
             } else {
                 // Otherwise no change (user tried to reveal an already revealed square)
-                Ok(PlayResult::NoChange)
+                Ok(PlayResult::default())
             }
         }
     }
 
-    fn reveal_protected(&mut self, x: i32, y: i32) -> PlayResult {
-        if x < 0 {
-            return PlayResult::NoChange;
-        }
-        if y < 0 {
-            return PlayResult::NoChange;
-        }
-
-        self.reveal(x as u32, y as u32)
-            .unwrap_or(PlayResult::NoChange)
-    }
-
     /// Determine whether a given square can be chorded.
     ///
     /// Has a zero numeral: yes
@@ -429,7 +937,7 @@ impl GameBoard {
     ///     there is an abiguity and the square cannot be chorded.
     pub fn can_chord_square(&self, x: u32, y: u32) -> Result<bool, Error> {
         if x >= self.width || y >= self.height {
-            return Err(Error::InvalidCoordinates);
+            return Err(self.invalid_coordinates(x, y));
         }
         let sqr = self.get_square(x, y)?;
 
@@ -441,66 +949,64 @@ impl GameBoard {
         }
     }
 
-    /// Executes a 'chord' reveal on the requested square.
+    /// Executes a 'chord' reveal on the requested square, revealing it and the squares
+    /// around it. Flagged squares are skipped, so a wrong flag leaves a mine to go off.
     pub fn chord(&mut self, x: u32, y: u32) -> Result<PlayResult, Error> {
-        if x >= self.width || y >= self.height {
-            Err(Error::InvalidCoordinates)
-        } else if !self.can_chord_square(x, y)? {
-            Ok(PlayResult::NoChange)
-        } else {
-            let results = iproduct!(-1_i32..2_i32, -1_i32..2_i32)
-                .map(|(dx, dy)| self.reveal_protected(x as i32 + dx, y as i32 + dy))
-                .collect::<Vec<PlayResult>>();
-
-            Ok(PlayResult::CascadedReveal(results))
+        let mut result = PlayResult::default();
+        if !self.can_chord_square(x, y)? {
+            return Ok(result);
         }
+        std::iter::once(self.xy_to_idx(x, y))
+            .chain(self.neighbors_of(x, y))
+            .try_for_each(|i| {
+                let coord = Coordinate::from((i % self.width, i / self.width));
+                // The squares around the one chorded are a ring further out
+                let ring = u32::from(coord != Coordinate::from((x, y)));
+                if self.squares[i as usize].is_flagged {
+                    result.push(coord, ChangeKind::Skipped, ring);
+                } else {
+                    let revealed = self.reveal(coord.x, coord.y)?;
+                    result
+                        .changes
+                        .extend(revealed.changes.into_iter().map(|c| CellChange {
+                            depth: c.depth + ring,
+                            ..c
+                        }));
+                }
+                Ok(())
+            })?;
+        Ok(result)
     }
 
-    /// Performs a unified reveal then chord in the same coordinate
+    /// Performs a unified reveal then chord in the same coordinate. A mine that goes off
+    /// isn't chorded around.
     pub fn revealchord(&mut self, x: u32, y: u32) -> Result<PlayResult, Error> {
-        let rv = self.reveal(x, y)?;
-        let rc = self.chord(x, y)?;
-
-        if let PlayResult::CascadedReveal(mut v) = rc {
-            v.push(rv);
-            Ok(PlayResult::CascadedReveal(v))
-        } else if PlayResult::NoChange == rc {
-            Ok(PlayResult::CascadedReveal(vec![rv]))
-        } else {
-            Err(Error::UnexpectedResult)
+        let mut result = self.reveal(x, y)?;
+        if result.explosion().is_none() {
+            result.changes.extend(self.chord(x, y)?.changes);
         }
+        Ok(result)
     }
 
     /// Determine if the board is in a winning configuration.
     ///
     /// Conditions
-    /// - All non-mine squares are revealed (mined need not be flagged)
+    /// - All non-mine squares inside the board's shape are revealed (mined need not be
+    ///   flagged)
+    /// - No mine has been detonated, even by the play that revealed the last safe square
     #[allow(dead_code)]
     pub fn is_win_configuration(&self) -> bool {
-        self.squares
-            .clone()
-            .into_iter()
-            .map(|s| if !s.is_mine() && !s.is_revealed { 1 } else { 0 })
-            .collect::<Vec<u32>>()
-            .into_iter()
-            .sum::<u32>()
-            == 0_u32
+        self.detonated_count == 0 && self.revealed_count == self.num_squares() - self.num_mines
     }
 
     #[allow(dead_code)]
     pub fn is_loss_configuration(&self) -> bool {
-        self.squares
-            .clone()
-            .into_iter()
-            .map(|s| if s.is_mine() && s.is_revealed { 1 } else { 0 })
-            .collect::<Vec<u32>>()
-            .into_iter()
-            .sum::<u32>()
-            > 0_u32
+        self.detonated_count > 0
     }
 
     pub fn play(&mut self, x: u32, y: u32, reveal_type: RevealType) -> Result<PlayResult, Error> {
         match reveal_type {
+            RevealType::Flag if self.no_flags => Ok(PlayResult::default()),
             RevealType::Flag => self.flag(x, y),
             RevealType::Reveal => self.reveal(x, y),
             RevealType::Chord => self.chord(x, y),
@@ -508,24 +1014,36 @@ impl GameBoard {
         }
     }
 
+    pub fn layers(&self) -> u32 {
+        self.layers
+    }
+
+    /// Rows in each layer
+    pub fn layer_height(&self) -> u32 {
+        self.height / self.layers
+    }
+
+    /// Squares inside the board's shape, leaving out the voids
+    pub fn num_squares(&self) -> u32 {
+        self.width * self.height - self.void_count
+    }
+
     pub fn num_flags(&self) -> u32 {
-        self.squares
-            .clone()
-            .into_iter()
-            .map(|s| if s.is_flagged { 1 } else { 0 })
-            .collect::<Vec<u32>>()
-            .into_iter()
-            .sum::<u32>()
+        self.flag_count
     }
 
     pub fn num_revealed(&self) -> u32 {
-        self.squares
-            .clone()
-            .into_iter()
-            .map(|s| if s.is_revealed { 1 } else { 0 })
-            .collect::<Vec<u32>>()
-            .into_iter()
-            .sum::<u32>()
+        self.revealed_count
+    }
+
+    /// Revealed squares that aren't mines
+    pub fn num_safe_revealed(&self) -> u32 {
+        self.revealed_count - self.detonated_count
+    }
+
+    /// Mines that have been revealed
+    pub fn num_detonated(&self) -> u32 {
+        self.detonated_count
     }
 
     // Don't cheat
@@ -534,13 +1052,502 @@ impl GameBoard {
         for sqr in self.squares.iter_mut() {
             sqr.is_flagged = sqr.is_mine();
         }
+        self.recount();
     }
 
     #[allow(dead_code)]
+    /// Covers the board back up to be played again. Once its mines have been placed, the
+    /// board counts as replayed since the player may already know where they are.
     pub fn reset_existing(&mut self) {
         for sqr in self.squares.iter_mut() {
             sqr.is_flagged = false;
             sqr.is_revealed = false;
         }
+        self.replayed |= self.is_populated;
+        self.recount();
+    }
+
+    pub fn is_replayed(&self) -> bool {
+        self.replayed
+    }
+
+    /// The board as the player sees it. Where the mines are is only given away with
+    /// `reveal_mines`, once the game is over.
+    pub fn player_view(&self, reveal_mines: bool) -> PlayerView<'_> {
+        PlayerView {
+            board: self,
+            reveal_mines,
+        }
+    }
+}
+
+/// What can be seen of a square
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub enum SquareView {
+    Covered,
+    Flagged,
+    /// A revealed safe square and its numeral, 0 for a blank
+    Revealed(u32),
+    /// A mine that was set off
+    Detonated,
+    /// A mine that was never found, only shown once the game is over
+    HiddenMine,
+    /// A flag on a safe square, only shown once the game is over
+    Misflagged,
+    /// Outside the board's shape, shown as empty space
+    Void,
+}
+
+/// Read-only access to what can be seen of a board, for the UI and other front ends
+pub trait BoardView {
+    fn width(&self) -> u32;
+    fn height(&self) -> u32;
+    fn square_at(&self, coord: &Coordinate) -> Result<SquareView, Error>;
+
+    /// The squares a row at a time, from the top
+    fn iter_rows(&self) -> Rows<'_, Self>
+    where
+        Self: Sized,
+    {
+        Rows { view: self, y: 0 }
+    }
+
+    /// A copy of what can be seen, which can be kept or sent over the network without
+    /// giving away anything more
+    fn snapshot(&self) -> Result<BoardSnapshot, Error>
+    where
+        Self: Sized,
+    {
+        Ok(BoardSnapshot {
+            width: self.width(),
+            height: self.height(),
+            squares: self.iter_rows().collect::<Result<Vec<_>, _>>()?.concat(),
+        })
+    }
+}
+
+/// Iterator over the rows of a `BoardView`
+pub struct Rows<'a, V> {
+    view: &'a V,
+    y: u32,
+}
+
+impl<'a, V: BoardView> Iterator for Rows<'a, V> {
+    type Item = Result<Vec<SquareView>, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.y >= self.view.height() {
+            return None;
+        }
+        let y = self.y;
+        self.y += 1;
+        Some(
+            (0..self.view.width())
+                .map(|x| self.view.square_at(&Coordinate::from((x, y))))
+                .collect(),
+        )
+    }
+}
+
+/// A board as the player sees it, made by `GameBoard::player_view`
+pub struct PlayerView<'a> {
+    board: &'a GameBoard,
+    reveal_mines: bool,
+}
+
+impl<'a> BoardView for PlayerView<'a> {
+    fn width(&self) -> u32 {
+        self.board.width
+    }
+
+    fn height(&self) -> u32 {
+        self.board.height
+    }
+
+    fn square_at(&self, coord: &Coordinate) -> Result<SquareView, Error> {
+        let sqr = self.board.get_square_by_coordinate(coord)?;
+        Ok(match sqr {
+            s if s.is_void() => SquareView::Void,
+            s if s.is_revealed && s.is_mine() => SquareView::Detonated,
+            s if s.is_revealed => SquareView::Revealed(s.numeral),
+            s if s.is_flagged && !s.is_mine() && self.reveal_mines => SquareView::Misflagged,
+            s if s.is_flagged => SquareView::Flagged,
+            s if s.is_mine() && self.reveal_mines => SquareView::HiddenMine,
+            _ => SquareView::Covered,
+        })
+    }
+}
+
+/// What could be seen of a board at one moment
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct BoardSnapshot {
+    pub width: u32,
+    pub height: u32,
+    squares: Vec<SquareView>,
+}
+
+impl BoardView for BoardSnapshot {
+    fn width(&self) -> u32 {
+        self.width
+    }
+
+    fn height(&self) -> u32 {
+        self.height
+    }
+
+    fn square_at(&self, coord: &Coordinate) -> Result<SquareView, Error> {
+        if coord.x >= self.width || coord.y >= self.height {
+            return Err(Error::InvalidCoordinates {
+                x: coord.x,
+                y: coord.y,
+                width: self.width,
+                height: self.height,
+            });
+        }
+        Ok(self.squares[(coord.y * self.width + coord.x) as usize])
     }
 }
+
+#[test]
+fn test_replayed() -> Result<(), Error> {
+    let mut gb = GameBoard::new(9, 9);
+    gb.reset_existing();
+    assert!(!gb.is_replayed());
+
+    gb.populate_mines_around(10, Some(Coordinate { x: 4, y: 4 }))?;
+    gb.populate_numerals()?;
+    gb.play(4, 4, RevealType::Reveal)?;
+    gb.reset_existing();
+    assert!(gb.is_replayed());
+    assert_eq!(gb.num_revealed(), 0);
+    Ok(())
+}
+
+#[test]
+fn test_no_flags() -> Result<(), Error> {
+    let mut gb = GameBoard::new_with_mines(5, 1, &[Coordinate::from((3, 0))])?;
+    gb.no_flags = true;
+    assert!(gb.play(3, 0, RevealType::Flag)?.is_no_change());
+    assert_eq!(gb.num_flags(), 0);
+    gb.play(0, 0, RevealType::Reveal)?;
+    gb.play(4, 0, RevealType::Reveal)?;
+    assert!(gb.is_win_configuration());
+    Ok(())
+}
+
+#[test]
+fn test_cached_counters() -> Result<(), Error> {
+    let mut gb = GameBoard::new_populated_around(16, 16, 40, Coordinate { x: 8, y: 8 })?;
+    gb.play(8, 8, RevealType::Reveal)?;
+    gb.play(0, 0, RevealType::Flag)?;
+    gb.play(15, 15, RevealType::Flag)?;
+    gb.play(15, 15, RevealType::Flag)?;
+
+    let count = |f: fn(&Square) -> bool| gb.squares.iter().filter(|s| f(s)).count() as u32;
+    assert_eq!(gb.num_flags(), count(|s| s.is_flagged));
+    assert_eq!(gb.num_revealed(), count(|s| s.is_revealed));
+    assert_eq!(gb.num_mines, count(|s| s.is_mine()));
+    assert_eq!(
+        gb.num_safe_revealed(),
+        count(|s| s.is_revealed && !s.is_mine())
+    );
+    assert!(!gb.is_loss_configuration());
+    assert!(gb.take_dirty());
+    assert!(!gb.take_dirty());
+
+    gb.reset_existing();
+    assert_eq!(gb.num_flags(), 0);
+    assert_eq!(gb.num_revealed(), 0);
+    Ok(())
+}
+
+#[test]
+fn test_cascade_large_empty_board() -> Result<(), Error> {
+    let mut gb = GameBoard::new_populated(500, 500, 0)?;
+    let result = gb.reveal(0, 0)?;
+    assert_eq!(result.changes.len(), 500 * 500);
+    assert!(result
+        .changes
+        .iter()
+        .all(|c| c.kind == ChangeKind::Revealed));
+    assert!(gb.is_win_configuration());
+
+    // The squares come out in rings around the one played, each as far from it as it takes
+    // kings' moves to get there
+    let mut gb = GameBoard::new_populated(20, 10, 0)?;
+    let result = gb.reveal(5, 5)?;
+    assert!(result.changes.windows(2).all(|w| w[0].depth <= w[1].depth));
+    assert!(result
+        .changes
+        .iter()
+        .all(|c| c.depth == c.coord.x.abs_diff(5).max(c.coord.y.abs_diff(5))));
+    Ok(())
+}
+
+#[test]
+fn test_populate_mines_high_density() -> Result<(), Error> {
+    // Every square outside of the keep clear zone is mined
+    let keep_clear = Coordinate { x: 4, y: 4 };
+    let gb = GameBoard::new_populated_around(9, 9, 72, keep_clear.clone())?;
+    assert_eq!(gb.num_mines, 72);
+    iproduct!(3..6, 3..6).for_each(|(x, y)| {
+        assert!(!gb.get_square(x, y).unwrap().is_mine());
+    });
+
+    assert!(GameBoard::new_populated_around(9, 9, 73, keep_clear).is_err());
+    Ok(())
+}
+
+#[test]
+fn test_bbbv() -> Result<(), Error> {
+    // No mines: a single opening
+    let gb = GameBoard::new_populated(9, 9, 0)?;
+    assert_eq!(gb.bbbv(), 1);
+
+    // Every square touches the mine in the middle, so each needs its own click
+    let gb = GameBoard::new_with_mines(3, 3, &[Coordinate::from((1, 1))])?;
+    assert_eq!(gb.bbbv(), 8);
+
+    // [0 0 1 * 1]: one opening that takes the first 1 with it, plus the last 1
+    let mut gb = GameBoard::new_with_mines(5, 1, &[Coordinate::from((3, 0))])?;
+    assert_eq!(gb.bbbv(), 2);
+    assert_eq!(gb.solved_bbbv(), 0);
+    // Revealing the 1 at the edge of the opening doesn't clear the opening
+    gb.reveal(2, 0)?;
+    assert_eq!(gb.solved_bbbv(), 0);
+    gb.reveal(0, 0)?;
+    assert_eq!(gb.solved_bbbv(), 1);
+    gb.reveal(4, 0)?;
+    assert_eq!(gb.solved_bbbv(), 2);
+
+    // [0 1 * 1 0 / 0 1 1 1 0 / 0 0 0 0 0]: the openings on either side of the mine join
+    // up underneath it
+    let gb = GameBoard::new_with_mines(5, 3, &[Coordinate::from((2, 0))])?;
+    assert_eq!(gb.bbbv(), 1);
+
+    assert!(GameBoard::new_with_mines(5, 3, &[Coordinate::from((5, 0))]).is_err());
+    Ok(())
+}
+
+#[test]
+fn test_seeded_boards_match() -> Result<(), Error> {
+    let keep_clear = Coordinate { x: 3, y: 7 };
+    let mut a = GameBoard::new_seeded(30, 16, 12345);
+    let mut b = GameBoard::new_seeded(30, 16, 12345);
+    a.populate_mines_around(80, Some(keep_clear.clone()))?;
+    b.populate_mines_around(80, Some(keep_clear))?;
+    assert_eq!(a.squares, b.squares);
+    Ok(())
+}
+
+#[test]
+fn test_chord() -> Result<(), Error> {
+    // *1.
+    // 11.
+    let board = GameBoard::new_with_mines(3, 2, &[Coordinate::from((0, 0))])?;
+
+    let mut right = board.clone();
+    right.play(1, 0, RevealType::Reveal)?;
+    right.play(0, 0, RevealType::Flag)?;
+    let chord = right.play(1, 0, RevealType::Chord)?;
+    assert!(chord.skipped().eq([&Coordinate::from((0, 0))]));
+    assert_eq!(chord.explosion(), None);
+    assert_eq!(chord.coordinates().len(), 4);
+    assert!(right.is_win_configuration());
+    // Nothing's left for a second chord to do
+    assert!(right.play(1, 0, RevealType::Chord)?.is_no_change());
+
+    // A wrong flag is skipped, and the mine it should have been on goes off
+    let mut wrong = board.clone();
+    wrong.play(0, 1, RevealType::Flag)?;
+    let chord = wrong.play(1, 0, RevealType::RevealChord)?;
+    assert!(chord.skipped().eq([&Coordinate::from((0, 1))]));
+    assert_eq!(chord.explosion(), Some(&Coordinate::from((0, 0))));
+    assert_eq!(
+        chord.changes[0],
+        CellChange {
+            coord: Coordinate::from((1, 0)),
+            kind: ChangeKind::Revealed,
+            depth: 0
+        }
+    );
+    assert_eq!(chord.coordinates().len(), 5);
+    assert!(wrong.is_loss_configuration());
+    Ok(())
+}
+
+#[test]
+fn test_toggle_mine() -> Result<(), Error> {
+    let mut gb = GameBoard::new_with_mines(5, 1, &[Coordinate::from((2, 0))])?;
+    gb.toggle_mine(2, 0)?;
+    gb.toggle_mine(4, 0)?;
+    assert_eq!(gb.num_mines, 1);
+    // Numerals stay as they were until they're worked out again
+    assert_eq!(gb.to_ascii(true), ".1.1*\n");
+    gb.populate_numerals()?;
+    assert_eq!(gb.to_ascii(true), "...1*\n");
+    assert!(gb.toggle_mine(5, 0).is_err());
+
+    let mut empty = GameBoard::new(3, 1);
+    empty.toggle_mine(0, 0)?;
+    assert!(empty.is_populated);
+    Ok(())
+}
+
+#[test]
+fn test_ascii_round_trip() -> Result<(), Error> {
+    let gb = GameBoard::new_with_mines(5, 3, &[Coordinate::from((2, 0))])?;
+    let text = gb.to_ascii(true);
+    assert_eq!(text, ".1*1.\n.111.\n.....\n");
+    let reloaded = GameBoard::from_ascii(&text)?;
+    assert_eq!(reloaded.squares, gb.squares);
+
+    let mut played = reloaded;
+    played.play(0, 2, RevealType::Reveal)?;
+    played.play(2, 0, RevealType::Flag)?;
+    assert_eq!(played.to_ascii(false), ".1>1.\n.111.\n.....\n");
+
+    let commented = GameBoard::from_ascii(&format!("# seed 42\n{}", text))?;
+    assert_eq!(commented.squares, gb.squares);
+
+    assert!(GameBoard::from_ascii("..*\n..\n").is_err());
+    assert!(GameBoard::from_ascii("..x\n").is_err());
+    assert!(GameBoard::from_ascii("").is_err());
+    Ok(())
+}
+
+#[test]
+fn test_mbf_round_trip() -> Result<(), Error> {
+    let gb =
+        GameBoard::new_with_mines(5, 3, &[Coordinate::from((2, 0)), Coordinate::from((4, 2))])?;
+    let data = gb.to_mbf()?;
+    assert_eq!(data, [5, 3, 0, 2, 2, 0, 4, 2]);
+    assert_eq!(GameBoard::from_mbf(&data)?.squares, gb.squares);
+
+    assert!(GameBoard::from_mbf(&[5, 3, 0]).is_err());
+    assert!(GameBoard::from_mbf(&[0, 3, 0, 0]).is_err());
+    assert!(GameBoard::from_mbf(&[5, 3, 0, 2, 2, 0]).is_err());
+    assert!(GameBoard::from_mbf(&[5, 3, 0, 2, 2, 0, 2, 0]).is_err());
+    assert!(GameBoard::from_mbf(&[5, 3, 0, 1, 5, 0]).is_err());
+    assert!(GameBoard::from_ascii("_*.\n")?.to_mbf().is_err());
+    Ok(())
+}
+
+#[test]
+fn test_voids() -> Result<(), Error> {
+    let mut gb = GameBoard::from_ascii("__1*\n..11\n....\n")?;
+    assert_eq!(gb.num_squares(), 10);
+    assert_eq!(gb.bbbv(), 1);
+
+    // Voids can't be played, and aren't opened by a cascade next to them
+    assert!(gb.play(0, 0, RevealType::Flag)?.is_no_change());
+    assert!(gb.play(1, 0, RevealType::Reveal)?.is_no_change());
+    gb.play(0, 2, RevealType::Reveal)?;
+    assert_eq!(gb.to_ascii(false), "__1-\n..11\n....\n");
+    assert!(gb.is_win_configuration());
+    assert_eq!(
+        gb.player_view(true).square_at(&Coordinate::from((0, 0)))?,
+        SquareView::Void
+    );
+
+    // Mines only go inside the board's shape, which it keeps when reset
+    let mut gb = GameBoard::new(4, 4);
+    gb.set_voids(|x, _| x == 0);
+    gb.populate_mines(12)?;
+    assert_eq!(gb.num_mines, 12);
+    assert!((0..4).all(|y| gb.get_square(0, y).is_ok_and(|s| s.is_void())));
+    gb.reset();
+    assert_eq!(gb.num_squares(), 12);
+    assert!(gb.populate_mines(13).is_err());
+    Ok(())
+}
+
+#[test]
+fn test_layers() -> Result<(), Error> {
+    // Two 3x3 layers with a mine in the bottom left corner of the first
+    let mut gb = GameBoard::new_layered(3, 3, 2, 1);
+    assert_eq!((gb.height, gb.layer_height()), (6, 3));
+    let template = TemplatePlacement {
+        mines: vec![Coordinate::from((0, 2))],
+    };
+    gb.populate_mines_with(&template, 1, None)?;
+    gb.populate_numerals()?;
+
+    // The mine is next to the squares above it on the second layer, but not the ones right
+    // after it in the rows
+    assert_eq!(gb.get_square(0, 3)?.numeral, 0);
+    assert_eq!(gb.get_square(0, 4)?.numeral, 1);
+    assert_eq!(gb.get_square(1, 5)?.numeral, 1);
+    assert_eq!(neighbors(3, 9, 3, 13).count(), 26);
+
+    // Openings spread from one layer to the next, leaving only the square above the mine's
+    // corner, which has no blank neighbors
+    gb.play(2, 3, RevealType::Reveal)?;
+    assert_eq!(gb.num_revealed(), 16);
+    assert!(!gb.get_square(0, 5)?.is_revealed);
+
+    // A square in the middle of three layers has 26 neighbors, more than a digit can show
+    let mut gb = GameBoard::new_layered(3, 3, 3, 1);
+    let template = TemplatePlacement {
+        mines: iproduct!(0..9, 0..3)
+            .filter(|&(y, x)| (x, y) != (1, 4))
+            .map(|(y, x)| Coordinate::from((x, y)))
+            .collect(),
+    };
+    gb.populate_mines_with(&template, 26, None)?;
+    gb.populate_numerals()?;
+    let text = gb.to_ascii(true);
+    assert_eq!(text.lines().nth(4), Some("*q*"));
+    let reloaded = GameBoard::from_ascii(&text)?;
+    assert_eq!(reloaded.squares.iter().filter(|s| s.is_mine()).count(), 26);
+    Ok(())
+}
+
+#[test]
+fn test_player_view() -> Result<(), Error> {
+    // [. 1 * / . 1 1] with the mine's neighbor flagged by mistake
+    let mut gb = GameBoard::new_with_mines(3, 2, &[Coordinate::from((2, 0))])?;
+    gb.play(0, 0, RevealType::Reveal)?;
+    gb.play(2, 1, RevealType::Flag)?;
+
+    let view = gb.player_view(false);
+    let rows = view.iter_rows().collect::<Result<Vec<_>, _>>()?;
+    assert_eq!(
+        rows,
+        [
+            [
+                SquareView::Revealed(0),
+                SquareView::Revealed(1),
+                SquareView::Covered
+            ],
+            [
+                SquareView::Revealed(0),
+                SquareView::Revealed(1),
+                SquareView::Flagged
+            ],
+        ]
+    );
+    let snapshot = view.snapshot()?;
+    assert_eq!(
+        snapshot.square_at(&Coordinate::from((1, 1)))?,
+        SquareView::Revealed(1)
+    );
+    assert!(snapshot.square_at(&Coordinate::from((3, 0))).is_err());
+
+    // Once the game is over the mistakes are shown
+    let view = gb.player_view(true);
+    assert_eq!(
+        view.square_at(&Coordinate::from((2, 0)))?,
+        SquareView::HiddenMine
+    );
+    assert_eq!(
+        view.square_at(&Coordinate::from((2, 1)))?,
+        SquareView::Misflagged
+    );
+    gb.play(2, 0, RevealType::Reveal)?;
+    assert_eq!(
+        gb.player_view(false).square_at(&Coordinate::from((2, 0)))?,
+        SquareView::Detonated
+    );
+    Ok(())
+}
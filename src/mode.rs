@@ -0,0 +1,226 @@
+use std::rc::Rc;
+
+use crate::i18n::{tr, tr_with};
+use crate::minesweeper::{Coordinate, GameBoard, RevealType};
+use crate::placement::{MinePlacement, MinePlacementStrategy};
+use crate::shape::BoardShape;
+use crate::state::GameSettings;
+
+/// The rules of a way to play: how the mines are placed, which plays are allowed, when a
+/// board is won or lost and how a win is scored. The built in modes are picked from the
+/// game's settings by `mode_for()`. Implement it for a mode of your own, and hand it to
+/// `MinesOfRustApp::set_game_mode`.
+pub trait GameMode {
+    /// The name shown to the player
+    fn name(&self) -> String;
+
+    /// How the mines are spread over a board, when its first square is revealed
+    fn placement(&self) -> Box<dyn MinePlacementStrategy>;
+
+    /// Whether `play_type` can be made at `coord` on `board`. Plays that aren't allowed do
+    /// nothing at all.
+    fn allows_play(
+        &self,
+        _board: &GameBoard,
+        _coord: &Coordinate,
+        _play_type: &RevealType,
+    ) -> bool {
+        true
+    }
+
+    /// Every board of the game has to be won for the game to be won
+    fn is_won(&self, board: &GameBoard) -> bool {
+        board.is_win_configuration()
+    }
+
+    /// Losing any board of the game loses the game
+    fn is_lost(&self, board: &GameBoard) -> bool {
+        board.is_loss_configuration()
+    }
+
+    /// What a win `time` seconds long is ranked by, lowest first
+    fn score(&self, time: f64) -> f64 {
+        time
+    }
+
+    /// Whether wins go on the leaderboard and into the statistics. Modes other than the
+    /// standard game are practice unless they say otherwise.
+    fn is_ranked(&self) -> bool {
+        false
+    }
+}
+
+/// The standard game, with or without flags
+pub struct ClassicMode {
+    pub no_flags: bool,
+}
+
+impl GameMode for ClassicMode {
+    fn name(&self) -> String {
+        if self.no_flags {
+            tr("mode-no-flags").to_string()
+        } else {
+            tr("mode-classic").to_string()
+        }
+    }
+
+    fn placement(&self) -> Box<dyn MinePlacementStrategy> {
+        MinePlacement::Uniform.strategy()
+    }
+
+    fn allows_play(&self, _board: &GameBoard, _coord: &Coordinate, play_type: &RevealType) -> bool {
+        !(self.no_flags && *play_type == RevealType::Flag)
+    }
+
+    fn is_ranked(&self) -> bool {
+        true
+    }
+}
+
+/// The standard game with the mines spread unevenly: denser to one side, out at the edges
+/// or clumped together
+pub struct PlacementMode {
+    pub placement: MinePlacement,
+    pub no_flags: bool,
+}
+
+impl GameMode for PlacementMode {
+    fn name(&self) -> String {
+        self.placement.name().to_string()
+    }
+
+    fn placement(&self) -> Box<dyn MinePlacementStrategy> {
+        self.placement.strategy()
+    }
+
+    fn allows_play(&self, board: &GameBoard, coord: &Coordinate, play_type: &RevealType) -> bool {
+        ClassicMode {
+            no_flags: self.no_flags,
+        }
+        .allows_play(board, coord, play_type)
+    }
+}
+
+/// A shaped or layered board, played like the standard game on the squares inside it
+pub struct ShapedMode {
+    pub shape: BoardShape,
+    pub layers: u32,
+    pub placement: MinePlacement,
+    pub no_flags: bool,
+}
+
+impl GameMode for ShapedMode {
+    fn name(&self) -> String {
+        if self.layers > 1 {
+            tr_with("mode-layered", &[("layers", &self.layers)])
+        } else {
+            self.shape.name().to_string()
+        }
+    }
+
+    fn placement(&self) -> Box<dyn MinePlacementStrategy> {
+        self.placement.strategy()
+    }
+
+    fn allows_play(&self, board: &GameBoard, coord: &Coordinate, play_type: &RevealType) -> bool {
+        ClassicMode {
+            no_flags: self.no_flags,
+        }
+        .allows_play(board, coord, play_type)
+    }
+}
+
+/// A handcrafted puzzle board, whose mines are already placed
+pub struct PuzzleMode {
+    pub name: String,
+}
+
+impl GameMode for PuzzleMode {
+    fn name(&self) -> String {
+        self.name.clone()
+    }
+
+    fn placement(&self) -> Box<dyn MinePlacementStrategy> {
+        MinePlacement::Uniform.strategy()
+    }
+}
+
+/// The tutorial's board. Which plays it takes is up to the step the tutorial is on.
+pub struct TutorialMode;
+
+impl GameMode for TutorialMode {
+    fn name(&self) -> String {
+        tr("menu-tutorial").to_string()
+    }
+
+    fn placement(&self) -> Box<dyn MinePlacementStrategy> {
+        MinePlacement::Uniform.strategy()
+    }
+}
+
+/// The built in mode the settings are for
+pub fn mode_for(settings: &GameSettings) -> Rc<dyn GameMode> {
+    if settings.shape != BoardShape::Rectangle || settings.layers > 1 {
+        return Rc::new(ShapedMode {
+            shape: settings.shape,
+            layers: settings.layers,
+            placement: settings.placement,
+            no_flags: settings.no_flags,
+        });
+    }
+    match settings.placement {
+        MinePlacement::Uniform => Rc::new(ClassicMode {
+            no_flags: settings.no_flags,
+        }),
+        placement => Rc::new(PlacementMode {
+            placement,
+            no_flags: settings.no_flags,
+        }),
+    }
+}
+
+#[test]
+fn test_game_modes() -> Result<(), crate::minesweeper::Error> {
+    let board = GameBoard::new_with_mines(5, 1, &[Coordinate::from((3, 0))])?;
+    let coord = Coordinate::from((3, 0));
+
+    let classic = mode_for(&GameSettings::beginner());
+    assert!(classic.is_ranked());
+    assert!(classic.allows_play(&board, &coord, &RevealType::Flag));
+    assert_eq!(classic.score(12.5), 12.5);
+
+    let no_flags = mode_for(&GameSettings {
+        no_flags: true,
+        placement: MinePlacement::Gradient,
+        ..GameSettings::beginner()
+    });
+    assert!(!no_flags.is_ranked());
+    assert!(!no_flags.allows_play(&board, &coord, &RevealType::Flag));
+    assert!(no_flags.allows_play(&board, &coord, &RevealType::Reveal));
+
+    let layered = mode_for(&GameSettings {
+        layers: 2,
+        ..GameSettings::beginner()
+    });
+    assert!(!layered.is_ranked());
+    assert!(layered.allows_play(&board, &coord, &RevealType::Flag));
+
+    // A mode of its own, won by revealing any one square
+    struct FirstClick;
+    impl GameMode for FirstClick {
+        fn name(&self) -> String {
+            "First Click".to_string()
+        }
+        fn placement(&self) -> Box<dyn MinePlacementStrategy> {
+            MinePlacement::Uniform.strategy()
+        }
+        fn is_won(&self, board: &GameBoard) -> bool {
+            board.num_revealed() > 0
+        }
+    }
+    let mut played = board.clone();
+    played.play(0, 0, RevealType::Reveal)?;
+    assert!(!FirstClick.is_won(&board) && FirstClick.is_won(&played));
+    assert!(!FirstClick.is_ranked());
+    Ok(())
+}
@@ -0,0 +1,646 @@
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{Shutdown, TcpListener, TcpStream, ToSocketAddrs};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, SyncSender};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::constants::DEFAULT_NET_PORT;
+use crate::enums::GameDifficulty;
+use crate::minesweeper::Coordinate;
+
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+/// How often the host checks for new players while waiting for connections
+const ACCEPT_INTERVAL: Duration = Duration::from_millis(100);
+/// Longest line read from a connection. A longer one closes the connection rather than
+/// being buffered without end.
+const MAX_LINE_LENGTH: usize = 64 * 1024;
+/// Lines waiting to be sent to a connection before it's taken to have stopped reading
+const MAX_QUEUED_LINES: usize = 1024;
+/// Longest a write to a connection can block before the connection is closed
+const WRITE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// How a player's race ended
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize, Serialize)]
+pub struct Finish {
+    pub won: bool,
+    pub time: f64,
+}
+
+/// Sent between players, one JSON object per line. The host relays everything it receives
+/// to the other players.
+#[derive(Clone, PartialEq, Deserialize, Serialize)]
+#[serde(tag = "type")]
+pub enum Message {
+    Join {
+        name: String,
+    },
+    Leave {
+        name: String,
+    },
+    /// Everyone plays the board made from `seed`, opened at `opening`
+    Start {
+        difficulty: GameDifficulty,
+        seed: u64,
+        opening: Coordinate,
+    },
+    Progress {
+        name: String,
+        /// Percentage of the safe squares revealed
+        percent: f32,
+        finish: Option<Finish>,
+    },
+}
+
+/// Everything is sent as one JSON object per line
+pub fn encode_line(message: &impl Serialize) -> Result<String> {
+    Ok(serde_json::to_string(message)? + "\n")
+}
+
+/// The lines read from `stream` until it closes, fails or sends a line longer than
+/// `MAX_LINE_LENGTH`
+pub fn read_lines(stream: impl Read) -> impl Iterator<Item = String> {
+    let mut reader = BufReader::new(stream);
+    std::iter::from_fn(move || {
+        let mut line = Vec::new();
+        let read = (&mut reader)
+            .take(MAX_LINE_LENGTH as u64 + 1)
+            .read_until(b'\n', &mut line)
+            .ok()?;
+        if read == 0 {
+            return None;
+        }
+        if line.last() == Some(&b'\n') {
+            line.pop();
+        } else if line.len() > MAX_LINE_LENGTH {
+            log::warn!(
+                "Closing a connection that sent a line over {} bytes",
+                MAX_LINE_LENGTH
+            );
+            return None;
+        }
+        if line.last() == Some(&b'\r') {
+            line.pop();
+        }
+        String::from_utf8(line).ok()
+    })
+}
+
+impl Message {
+    fn encode(&self) -> Result<String> {
+        encode_line(self)
+    }
+
+    fn decode(line: &str) -> Result<Self> {
+        Ok(serde_json::from_str(line)?)
+    }
+
+    /// Whether the host takes the message from a guest that joined as `joined_as`. Only the
+    /// host starts races, guests only speak for themselves, and nobody joins twice.
+    fn allowed_from_guest(&self, joined_as: Option<&str>) -> bool {
+        match self {
+            Message::Join { .. } => joined_as.is_none(),
+            Message::Start { .. } => false,
+            Message::Leave { name } | Message::Progress { name, .. } => {
+                joined_as == Some(name.as_str())
+            }
+        }
+    }
+}
+
+/// Another player in the race
+#[derive(Clone, PartialEq)]
+pub struct Opponent {
+    pub name: String,
+    pub percent: f32,
+    pub finish: Option<Finish>,
+}
+
+impl Opponent {
+    /// Short summary for the status bar
+    pub fn status(&self) -> String {
+        match self.finish {
+            Some(f) if f.won => format!("{}: {:.2}s", self.name, f.time),
+            Some(_) => format!("{}: 💥 {:.0}%", self.name, self.percent),
+            None => format!("{}: {:.0}%", self.name, self.percent),
+        }
+    }
+}
+
+/// Open connections by id, each written to by a thread of its own so that one that stops
+/// reading can't hold up the others, or whoever is sending
+#[derive(Default)]
+pub struct Peers {
+    peers: Vec<Peer>,
+    next_id: usize,
+}
+
+struct Peer {
+    id: usize,
+    stream: TcpStream,
+    queue: SyncSender<String>,
+}
+
+impl Peers {
+    pub fn add(&mut self, stream: &TcpStream) -> Result<usize> {
+        let mut writer = stream.try_clone()?;
+        writer.set_write_timeout(Some(WRITE_TIMEOUT))?;
+        let (queue, lines) = mpsc::sync_channel::<String>(MAX_QUEUED_LINES);
+        thread::spawn(move || {
+            for line in lines {
+                if writer.write_all(line.as_bytes()).is_err() {
+                    // Its reader thread sees the connection close and drops it
+                    let _ = writer.shutdown(Shutdown::Both);
+                    return;
+                }
+            }
+        });
+
+        let id = self.next_id;
+        self.next_id += 1;
+        self.peers.push(Peer {
+            id,
+            stream: stream.try_clone()?,
+            queue,
+        });
+        Ok(id)
+    }
+
+    pub fn remove(&mut self, id: usize) {
+        self.peers.retain(|p| p.id != id);
+    }
+
+    pub fn len(&self) -> usize {
+        self.peers.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.peers.is_empty()
+    }
+
+    /// Sends a line to one connection
+    pub fn send(&mut self, id: usize, line: &str) {
+        self.send_where(line, |i| i == id);
+    }
+
+    /// Sends a line to every connection other than `except`
+    pub fn broadcast(&mut self, line: &str, except: Option<usize>) {
+        self.send_where(line, |id| Some(id) != except);
+    }
+
+    /// Queues a line for each connection `to` picks, without waiting on any of them. One
+    /// whose queue is full has stopped reading, and is closed and dropped.
+    fn send_where(&mut self, line: &str, to: impl Fn(usize) -> bool) {
+        self.peers.retain(|p| {
+            if !to(p.id) || p.queue.try_send(line.to_string()).is_ok() {
+                return true;
+            }
+            log::warn!("Dropping a connection that isn't keeping up");
+            let _ = p.stream.shutdown(Shutdown::Both);
+            false
+        });
+    }
+
+    /// Closes every connection, which ends their reader threads
+    pub fn shutdown(&self) {
+        self.peers.iter().for_each(|p| {
+            let _ = p.stream.shutdown(Shutdown::Both);
+        });
+    }
+}
+
+/// Runs `on_close` once the last copy of the session holding it is dropped, so that copies
+/// of a session share its connections
+pub struct Closer {
+    pub closed: Arc<AtomicBool>,
+    on_close: Box<dyn Fn() + Send + Sync>,
+}
+
+impl Closer {
+    pub fn new(on_close: impl Fn() + Send + Sync + 'static) -> Self {
+        Closer {
+            closed: Arc::new(AtomicBool::new(false)),
+            on_close: Box::new(on_close),
+        }
+    }
+}
+
+impl Drop for Closer {
+    fn drop(&mut self) {
+        self.closed.store(true, Ordering::Relaxed);
+        (self.on_close)();
+    }
+}
+
+/// Listens for connections on `port` in the background until `closed` is set, handing each
+/// to `on_accept`. `on_error` gets the error that stopped it, if any.
+pub fn accept_connections(
+    port: u16,
+    closed: Arc<AtomicBool>,
+    mut on_accept: impl FnMut(TcpStream) -> Result<()> + Send + 'static,
+    on_error: impl FnOnce(std::io::Error) + Send + 'static,
+) -> Result<()> {
+    let listener = TcpListener::bind(("0.0.0.0", port))?;
+    listener.set_nonblocking(true)?;
+    thread::spawn(move || {
+        while !closed.load(Ordering::Relaxed) {
+            match listener.accept() {
+                Ok((stream, _)) => {
+                    if let Err(e) = stream
+                        .set_nonblocking(false)
+                        .map_err(anyhow::Error::from)
+                        .and_then(|_| on_accept(stream))
+                    {
+                        log::warn!("Failed to accept connection: {}", e);
+                    }
+                }
+                Err(e) if e.kind() == std::io::ErrorKind::WouldBlock => {
+                    thread::sleep(ACCEPT_INTERVAL)
+                }
+                Err(e) => {
+                    on_error(e);
+                    return;
+                }
+            }
+        }
+    });
+    Ok(())
+}
+
+/// Connects to `address`, which takes `default_port` if it doesn't include one. Blocks for
+/// up to a few seconds, so call it from a background thread.
+pub fn connect(address: &str, default_port: u16) -> Result<TcpStream> {
+    let address = with_default_port(address, default_port);
+    let addr = address
+        .to_socket_addrs()?
+        .next()
+        .ok_or_else(|| anyhow!("No address found for {}", address))?;
+    Ok(TcpStream::connect_timeout(&addr, CONNECT_TIMEOUT)?)
+}
+
+/// State shared with the connection threads
+#[derive(Default)]
+struct Shared {
+    peers: Peers,
+    inbox: Vec<Message>,
+    /// Everyone who has joined, so the host can introduce them to players joining later
+    names: Vec<String>,
+    error: Option<String>,
+}
+
+/// A race over the network. One player hosts and the others join them. Requests don't block
+/// the UI; messages from other players are picked up by `poll()`.
+#[derive(Clone)]
+pub struct NetSession {
+    pub name: String,
+    pub is_host: bool,
+    /// True from `begin_race()` until this player finishes the race
+    racing: bool,
+    shared: Arc<Mutex<Shared>>,
+    closer: Arc<Closer>,
+    opponents: Vec<Opponent>,
+    last_sent: Option<(f32, Option<Finish>)>,
+}
+
+/// Adds the default port to an address without one
+fn with_default_port(address: &str, default_port: u16) -> String {
+    let address = address.trim();
+    if address.contains(':') {
+        address.to_string()
+    } else {
+        format!("{}:{}", address, default_port)
+    }
+}
+
+impl NetSession {
+    fn new(name: &str, is_host: bool) -> Self {
+        let shared = Arc::new(Mutex::new(Shared::default()));
+        let closing = shared.clone();
+        NetSession {
+            name: name.to_string(),
+            is_host,
+            racing: false,
+            shared,
+            closer: Arc::new(Closer::new(move || {
+                if let Ok(s) = closing.lock() {
+                    s.peers.shutdown();
+                }
+            })),
+            opponents: vec![],
+            last_sent: None,
+        }
+    }
+
+    /// Waits for other players to join on `port`
+    pub fn host(ctx: &egui::Context, name: &str, port: u16) -> Result<Self> {
+        let session = NetSession::new(name, true);
+        if let Ok(mut shared) = session.shared.lock() {
+            shared.names.push(name.to_string());
+        }
+
+        let (shared, ctx) = (session.shared.clone(), ctx.clone());
+        let (on_error_shared, on_error_ctx) = (shared.clone(), ctx.clone());
+        accept_connections(
+            port,
+            session.closer.closed.clone(),
+            move |stream| NetSession::welcome(&shared, &ctx, stream),
+            move |e| {
+                if let Ok(mut s) = on_error_shared.lock() {
+                    s.error = Some(format!("Stopped accepting players: {}", e));
+                }
+                on_error_ctx.request_repaint();
+            },
+        )?;
+        Ok(session)
+    }
+
+    /// Introduces a newly connected player to everyone already in the lobby
+    fn welcome(shared: &Arc<Mutex<Shared>>, ctx: &egui::Context, stream: TcpStream) -> Result<()> {
+        let id = {
+            let mut s = shared
+                .lock()
+                .map_err(|_| anyhow!("Connection state poisoned"))?;
+            let id = s.peers.add(&stream)?;
+            let lines = s
+                .names
+                .iter()
+                .map(|name| Message::Join { name: name.clone() }.encode())
+                .collect::<Result<String>>()?;
+            s.peers.send(id, &lines);
+            id
+        };
+        NetSession::read(shared.clone(), ctx.clone(), id, stream, true);
+        Ok(())
+    }
+
+    /// Connects to the host at `address`, which takes the default port if it doesn't
+    /// include one
+    pub fn join(ctx: &egui::Context, name: &str, address: &str) -> Self {
+        let session = NetSession::new(name, false);
+        let shared = session.shared.clone();
+        let ctx = ctx.clone();
+        let name = name.to_string();
+        let address = address.to_string();
+        thread::spawn(move || {
+            let connect = || -> Result<(usize, TcpStream)> {
+                let mut stream = connect(&address, DEFAULT_NET_PORT)?;
+                stream.write_all(Message::Join { name }.encode()?.as_bytes())?;
+                let id = shared
+                    .lock()
+                    .map_err(|_| anyhow!("Connection state poisoned"))?
+                    .peers
+                    .add(&stream)?;
+                Ok((id, stream))
+            };
+            match connect() {
+                Ok((id, stream)) => NetSession::read(shared, ctx, id, stream, false),
+                Err(e) => {
+                    if let Ok(mut s) = shared.lock() {
+                        s.error = Some(format!("Unable to join {}: {}", address, e));
+                    }
+                    ctx.request_repaint();
+                }
+            }
+        });
+        session
+    }
+
+    /// Reads messages from a connection until it closes. The host relays them to the
+    /// other players, once it's checked the guest sending them is allowed to.
+    fn read(
+        shared: Arc<Mutex<Shared>>,
+        ctx: egui::Context,
+        id: usize,
+        stream: TcpStream,
+        relay: bool,
+    ) {
+        thread::spawn(move || {
+            let mut joined_as: Option<String> = None;
+            for line in read_lines(stream) {
+                let Ok(message) = Message::decode(&line) else {
+                    log::warn!("Ignoring invalid message: {}", line);
+                    continue;
+                };
+                if relay && !message.allowed_from_guest(joined_as.as_deref()) {
+                    log::warn!("Ignoring a message the guest can't send: {}", line);
+                    continue;
+                }
+                let Ok(mut s) = shared.lock() else {
+                    return;
+                };
+                if let Message::Join { name } = &message {
+                    if relay {
+                        // Nobody can join as a player who's already here
+                        if s.names.contains(name) {
+                            log::warn!("Ignoring a second player joining as {}", name);
+                            continue;
+                        }
+                        s.names.push(name.clone());
+                    }
+                    joined_as = Some(name.clone());
+                }
+                if relay {
+                    s.peers.broadcast(&(line + "\n"), Some(id));
+                }
+                s.inbox.push(message);
+                ctx.request_repaint();
+            }
+
+            let Ok(mut s) = shared.lock() else {
+                return;
+            };
+            s.peers.remove(id);
+            if relay {
+                // Only the player who dropped leaves; the host knows who they joined as
+                if let Some(name) = joined_as {
+                    s.names.retain(|n| *n != name);
+                    let leave = Message::Leave { name };
+                    if let Ok(line) = leave.encode() {
+                        s.peers.broadcast(&line, None);
+                    }
+                    s.inbox.push(leave);
+                }
+            } else {
+                s.error = Some("Lost the connection to the host".to_string());
+            }
+            ctx.request_repaint();
+        });
+    }
+
+    /// The other players, in the order they joined
+    pub fn opponents(&self) -> &[Opponent] {
+        &self.opponents
+    }
+
+    pub fn last_error(&self) -> Option<String> {
+        self.shared.lock().ok().and_then(|s| s.error.clone())
+    }
+
+    /// True once connected to the host, and always for the host
+    pub fn is_connected(&self) -> bool {
+        self.is_host || self.shared.lock().is_ok_and(|s| !s.peers.is_empty())
+    }
+
+    fn send(&self, message: &Message) {
+        let Ok(line) = message.encode() else {
+            return;
+        };
+        if let Ok(mut s) = self.shared.lock() {
+            s.peers.broadcast(&line, None);
+        }
+    }
+
+    /// Starts a race for everyone. Only the host can start races.
+    pub fn start_race(&mut self, difficulty: GameDifficulty, seed: u64, opening: Coordinate) {
+        let start = Message::Start {
+            difficulty,
+            seed,
+            opening,
+        };
+        self.send(&start);
+        self.apply(start);
+        self.racing = false;
+    }
+
+    /// Call once the board for a race started by `poll()` or `start_race()` is ready
+    pub fn begin_race(&mut self) {
+        self.racing = true;
+        self.last_sent = None;
+    }
+
+    pub fn is_racing(&self) -> bool {
+        self.racing
+    }
+
+    /// Lets the other players know how far through the race this player is. Repeats of the
+    /// last update aren't sent.
+    pub fn send_progress(&mut self, percent: f32, finish: Option<Finish>) {
+        if !self.racing || self.last_sent == Some((percent, finish)) {
+            return;
+        }
+        self.last_sent = Some((percent, finish));
+        self.send(&Message::Progress {
+            name: self.name.clone(),
+            percent,
+            finish,
+        });
+        if finish.is_some() {
+            self.racing = false;
+        }
+    }
+
+    /// Applies the messages received since the last call. Call once per frame. Returns the
+    /// race to start, if one was started.
+    pub fn poll(&mut self) -> Option<(GameDifficulty, u64, Coordinate)> {
+        let inbox = match self.shared.lock() {
+            Ok(mut s) => std::mem::take(&mut s.inbox),
+            Err(_) => return None,
+        };
+        inbox.into_iter().filter_map(|m| self.apply(m)).last()
+    }
+
+    fn apply(&mut self, message: Message) -> Option<(GameDifficulty, u64, Coordinate)> {
+        match message {
+            Message::Join { name } => {
+                if name != self.name && !self.opponents.iter().any(|o| o.name == name) {
+                    self.opponents.push(Opponent {
+                        name,
+                        percent: 0.0,
+                        finish: None,
+                    });
+                }
+            }
+            Message::Leave { name } => self.opponents.retain(|o| o.name != name),
+            Message::Progress {
+                name,
+                percent,
+                finish,
+            } => {
+                if let Some(o) = self.opponents.iter_mut().find(|o| o.name == name) {
+                    o.percent = percent;
+                    o.finish = finish;
+                }
+            }
+            Message::Start {
+                difficulty,
+                seed,
+                opening,
+            } => {
+                self.opponents.iter_mut().for_each(|o| {
+                    o.percent = 0.0;
+                    o.finish = None;
+                });
+                return Some((difficulty, seed, opening));
+            }
+        }
+        None
+    }
+}
+
+#[test]
+fn test_net_session_messages() -> Result<()> {
+    let progress = Message::Progress {
+        name: "Player 2".to_string(),
+        percent: 50.0,
+        finish: Some(Finish {
+            won: true,
+            time: 12.5,
+        }),
+    };
+    assert!(Message::decode(progress.encode()?.trim_end())? == progress);
+    assert_eq!(with_default_port(" example.com ", 7878), "example.com:7878");
+    assert_eq!(with_default_port("10.0.0.1:9000", 7878), "10.0.0.1:9000");
+
+    let mut session = NetSession::new("Player 1", true);
+    session.apply(Message::Join {
+        name: "Player 1".to_string(),
+    });
+    session.apply(Message::Join {
+        name: "Player 2".to_string(),
+    });
+    session.apply(progress);
+    assert_eq!(session.opponents().len(), 1);
+    assert_eq!(session.opponents()[0].status(), "Player 2: 12.50s");
+
+    let start = session.apply(Message::Start {
+        difficulty: GameDifficulty::Beginner,
+        seed: 42,
+        opening: Coordinate::from((4, 4)),
+    });
+    assert!(start.is_some_and(|(_, seed, _)| seed == 42));
+    assert_eq!(session.opponents()[0].status(), "Player 2: 0%");
+
+    session.apply(Message::Leave {
+        name: "Player 2".to_string(),
+    });
+    assert!(session.opponents().is_empty());
+
+    // Guests can only join once, speak for themselves, and never start a race
+    let join = Message::Join {
+        name: "Player 2".to_string(),
+    };
+    assert!(join.allowed_from_guest(None));
+    assert!(!join.allowed_from_guest(Some("Player 2")));
+    let leave = Message::Leave {
+        name: "Player 1".to_string(),
+    };
+    assert!(!leave.allowed_from_guest(Some("Player 2")));
+    assert!(leave.allowed_from_guest(Some("Player 1")));
+    let start = Message::Start {
+        difficulty: GameDifficulty::Beginner,
+        seed: 42,
+        opening: Coordinate::from((4, 4)),
+    };
+    assert!(!start.allowed_from_guest(Some("Player 2")));
+
+    // A line that never ends closes the connection
+    let lines: Vec<String> = read_lines("one\r\ntwo\nthree".as_bytes()).collect();
+    assert_eq!(lines, ["one", "two", "three"]);
+    let long = format!("short\n{}", "x".repeat(MAX_LINE_LENGTH + 1));
+    assert_eq!(read_lines(long.as_bytes()).collect::<Vec<_>>(), ["short"]);
+    Ok(())
+}
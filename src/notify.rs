@@ -0,0 +1,52 @@
+/// Whether notifications can be shown in this build: natively with the `notifications`
+/// feature, and always on the web
+pub const SUPPORTED: bool = cfg!(any(feature = "notifications", target_arch = "wasm32"));
+
+/// Shows a desktop notification, or a browser one on the web. A notification that can't be
+/// shown is only logged, as it's never worth interrupting the game over.
+pub fn notify(title: &str, body: &str) {
+    if let Err(e) = show(title, body) {
+        log::warn!("Failed to show notification: {}", e);
+    }
+}
+
+/// Asks the browser for permission to show notifications, if it hasn't been given or refused
+/// yet. Desktops don't ask.
+pub fn request_permission() {
+    #[cfg(target_arch = "wasm32")]
+    if web_sys::Notification::permission() == web_sys::NotificationPermission::Default {
+        let _ = web_sys::Notification::request_permission();
+    }
+}
+
+#[cfg(all(feature = "notifications", not(target_arch = "wasm32")))]
+fn show(title: &str, body: &str) -> Result<(), String> {
+    notify_rust::Notification::new()
+        .appname("Mines of Rust")
+        .summary(title)
+        .body(body)
+        .show()
+        .map(|_| ())
+        .map_err(|e| e.to_string())
+}
+
+#[cfg(target_arch = "wasm32")]
+fn show(title: &str, body: &str) -> Result<(), String> {
+    use web_sys::{Notification, NotificationOptions, NotificationPermission};
+
+    // Without permission the browser drops the notification anyway
+    if Notification::permission() != NotificationPermission::Granted {
+        return Ok(());
+    }
+    let options = NotificationOptions::new();
+    options.set_body(body);
+    Notification::new_with_options(title, &options)
+        .map(|_| ())
+        .map_err(|e| format!("{:?}", e))
+}
+
+#[cfg(not(any(feature = "notifications", target_arch = "wasm32")))]
+fn show(title: &str, body: &str) -> Result<(), String> {
+    log::info!("Notification: {}: {}", title, body);
+    Ok(())
+}
@@ -0,0 +1,279 @@
+use std::sync::{Arc, Mutex};
+
+use anyhow::Result;
+use chrono::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::config::{self, Versioned};
+use crate::enums::GameDifficulty;
+use crate::leader::{as_df_date, as_hex_seed, Entry};
+
+const QUEUE_FILE_NAME: &str = "minesofrust-online-queue.toml";
+
+/// A winning time to be posted to the global leaderboard
+#[derive(Clone, Deserialize, Serialize)]
+pub struct Submission {
+    pub player_name: String,
+    pub difficulty: GameDifficulty,
+    pub time: f64,
+
+    #[serde(with = "as_hex_seed")]
+    pub seed: u64,
+
+    #[serde(with = "as_df_date")]
+    pub date: DateTime<FixedOffset>,
+
+    /// Signature of the matching local leaderboard entry
+    pub signature: String,
+}
+
+impl Submission {
+    pub fn from_entry(difficulty: GameDifficulty, entry: &Entry) -> Self {
+        Submission {
+            player_name: entry.player_name.clone(),
+            difficulty,
+            time: entry.time,
+            seed: entry.seed,
+            date: entry.date,
+            signature: entry.signature.clone(),
+        }
+    }
+}
+
+/// An entry on the global leaderboard as returned by the server
+#[derive(Clone, Deserialize, Serialize)]
+pub struct GlobalEntry {
+    pub player_name: String,
+    pub time: f64,
+
+    #[serde(with = "as_df_date")]
+    pub date: DateTime<FixedOffset>,
+}
+
+/// Submissions that haven't reached the server yet, kept on disk so that wins recorded
+/// while offline are sent the next time the server can be reached
+#[derive(Clone, Deserialize, Serialize)]
+pub struct SubmissionQueue {
+    #[serde(default)]
+    pub version: i64,
+    #[serde(default)]
+    pub pending: Vec<Submission>,
+}
+
+impl Default for SubmissionQueue {
+    fn default() -> Self {
+        Self {
+            version: <SubmissionQueue as Versioned>::VERSION,
+            pending: vec![],
+        }
+    }
+}
+
+impl Versioned for SubmissionQueue {
+    // 1: Initial version
+    const VERSION: i64 = 1;
+}
+
+impl SubmissionQueue {
+    pub fn load_from_userhome() -> Result<Option<Self>> {
+        config::load_toml(QUEUE_FILE_NAME)
+    }
+
+    pub fn save_to_userhome(&self) -> Result<()> {
+        config::save_toml(self, QUEUE_FILE_NAME)
+    }
+}
+
+/// Responses from requests running in the background
+enum Reply {
+    Submitted(Result<(), String>),
+    Fetched(Result<(GameDifficulty, Vec<GlobalEntry>), String>),
+}
+
+/// Client for the global leaderboard server.
+///
+/// Expects the server to accept a JSON `Submission` as `POST {endpoint}/scores` and return
+/// a JSON array of `GlobalEntry`, fastest first, from `GET {endpoint}/scores?difficulty=...`.
+/// Requests don't block the UI; their results are picked up by `poll()`.
+#[derive(Clone, Default)]
+pub struct OnlineLeaderboard {
+    queue: SubmissionQueue,
+    replies: Arc<Mutex<Vec<Reply>>>,
+    submitting: bool,
+    fetching: bool,
+    global: Option<(GameDifficulty, Vec<GlobalEntry>)>,
+    last_error: Option<String>,
+}
+
+fn scores_url(endpoint: &str) -> String {
+    format!("{}/scores", endpoint.trim().trim_end_matches('/'))
+}
+
+impl OnlineLeaderboard {
+    pub fn new(queue: SubmissionQueue) -> Self {
+        OnlineLeaderboard {
+            queue,
+            ..Default::default()
+        }
+    }
+
+    pub fn num_pending(&self) -> usize {
+        self.queue.pending.len()
+    }
+
+    pub fn is_busy(&self) -> bool {
+        self.submitting || self.fetching
+    }
+
+    pub fn last_error(&self) -> Option<&str> {
+        self.last_error.as_deref()
+    }
+
+    /// Global entries for `difficulty`, if they've been fetched
+    pub fn global_entries(&self, difficulty: &GameDifficulty) -> Option<&[GlobalEntry]> {
+        match &self.global {
+            Some((d, entries)) if d == difficulty => Some(entries),
+            _ => None,
+        }
+    }
+
+    pub fn save_to_userhome(&self) -> Result<()> {
+        self.queue.save_to_userhome()
+    }
+
+    /// Queues a winning time and tries to send everything waiting in the queue
+    pub fn submit(&mut self, ctx: &egui::Context, endpoint: &str, submission: Submission) {
+        self.queue.pending.push(submission);
+        self.flush(ctx, endpoint);
+    }
+
+    /// Sends the oldest queued submission. The rest follow one at a time as each is
+    /// accepted, so the queue order is kept and nothing is lost if the network drops midway.
+    pub fn flush(&mut self, ctx: &egui::Context, endpoint: &str) {
+        if self.submitting || endpoint.trim().is_empty() {
+            return;
+        }
+        let Some(submission) = self.queue.pending.first() else {
+            return;
+        };
+        let request = match ehttp::Request::json(scores_url(endpoint), submission) {
+            Ok(r) => r,
+            Err(e) => {
+                self.last_error = Some(format!("Failed to encode submission: {}", e));
+                return;
+            }
+        };
+
+        self.submitting = true;
+        self.send(ctx, request, |result| {
+            Reply::Submitted(result.and_then(|response| {
+                if response.ok {
+                    Ok(())
+                } else {
+                    Err(format!(
+                        "Server rejected submission: {} {}",
+                        response.status, response.status_text
+                    ))
+                }
+            }))
+        });
+    }
+
+    /// Requests the global leaderboard for a difficulty level
+    pub fn fetch(&mut self, ctx: &egui::Context, endpoint: &str, difficulty: GameDifficulty) {
+        if self.fetching || endpoint.trim().is_empty() {
+            return;
+        }
+        let request = ehttp::Request::get(format!(
+            "{}?difficulty={}",
+            scores_url(endpoint),
+            difficulty.as_str()
+        ));
+
+        self.fetching = true;
+        self.send(ctx, request, move |result| {
+            Reply::Fetched(result.and_then(|response| {
+                if !response.ok {
+                    return Err(format!(
+                        "Failed to fetch global leaderboard: {} {}",
+                        response.status, response.status_text
+                    ));
+                }
+                response
+                    .json::<Vec<GlobalEntry>>()
+                    .map(|entries| (difficulty, entries))
+                    .map_err(|e| format!("Invalid global leaderboard: {}", e))
+            }))
+        });
+    }
+
+    fn send(
+        &self,
+        ctx: &egui::Context,
+        request: ehttp::Request,
+        on_done: impl 'static + Send + FnOnce(Result<ehttp::Response, String>) -> Reply,
+    ) {
+        let replies = self.replies.clone();
+        let ctx = ctx.clone();
+        ehttp::fetch(request, move |result| {
+            let reply =
+                on_done(result.map_err(|e| format!("Unable to reach leaderboard server: {}", e)));
+            if let Ok(mut r) = replies.lock() {
+                r.push(reply);
+            }
+            ctx.request_repaint();
+        });
+    }
+
+    /// Applies the results of any finished requests. Call once per frame.
+    pub fn poll(&mut self, ctx: &egui::Context, endpoint: &str) {
+        let replies = match self.replies.lock() {
+            Ok(mut r) => std::mem::take(&mut *r),
+            Err(_) => return,
+        };
+        for reply in replies {
+            let result = match reply {
+                Reply::Submitted(result) => {
+                    self.submitting = false;
+                    result.map(|_| {
+                        if !self.queue.pending.is_empty() {
+                            self.queue.pending.remove(0);
+                        }
+                        self.flush(ctx, endpoint);
+                    })
+                }
+                Reply::Fetched(result) => {
+                    self.fetching = false;
+                    result.map(|global| self.global = Some(global))
+                }
+            };
+            // Submissions that failed stay queued until the next flush
+            match result {
+                Ok(_) => self.last_error = None,
+                Err(e) => {
+                    log::warn!("{}", e);
+                    self.last_error = Some(e);
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn test_submission_queue_round_trip() -> Result<()> {
+    let entry = Entry::new("Player 1", 12.345, u64::MAX, 0, 0);
+    let mut queue = SubmissionQueue::default();
+    queue
+        .pending
+        .push(Submission::from_entry(GameDifficulty::Expert, &entry));
+
+    let reloaded: SubmissionQueue = config::parse_versioned(&toml::to_string(&queue)?)?;
+    assert_eq!(reloaded.pending.len(), 1);
+    assert_eq!(reloaded.pending[0].seed, u64::MAX);
+    assert_eq!(reloaded.pending[0].signature, entry.signature);
+    assert_eq!(
+        scores_url("http://example.com/api/ "),
+        "http://example.com/api/scores"
+    );
+    Ok(())
+}
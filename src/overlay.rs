@@ -0,0 +1,110 @@
+use std::fs;
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use serde::Serialize;
+
+use crate::config;
+
+const OVERLAY_JSON_FILE_NAME: &str = "minesofrust-overlay.json";
+const OVERLAY_TEXT_FILE_NAME: &str = "minesofrust-overlay.txt";
+
+/// The game as a streaming overlay shows it, written where an OBS text source can read it
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct OverlayStats {
+    pub state: String,
+    /// Seconds on the clock
+    pub time: f64,
+    /// Mines less flags placed. Negative if there are more flags than mines.
+    pub mines_left: i64,
+    pub bbbv_solved: u32,
+    pub bbbv: u32,
+    /// Seconds behind the personal best, or ahead if negative. `None` without one.
+    pub best_delta: Option<f64>,
+}
+
+impl OverlayStats {
+    /// How much of the 3BV has been cleared, as a percentage
+    pub fn progress(&self) -> f64 {
+        if self.bbbv == 0 {
+            return 0.0;
+        }
+        self.bbbv_solved as f64 / self.bbbv as f64 * 100.0
+    }
+
+    /// One stat to a line, for a text source to show as it is
+    pub fn to_text(&self) -> String {
+        let mut text = format!(
+            "{}\nTime: {:.2}\nMines left: {}\n3BV: {}/{} ({:.0}%)\n",
+            self.state,
+            self.time,
+            self.mines_left,
+            self.bbbv_solved,
+            self.bbbv,
+            self.progress()
+        );
+        if let Some(delta) = self.best_delta {
+            text += &format!("PB: {:+.2}\n", delta);
+        }
+        text
+    }
+
+    /// Writes the stats to the config directory, as JSON and as text
+    pub fn save(&self) -> Result<()> {
+        let config_dir = config::config_dir()?;
+        fs::create_dir_all(&config_dir)?;
+        self.save_to(&config_dir)
+    }
+
+    fn save_to(&self, dir: &Path) -> Result<()> {
+        [
+            (OVERLAY_JSON_FILE_NAME, serde_json::to_string_pretty(self)?),
+            (OVERLAY_TEXT_FILE_NAME, self.to_text()),
+        ]
+        .into_iter()
+        .try_for_each(|(file_name, contents)| {
+            // Written whole and then moved into place, so OBS never reads half a file
+            let path = dir.join(file_name);
+            let temp_path = path.with_extension("tmp");
+            fs::write(&temp_path, contents)
+                .and_then(|_| fs::rename(&temp_path, &path))
+                .with_context(|| format!("Failed to write {:?}", path))
+        })
+    }
+}
+
+#[test]
+fn test_overlay_stats() -> Result<()> {
+    let stats = OverlayStats {
+        state: "Playing".to_string(),
+        time: 12.345,
+        mines_left: 7,
+        bbbv_solved: 30,
+        bbbv: 120,
+        best_delta: Some(-1.5),
+    };
+    assert_eq!(
+        stats.to_text(),
+        "Playing\nTime: 12.35\nMines left: 7\n3BV: 30/120 (25%)\nPB: -1.50\n"
+    );
+    assert!(!OverlayStats {
+        best_delta: None,
+        ..stats.clone()
+    }
+    .to_text()
+    .contains("PB"));
+
+    let dir = std::env::temp_dir().join(format!("minesofrust-overlay-{}", std::process::id()));
+    fs::create_dir_all(&dir)?;
+    stats.save_to(&dir)?;
+    let json: serde_json::Value =
+        serde_json::from_str(&fs::read_to_string(dir.join(OVERLAY_JSON_FILE_NAME))?)?;
+    assert_eq!(json["bbbv_solved"], 30);
+    assert_eq!(json["best_delta"], -1.5);
+    assert_eq!(
+        fs::read_to_string(dir.join(OVERLAY_TEXT_FILE_NAME))?,
+        stats.to_text()
+    );
+    fs::remove_dir_all(&dir)?;
+    Ok(())
+}
@@ -0,0 +1,328 @@
+use std::str::FromStr;
+
+use anyhow::anyhow;
+use itertools::iproduct;
+use rand::distributions::WeightedIndex;
+use rand::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::i18n::tr;
+use crate::minesweeper::{Coordinate, Error};
+
+/// How likely the emptiest squares of a gradient board are to get a mine, next to the
+/// fullest
+const MIN_GRADIENT_WEIGHT: f64 = 0.1;
+
+/// How much more likely a square gets a mine for each mine already next to it on a
+/// clustered board
+const CLUSTER_WEIGHT: f64 = 10.0;
+
+/// Picks the squares a board's mines go on. Implement it to control how mines are spread,
+/// and hand it to `GameBoard::populate_mines_with`.
+pub trait MinePlacementStrategy {
+    /// Picks `num_mines` of the `eligible` squares of a `width` by `height` board, given by
+    /// their index in the board's row by row order, drawing any randomness from `rng`
+    fn place(
+        &self,
+        rng: &mut StdRng,
+        width: u32,
+        height: u32,
+        eligible: &[u32],
+        num_mines: u32,
+    ) -> Result<Vec<u32>, Error>;
+}
+
+/// Every square is as likely as any other to get a mine
+pub struct UniformPlacement;
+
+impl MinePlacementStrategy for UniformPlacement {
+    fn place(
+        &self,
+        rng: &mut StdRng,
+        _width: u32,
+        _height: u32,
+        eligible: &[u32],
+        num_mines: u32,
+    ) -> Result<Vec<u32>, Error> {
+        Ok(
+            rand::seq::index::sample(rng, eligible.len(), num_mines as usize)
+                .into_iter()
+                .map(|i| eligible[i])
+                .collect(),
+        )
+    }
+}
+
+/// Picks squares without replacement, each as likely to be picked as `weight` says for its
+/// column and row
+fn place_weighted(
+    rng: &mut StdRng,
+    width: u32,
+    height: u32,
+    eligible: &[u32],
+    num_mines: u32,
+    weight: impl Fn(u32, u32) -> f64,
+) -> Result<Vec<u32>, Error> {
+    Ok(rand::seq::index::sample_weighted(
+        rng,
+        eligible.len(),
+        |i| weight(eligible[i] % width, eligible[i] / width),
+        num_mines as usize,
+    )
+    .map_err(|_| Error::ExcessiveMines {
+        num_mines,
+        width,
+        height,
+    })?
+    .into_iter()
+    .map(|i| eligible[i])
+    .collect())
+}
+
+/// Scales `density`, from 0 to 1, to a weight that still leaves the emptiest squares a
+/// chance
+fn gradient_weight(density: f64) -> f64 {
+    MIN_GRADIENT_WEIGHT + (1.0 - MIN_GRADIENT_WEIGHT) * density
+}
+
+/// Mines get denser from the left edge to the right, for an easy start and a hard finish
+pub struct GradientPlacement;
+
+impl MinePlacementStrategy for GradientPlacement {
+    fn place(
+        &self,
+        rng: &mut StdRng,
+        width: u32,
+        height: u32,
+        eligible: &[u32],
+        num_mines: u32,
+    ) -> Result<Vec<u32>, Error> {
+        place_weighted(rng, width, height, eligible, num_mines, |x, _| {
+            gradient_weight(x as f64 / (width.max(2) - 1) as f64)
+        })
+    }
+}
+
+/// Mines get denser from the middle out to the edges
+pub struct RadialPlacement;
+
+impl MinePlacementStrategy for RadialPlacement {
+    fn place(
+        &self,
+        rng: &mut StdRng,
+        width: u32,
+        height: u32,
+        eligible: &[u32],
+        num_mines: u32,
+    ) -> Result<Vec<u32>, Error> {
+        let center_x = (width as f64 - 1.0) / 2.0;
+        let center_y = (height as f64 - 1.0) / 2.0;
+        let corner = center_x.hypot(center_y).max(1.0);
+        place_weighted(rng, width, height, eligible, num_mines, |x, y| {
+            gradient_weight((x as f64 - center_x).hypot(y as f64 - center_y) / corner)
+        })
+    }
+}
+
+/// Mines clump together, each one making the squares around it more likely to get one too
+pub struct ClusteredPlacement;
+
+impl MinePlacementStrategy for ClusteredPlacement {
+    fn place(
+        &self,
+        rng: &mut StdRng,
+        width: u32,
+        height: u32,
+        eligible: &[u32],
+        num_mines: u32,
+    ) -> Result<Vec<u32>, Error> {
+        // Where each square of the board is in `eligible`, to find a mine's neighbors
+        let mut position = vec![None; (width * height) as usize];
+        eligible
+            .iter()
+            .enumerate()
+            .for_each(|(i, &idx)| position[idx as usize] = Some(i));
+
+        let mut weights = vec![1.0; eligible.len()];
+        let mut chosen = Vec::with_capacity(num_mines as usize);
+        for _ in 0..num_mines {
+            let i = WeightedIndex::new(&weights)
+                .map_err(|_| Error::ExcessiveMines {
+                    num_mines,
+                    width,
+                    height,
+                })?
+                .sample(rng);
+            weights[i] = 0.0;
+            chosen.push(eligible[i]);
+
+            let (x, y) = ((eligible[i] % width) as i64, (eligible[i] / width) as i64);
+            iproduct!(x - 1..=x + 1, y - 1..=y + 1)
+                .filter(|&(nx, ny)| nx >= 0 && ny >= 0 && nx < width as i64 && ny < height as i64)
+                .filter_map(|(nx, ny)| position[(ny * width as i64 + nx) as usize])
+                .for_each(|j| {
+                    if weights[j] > 0.0 {
+                        weights[j] += CLUSTER_WEIGHT;
+                    }
+                });
+        }
+        Ok(chosen)
+    }
+}
+
+/// Mines go on exactly the given squares, whatever the number asked for, so tests and
+/// hand made boards can say where they are
+pub struct TemplatePlacement {
+    pub mines: Vec<Coordinate>,
+}
+
+impl MinePlacementStrategy for TemplatePlacement {
+    fn place(
+        &self,
+        _rng: &mut StdRng,
+        width: u32,
+        height: u32,
+        eligible: &[u32],
+        _num_mines: u32,
+    ) -> Result<Vec<u32>, Error> {
+        self.mines
+            .iter()
+            .map(|c| {
+                if c.x >= width || c.y >= height {
+                    return Err(Error::InvalidCoordinates {
+                        x: c.x,
+                        y: c.y,
+                        width,
+                        height,
+                    });
+                }
+                let idx = c.y * width + c.x;
+                if eligible.contains(&idx) {
+                    Ok(idx)
+                } else {
+                    Err(Error::IneligibleMine { x: c.x, y: c.y })
+                }
+            })
+            .collect()
+    }
+}
+
+/// The built in ways to spread mines, as picked by the player and saved with their
+/// settings and games
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+pub enum MinePlacement {
+    #[default]
+    Uniform,
+    Clustered,
+    Gradient,
+    Radial,
+}
+
+impl MinePlacement {
+    pub fn all() -> [MinePlacement; 4] {
+        [
+            MinePlacement::Uniform,
+            MinePlacement::Clustered,
+            MinePlacement::Gradient,
+            MinePlacement::Radial,
+        ]
+    }
+
+    /// The name used on the command line and in board comments
+    pub fn as_str(&self) -> &'static str {
+        match *self {
+            MinePlacement::Uniform => "uniform",
+            MinePlacement::Clustered => "clustered",
+            MinePlacement::Gradient => "gradient",
+            MinePlacement::Radial => "radial",
+        }
+    }
+
+    /// The name shown to the player, in the UI's language
+    pub fn name(&self) -> &'static str {
+        match *self {
+            MinePlacement::Uniform => tr("placement-uniform"),
+            MinePlacement::Clustered => tr("placement-clustered"),
+            MinePlacement::Gradient => tr("placement-gradient"),
+            MinePlacement::Radial => tr("placement-radial"),
+        }
+    }
+
+    pub fn strategy(&self) -> Box<dyn MinePlacementStrategy> {
+        match *self {
+            MinePlacement::Uniform => Box::new(UniformPlacement),
+            MinePlacement::Clustered => Box::new(ClusteredPlacement),
+            MinePlacement::Gradient => Box::new(GradientPlacement),
+            MinePlacement::Radial => Box::new(RadialPlacement),
+        }
+    }
+}
+
+impl FromStr for MinePlacement {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        MinePlacement::all()
+            .into_iter()
+            .find(|p| p.as_str() == s)
+            .ok_or_else(|| anyhow!("Unknown mine placement: {}", s))
+    }
+}
+
+#[test]
+fn test_mine_placement() -> Result<(), Error> {
+    use crate::minesweeper::GameBoard;
+
+    let populate = |placement: MinePlacement| {
+        let mut gb = GameBoard::new_seeded(30, 16, 7);
+        gb.placement = placement;
+        gb.populate_mines(99).map(|_| gb)
+    };
+    let is_mine = |gb: &GameBoard, x, y| gb.get_square(x, y).is_ok_and(|s| s.is_mine());
+    let mines_in = |gb: &GameBoard, xs: std::ops::Range<u32>| {
+        iproduct!(xs, 0..gb.height)
+            .filter(|&(x, y)| is_mine(gb, x, y))
+            .count()
+    };
+    // Mines next to other mines, counted once from each side
+    let touching = |gb: &GameBoard| -> u32 {
+        iproduct!(0..gb.width, 0..gb.height)
+            .filter(|&(x, y)| is_mine(gb, x, y))
+            .filter_map(|(x, y)| gb.mined_neighbor_count(x, y).ok())
+            .sum()
+    };
+
+    // The same seed gives the same board
+    let a = populate(MinePlacement::Gradient)?;
+    let b = populate(MinePlacement::Gradient)?;
+    assert_eq!(a.num_mines, 99);
+    assert!(iproduct!(0..30, 0..16).all(|(x, y)| is_mine(&a, x, y) == is_mine(&b, x, y)));
+    // Mines crowd the right of a gradient board, the edges of a radial one and each other on
+    // a clustered one
+    assert!(mines_in(&a, 0..10) * 2 < mines_in(&a, 20..30));
+    let radial = populate(MinePlacement::Radial)?;
+    assert!(mines_in(&radial, 10..20) < mines_in(&radial, 0..5) + mines_in(&radial, 25..30));
+    let clustered = populate(MinePlacement::Clustered)?;
+    assert_eq!(clustered.num_mines, 99);
+    assert!(touching(&clustered) > touching(&populate(MinePlacement::Uniform)?) * 3 / 2);
+
+    // A template puts the mines exactly where it says, as long as they can go there
+    let mut gb = GameBoard::new_seeded(5, 5, 1);
+    let template = TemplatePlacement {
+        mines: vec![Coordinate::from((0, 0)), Coordinate::from((4, 4))],
+    };
+    gb.populate_mines_with(&template, 2, Some(Coordinate::from((2, 2))))?;
+    assert!(is_mine(&gb, 0, 0) && is_mine(&gb, 4, 4));
+    assert_eq!(gb.num_mines, 2);
+    let mut gb = GameBoard::new_seeded(5, 5, 1);
+    assert!(gb
+        .populate_mines_with(&template, 2, Some(Coordinate::from((1, 1))))
+        .is_err());
+
+    assert_eq!(
+        "radial".parse::<MinePlacement>().ok(),
+        Some(MinePlacement::Radial)
+    );
+    assert!("lumpy".parse::<MinePlacement>().is_err());
+    Ok(())
+}
@@ -0,0 +1,360 @@
+use serde::{Deserialize, Serialize};
+
+use crate::minesweeper::*;
+use crate::solver::Deduction;
+use crate::state::GameSettings;
+
+/// What an entry in the play log records
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+pub enum PlayKind {
+    /// A click on the board
+    #[default]
+    Click,
+    /// A sonar ping in fog of war mode, which clears the fog further around `coord` for a
+    /// moment but doesn't change the board
+    Sonar,
+    /// A square proven safe by the numerals next to it, revealed by the auto-open assist
+    /// rather than the player
+    AutoOpen,
+}
+
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct PlayEntry {
+    #[serde(default)]
+    pub kind: PlayKind,
+    pub coord: Coordinate,
+    pub play_type: RevealType,
+    /// Number of squares revealed, exploded or (un)flagged by the play
+    #[serde(default)]
+    pub changed: u32,
+    /// Seconds on the clock when the play was made. Zero for plays logged before it was
+    /// tracked.
+    #[serde(default)]
+    pub time: f64,
+    /// Whether a reveal was a guess. `None` for other plays, the first click, and reveals
+    /// logged before it was tracked.
+    #[serde(default)]
+    pub deduction: Option<Deduction>,
+}
+
+impl PlayEntry {
+    pub fn is_click(&self) -> bool {
+        self.kind == PlayKind::Click
+    }
+
+    pub fn is_chord(&self) -> bool {
+        self.is_click() && matches!(self.play_type, RevealType::Chord | RevealType::RevealChord)
+    }
+
+    /// Whether the play did something to the board, which sonar pings don't
+    pub fn changes_board(&self) -> bool {
+        self.kind != PlayKind::Sonar
+    }
+}
+
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct PlayList {
+    pub list: Vec<PlayEntry>,
+}
+
+impl PlayList {
+    /// Records a click on the board along with the result it had, `time` seconds into the
+    /// game
+    pub fn record(
+        &mut self,
+        coord: Coordinate,
+        play_type: RevealType,
+        result: &PlayResult,
+        time: f64,
+        deduction: Option<Deduction>,
+    ) {
+        self.list.push(PlayEntry {
+            kind: PlayKind::Click,
+            coord,
+            play_type,
+            changed: result.squares_changed(),
+            time,
+            deduction,
+        });
+    }
+
+    /// The first square revealed, which the mines were placed around
+    pub fn first_reveal(&self) -> Option<&Coordinate> {
+        self.click_entries()
+            .find(|e| matches!(e.play_type, RevealType::Reveal | RevealType::RevealChord))
+            .map(|e| &e.coord)
+    }
+
+    /// Records a sonar ping centered on `coord`, `time` seconds into the game
+    pub fn record_sonar(&mut self, coord: Coordinate, time: f64) {
+        self.list.push(PlayEntry {
+            kind: PlayKind::Sonar,
+            coord,
+            time,
+            ..Default::default()
+        });
+    }
+
+    /// Records a square revealed by the auto-open assist, `time` seconds into the game
+    pub fn record_auto_open(&mut self, coord: Coordinate, result: &PlayResult, time: f64) {
+        self.list.push(PlayEntry {
+            kind: PlayKind::AutoOpen,
+            coord,
+            play_type: RevealType::Reveal,
+            changed: result.squares_changed(),
+            time,
+            deduction: Some(Deduction::Deduced),
+        });
+    }
+
+    /// Makes the plays again on `boards`, fresh from their seeds. Each board's mines are
+    /// placed around the first square revealed on it, the same as when the game was played.
+    pub fn play_on(&self, settings: &GameSettings, boards: &mut [GameBoard]) -> Result<(), Error> {
+        self.list
+            .iter()
+            .filter(|e| e.changes_board())
+            .try_for_each(|e| {
+                let Some((board, local)) = settings
+                    .board_coordinate(&e.coord)
+                    .filter(|(b, _)| *b < boards.len())
+                else {
+                    return Err(Error::InvalidCoordinates {
+                        x: e.coord.x,
+                        y: e.coord.y,
+                        width: settings.width,
+                        height: settings.height,
+                    });
+                };
+                let gameboard = &mut boards[board];
+                let reveal = matches!(e.play_type, RevealType::Reveal | RevealType::RevealChord);
+                if reveal && !gameboard.is_populated {
+                    gameboard.populate_mines_around(settings.num_mines, Some(local.clone()))?;
+                    if settings.use_numerals {
+                        gameboard.populate_numerals()?;
+                    }
+                }
+                gameboard
+                    .play(local.x, local.y, e.play_type.clone())
+                    .map(|_| ())
+            })
+    }
+
+    pub fn clear(&mut self) {
+        self.list.clear();
+    }
+
+    pub fn len(&self) -> usize {
+        self.list.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.list.is_empty()
+    }
+
+    /// The clicks on the board, leaving out sonar pings and auto opens
+    fn click_entries(&self) -> impl Iterator<Item = &PlayEntry> {
+        self.list.iter().filter(|e| e.is_click())
+    }
+
+    pub fn clicks(&self) -> u32 {
+        self.click_entries().count() as u32
+    }
+
+    pub fn reveals(&self) -> u32 {
+        self.click_entries()
+            .filter(|e| matches!(e.play_type, RevealType::Reveal | RevealType::RevealChord))
+            .count() as u32
+    }
+
+    pub fn flagged(&self) -> u32 {
+        self.click_entries()
+            .filter(|e| e.play_type == RevealType::Flag)
+            .count() as u32
+    }
+
+    pub fn chords(&self) -> u32 {
+        self.list.iter().filter(|e| e.is_chord()).count() as u32
+    }
+
+    /// Clicks that didn't change anything on the board
+    pub fn wasted(&self) -> u32 {
+        self.click_entries().filter(|e| e.changed == 0).count() as u32
+    }
+
+    pub fn sonars(&self) -> u32 {
+        self.list
+            .iter()
+            .filter(|e| e.kind == PlayKind::Sonar)
+            .count() as u32
+    }
+
+    /// Squares revealed by the auto-open assist
+    pub fn auto_opens(&self) -> u32 {
+        self.list
+            .iter()
+            .filter(|e| e.kind == PlayKind::AutoOpen)
+            .count() as u32
+    }
+
+    /// Standard efficiency: the board's 3BV as a percentage of the clicks taken. Over 100%
+    /// means chording saved clicks.
+    pub fn efficiency(&self, bbbv: u32) -> Option<f32> {
+        (self.clicks() > 0).then(|| bbbv as f32 / self.clicks() as f32 * 100.0)
+    }
+
+    /// Flag clicks per mine on the board
+    pub fn flags_per_mine(&self, num_mines: u32) -> Option<f32> {
+        (num_mines > 0).then(|| self.flagged() as f32 / num_mines as f32)
+    }
+
+    /// How many clicks were made on each square of a `width` by `height` grid, row by row
+    pub fn heatmap(&self, width: u32, height: u32) -> Vec<u32> {
+        let mut counts = vec![0; (width * height) as usize];
+        self.click_entries()
+            .filter(|e| e.coord.x < width && e.coord.y < height)
+            .for_each(|e| counts[(e.coord.y * width + e.coord.x) as usize] += 1);
+        counts
+    }
+
+    /// Reveals made on squares that couldn't be deduced to be safe
+    pub fn guesses(&self) -> u32 {
+        self.list
+            .iter()
+            .filter(|e| e.deduction.is_some_and(|d| d.is_guess()))
+            .count() as u32
+    }
+
+    /// Guesses made while some other square was known to be safe
+    pub fn avoidable_guesses(&self) -> u32 {
+        self.list
+            .iter()
+            .filter(|e| e.deduction == Some(Deduction::AvoidableGuess))
+            .count() as u32
+    }
+
+    /// True if every guess taken was one there was no way around. Games with no reveals
+    /// tracked, like those logged before it was, don't count.
+    pub fn is_flawless(&self) -> bool {
+        self.list.iter().any(|e| e.deduction.is_some()) && self.avoidable_guesses() == 0
+    }
+
+    /// Percentage of chords that revealed at least one square
+    pub fn chord_usefulness(&self) -> Option<f32> {
+        let useful = self
+            .list
+            .iter()
+            .filter(|e| e.is_chord() && e.changed > 0)
+            .count();
+        (self.chords() > 0).then(|| useful as f32 / self.chords() as f32 * 100.0)
+    }
+}
+
+/// Builds the board `[0 0 1 * 1]` and replays a game on it
+#[cfg(test)]
+fn play_reference_game(plays: &[(u32, RevealType)]) -> Result<(GameBoard, PlayList), Error> {
+    let mut gb = GameBoard::new_with_mines(5, 1, &[Coordinate::from((3, 0))])?;
+
+    let mut list = PlayList::default();
+    for (x, play_type) in plays {
+        let coord = Coordinate::from((*x, 0));
+        let deduction = Deduction::of_reveal(&gb, &coord);
+        let result = gb.play(*x, 0, play_type.clone())?;
+        list.record(coord, play_type.clone(), &result, *x as f64, deduction);
+    }
+    Ok((gb, list))
+}
+
+#[test]
+fn test_perfect_game_metrics() -> Result<(), Error> {
+    let (gb, plays) = play_reference_game(&[(0, RevealType::Reveal), (4, RevealType::Reveal)])?;
+    assert!(gb.is_win_configuration());
+    assert_eq!(gb.bbbv(), 2);
+    assert_eq!(plays.clicks(), 2);
+    assert_eq!(plays.wasted(), 0);
+    assert_eq!(plays.efficiency(gb.bbbv()), Some(100.0));
+    assert_eq!(plays.flags_per_mine(gb.num_mines), Some(0.0));
+    assert_eq!(plays.chord_usefulness(), None);
+    // Nothing past the 1 can be deduced, so the last reveal was a guess with no way around
+    // it, the same as the first with nothing revealed
+    assert_eq!(plays.guesses(), 2);
+    assert_eq!(plays.avoidable_guesses(), 0);
+    assert!(plays.is_flawless());
+    assert!(!PlayList::default().is_flawless());
+    Ok(())
+}
+
+#[test]
+fn test_sloppy_game_metrics() -> Result<(), Error> {
+    let (gb, plays) = play_reference_game(&[
+        (0, RevealType::Reveal),
+        (1, RevealType::Reveal), // Already revealed
+        (3, RevealType::Flag),
+        (2, RevealType::Chord),       // Nothing left around it to reveal
+        (4, RevealType::RevealChord), // Reveals the last square
+    ])?;
+    assert!(gb.is_win_configuration());
+    assert_eq!(plays.clicks(), 5);
+    assert_eq!(plays.wasted(), 2);
+    assert_eq!(plays.efficiency(gb.bbbv()), Some(40.0));
+    assert_eq!(plays.flags_per_mine(gb.num_mines), Some(1.0));
+    assert_eq!(plays.chord_usefulness(), Some(50.0));
+    assert_eq!(plays.first_reveal(), Some(&Coordinate::from((0, 0))));
+    assert_eq!(plays.heatmap(5, 1), [1, 1, 1, 1, 1]);
+    // Clicks off the grid are left out
+    assert_eq!(plays.heatmap(2, 1), [1, 1]);
+
+    // Sonar pings are logged, but aren't clicks
+    let mut plays = plays;
+    plays.record_sonar(Coordinate::from((2, 0)), 5.0);
+    assert_eq!(plays.sonars(), 1);
+    assert_eq!(plays.clicks(), 5);
+    assert_eq!(plays.reveals(), 3);
+    assert_eq!(plays.wasted(), 2);
+    assert_eq!(plays.heatmap(5, 1), [1, 1, 1, 1, 1]);
+    Ok(())
+}
+
+#[test]
+fn test_auto_open_replay() -> Result<(), Error> {
+    let settings = GameSettings {
+        width: 5,
+        height: 1,
+        num_mines: 1,
+        ..GameSettings::beginner()
+    };
+    let mut played = GameBoard::new_seeded(5, 1, 7);
+    played.populate_mines_around(1, Some(Coordinate::from((0, 0))))?;
+    played.populate_numerals()?;
+    let mut plays = PlayList::default();
+    let result = played.play(0, 0, RevealType::Reveal)?;
+    plays.record(
+        Coordinate::from((0, 0)),
+        RevealType::Reveal,
+        &result,
+        0.0,
+        None,
+    );
+
+    // Auto open whatever's left that isn't a mine
+    let safe = (0..5)
+        .map(|x| Coordinate::from((x, 0)))
+        .filter(|c| {
+            played
+                .get_square_by_coordinate(c)
+                .is_ok_and(|s| !s.is_revealed && !s.is_mine())
+        })
+        .collect::<Vec<_>>();
+    for coord in safe {
+        let result = played.play(coord.x, coord.y, RevealType::Reveal)?;
+        plays.record_auto_open(coord, &result, 1.0);
+    }
+    assert!(played.is_win_configuration());
+    assert_eq!(plays.clicks(), 1);
+    assert_eq!(plays.guesses(), 0);
+
+    // Auto opens are replayed along with the clicks
+    let mut replayed = [GameBoard::new_seeded(5, 1, 7)];
+    plays.play_on(&settings, &mut replayed)?;
+    assert!(replayed[0].is_win_configuration());
+    Ok(())
+}
@@ -0,0 +1,134 @@
+use std::fs;
+use std::path::Path;
+
+use anyhow::{anyhow, Context, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::config::{self, Versioned};
+use crate::history::GameHistory;
+use crate::leader::LeaderBoards;
+use crate::puzzle::PuzzleProgress;
+use crate::state::AppState;
+
+/// Everything kept about the player in one file, for moving to another machine or backing
+/// up. Each part keeps its own version, so it can be migrated like the file it came from.
+#[derive(Clone, Deserialize, Serialize)]
+pub struct Profile {
+    pub version: i64,
+    pub settings: AppState,
+    pub leaderboards: LeaderBoards,
+    pub history: GameHistory,
+    pub puzzles: PuzzleProgress,
+}
+
+impl Versioned for Profile {
+    // 1: Initial version
+    // 2: Added puzzles
+    const VERSION: i64 = 2;
+}
+
+impl Profile {
+    pub fn new(
+        settings: AppState,
+        leaderboards: LeaderBoards,
+        history: GameHistory,
+        puzzles: PuzzleProgress,
+    ) -> Self {
+        Profile {
+            version: <Profile as Versioned>::VERSION,
+            settings,
+            leaderboards,
+            history,
+            puzzles,
+        }
+    }
+
+    /// Reads a profile, migrating each part written by an older version
+    pub fn parse(t: &str) -> Result<Self> {
+        let mut table: toml::Table = toml::from_str(t)?;
+        let version = table
+            .get("version")
+            .and_then(|v| v.as_integer())
+            .unwrap_or(0);
+        if version > <Profile as Versioned>::VERSION {
+            return Err(anyhow!(
+                "Profile version {} is newer than the supported version {}",
+                version,
+                <Profile as Versioned>::VERSION
+            ));
+        }
+        let mut part = |name: &str| match table.remove(name) {
+            Some(toml::Value::Table(t)) => Ok(t),
+            _ => Err(anyhow!("Profile is missing its {}", name)),
+        };
+        // Profiles from before puzzles have none completed
+        let puzzles = match part("puzzles") {
+            Ok(t) => config::from_versioned_table(t)?,
+            Err(_) => PuzzleProgress::default(),
+        };
+        Ok(Profile::new(
+            config::from_versioned_table(part("settings")?)?,
+            config::from_versioned_table(part("leaderboards")?)?,
+            config::from_versioned_table(part("history")?)?,
+            puzzles,
+        ))
+    }
+
+    pub fn load(path: &Path) -> Result<Self> {
+        let t = fs::read_to_string(path).with_context(|| format!("Failed to read {:?}", path))?;
+        Profile::parse(&t).with_context(|| format!("{:?} isn't a valid profile", path))
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        fs::write(path, toml::to_string(self)?)
+            .with_context(|| format!("Failed to write {:?}", path))
+    }
+}
+
+#[test]
+fn test_profile_round_trip() -> Result<()> {
+    use crate::enums::{GameDifficulty, VisualTheme};
+    use crate::history::GameRecord;
+    use crate::leader::{Entry, LeaderboardCategory};
+
+    let settings = AppState {
+        theme: VisualTheme::Light,
+        ..Default::default()
+    };
+    let mut leaderboards = LeaderBoards::default();
+    leaderboards.add(
+        &LeaderboardCategory::new(GameDifficulty::Expert, false, 1),
+        Entry::new("Player 1", 99.0, 1, 0, 0),
+        None,
+    );
+    let mut history = GameHistory::default();
+    history.add(GameRecord::new(GameDifficulty::Expert, true, 99.0, 1, 0, 0));
+
+    let mut puzzles = PuzzleProgress::default();
+    puzzles.complete("first-steps");
+
+    let text = toml::to_string(&Profile::new(settings, leaderboards, history, puzzles))?;
+    let profile = Profile::parse(&text)?;
+    assert!(profile.settings.theme == VisualTheme::Light);
+    assert_eq!(profile.leaderboards.expert.entries.len(), 1);
+    assert_eq!(profile.history.games.len(), 1);
+    assert!(profile.puzzles.completed.contains("first-steps"));
+
+    // A part without a version is from before versioning, and is migrated like its file
+    let mut table: toml::Table = toml::from_str(&text)?;
+    if let Some(toml::Value::Table(leaderboards)) = table.get_mut("leaderboards") {
+        leaderboards.remove("version");
+    }
+    // Version 1 profiles have no puzzles
+    table.remove("puzzles");
+    table.insert("version".to_string(), toml::Value::Integer(1));
+    let profile = Profile::parse(&toml::to_string(&table)?)?;
+    assert_eq!(
+        profile.leaderboards.version,
+        <LeaderBoards as Versioned>::VERSION
+    );
+    assert!(profile.puzzles.completed.is_empty());
+
+    assert!(Profile::parse("version = 1").is_err());
+    Ok(())
+}
@@ -0,0 +1,82 @@
+use crate::history::{GameHistory, GameRecord};
+
+/// Points needed to go from level 1 to level 2. Each level after that takes this many more
+/// than the one before.
+const LEVEL_POINTS_STEP: u64 = 100;
+
+/// Points for a game: one for each 3BV cleared, times one plus the 3BV cleared a second, so
+/// bigger boards and faster wins are both worth more. Losses and practice games are worth
+/// nothing.
+pub fn game_points(game: &GameRecord) -> u64 {
+    match game.bbbv_per_second() {
+        Some(speed) if !game.practice => (game.bbbv as f64 * (1.0 + speed)).round() as u64,
+        _ => 0,
+    }
+}
+
+/// Total points needed to reach `level`
+pub fn points_for_level(level: u32) -> u64 {
+    let level = level.max(1) as u64;
+    LEVEL_POINTS_STEP * (level - 1) * level / 2
+}
+
+/// The player's points and level, earned by winning games. Worked out from the game history,
+/// so it moves between machines with the rest of the profile.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Progress {
+    pub points: u64,
+}
+
+impl Progress {
+    pub fn from_history(history: &GameHistory) -> Self {
+        Progress {
+            points: history.games.iter().map(game_points).sum(),
+        }
+    }
+
+    pub fn level(&self) -> u32 {
+        let mut level = 1;
+        while points_for_level(level + 1) <= self.points {
+            level += 1;
+        }
+        level
+    }
+
+    /// How far through the current level the player is, from 0 to 1
+    pub fn level_fraction(&self) -> f32 {
+        let level = self.level();
+        let start = points_for_level(level);
+        let end = points_for_level(level + 1);
+        (self.points - start) as f32 / (end - start) as f32
+    }
+}
+
+#[test]
+fn test_progress() {
+    use crate::enums::GameDifficulty;
+
+    // 20 3BV in 10 seconds is 2 3BV/s, worth 20 * 3 points
+    let win = GameRecord::new(GameDifficulty::Beginner, true, 10.0, 0, 20, 25);
+    assert_eq!(game_points(&win), 60);
+    let loss = GameRecord::new(GameDifficulty::Beginner, false, 10.0, 0, 20, 25);
+    assert_eq!(game_points(&loss), 0);
+    let practice = GameRecord {
+        practice: true,
+        ..win.clone()
+    };
+    assert_eq!(game_points(&practice), 0);
+
+    assert_eq!(points_for_level(1), 0);
+    assert_eq!(points_for_level(2), 100);
+    assert_eq!(points_for_level(3), 300);
+
+    let mut history = GameHistory::default();
+    assert_eq!(Progress::from_history(&history).level(), 1);
+    [win.clone(), loss, practice, win]
+        .into_iter()
+        .for_each(|g| history.add(g));
+    let progress = Progress::from_history(&history);
+    assert_eq!(progress.points, 120);
+    assert_eq!(progress.level(), 2);
+    assert_eq!(progress.level_fraction(), 0.1);
+}
@@ -0,0 +1,290 @@
+use std::collections::BTreeSet;
+
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::cli::parse_coordinate;
+use crate::config::{self, Versioned};
+use crate::i18n::{tr, tr_with};
+use crate::minesweeper::{self, Coordinate, GameBoard, RevealType};
+use crate::plays::PlayList;
+use crate::state::GameSettings;
+
+const CONFIG_FILE_NAME: &str = "minesofrust-puzzles.toml";
+
+/// Folder in the config directory the player's own puzzles are read from
+#[cfg(not(target_arch = "wasm32"))]
+const PUZZLE_DIR_NAME: &str = "puzzles";
+
+/// The campaign, in the order it's played
+const CAMPAIGN: [(&str, &str); 6] = [
+    (
+        "first-steps",
+        include_str!("../assets/puzzles/01-first-steps.txt"),
+    ),
+    (
+        "light-touch",
+        include_str!("../assets/puzzles/02-light-touch.txt"),
+    ),
+    (
+        "bare-hands",
+        include_str!("../assets/puzzles/03-bare-hands.txt"),
+    ),
+    (
+        "the-long-hall",
+        include_str!("../assets/puzzles/04-the-long-hall.txt"),
+    ),
+    (
+        "minefield",
+        include_str!("../assets/puzzles/05-minefield.txt"),
+    ),
+    (
+        "grand-finale",
+        include_str!("../assets/puzzles/06-grand-finale.txt"),
+    ),
+];
+
+/// What it takes to complete a puzzle, besides clearing the board
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PuzzleObjective {
+    /// Clearing the board is enough
+    Clear,
+    /// Clear it in at most this many clicks
+    MaxClicks(u32),
+    /// Clear it without placing a flag
+    NoFlags,
+}
+
+impl PuzzleObjective {
+    /// Reads an objective as written in a puzzle file: `clear`, `clicks N` or `no-flags`
+    fn parse(s: &str) -> Option<Self> {
+        let words: Vec<&str> = s.split_whitespace().collect();
+        match words[..] {
+            ["clear"] => Some(PuzzleObjective::Clear),
+            ["clicks", n] => n.parse().ok().map(PuzzleObjective::MaxClicks),
+            ["no-flags"] => Some(PuzzleObjective::NoFlags),
+            _ => None,
+        }
+    }
+
+    pub fn description(&self) -> String {
+        match *self {
+            PuzzleObjective::Clear => tr("objective-clear").to_string(),
+            PuzzleObjective::MaxClicks(n) => tr_with("objective-max-clicks", &[("clicks", &n)]),
+            PuzzleObjective::NoFlags => tr("objective-no-flags").to_string(),
+        }
+    }
+
+    /// Whether a won game met the objective, going by the plays made in it
+    pub fn is_met(&self, plays: &PlayList) -> bool {
+        match *self {
+            PuzzleObjective::Clear => true,
+            PuzzleObjective::MaxClicks(n) => plays.clicks() <= n,
+            PuzzleObjective::NoFlags => plays.flagged() == 0,
+        }
+    }
+}
+
+/// A fixed board to clear with an objective. Puzzle files are boards in the text format
+/// `GameBoard::from_ascii` reads, headed by comments naming the puzzle and its objective,
+/// and optionally a square opened for the player so they don't have to guess:
+///
+/// ```text
+/// # name: Light Touch
+/// # objective: clicks 18
+/// # start: 4,4
+/// ```
+#[derive(Debug, Clone)]
+pub struct Puzzle {
+    /// Completions are recorded under this
+    pub id: String,
+    pub name: String,
+    pub objective: PuzzleObjective,
+    start: Option<Coordinate>,
+    board: GameBoard,
+}
+
+impl Puzzle {
+    pub fn parse(id: &str, text: &str) -> Result<Self> {
+        let header = |key: &str| {
+            text.lines()
+                .filter_map(|l| l.trim().strip_prefix('#')?.split_once(':'))
+                .find(|(k, _)| k.trim() == key)
+                .map(|(_, v)| v.trim())
+        };
+        let board = GameBoard::from_ascii(text)?;
+        let objective = match header("objective") {
+            Some(o) => {
+                PuzzleObjective::parse(o).ok_or_else(|| anyhow!("Unknown objective: {}", o))?
+            }
+            None => PuzzleObjective::Clear,
+        };
+        let start = header("start").map(parse_coordinate).transpose()?;
+        if let Some(start) = &start {
+            if board.get_square_by_coordinate(start)?.is_mine() {
+                return Err(anyhow!("The start ({}, {}) is a mine", start.x, start.y));
+            }
+        }
+        Ok(Puzzle {
+            id: id.to_string(),
+            name: header("name").unwrap_or(id).to_string(),
+            objective,
+            start,
+            board,
+        })
+    }
+
+    /// A fresh copy of the puzzle's board, with the start opened
+    pub fn board(&self) -> Result<GameBoard, minesweeper::Error> {
+        let mut board = self.board.clone();
+        if let Some(start) = &self.start {
+            board.play(start.x, start.y, RevealType::Reveal)?;
+        }
+        Ok(board)
+    }
+
+    pub fn settings(&self) -> GameSettings {
+        GameSettings::for_board(self.board.width, self.board.height, self.board.num_mines)
+    }
+}
+
+/// The campaign's puzzles, in order
+pub fn campaign() -> Vec<Puzzle> {
+    CAMPAIGN
+        .iter()
+        .map(|(id, text)| Puzzle::parse(id, text).expect("Campaign puzzles are valid"))
+        .collect()
+}
+
+/// The player's own puzzles, from the `.txt` files in the `puzzles` folder of the config
+/// directory in file name order. Files that aren't valid puzzles are skipped with a warning.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn load_user_puzzles() -> Vec<Puzzle> {
+    let Ok(entries) = config::config_dir().and_then(|d| Ok(d.join(PUZZLE_DIR_NAME).read_dir()?))
+    else {
+        return vec![];
+    };
+    let mut paths: Vec<_> = entries
+        .filter_map(|e| e.ok())
+        .map(|e| e.path())
+        .filter(|p| p.extension().is_some_and(|e| e == "txt"))
+        .collect();
+    paths.sort();
+    paths
+        .iter()
+        .filter_map(|path| {
+            let id = format!("user/{}", path.file_stem()?.to_string_lossy());
+            let puzzle = std::fs::read_to_string(path)
+                .map_err(anyhow::Error::from)
+                .and_then(|text| Puzzle::parse(&id, &text));
+            match puzzle {
+                Ok(puzzle) => Some(puzzle),
+                Err(e) => {
+                    log::warn!("Skipping puzzle {:?}: {:#}", path, e);
+                    None
+                }
+            }
+        })
+        .collect()
+}
+
+/// Which puzzles the player has completed, by id
+#[derive(Clone, Deserialize, Serialize)]
+pub struct PuzzleProgress {
+    #[serde(default)]
+    pub version: i64,
+    #[serde(default)]
+    pub completed: BTreeSet<String>,
+}
+
+impl Default for PuzzleProgress {
+    fn default() -> Self {
+        Self {
+            version: <PuzzleProgress as Versioned>::VERSION,
+            completed: BTreeSet::new(),
+        }
+    }
+}
+
+impl Versioned for PuzzleProgress {
+    // 1: Initial version
+    const VERSION: i64 = 1;
+}
+
+impl PuzzleProgress {
+    pub fn load_from_userhome() -> Result<Option<Self>> {
+        config::load_toml(CONFIG_FILE_NAME)
+    }
+
+    pub fn save_to_userhome(&self) -> Result<()> {
+        config::save_toml(self, CONFIG_FILE_NAME)
+    }
+
+    pub fn is_completed(&self, puzzle: &Puzzle) -> bool {
+        self.completed.contains(&puzzle.id)
+    }
+
+    /// Records `id` as completed. Returns whether it wasn't already.
+    pub fn complete(&mut self, id: &str) -> bool {
+        self.completed.insert(id.to_string())
+    }
+
+    pub fn merge(&mut self, other: &PuzzleProgress) {
+        self.completed.extend(other.completed.iter().cloned());
+    }
+}
+
+#[test]
+fn test_puzzle() -> Result<()> {
+    let puzzle = Puzzle::parse(
+        "test",
+        "# name: Test\n# objective: clicks 2\n# start: 0,2\n.1*\n.11\n...\n",
+    )?;
+    assert_eq!(puzzle.name, "Test");
+    assert_eq!(puzzle.objective, PuzzleObjective::MaxClicks(2));
+    let board = puzzle.board()?;
+    assert_eq!(board.num_mines, 1);
+    assert_eq!(board.num_revealed(), 8);
+    assert_eq!(puzzle.settings().width, 3);
+
+    let plain = Puzzle::parse("plain", ".1*\n")?;
+    assert_eq!(plain.name, "plain");
+    assert_eq!(plain.objective, PuzzleObjective::Clear);
+    assert_eq!(plain.board()?.num_revealed(), 0);
+
+    assert!(Puzzle::parse("mine", "# start: 2,0\n.1*\n").is_err());
+    assert!(Puzzle::parse("unknown", "# objective: fast\n.1*\n").is_err());
+    assert_eq!(
+        PuzzleObjective::parse("no-flags"),
+        Some(PuzzleObjective::NoFlags)
+    );
+    assert_eq!(PuzzleObjective::parse("clicks"), None);
+
+    let mut plays = PlayList::default();
+    plays.record(
+        Coordinate::from((0, 0)),
+        RevealType::Flag,
+        &minesweeper::PlayResult::single(
+            Coordinate::from((0, 0)),
+            minesweeper::ChangeKind::Flagged,
+        ),
+        0.0,
+        None,
+    );
+    assert!(PuzzleObjective::MaxClicks(1).is_met(&plays));
+    assert!(!PuzzleObjective::NoFlags.is_met(&plays));
+
+    // Every campaign puzzle can be opened at its start
+    campaign()
+        .iter()
+        .try_for_each(|p| p.board().map(|b| assert!(!b.is_loss_configuration())))?;
+
+    let mut progress = PuzzleProgress::default();
+    assert!(progress.complete("first-steps"));
+    assert!(!progress.complete("first-steps"));
+    let mut other = PuzzleProgress::default();
+    other.complete("bare-hands");
+    progress.merge(&other);
+    assert_eq!(progress.completed.len(), 2);
+    Ok(())
+}
@@ -0,0 +1,233 @@
+use std::sync::OnceLock;
+
+use ab_glyph::{Font, FontVec, PxScale, ScaleFont};
+use egui::Color32;
+use image::{Pixel, Rgba, RgbaImage};
+use imageproc::drawing::{
+    draw_filled_circle_mut, draw_filled_rect_mut, draw_line_segment_mut, draw_polygon_mut,
+};
+use imageproc::point::Point;
+use imageproc::rect::Rect;
+use itertools::iproduct;
+
+use crate::constants;
+use crate::minesweeper::*;
+
+/// The font the numerals are drawn in, the same one as the board's numeral tiles
+fn numeral_font() -> &'static FontVec {
+    static FONT: OnceLock<FontVec> = OnceLock::new();
+    FONT.get_or_init(|| {
+        let data = epaint::text::FontDefinitions::default()
+            .font_data
+            .remove("Ubuntu-Light")
+            .expect("Default fonts are missing Ubuntu-Light");
+        FontVec::try_from_vec(data.font.into_owned()).expect("Invalid numeral font")
+    })
+}
+
+fn rgba(color: Color32) -> Rgba<u8> {
+    Rgba(color.to_array())
+}
+
+/// Draws `text` centered in the `size` pixel square at `(x, y)`
+fn draw_centered_text(
+    image: &mut RgbaImage,
+    x: u32,
+    y: u32,
+    size: u32,
+    text: &str,
+    color: Color32,
+) {
+    let font = numeral_font().as_scaled(PxScale::from(size as f32 * constants::NUMERAL_TEXT_SCALE));
+    let width: f32 = text.chars().map(|c| font.h_advance(font.glyph_id(c))).sum();
+    let left = x as f32 + (size as f32 - width) / 2.0;
+    let baseline = y as f32 + (size as f32 + font.ascent() + font.descent()) / 2.0;
+
+    text.chars().fold(left, |pen, c| {
+        let glyph = font
+            .glyph_id(c)
+            .with_scale_and_position(font.scale(), ab_glyph::point(pen, baseline));
+        let advance = font.h_advance(glyph.id);
+        if let Some(outline) = font.outline_glyph(glyph) {
+            let bounds = outline.px_bounds();
+            outline.draw(|gx, gy, coverage| {
+                let (px, py) = (
+                    bounds.min.x as i32 + gx as i32,
+                    bounds.min.y as i32 + gy as i32,
+                );
+                if px >= 0 && py >= 0 && (px as u32) < image.width() && (py as u32) < image.height()
+                {
+                    let mut ink = rgba(color);
+                    ink.0[3] = (coverage.clamp(0.0, 1.0) * 255.0) as u8;
+                    image.get_pixel_mut(px as u32, py as u32).blend(&ink);
+                }
+            });
+        }
+        pen + advance
+    });
+}
+
+fn draw_mine(image: &mut RgbaImage, x: u32, y: u32, size: u32, color: Color32) {
+    let (cx, cy) = ((x + size / 2) as i32, (y + size / 2) as i32);
+    let spike = size as i32 * 2 / 5;
+    let thickness = (size / 10).max(1);
+    draw_filled_circle_mut(image, (cx, cy), size as i32 * 3 / 10, rgba(color));
+    draw_filled_rect_mut(
+        image,
+        Rect::at(cx - spike, cy - thickness as i32 / 2).of_size(spike as u32 * 2, thickness),
+        rgba(color),
+    );
+    draw_filled_rect_mut(
+        image,
+        Rect::at(cx - thickness as i32 / 2, cy - spike).of_size(thickness, spike as u32 * 2),
+        rgba(color),
+    );
+}
+
+fn draw_flag(image: &mut RgbaImage, x: u32, y: u32, size: u32) {
+    let (x, y, s) = (x as i32, y as i32, size as i32);
+    let pole = (size / 12).max(1);
+    draw_filled_rect_mut(
+        image,
+        Rect::at(x + s / 2, y + s / 5).of_size(pole, size * 3 / 5),
+        rgba(Color32::BLACK),
+    );
+    draw_filled_rect_mut(
+        image,
+        Rect::at(x + s / 4, y + s * 4 / 5 - pole as i32).of_size(size / 2, pole),
+        rgba(Color32::BLACK),
+    );
+    draw_polygon_mut(
+        image,
+        &[
+            Point::new(x + s / 2, y + s / 5),
+            Point::new(x + s / 2, y + s / 2),
+            Point::new(x + s / 5, y + s * 7 / 20),
+        ],
+        rgba(Color32::RED),
+    );
+}
+
+fn draw_cross(image: &mut RgbaImage, x: u32, y: u32, size: u32) {
+    let (x, y, s) = (x as f32, y as f32, size as f32);
+    let inset = s * 0.2;
+    (0..(size / 12).max(1)).for_each(|i| {
+        let i = i as f32;
+        draw_line_segment_mut(
+            image,
+            (x + inset + i, y + inset),
+            (x + s - inset + i, y + s - inset),
+            rgba(Color32::RED),
+        );
+        draw_line_segment_mut(
+            image,
+            (x + s - inset + i, y + inset),
+            (x + inset + i, y + s - inset),
+            rgba(Color32::RED),
+        );
+    });
+}
+
+/// Draws one square of a board, `size` pixels across, with its top left corner at `(x, y)`
+fn draw_square(image: &mut RgbaImage, x: u32, y: u32, size: u32, sqr: SquareView) {
+    let background = match sqr {
+        // Left see through, border and all
+        SquareView::Void => {
+            draw_filled_rect_mut(
+                image,
+                Rect::at(x as i32, y as i32).of_size(size.max(1), size.max(1)),
+                rgba(Color32::TRANSPARENT),
+            );
+            return;
+        }
+        SquareView::Covered | SquareView::Flagged | SquareView::HiddenMine => {
+            constants::COLOR_UNREVEALED
+        }
+        SquareView::Misflagged => constants::COLOR_MISFLAGGED,
+        SquareView::Detonated => constants::COLOR_DETONATED,
+        SquareView::Revealed(_) => constants::COLOR_REVEALED,
+    };
+    // Squares are separated by a pixel of the border color underneath
+    draw_filled_rect_mut(
+        image,
+        Rect::at(x as i32 + 1, y as i32 + 1).of_size(size.max(2) - 1, size.max(2) - 1),
+        rgba(background),
+    );
+
+    match sqr {
+        SquareView::Revealed(0) | SquareView::Covered | SquareView::Void => {}
+        SquareView::Revealed(numeral) => {
+            let colors = constants::COLORS_NUMERAL_DARK;
+            let color = colors[(numeral as usize).clamp(1, colors.len()) - 1];
+            draw_centered_text(image, x, y, size, &numeral.to_string(), color);
+        }
+        SquareView::Flagged => draw_flag(image, x, y, size),
+        SquareView::Misflagged => {
+            draw_flag(image, x, y, size);
+            draw_cross(image, x, y, size);
+        }
+        SquareView::Detonated => draw_mine(image, x, y, size, Color32::BLACK),
+        SquareView::HiddenMine => draw_mine(image, x, y, size, Color32::GRAY),
+    }
+}
+
+/// Draws `boards` laid out `columns` to a row, the way they're shown in the game, without a
+/// window to draw them in. Squares are `cell_size` pixels across, and the mines are only
+/// shown with `reveal_mines`.
+pub fn render_boards(
+    boards: &[GameBoard],
+    columns: u32,
+    cell_size: u32,
+    reveal_mines: bool,
+) -> RgbaImage {
+    let Some(first) = boards.first() else {
+        return RgbaImage::new(0, 0);
+    };
+    let columns = columns.clamp(1, boards.len() as u32);
+    let rows = (boards.len() as u32 - 1) / columns + 1;
+    let gap = cell_size / 2;
+    let (board_width, board_height) = (first.width * cell_size, first.height * cell_size);
+
+    let mut image = RgbaImage::from_pixel(
+        columns * board_width + (columns - 1) * gap + 1,
+        rows * board_height + (rows - 1) * gap + 1,
+        rgba(constants::COLOR_BORDER),
+    );
+    boards.iter().enumerate().for_each(|(i, board)| {
+        let left = i as u32 % columns * (board_width + gap);
+        let top = i as u32 / columns * (board_height + gap);
+        let view = board.player_view(reveal_mines);
+        iproduct!(0..board.height, 0..board.width).for_each(|(y, x)| {
+            let sqr = view
+                .square_at(&Coordinate::from((x, y)))
+                .unwrap_or(SquareView::Covered);
+            draw_square(
+                &mut image,
+                left + x * cell_size,
+                top + y * cell_size,
+                cell_size,
+                sqr,
+            );
+        });
+    });
+    image
+}
+
+#[test]
+fn test_render_boards() -> Result<(), Error> {
+    // [0 0 1 * 1]
+    let mut gb = GameBoard::new_with_mines(5, 1, &[Coordinate::from((3, 0))])?;
+    gb.play(0, 0, RevealType::Reveal)?;
+    gb.play(3, 0, RevealType::Flag)?;
+
+    let image = render_boards(&[gb.clone()], 1, 10, false);
+    assert_eq!(image.dimensions(), (51, 11));
+    let at = |image: &RgbaImage, x: u32| *image.get_pixel(x * 10 + 2, 2);
+    assert_eq!(at(&image, 0), rgba(constants::COLOR_REVEALED));
+    assert_eq!(at(&image, 4), rgba(constants::COLOR_UNREVEALED));
+
+    // Side by side, with half a square between them
+    let image = render_boards(&[gb.clone(), gb], 2, 10, false);
+    assert_eq!(image.dimensions(), (106, 11));
+    Ok(())
+}
@@ -0,0 +1,327 @@
+use std::cell::RefCell;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+
+use anyhow::{anyhow, Context, Result};
+use rhai::module_resolvers::DummyModuleResolver;
+use rhai::{Array, CallFnOptions, Dynamic, Engine, EvalAltResult, Scope, AST};
+
+use crate::config;
+use crate::minesweeper::*;
+use crate::state::GameSettings;
+
+/// Longest a script can run, in Rhai's operations, so one stuck in a loop can't hang the game
+const MAX_OPERATIONS: u64 = 5_000_000;
+/// Biggest string, array and object map a script can make. Doubling one takes only a few
+/// operations, so without these it could run out of memory long before `MAX_OPERATIONS`.
+const MAX_STRING_SIZE: usize = 1024 * 1024;
+const MAX_ARRAY_SIZE: usize = 100_000;
+const MAX_MAP_SIZE: usize = 100_000;
+
+/// The function a script can define to be run after each of the player's plays
+pub const ON_PLAY: &str = "on_play";
+
+pub const EXAMPLE_SCRIPT: &str = r#"// Flags the squares around each numeral that can only be mines
+fn flag_obvious(board) {
+    for y in 0..board.height {
+        for x in 0..board.width {
+            let n = board.numeral(x, y);
+            let covered = [];
+            for s in board.neighbors(x, y) {
+                if !board.is_revealed(s[0], s[1]) {
+                    covered.push(s);
+                }
+            }
+            if n > 0 && covered.len() == n {
+                for s in covered {
+                    if !board.is_flagged(s[0], s[1]) {
+                        board.play(s[0], s[1], "flag");
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn on_play(board) {
+    flag_obvious(board);
+}
+
+flag_obvious(board);
+"#;
+
+/// Where scripts can read and write files: a `scripts` folder in the config directory, and
+/// nowhere else
+fn script_path(file_name: &str) -> Result<PathBuf> {
+    let plain = Path::new(file_name).file_name().and_then(|n| n.to_str()) == Some(file_name);
+    if !plain || file_name.starts_with('.') {
+        return Err(anyhow!(
+            "{:?} isn't a file name in the scripts folder",
+            file_name
+        ));
+    }
+    Ok(config::config_dir()?.join("scripts").join(file_name))
+}
+
+pub fn read_script_file(file_name: &str) -> Result<String> {
+    let path = script_path(file_name)?;
+    fs::read_to_string(&path).with_context(|| format!("Failed to read {:?}", path))
+}
+
+pub fn write_script_file(file_name: &str, contents: &str) -> Result<()> {
+    let path = script_path(file_name)?;
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    fs::write(&path, contents).with_context(|| format!("Failed to write {:?}", path))
+}
+
+/// The board as a script sees it. Plays are made on a copy of the game's board, so the
+/// script sees what they did straight away, and noted for the game to make them for real.
+/// The mines aren't placed until the first click, so that has to be the player's.
+#[derive(Clone)]
+struct ScriptBoard {
+    board: Rc<RefCell<GameBoard>>,
+    settings: GameSettings,
+    plays: Rc<RefCell<Vec<(Coordinate, RevealType)>>>,
+}
+
+impl ScriptBoard {
+    fn square(&self, x: i64, y: i64) -> Option<Square> {
+        let (x, y) = (u32::try_from(x).ok()?, u32::try_from(y).ok()?);
+        self.board.borrow().get_square(x, y).ok()
+    }
+
+    fn play(&mut self, x: i64, y: i64, kind: &str) -> Result<i64, Box<EvalAltResult>> {
+        let play_type = match kind {
+            "reveal" => RevealType::Reveal,
+            "flag" => RevealType::Flag,
+            "chord" => RevealType::Chord,
+            "revealchord" => RevealType::RevealChord,
+            _ => return Err(format!("Unknown play {:?}", kind).into()),
+        };
+        let coord = Coordinate::from((
+            u32::try_from(x).map_err(|e| e.to_string())?,
+            u32::try_from(y).map_err(|e| e.to_string())?,
+        ));
+        let mut board = self.board.borrow_mut();
+        if !board.is_populated {
+            return Err("The mines aren't placed until the first click".into());
+        }
+        let result = board
+            .play(coord.x, coord.y, play_type.clone())
+            .map_err(|e| e.to_string())?;
+        self.plays.borrow_mut().push((coord, play_type));
+        Ok(result.squares_changed() as i64)
+    }
+
+    fn neighbors(&self, x: i64, y: i64) -> Array {
+        let board = self.board.borrow();
+        (-1..=1)
+            .flat_map(|dy| (-1..=1).map(move |dx| (x + dx, y + dy)))
+            .filter(|&(nx, ny)| {
+                (nx, ny) != (x, y)
+                    && (0..board.width as i64).contains(&nx)
+                    && (0..board.height as i64).contains(&ny)
+            })
+            .map(|(nx, ny)| Dynamic::from_array(vec![nx.into(), ny.into()]))
+            .collect()
+    }
+}
+
+/// What running a script did: the plays it made, in order, what it printed and the error
+/// it stopped on, if it didn't finish
+#[derive(Debug, Default)]
+pub struct ScriptRun {
+    pub plays: Vec<(Coordinate, RevealType)>,
+    pub output: Vec<String>,
+    pub error: Option<String>,
+}
+
+fn engine(output: Rc<RefCell<Vec<String>>>) -> Engine {
+    let mut engine = Engine::new();
+    // Rhai would otherwise load any module on disk, around the scripts folder check
+    engine.set_module_resolver(DummyModuleResolver::new());
+    engine.set_max_operations(MAX_OPERATIONS);
+    engine.set_max_string_size(MAX_STRING_SIZE);
+    engine.set_max_array_size(MAX_ARRAY_SIZE);
+    engine.set_max_map_size(MAX_MAP_SIZE);
+    // Debug builds allow so little nesting that loops over the board in a function are
+    // too deep
+    engine.set_max_expr_depths(64, 64);
+    let printed = output.clone();
+    engine.on_print(move |s| printed.borrow_mut().push(s.to_string()));
+    engine.on_debug(move |s, _, _| output.borrow_mut().push(s.to_string()));
+
+    engine
+        .register_type_with_name::<ScriptBoard>("Board")
+        .register_get("width", |b: &mut ScriptBoard| b.board.borrow().width as i64)
+        .register_get("height", |b: &mut ScriptBoard| {
+            b.board.borrow().height as i64
+        })
+        .register_get("mines", |b: &mut ScriptBoard| b.settings.num_mines as i64)
+        .register_get("flags", |b: &mut ScriptBoard| {
+            b.board.borrow().num_flags() as i64
+        })
+        .register_get("is_won", |b: &mut ScriptBoard| {
+            b.board.borrow().is_win_configuration()
+        })
+        .register_get("is_lost", |b: &mut ScriptBoard| {
+            b.board.borrow().is_loss_configuration()
+        })
+        .register_fn("is_revealed", |b: &mut ScriptBoard, x: i64, y: i64| {
+            b.square(x, y).is_some_and(|s| s.is_revealed)
+        })
+        .register_fn("is_flagged", |b: &mut ScriptBoard, x: i64, y: i64| {
+            b.square(x, y).is_some_and(|s| s.is_flagged)
+        })
+        // Only what the player can see: -1 for a covered square
+        .register_fn("numeral", |b: &mut ScriptBoard, x: i64, y: i64| {
+            b.square(x, y)
+                .filter(|s| s.is_revealed && !s.is_mine())
+                .map_or(-1, |s| s.numeral as i64)
+        })
+        .register_fn("neighbors", |b: &mut ScriptBoard, x: i64, y: i64| {
+            b.neighbors(x, y)
+        })
+        .register_fn("play", ScriptBoard::play);
+
+    engine
+        .register_fn(
+            "read_file",
+            |name: &str| -> Result<String, Box<EvalAltResult>> {
+                read_script_file(name).map_err(|e| format!("{:#}", e).into())
+            },
+        )
+        .register_fn(
+            "write_file",
+            |name: &str, contents: &str| -> Result<(), Box<EvalAltResult>> {
+                write_script_file(name, contents).map_err(|e| format!("{:#}", e).into())
+            },
+        );
+    engine
+}
+
+/// Whether the script defines `name`. A script that doesn't compile defines nothing.
+pub fn defines(source: &str, name: &str) -> bool {
+    engine(Rc::default())
+        .compile(source)
+        .is_ok_and(|ast: AST| ast.iter_functions().any(|f| f.name == name))
+}
+
+/// Runs a script against a copy of `board`, as `board`. With `function`, only that function
+/// is called, with the board, instead of the whole script being run.
+pub fn run(
+    source: &str,
+    function: Option<&str>,
+    board: &GameBoard,
+    settings: &GameSettings,
+) -> ScriptRun {
+    let output = Rc::new(RefCell::new(vec![]));
+    let engine = engine(output.clone());
+    let script_board = ScriptBoard {
+        board: Rc::new(RefCell::new(board.clone())),
+        settings: settings.clone(),
+        plays: Rc::default(),
+    };
+    let result = engine
+        .compile(source)
+        .map_err(|e| e.into())
+        .and_then(|ast| {
+            let mut scope = Scope::new();
+            match function {
+                Some(function) => engine.call_fn_with_options::<()>(
+                    CallFnOptions::new().eval_ast(false),
+                    &mut scope,
+                    &ast,
+                    function,
+                    (script_board.clone(),),
+                ),
+                None => {
+                    scope.push("board", script_board.clone());
+                    engine.run_ast_with_scope(&mut scope, &ast)
+                }
+            }
+        });
+    let plays = script_board.plays.take();
+    let output = output.take();
+    ScriptRun {
+        plays,
+        output,
+        error: result.err().map(|e| e.to_string()),
+    }
+}
+
+#[test]
+fn test_run_script() -> Result<()> {
+    let settings = GameSettings {
+        width: 5,
+        height: 1,
+        num_mines: 1,
+        ..GameSettings::beginner()
+    };
+    let board = GameBoard::new_with_mines(5, 1, &[Coordinate::from((3, 0))])?;
+
+    // Plays are seen by the rest of the script straight away
+    let run = run(
+        r#"board.play(0, 0, "reveal"); print(board.numeral(2, 0)); print(board.numeral(4, 0));"#,
+        None,
+        &board,
+        &settings,
+    );
+    assert_eq!(run.error, None);
+    assert_eq!(run.output, ["1", "-1"]);
+    assert_eq!(run.plays, [(Coordinate::from((0, 0)), RevealType::Reveal)]);
+
+    // The example flags the mine once the 1 beside it is revealed
+    let mut revealed = board.clone();
+    revealed.play(0, 0, RevealType::Reveal)?;
+    assert!(defines(EXAMPLE_SCRIPT, ON_PLAY));
+    let run = self::run(EXAMPLE_SCRIPT, Some(ON_PLAY), &revealed, &settings);
+    assert_eq!(run.error, None);
+    assert_eq!(run.plays, [(Coordinate::from((3, 0)), RevealType::Flag)]);
+
+    // The first click is left to the player
+    let run = self::run(
+        r#"board.play(0, 0, "reveal")"#,
+        None,
+        &GameBoard::new(5, 1),
+        &settings,
+    );
+    assert!(run.error.is_some() && run.plays.is_empty());
+
+    // Runaway scripts are stopped, and files outside the scripts folder are off limits,
+    // modules included
+    assert!(self::run("loop {}", None, &board, &settings)
+        .error
+        .is_some());
+    [
+        r#"let s = "x"; loop { s += s; }"#,
+        "let a = [0]; loop { a += a; }",
+        "let m = #{}; let i = 0; loop { m[`${i}`] = i; i += 1; }",
+    ]
+    .into_iter()
+    .for_each(|script| assert!(self::run(script, None, &board, &settings).error.is_some()));
+    let run = self::run(
+        r#"read_file("../minesofrust.toml")"#,
+        None,
+        &board,
+        &settings,
+    );
+    assert!(run.error.is_some_and(|e| e.contains("scripts folder")));
+    let module = std::env::temp_dir().join(format!("minesofrust-module-{}", std::process::id()));
+    fs::write(module.with_extension("rhai"), "fn f() { 1 }")?;
+    let run = self::run(
+        &format!("import {:?} as m; print(m::f());", module),
+        None,
+        &board,
+        &settings,
+    );
+    fs::remove_file(module.with_extension("rhai"))?;
+    assert!(run.error.is_some() && run.output.is_empty());
+    assert!(script_path("/etc/passwd").is_err());
+    assert!(script_path(".hidden").is_err());
+    assert!(script_path("bot.rhai").is_ok());
+    Ok(())
+}
@@ -0,0 +1,126 @@
+use egui::{pos2, vec2, Rect};
+
+use crate::constants;
+
+// Segment bit order: a (top), b (upper right), c (lower right), d (bottom),
+// e (lower left), f (upper left), g (middle)
+const SEG_A: u8 = 0b0000001;
+const SEG_B: u8 = 0b0000010;
+const SEG_C: u8 = 0b0000100;
+const SEG_D: u8 = 0b0001000;
+const SEG_E: u8 = 0b0010000;
+const SEG_F: u8 = 0b0100000;
+const SEG_G: u8 = 0b1000000;
+
+const DIGIT_SEGMENTS: [u8; 10] = [
+    SEG_A | SEG_B | SEG_C | SEG_D | SEG_E | SEG_F, // 0
+    SEG_B | SEG_C,                                 // 1
+    SEG_A | SEG_B | SEG_D | SEG_E | SEG_G,         // 2
+    SEG_A | SEG_B | SEG_C | SEG_D | SEG_G,         // 3
+    SEG_B | SEG_C | SEG_F | SEG_G,                 // 4
+    SEG_A | SEG_C | SEG_D | SEG_F | SEG_G,         // 5
+    SEG_A | SEG_C | SEG_D | SEG_E | SEG_F | SEG_G, // 6
+    SEG_A | SEG_B | SEG_C,                         // 7
+    SEG_A | SEG_B | SEG_C | SEG_D | SEG_E | SEG_F | SEG_G, // 8
+    SEG_A | SEG_B | SEG_C | SEG_D | SEG_F | SEG_G, // 9
+];
+
+/// Returns the lit segments for a character. Anything not displayable is blank.
+fn segments_for_char(c: char) -> u8 {
+    match c {
+        '0'..='9' => DIGIT_SEGMENTS[c as usize - '0' as usize],
+        '-' => SEG_G,
+        _ => 0,
+    }
+}
+
+/// Returns the rectangle for each of the seven segments, in bit order, within a digit cell.
+fn segment_rects(cell: Rect, thickness: f32) -> [Rect; 7] {
+    let (l, r, t, b) = (cell.left(), cell.right(), cell.top(), cell.bottom());
+    let m = cell.center().y;
+    let h = thickness * 0.5;
+    [
+        Rect::from_min_max(pos2(l + thickness, t), pos2(r - thickness, t + thickness)),
+        Rect::from_min_max(pos2(r - thickness, t + thickness), pos2(r, m - h)),
+        Rect::from_min_max(pos2(r - thickness, m + h), pos2(r, b - thickness)),
+        Rect::from_min_max(pos2(l + thickness, b - thickness), pos2(r - thickness, b)),
+        Rect::from_min_max(pos2(l, m + h), pos2(l + thickness, b - thickness)),
+        Rect::from_min_max(pos2(l, t + thickness), pos2(l + thickness, m - h)),
+        Rect::from_min_max(pos2(l + thickness, m - h), pos2(r - thickness, m + h)),
+    ]
+}
+
+/// Draws `text` as a retro seven-segment LED display. Supports the digits, '-', '.' and ' '.
+/// A `warning` display lights up red to catch the player's eye.
+pub fn segment_display_ui(
+    ui: &mut egui::Ui,
+    text: &str,
+    digit_height: f32,
+    warning: bool,
+) -> egui::Response {
+    let digit_width = digit_height * 0.55;
+    let thickness = digit_height * 0.12;
+    let spacing = digit_height * 0.15;
+    let dot_width = thickness * 2.0;
+
+    let content_width: f32 = text
+        .chars()
+        .map(|c| if c == '.' { dot_width } else { digit_width } + spacing)
+        .sum();
+    let desired_size = vec2(content_width + spacing, digit_height + spacing * 2.0);
+    let (rect, response) = ui.allocate_exact_size(desired_size, egui::Sense::hover());
+    response.widget_info(|| egui::WidgetInfo::labeled(egui::WidgetType::Label, text));
+
+    if ui.is_rect_visible(rect) {
+        let painter = ui.painter();
+        let (background, on, off) = if warning {
+            (
+                constants::COLOR_SEGMENT_WARNING_BACKGROUND,
+                constants::COLOR_SEGMENT_WARNING_ON,
+                constants::COLOR_SEGMENT_WARNING_OFF,
+            )
+        } else {
+            (
+                constants::COLOR_SEGMENT_BACKGROUND,
+                constants::COLOR_SEGMENT_ON,
+                constants::COLOR_SEGMENT_OFF,
+            )
+        };
+        painter.rect_filled(rect, 2.0, background);
+
+        let mut x = rect.left() + spacing;
+        let top = rect.top() + spacing;
+        for c in text.chars() {
+            if c == '.' {
+                let dot = Rect::from_min_size(
+                    pos2(x + thickness * 0.5, top + digit_height - thickness),
+                    vec2(thickness, thickness),
+                );
+                painter.rect_filled(dot, 0.0, on);
+                x += dot_width + spacing;
+                continue;
+            }
+
+            let lit = segments_for_char(c);
+            let cell = Rect::from_min_size(pos2(x, top), vec2(digit_width, digit_height));
+            segment_rects(cell, thickness)
+                .iter()
+                .enumerate()
+                .for_each(|(i, seg)| {
+                    let color = if lit & (1 << i) != 0 { on } else { off };
+                    painter.rect_filled(seg.shrink(thickness * 0.1), thickness * 0.3, color);
+                });
+            x += digit_width + spacing;
+        }
+    }
+
+    response
+}
+
+#[test]
+fn test_segments_for_char() {
+    assert_eq!(segments_for_char('8'), 0b1111111);
+    assert_eq!(segments_for_char('1'), SEG_B | SEG_C);
+    assert_eq!(segments_for_char('-'), SEG_G);
+    assert_eq!(segments_for_char(' '), 0);
+}
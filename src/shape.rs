@@ -0,0 +1,111 @@
+use serde::{Deserialize, Serialize};
+
+use crate::i18n::tr;
+use crate::minesweeper::GameBoard;
+
+/// The outline of a board. Every shape but the rectangle is read from a template, which
+/// is stretched to fit the board: `.` for a square and `_` for a void, the same as in the
+/// board text `GameBoard::from_ascii` reads. Lines starting with `#` are comments.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+pub enum BoardShape {
+    #[default]
+    Rectangle,
+    Heart,
+    Diamond,
+    Donut,
+}
+
+impl BoardShape {
+    pub fn all() -> [BoardShape; 4] {
+        [
+            BoardShape::Rectangle,
+            BoardShape::Heart,
+            BoardShape::Diamond,
+            BoardShape::Donut,
+        ]
+    }
+
+    /// The name shown to the player, in the UI's language
+    pub fn name(&self) -> &'static str {
+        match *self {
+            BoardShape::Rectangle => tr("shape-rectangle"),
+            BoardShape::Heart => tr("shape-heart"),
+            BoardShape::Diamond => tr("shape-diamond"),
+            BoardShape::Donut => tr("shape-donut"),
+        }
+    }
+
+    fn template(&self) -> Option<&'static str> {
+        match *self {
+            BoardShape::Rectangle => None,
+            BoardShape::Heart => Some(include_str!("../assets/shapes/heart.txt")),
+            BoardShape::Diamond => Some(include_str!("../assets/shapes/diamond.txt")),
+            BoardShape::Donut => Some(include_str!("../assets/shapes/donut.txt")),
+        }
+    }
+
+    /// Whether each square of a `width` by `height` board is a void, row by row
+    pub fn voids(&self, width: u32, height: u32) -> Vec<bool> {
+        let Some(template) = self.template() else {
+            return vec![false; (width * height) as usize];
+        };
+        let rows: Vec<Vec<bool>> = template
+            .lines()
+            .map(|l| l.trim())
+            .filter(|l| !l.is_empty() && !l.starts_with('#'))
+            .map(|l| l.chars().map(|c| c == '_').collect())
+            .collect();
+        // Each square takes whatever is under its middle in the template
+        (0..height)
+            .flat_map(|y| (0..width).map(move |x| (x, y)))
+            .map(|(x, y)| {
+                let row = &rows[((2 * y + 1) as usize * rows.len()) / (2 * height as usize)];
+                row[((2 * x + 1) as usize * row.len()) / (2 * width as usize)]
+            })
+            .collect()
+    }
+
+    /// Cuts the shape out of each layer of a board that hasn't been populated yet
+    pub fn apply(&self, board: &mut GameBoard) {
+        if *self == BoardShape::Rectangle {
+            return;
+        }
+        let (width, layer_height) = (board.width, board.layer_height());
+        let voids = self.voids(width, layer_height);
+        board.set_voids(|x, y| voids[(y % layer_height * width + x) as usize]);
+    }
+
+    /// Mines for a `width` by `height` board of this shape, so the squares left are as
+    /// densely mined as `num_mines` would make the whole rectangle
+    pub fn mines_for(&self, width: u32, height: u32, num_mines: u32) -> u32 {
+        let voids = self.voids(width, height);
+        let squares = voids.iter().filter(|v| !**v).count() as u64;
+        let total = voids.len().max(1) as u64;
+        ((num_mines as u64 * squares + total / 2) / total).max(1) as u32
+    }
+}
+
+#[test]
+fn test_board_shape() -> Result<(), crate::minesweeper::Error> {
+    // The templates are the same size, so each square of a board that size is one of theirs
+    BoardShape::all().into_iter().for_each(|shape| {
+        let voids = shape.voids(17, 15);
+        assert_eq!(voids.len(), 17 * 15);
+        assert_eq!(voids.iter().any(|v| *v), shape != BoardShape::Rectangle);
+    });
+    // The donut's hole is in the middle, whatever the size of the board
+    let donut = BoardShape::Donut.voids(9, 9);
+    assert!(donut[4 * 9 + 4]);
+    assert!(!donut[4 * 9]);
+
+    assert_eq!(BoardShape::Rectangle.mines_for(9, 9, 10), 10);
+    let mines = BoardShape::Heart.mines_for(30, 16, 99);
+    assert!(mines > 50 && mines < 99);
+
+    let mut board = GameBoard::new(9, 9);
+    BoardShape::Diamond.apply(&mut board);
+    assert!(board.get_square(0, 0)?.is_void());
+    assert!(!board.get_square(4, 4)?.is_void());
+    board.populate_mines(BoardShape::Diamond.mines_for(9, 9, 10))?;
+    Ok(())
+}
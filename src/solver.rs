@@ -0,0 +1,548 @@
+use std::collections::BTreeSet;
+
+use serde::{Deserialize, Serialize};
+
+use crate::history::LossCause;
+use crate::minesweeper::*;
+
+/// A revealed numeral's constraint on its unknown neighbors: exactly `mines` of `squares`
+/// are mined
+#[derive(PartialEq, Eq)]
+struct Constraint {
+    squares: BTreeSet<u32>,
+    mines: u32,
+}
+
+/// What can be logically deduced about the unrevealed squares of a board from its revealed
+/// numerals alone. Flags are ignored, since they may be wrong.
+#[derive(Debug, Clone, Default)]
+pub struct Analysis {
+    pub safe: Vec<Coordinate>,
+    pub mines: Vec<Coordinate>,
+}
+
+impl Analysis {
+    pub fn is_mine(&self, coord: &Coordinate) -> bool {
+        self.mines.contains(coord)
+    }
+}
+
+/// Deduces which unrevealed squares are safe and which are mined. `visible` holds the
+/// numeral of each revealed square and `None` for unrevealed ones, and `voids` marks the
+/// squares outside the board's shape, which are left out. `height` counts the rows of all
+/// `layers` of the board.
+///
+/// Applies the single numeral rules (a numeral satisfied by known mines makes the rest of
+/// its neighbors safe, one with as many unknown neighbors as remaining mines makes them
+/// all mines) and the subset rule between pairs of numerals, until nothing more can be
+/// deduced. This isn't a complete solver, so some positions that could be worked out by
+/// exhaustive search are reported as unknown.
+pub fn analyze(
+    width: u32,
+    height: u32,
+    layers: u32,
+    visible: &[Option<u32>],
+    voids: &[bool],
+) -> Analysis {
+    deduce(width, height, layers, visible, voids, true)
+}
+
+/// The unrevealed squares proven safe by the single numeral rules alone, as `analyze()`
+/// would find them without comparing numerals. These are the ones there's no thinking
+/// to, just busywork.
+pub fn trivially_safe(
+    width: u32,
+    height: u32,
+    layers: u32,
+    visible: &[Option<u32>],
+    voids: &[bool],
+) -> Vec<Coordinate> {
+    deduce(width, height, layers, visible, voids, false).safe
+}
+
+fn deduce(
+    width: u32,
+    height: u32,
+    layers: u32,
+    visible: &[Option<u32>],
+    voids: &[bool],
+    subsets: bool,
+) -> Analysis {
+    let mut safe = BTreeSet::new();
+    let mut mines = BTreeSet::new();
+
+    loop {
+        let mut constraints: Vec<Constraint> = vec![];
+        visible.iter().enumerate().for_each(|(idx, v)| {
+            let Some(numeral) = *v else {
+                return;
+            };
+            let mut remaining = numeral;
+            let mut squares = BTreeSet::new();
+            neighbors(width, height, layers, idx as u32)
+                .filter(|n| {
+                    visible[*n as usize].is_none() && !voids[*n as usize] && !safe.contains(n)
+                })
+                .for_each(|n| {
+                    if mines.contains(&n) {
+                        remaining = remaining.saturating_sub(1);
+                    } else {
+                        squares.insert(n);
+                    }
+                });
+            let c = Constraint {
+                squares,
+                mines: remaining,
+            };
+            if !c.squares.is_empty() && !constraints.contains(&c) {
+                constraints.push(c);
+            }
+        });
+
+        let mut deduced_safe = BTreeSet::new();
+        let mut deduced_mines = BTreeSet::new();
+        constraints.iter().for_each(|c| {
+            if c.mines == 0 {
+                deduced_safe.extend(c.squares.iter().copied());
+            } else if c.mines as usize == c.squares.len() {
+                deduced_mines.extend(c.squares.iter().copied());
+            }
+        });
+
+        // Only look for subsets when the simple rules are exhausted, as it's much slower
+        if subsets && deduced_safe.is_empty() && deduced_mines.is_empty() {
+            constraint_pairs(&constraints).for_each(|(a, b)| {
+                if a.mines > b.mines || !a.squares.is_subset(&b.squares) {
+                    return;
+                }
+                let rest: Vec<u32> = b.squares.difference(&a.squares).copied().collect();
+                let rest_mines = b.mines - a.mines;
+                if rest_mines == 0 {
+                    deduced_safe.extend(rest);
+                } else if rest_mines as usize == rest.len() {
+                    deduced_mines.extend(rest);
+                }
+            });
+        }
+
+        if deduced_safe.is_empty() && deduced_mines.is_empty() {
+            break;
+        }
+        safe.extend(deduced_safe);
+        mines.extend(deduced_mines);
+    }
+
+    let to_coordinate = |idx: u32| Coordinate::from((idx % width, idx / width));
+    Analysis {
+        safe: safe.into_iter().map(to_coordinate).collect(),
+        mines: mines.into_iter().map(to_coordinate).collect(),
+    }
+}
+
+/// How far playing a board by deduction alone got
+#[derive(Debug, Clone)]
+pub struct Solution {
+    pub won: bool,
+    /// Rounds of deduction after the first click
+    pub steps: u32,
+    /// Safe squares still covered when nothing more could be deduced
+    pub remaining: u32,
+}
+
+/// Plays a populated board from `first_click` without ever guessing. Every square that can
+/// be deduced to be safe is revealed and every deduced mine flagged, round after round,
+/// until the board is won or a guess would be needed.
+pub fn solve(board: &mut GameBoard, first_click: &Coordinate) -> Result<Solution, Error> {
+    board.play(first_click.x, first_click.y, RevealType::Reveal)?;
+    let mut steps = 0;
+    while !board.is_win_configuration() && !board.is_loss_configuration() {
+        let analysis = analyze(
+            board.width,
+            board.height,
+            board.layers(),
+            &board.revealed_numerals(),
+            &board.voids(),
+        );
+        if analysis.safe.is_empty() {
+            break;
+        }
+        analysis
+            .safe
+            .iter()
+            .try_for_each(|c| board.play(c.x, c.y, RevealType::Reveal).map(|_| ()))?;
+        analysis.mines.iter().try_for_each(|c| {
+            if !board.get_square_by_coordinate(c)?.is_flagged {
+                board.play(c.x, c.y, RevealType::Flag)?;
+            }
+            Ok::<(), Error>(())
+        })?;
+        steps += 1;
+    }
+    Ok(Solution {
+        won: board.is_win_configuration(),
+        steps,
+        remaining: (board.num_squares() - board.num_mines)
+            .saturating_sub(board.num_safe_revealed()),
+    })
+}
+
+/// Keeps track of whether any square of a board can be deduced to be safe, so the player
+/// knows when they have to guess. Deductions stay true as more of the board is revealed, so
+/// the board is only analyzed again once every square known to be safe has been revealed.
+#[derive(Debug, Clone, Default)]
+pub struct GuessTracker {
+    /// Squares deduced to be safe that were still covered at the last update
+    known_safe: Vec<Coordinate>,
+    /// Squares revealed at the last update
+    updated_at: Option<u32>,
+    /// Squares revealed when the board was last analyzed
+    analyzed_at: Option<u32>,
+}
+
+impl GuessTracker {
+    /// Catches up with the plays made on `board` since the last update
+    pub fn update(&mut self, board: &GameBoard) {
+        let num_revealed = board.num_revealed();
+        if self.updated_at == Some(num_revealed) {
+            return;
+        }
+        self.known_safe.retain(|c| {
+            board
+                .get_square_by_coordinate(c)
+                .is_ok_and(|s| !s.is_revealed)
+        });
+        if self.known_safe.is_empty() {
+            self.analyze(board);
+        }
+        self.updated_at = Some(num_revealed);
+    }
+
+    /// True if nothing on the board can be deduced to be safe
+    pub fn needs_guess(&self) -> bool {
+        self.known_safe.is_empty()
+    }
+
+    /// Works out whether revealing `coord` on `board` would be a guess, as
+    /// `Deduction::of_reveal` does. Revealing a square already known to be safe doesn't need
+    /// the board analyzed again.
+    pub fn deduction(&mut self, board: &GameBoard, coord: &Coordinate) -> Option<Deduction> {
+        let covered = board
+            .get_square_by_coordinate(coord)
+            .is_ok_and(|s| !s.is_revealed && !s.is_flagged);
+        if !covered || !board.is_populated {
+            return None;
+        }
+        self.update(board);
+        // Squares that became known to be safe since the last analysis aren't in the list
+        if !self.known_safe.contains(coord) && self.analyzed_at != self.updated_at {
+            self.analyze(board);
+        }
+        Some(if self.known_safe.contains(coord) {
+            Deduction::Deduced
+        } else if self.known_safe.is_empty() {
+            Deduction::ForcedGuess
+        } else {
+            Deduction::AvoidableGuess
+        })
+    }
+
+    fn analyze(&mut self, board: &GameBoard) {
+        self.known_safe = analyze(
+            board.width,
+            board.height,
+            board.layers(),
+            &board.revealed_numerals(),
+            &board.voids(),
+        )
+        .safe;
+        self.analyzed_at = Some(board.num_revealed());
+    }
+}
+
+/// Whether the square a reveal was made on could have been deduced to be safe at the time
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+pub enum Deduction {
+    /// The square was known to be safe
+    Deduced,
+    /// Nothing on the board could be deduced to be safe, so there was no choice but to guess
+    ForcedGuess,
+    /// A guess made while other squares were known to be safe
+    AvoidableGuess,
+}
+
+impl Deduction {
+    /// Works out whether revealing `coord` on `board` would be a guess. `None` if it
+    /// wouldn't reveal anything, or the board's mines haven't been placed yet, as the first
+    /// click is always kept clear.
+    pub fn of_reveal(board: &GameBoard, coord: &Coordinate) -> Option<Self> {
+        GuessTracker::default().deduction(board, coord)
+    }
+
+    pub fn is_guess(&self) -> bool {
+        *self != Deduction::Deduced
+    }
+}
+
+/// What the player could have known when they made the play that lost the game
+#[derive(Debug, Clone)]
+pub struct PostMortem {
+    pub cause: LossCause,
+    /// Squares that could have been deduced to be safe before the losing play
+    pub missed_safe: Vec<Coordinate>,
+    /// Flags that a losing chord went around which weren't on mines
+    pub wrong_flags: Vec<Coordinate>,
+}
+
+impl PostMortem {
+    /// Analyzes the board as it was before the losing play. `visible` must not include
+    /// squares revealed by that play.
+    pub fn new(
+        width: u32,
+        height: u32,
+        layers: u32,
+        visible: &[Option<u32>],
+        voids: &[bool],
+        detonated_on: &Coordinate,
+    ) -> Self {
+        let analysis = analyze(width, height, layers, visible, voids);
+        PostMortem {
+            cause: if analysis.is_mine(detonated_on) {
+                LossCause::Misclick
+            } else {
+                LossCause::Guess
+            },
+            missed_safe: analysis.safe,
+            wrong_flags: vec![],
+        }
+    }
+
+    /// True if there was nothing left to do but guess
+    pub fn is_forced_guess(&self) -> bool {
+        self.cause == LossCause::Guess && self.missed_safe.is_empty()
+    }
+
+    pub fn verdict(&self) -> String {
+        let missed = match self.missed_safe.len() {
+            1 => "1 square was".to_string(),
+            n => format!("{} squares were", n),
+        };
+        if self.cause == LossCause::Misclick {
+            format!(
+                "That square could have been deduced to be a mine, and {} known to be safe.",
+                missed
+            )
+        } else if self.is_forced_guess() {
+            "That was a forced guess: no square could have been deduced to be safe.".to_string()
+        } else {
+            format!(
+                "That was a guess that could have been avoided: {} known to be safe.",
+                missed
+            )
+        }
+    }
+}
+
+/// A revealed numeral as the player reads it: `mines` more mines than the flags next to it
+/// already account for, among its covered `squares`. Negative if it has too many flags.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NumeralConstraint {
+    pub squares: Vec<Coordinate>,
+    pub mines: i64,
+}
+
+impl NumeralConstraint {
+    /// The constraint of the square at `coord`, if it's a revealed numeral
+    pub fn of(board: &GameBoard, coord: &Coordinate) -> Option<Self> {
+        let sqr = board.get_square_by_coordinate(coord).ok()?;
+        if !sqr.is_revealed || sqr.is_mine() || sqr.numeral == 0 {
+            return None;
+        }
+        let mut mines = sqr.numeral as i64;
+        let mut squares = vec![];
+        let idx = coord.y * board.width + coord.x;
+        neighbors(board.width, board.height, board.layers(), idx).for_each(|n| {
+            let c = Coordinate::from((n % board.width, n / board.width));
+            match board.get_square_by_coordinate(&c) {
+                Ok(s) if s.is_flagged => mines -= 1,
+                Ok(s) if !s.is_revealed && !s.is_void() => squares.push(c),
+                _ => {}
+            }
+        });
+        Some(NumeralConstraint { squares, mines })
+    }
+}
+
+/// Every ordered pair of distinct constraints
+fn constraint_pairs(
+    constraints: &[Constraint],
+) -> impl Iterator<Item = (&Constraint, &Constraint)> {
+    constraints.iter().enumerate().flat_map(move |(i, a)| {
+        constraints
+            .iter()
+            .enumerate()
+            .filter(move |&(j, _)| i != j)
+            .map(move |(_, b)| (a, b))
+    })
+}
+
+#[test]
+fn test_analyze_single_numeral() -> Result<(), Error> {
+    // [0 0 1 * 1] with the last two squares unrevealed
+    let gb = GameBoard::new_with_mines(5, 1, &[Coordinate::from((3, 0))])?;
+    let visible = [Some(0), Some(0), Some(1), None, None];
+    let analysis = analyze(gb.width, gb.height, 1, &visible, &gb.voids());
+    assert_eq!(analysis.mines, [Coordinate::from((3, 0))]);
+    assert!(analysis.safe.is_empty());
+
+    // [* 1 _]: the void can't be the 1's mine
+    let mut gb = GameBoard::from_ascii("*1_")?;
+    gb.reveal(1, 0)?;
+    let analysis = analyze(gb.width, gb.height, 1, &gb.revealed_numerals(), &gb.voids());
+    assert_eq!(analysis.mines, [Coordinate::from((0, 0))]);
+    Ok(())
+}
+
+#[test]
+fn test_numeral_constraint() -> Result<(), Error> {
+    // [. 1 * *] with the mines covered and one of them flagged
+    let mut gb = GameBoard::from_ascii(".1**")?;
+    gb.reveal(0, 0)?;
+    let constraint = NumeralConstraint::of(&gb, &Coordinate::from((1, 0)));
+    assert_eq!(
+        constraint,
+        Some(NumeralConstraint {
+            squares: vec![Coordinate::from((2, 0))],
+            mines: 1,
+        })
+    );
+    gb.flag(2, 0)?;
+    let constraint = NumeralConstraint::of(&gb, &Coordinate::from((1, 0)));
+    assert!(constraint.is_some_and(|c| c.squares.is_empty() && c.mines == 0));
+
+    // Only revealed numerals have one
+    assert_eq!(NumeralConstraint::of(&gb, &Coordinate::from((0, 0))), None);
+    assert_eq!(NumeralConstraint::of(&gb, &Coordinate::from((3, 0))), None);
+    Ok(())
+}
+
+#[test]
+fn test_trivially_safe() {
+    // [1 * 1 . .]: the first 1 gives away the mine, which is all the second 1 needs
+    let visible = [Some(1), None, Some(1), None, None];
+    let safe = trivially_safe(5, 1, 1, &visible, &[false; 5]);
+    assert_eq!(safe, [Coordinate::from((3, 0))]);
+
+    // The middle of the top row above a 1-2-1 takes comparing the numerals
+    let visible = [None, None, None, Some(1), Some(2), Some(1)];
+    assert!(trivially_safe(3, 2, 1, &visible, &[false; 6]).is_empty());
+}
+
+#[test]
+fn test_post_mortem() {
+    // [0 0 1 * 1] before revealing anything past the 1
+    let visible = [Some(0), Some(0), Some(1), None, None];
+    let post_mortem = PostMortem::new(5, 1, 1, &visible, &[false; 5], &Coordinate::from((3, 0)));
+    assert_eq!(post_mortem.cause, LossCause::Misclick);
+    assert!(post_mortem.missed_safe.is_empty());
+
+    // A lone 1 in the corner of a 2x2 board gives nothing away
+    let visible = [Some(1), None, None, None];
+    let post_mortem = PostMortem::new(2, 2, 1, &visible, &[false; 4], &Coordinate::from((1, 1)));
+    assert!(post_mortem.is_forced_guess());
+
+    // With the 1-2-1 revealed, the middle of the top row is known to be safe
+    let visible = [None, None, None, Some(1), Some(2), Some(1)];
+    let post_mortem = PostMortem::new(3, 2, 1, &visible, &[false; 6], &Coordinate::from((0, 0)));
+    assert_eq!(post_mortem.cause, LossCause::Misclick);
+    assert_eq!(post_mortem.missed_safe, [Coordinate::from((1, 0))]);
+}
+
+#[test]
+fn test_analyze_one_two_one() -> Result<(), Error> {
+    // Top row unrevealed above a revealed 1-2-1
+    let mut gb =
+        GameBoard::new_with_mines(3, 2, &[Coordinate::from((0, 0)), Coordinate::from((2, 0))])?;
+    (0..3).try_for_each(|x| gb.reveal(x, 1).map(|_| ()))?;
+    let analysis = analyze(gb.width, gb.height, 1, &gb.revealed_numerals(), &gb.voids());
+    assert_eq!(
+        analysis.mines,
+        [Coordinate::from((0, 0)), Coordinate::from((2, 0))]
+    );
+    assert_eq!(analysis.safe, [Coordinate::from((1, 0))]);
+    Ok(())
+}
+
+#[test]
+fn test_deduction_of_reveal() -> Result<(), Error> {
+    // Above a revealed 1-2-1, only the middle square is known to be safe
+    let mut gb =
+        GameBoard::new_with_mines(3, 2, &[Coordinate::from((0, 0)), Coordinate::from((2, 0))])?;
+    (0..3).try_for_each(|x| gb.reveal(x, 1).map(|_| ()))?;
+    let deduction = |gb: &GameBoard, x, y| Deduction::of_reveal(gb, &Coordinate::from((x, y)));
+    assert_eq!(deduction(&gb, 1, 0), Some(Deduction::Deduced));
+    assert_eq!(deduction(&gb, 0, 0), Some(Deduction::AvoidableGuess));
+    assert_eq!(deduction(&gb, 1, 1), None);
+
+    // [0 0 1 * 1]: nothing past the 1 can be known to be safe
+    let mut gb = GameBoard::new_with_mines(5, 1, &[Coordinate::from((3, 0))])?;
+    gb.reveal(0, 0)?;
+    assert_eq!(deduction(&gb, 4, 0), Some(Deduction::ForcedGuess));
+    assert!(deduction(&gb, 4, 0).is_some_and(|d| d.is_guess()));
+
+    // The first click is never a guess
+    assert_eq!(deduction(&GameBoard::new_seeded(5, 1, 1), 0, 0), None);
+    Ok(())
+}
+
+#[test]
+fn test_guess_tracker() -> Result<(), Error> {
+    // [* 2 * / 1 2 1 / 0 0 0]
+    let mut gb =
+        GameBoard::new_with_mines(3, 3, &[Coordinate::from((0, 0)), Coordinate::from((2, 0))])?;
+    let mut tracker = GuessTracker::default();
+    tracker.update(&gb);
+    assert!(tracker.needs_guess());
+
+    // The opening gives away the 2, which stays known until it's revealed
+    gb.play(1, 2, RevealType::Reveal)?;
+    tracker.update(&gb);
+    assert!(!tracker.needs_guess());
+    gb.play(0, 0, RevealType::Flag)?;
+    tracker.update(&gb);
+    assert!(!tracker.needs_guess());
+    // Reveals are judged the same as on a board seen for the first time
+    for coord in [(1, 0), (2, 0)].map(Coordinate::from) {
+        assert_eq!(
+            tracker.deduction(&gb, &coord),
+            Deduction::of_reveal(&gb, &coord)
+        );
+    }
+    assert_eq!(
+        tracker.deduction(&gb, &Coordinate::from((1, 0))),
+        Some(Deduction::Deduced)
+    );
+
+    // Only the mines are left
+    gb.play(1, 0, RevealType::Reveal)?;
+    tracker.update(&gb);
+    assert!(tracker.needs_guess());
+    Ok(())
+}
+
+#[test]
+fn test_solve() -> Result<(), Error> {
+    // [* 2 * / 1 2 1 / 0 0 0]: the opening reveals the 1-2-1, which gives away the 2
+    let mut gb =
+        GameBoard::new_with_mines(3, 3, &[Coordinate::from((0, 0)), Coordinate::from((2, 0))])?;
+    let solution = solve(&mut gb, &Coordinate::from((1, 2)))?;
+    assert!(solution.won);
+    assert_eq!(solution.steps, 1);
+    assert_eq!(gb.num_flags(), 2);
+
+    // [0 0 1 * 1]: nothing says whether the last square is safe
+    let mut gb = GameBoard::new_with_mines(5, 1, &[Coordinate::from((3, 0))])?;
+    let solution = solve(&mut gb, &Coordinate::from((0, 0)))?;
+    assert!(!solution.won);
+    assert_eq!(solution.remaining, 1);
+    Ok(())
+}
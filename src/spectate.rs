@@ -0,0 +1,404 @@
+use std::net::TcpStream;
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::constants::DEFAULT_SPECTATE_PORT;
+use crate::enums::GameState;
+use crate::minesweeper::*;
+use crate::net::{accept_connections, connect, encode_line, read_lines, Closer, Peers};
+use crate::plays::PlayEntry;
+use crate::state::GameSettings;
+
+/// Most columns or rows of a board a spectator will make for the game being watched, so
+/// the player can't have it take up all of the spectator's memory
+const MAX_BOARD_SIDE: u32 = 1024;
+/// Most boards, and layers of a board, the game offers
+const MAX_BOARDS: u32 = 4;
+const MAX_LAYERS: u32 = 3;
+
+/// A change to the game being broadcast. Together they're enough to rebuild the boards move
+/// by move, since a board's mines are reproducible from its seed and first click.
+#[derive(Clone, Deserialize, Serialize)]
+#[serde(tag = "type")]
+pub enum SpectateEvent {
+    /// A new game, replacing the one being watched
+    Game {
+        name: String,
+        settings: GameSettings,
+    },
+    /// Mines were placed on a board around its first click
+    Populate {
+        board: usize,
+        seed: u64,
+        first_click: Coordinate,
+    },
+    /// A play on a board, at the board's own coordinates, `time` seconds into the game
+    Play {
+        board: usize,
+        entry: PlayEntry,
+        time: f64,
+    },
+    /// The boards were restarted with the same mines
+    Restart,
+    End {
+        won: bool,
+        time: f64,
+    },
+}
+
+/// The game being watched, rebuilt from the events received
+#[derive(Clone, Default)]
+pub struct SpectatorView {
+    /// Name of the player being watched
+    pub name: String,
+    pub settings: Option<GameSettings>,
+    pub boards: Vec<GameBoard>,
+    /// When the game started, by the spectator's clock
+    started: Option<f64>,
+    ended: Option<(bool, f64)>,
+}
+
+impl SpectatorView {
+    /// Applies an event received at `now` seconds
+    pub fn apply(&mut self, event: SpectateEvent, now: f64) -> Result<(), Error> {
+        match event {
+            SpectateEvent::Game { name, settings } => {
+                let sides = [settings.width, settings.height];
+                if sides.iter().any(|&n| n == 0 || n > MAX_BOARD_SIDE)
+                    || !(1..=MAX_BOARDS).contains(&settings.num_boards)
+                    || !(1..=MAX_LAYERS).contains(&settings.layers)
+                {
+                    return Err(Error::InvalidBoardData {
+                        reason: format!(
+                            "can't watch {} boards of {}x{} squares in {} layers",
+                            settings.num_boards, settings.width, settings.height, settings.layers
+                        ),
+                    });
+                }
+                self.boards = (0..settings.num_boards)
+                    .map(|_| settings.new_board(0))
+                    .collect();
+                self.name = name;
+                self.settings = Some(settings);
+                self.started = None;
+                self.ended = None;
+            }
+            SpectateEvent::Populate {
+                board,
+                seed,
+                first_click,
+            } => {
+                let Some(settings) = &self.settings else {
+                    return Ok(());
+                };
+                let mut gameboard = settings.new_board(seed);
+                gameboard.populate_mines_around(settings.num_mines, Some(first_click))?;
+                if settings.use_numerals {
+                    gameboard.populate_numerals()?;
+                }
+                if let Some(b) = self.boards.get_mut(board) {
+                    *b = gameboard;
+                }
+            }
+            SpectateEvent::Play { board, entry, time } => {
+                // Kept in step with the player's clock, which also catches up spectators
+                // who joined partway through
+                self.started = Some(now - time);
+                if let Some(b) = self.boards.get_mut(board) {
+                    b.play(entry.coord.x, entry.coord.y, entry.play_type)?;
+                }
+            }
+            SpectateEvent::Restart => {
+                self.boards.iter_mut().for_each(|b| b.reset_existing());
+                self.started = None;
+                self.ended = None;
+            }
+            SpectateEvent::End { won, time } => self.ended = Some((won, time)),
+        }
+        Ok(())
+    }
+
+    pub fn game_state(&self) -> GameState {
+        match self.ended {
+            Some((true, _)) => GameState::EndedWin,
+            Some((false, _)) => GameState::EndedLoss,
+            None if self.boards.iter().any(|b| b.is_loss_configuration()) => GameState::EndedLoss,
+            None if self.started.is_some() => GameState::Playing,
+            None => GameState::NotStarted,
+        }
+    }
+
+    /// Seconds into the game being watched
+    pub fn elapsed_time(&self, now: f64) -> f64 {
+        match (self.ended, self.started) {
+            (Some((_, time)), _) => time,
+            (None, Some(started)) => now - started,
+            (None, None) => 0.0,
+        }
+    }
+}
+
+/// Connections from spectators
+#[derive(Default)]
+struct Audience {
+    peers: Peers,
+    /// Everything sent since the current game began, to catch up spectators who join
+    /// partway through
+    events: Vec<String>,
+}
+
+/// Streams the player's games to anyone who connects
+#[derive(Clone)]
+pub struct Broadcaster {
+    audience: Arc<Mutex<Audience>>,
+    _closer: Arc<Closer>,
+}
+
+impl Broadcaster {
+    pub fn start(port: u16) -> Result<Self> {
+        let audience = Arc::new(Mutex::new(Audience::default()));
+        let closing = audience.clone();
+        let closer = Arc::new(Closer::new(move || {
+            if let Ok(a) = closing.lock() {
+                a.peers.shutdown();
+            }
+        }));
+
+        let accepting = audience.clone();
+        accept_connections(
+            port,
+            closer.closed.clone(),
+            move |stream| Broadcaster::welcome(&accepting, stream),
+            |e| log::warn!("Stopped accepting spectators: {}", e),
+        )?;
+        Ok(Broadcaster {
+            audience,
+            _closer: closer,
+        })
+    }
+
+    /// Catches a new spectator up on the current game
+    fn welcome(audience: &Arc<Mutex<Audience>>, stream: TcpStream) -> Result<()> {
+        let id = {
+            let mut a = audience
+                .lock()
+                .map_err(|_| anyhow!("Connection state poisoned"))?;
+            let id = a.peers.add(&stream)?;
+            let backlog = a.events.concat();
+            a.peers.send(id, &backlog);
+            id
+        };
+
+        // Spectators don't send anything, so reading only tells when they've gone
+        let audience = audience.clone();
+        thread::spawn(move || {
+            read_lines(stream).count();
+            if let Ok(mut a) = audience.lock() {
+                a.peers.remove(id);
+            }
+        });
+        Ok(())
+    }
+
+    pub fn send(&self, event: &SpectateEvent) {
+        let Ok(line) = encode_line(event) else {
+            return;
+        };
+        if let Ok(mut a) = self.audience.lock() {
+            if matches!(event, SpectateEvent::Game { .. }) {
+                a.events.clear();
+            }
+            a.peers.broadcast(&line, None);
+            a.events.push(line);
+        }
+    }
+
+    pub fn num_watching(&self) -> usize {
+        self.audience.lock().map_or(0, |a| a.peers.len())
+    }
+}
+
+/// Events received from the player being watched
+#[derive(Default)]
+struct Feed {
+    stream: Option<TcpStream>,
+    events: Vec<SpectateEvent>,
+    error: Option<String>,
+}
+
+/// A read-only live view of someone else's games
+#[derive(Clone)]
+pub struct Spectator {
+    pub view: SpectatorView,
+    feed: Arc<Mutex<Feed>>,
+    _closer: Arc<Closer>,
+}
+
+impl Spectator {
+    /// Connects to the player broadcasting at `address`, which takes the default port if it
+    /// doesn't include one
+    pub fn watch(ctx: &egui::Context, address: &str) -> Self {
+        let feed = Arc::new(Mutex::new(Feed::default()));
+        let closing = feed.clone();
+        let closer = Arc::new(Closer::new(move || {
+            if let Some(stream) = closing.lock().ok().and_then(|mut f| f.stream.take()) {
+                let _ = stream.shutdown(std::net::Shutdown::Both);
+            }
+        }));
+
+        let (reading, ctx, address) = (feed.clone(), ctx.clone(), address.to_string());
+        thread::spawn(move || {
+            let stream = match connect(&address, DEFAULT_SPECTATE_PORT)
+                .and_then(|s| Ok((s.try_clone()?, s)))
+            {
+                Ok((stream, reader)) => {
+                    if let Ok(mut f) = reading.lock() {
+                        f.stream = Some(stream);
+                    }
+                    reader
+                }
+                Err(e) => {
+                    if let Ok(mut f) = reading.lock() {
+                        f.error = Some(format!("Unable to watch {}: {}", address, e));
+                    }
+                    ctx.request_repaint();
+                    return;
+                }
+            };
+            for line in read_lines(stream) {
+                match serde_json::from_str(&line) {
+                    Ok(event) => {
+                        if let Ok(mut f) = reading.lock() {
+                            f.events.push(event);
+                        }
+                        ctx.request_repaint();
+                    }
+                    Err(e) => log::warn!("Ignoring invalid event: {}", e),
+                }
+            }
+            if let Ok(mut f) = reading.lock() {
+                f.stream = None;
+                f.error = Some("The broadcast has ended".to_string());
+            }
+            ctx.request_repaint();
+        });
+
+        Spectator {
+            view: SpectatorView::default(),
+            feed,
+            _closer: closer,
+        }
+    }
+
+    pub fn is_connected(&self) -> bool {
+        self.feed.lock().is_ok_and(|f| f.stream.is_some())
+    }
+
+    pub fn last_error(&self) -> Option<String> {
+        self.feed.lock().ok().and_then(|f| f.error.clone())
+    }
+
+    /// Applies the events received since the last call. Call once per frame.
+    pub fn poll(&mut self, now: f64) {
+        let events = match self.feed.lock() {
+            Ok(mut f) => std::mem::take(&mut f.events),
+            Err(_) => return,
+        };
+        for event in events {
+            if let Err(e) = self.view.apply(event, now) {
+                if let Ok(mut f) = self.feed.lock() {
+                    f.error = Some(format!("Lost track of the game: {}", e));
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn test_spectator_view() -> Result<(), Error> {
+    // The player's game, along with the events it was broadcast as
+    let settings = GameSettings::beginner();
+    let first_click = Coordinate::from((4, 4));
+    let mut player = GameBoard::new(settings.width, settings.height);
+    player.populate_mines_around(settings.num_mines, Some(first_click.clone()))?;
+    player.populate_numerals()?;
+    let mut events = vec![
+        SpectateEvent::Game {
+            name: "Player 1".to_string(),
+            settings: settings.clone(),
+        },
+        SpectateEvent::Populate {
+            board: 0,
+            seed: player.seed,
+            first_click: first_click.clone(),
+        },
+    ];
+    [
+        (first_click, RevealType::Reveal),
+        (Coordinate::from((0, 0)), RevealType::Flag),
+    ]
+    .into_iter()
+    .try_for_each(|(coord, play_type)| {
+        let result = player.play(coord.x, coord.y, play_type.clone())?;
+        events.push(SpectateEvent::Play {
+            board: 0,
+            entry: PlayEntry {
+                coord,
+                play_type,
+                changed: result.squares_changed(),
+                ..Default::default()
+            },
+            time: 2.0,
+        });
+        Ok::<(), Error>(())
+    })?;
+
+    let mut view = SpectatorView::default();
+    events.into_iter().try_for_each(|e| view.apply(e, 10.0))?;
+    assert_eq!(view.name, "Player 1");
+    assert_eq!(view.game_state(), GameState::Playing);
+    assert_eq!(view.elapsed_time(11.0), 3.0);
+    assert_eq!(view.boards[0].num_revealed(), player.num_revealed());
+    assert_eq!(view.boards[0].num_flags(), player.num_flags());
+    (0..settings.width * settings.height).try_for_each(|i| {
+        let (x, y) = (i % settings.width, i / settings.width);
+        assert_eq!(
+            view.boards[0].get_square(x, y)?.is_mine(),
+            player.get_square(x, y)?.is_mine()
+        );
+        Ok::<(), Error>(())
+    })?;
+
+    view.apply(
+        SpectateEvent::End {
+            won: true,
+            time: 5.0,
+        },
+        12.0,
+    )?;
+    assert_eq!(view.game_state(), GameState::EndedWin);
+    assert_eq!(view.elapsed_time(20.0), 5.0);
+
+    // The player can't have the spectator make boards of any size
+    let huge = SpectateEvent::Game {
+        name: "Player 1".to_string(),
+        settings: GameSettings {
+            width: u32::MAX,
+            ..settings.clone()
+        },
+    };
+    assert!(view.apply(huge, 30.0).is_err());
+    let none = SpectateEvent::Game {
+        name: "Player 1".to_string(),
+        settings: GameSettings {
+            num_boards: 0,
+            ..settings
+        },
+    };
+    assert!(view.apply(none, 30.0).is_err());
+    assert_eq!(view.boards.len(), 1);
+    Ok(())
+}
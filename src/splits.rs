@@ -0,0 +1,162 @@
+use std::io::Write;
+use std::net::{TcpStream, ToSocketAddrs};
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::{anyhow, Result};
+
+/// LiveSplit's server component is usually running on the same machine, so it answers
+/// straight away if it's there at all
+const CONNECT_TIMEOUT: Duration = Duration::from_millis(500);
+
+/// The milestones to split at, as percentages of the board's 3BV, from a list like
+/// `25, 50, 75, 100`. Anything that isn't a percentage is left out.
+pub fn parse_milestones(milestones: &str) -> Vec<u32> {
+    let mut parsed: Vec<u32> = milestones
+        .split(|c: char| c == ',' || c.is_whitespace())
+        .filter_map(|m| m.trim_end_matches('%').parse().ok())
+        .filter(|m| (1..=100).contains(m))
+        .collect();
+    parsed.sort_unstable();
+    parsed.dedup();
+    parsed
+}
+
+/// How many of `milestones` have been reached at `percent`
+fn milestones_reached(milestones: &[u32], percent: f64) -> usize {
+    milestones
+        .iter()
+        .take_while(|m| **m as f64 <= percent)
+        .count()
+}
+
+/// Drives a LiveSplit timer through its server component, starting it on the first
+/// click, splitting as the game passes each milestone and resetting it if the game is lost
+/// or abandoned. The connection is made when a game starts, and a timer that can't be
+/// reached is only logged, as it's never worth interrupting the game over.
+#[derive(Clone, Default)]
+pub struct SplitTimer {
+    stream: Option<Arc<TcpStream>>,
+    milestones: Vec<u32>,
+    /// Milestones passed so far in the run going on
+    passed: usize,
+    running: bool,
+}
+
+impl SplitTimer {
+    /// Starts a new run, connecting to the server at `address` if need be
+    pub fn start(&mut self, address: &str, milestones: &str) {
+        if self.stream.is_none() {
+            match connect(address) {
+                Ok(stream) => self.stream = Some(Arc::new(stream)),
+                Err(e) => {
+                    log::warn!("Failed to connect to LiveSplit at {}: {:#}", address, e);
+                    return;
+                }
+            }
+        }
+        self.milestones = parse_milestones(milestones);
+        self.passed = 0;
+        self.running = true;
+        self.send("reset");
+        self.send("starttimer");
+    }
+
+    /// Splits for each milestone passed since the last update, `percent` being how much of
+    /// the 3BV has been cleared
+    pub fn progress(&mut self, percent: f64) {
+        if !self.running {
+            return;
+        }
+        let reached = milestones_reached(&self.milestones, percent);
+        (self.passed..reached).for_each(|_| self.send("split"));
+        self.passed = self.passed.max(reached);
+        if self.passed == self.milestones.len() {
+            self.running = false;
+        }
+    }
+
+    pub fn pause(&mut self) {
+        if self.running {
+            self.send("pause");
+        }
+    }
+
+    pub fn resume(&mut self) {
+        if self.running {
+            self.send("resume");
+        }
+    }
+
+    /// Throws away the run going on, if there is one
+    pub fn reset(&mut self) {
+        if self.running {
+            self.running = false;
+            self.send("reset");
+        }
+    }
+
+    fn send(&mut self, command: &str) {
+        let Some(stream) = &self.stream else {
+            return;
+        };
+        if let Err(e) = stream
+            .as_ref()
+            .write_all(format!("{}\r\n", command).as_bytes())
+        {
+            log::warn!("Lost the connection to LiveSplit: {}", e);
+            self.stream = None;
+            self.running = false;
+        }
+    }
+}
+
+fn connect(address: &str) -> Result<TcpStream> {
+    let addr = address
+        .to_socket_addrs()?
+        .next()
+        .ok_or_else(|| anyhow!("No address found for {}", address))?;
+    let stream = TcpStream::connect_timeout(&addr, CONNECT_TIMEOUT)?;
+    stream.set_nodelay(true)?;
+    Ok(stream)
+}
+
+#[test]
+fn test_milestones() {
+    assert_eq!(parse_milestones("25, 50, 75, 100"), [25, 50, 75, 100]);
+    assert_eq!(parse_milestones("100 50% 0 150 x 50"), [50, 100]);
+    assert!(parse_milestones("").is_empty());
+
+    let milestones = [25, 50, 75, 100];
+    assert_eq!(milestones_reached(&milestones, 0.0), 0);
+    assert_eq!(milestones_reached(&milestones, 50.0), 2);
+    assert_eq!(milestones_reached(&milestones, 99.9), 3);
+    assert_eq!(milestones_reached(&milestones, 100.0), 4);
+}
+
+#[test]
+fn test_split_timer() -> Result<()> {
+    use std::io::Read;
+    use std::net::TcpListener;
+
+    let listener = TcpListener::bind("127.0.0.1:0")?;
+    let address = listener.local_addr()?.to_string();
+    let mut timer = SplitTimer::default();
+    timer.start(&address, "50, 100");
+    let (mut server, _) = listener.accept()?;
+    // A big jump passes both milestones at once, and the finished run can't be reset
+    timer.progress(10.0);
+    timer.pause();
+    timer.resume();
+    timer.progress(100.0);
+    timer.reset();
+    drop(timer);
+
+    let mut received = String::new();
+    server.read_to_string(&mut received)?;
+    assert_eq!(
+        received,
+        "reset\r\nstarttimer\r\npause\r\nresume\r\nsplit\r\nsplit\r\n"
+    );
+    Ok(())
+}
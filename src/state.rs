@@ -1,12 +1,14 @@
-use std::fs;
-use std::fs::File;
-use std::io::Write;
-
-use anyhow::{anyhow, Result};
+use anyhow::Result;
 use serde::{Deserialize, Serialize};
 
+use crate::config::{self, Versioned};
 use crate::constants::*;
 use crate::enums::*;
+use crate::minesweeper::{Coordinate, GameBoard};
+use crate::placement::MinePlacement;
+use crate::shape::BoardShape;
+#[cfg(feature = "updates")]
+use crate::update::Release;
 
 #[derive(Clone, Deserialize, Serialize)]
 pub struct GameSettings {
@@ -14,10 +16,28 @@ pub struct GameSettings {
     pub height: u32,
     pub num_mines: u32,
     pub use_numerals: bool,
+    /// Flagging is disabled, for no flag (NF) play
+    pub no_flags: bool,
+    /// Independent boards played at once, all of which have to be cleared
+    pub num_boards: u32,
+    /// How the mines are spread over the boards
+    #[serde(default)]
+    pub placement: MinePlacement,
+    /// The outline of the boards. `num_mines` is for the squares inside it.
+    #[serde(default)]
+    pub shape: BoardShape,
+    /// 2D layers each board is made of, stacked into a 3D board. `height` is the rows of a
+    /// single layer.
+    #[serde(default = "default_layers")]
+    pub layers: u32,
     pub ui_width: f32,
     pub ui_height: f32,
 }
 
+fn default_layers() -> u32 {
+    1
+}
+
 impl GameSettings {
     pub fn beginner() -> Self {
         GameSettings {
@@ -25,6 +45,11 @@ impl GameSettings {
             height: DEFAULT_BEGINNER_HEIGHT,
             num_mines: DEFAULT_BEGINNER_NUM_MINES,
             use_numerals: true,
+            no_flags: false,
+            num_boards: 1,
+            placement: MinePlacement::Uniform,
+            shape: BoardShape::Rectangle,
+            layers: 1,
             ui_width: DEFAULT_BEGINNER_UI_WIDTH,
             ui_height: DEFAULT_BEGINNER_UI_HEIGHT,
         }
@@ -36,6 +61,11 @@ impl GameSettings {
             height: DEFAULT_INTERMEDIATE_HEIGHT,
             num_mines: DEFAULT_INTERMEDIATE_NUM_MINES,
             use_numerals: true,
+            no_flags: false,
+            num_boards: 1,
+            placement: MinePlacement::Uniform,
+            shape: BoardShape::Rectangle,
+            layers: 1,
             ui_width: DEFAULT_INTERMEDIATE_UI_WIDTH,
             ui_height: DEFAULT_INTERMEDIATE_UI_HEIGHT,
         }
@@ -47,6 +77,11 @@ impl GameSettings {
             height: DEFAULT_EXPERT_HEIGHT,
             num_mines: DEFAULT_EXPERT_NUM_MINES,
             use_numerals: true,
+            no_flags: false,
+            num_boards: 1,
+            placement: MinePlacement::Uniform,
+            shape: BoardShape::Rectangle,
+            layers: 1,
             ui_width: DEFAULT_EXPERT_UI_WIDTH,
             ui_height: DEFAULT_EXPERT_UI_HEIGHT,
         }
@@ -59,53 +94,554 @@ impl GameSettings {
             GameDifficulty::Expert => GameSettings::expert(),
         }
     }
+
+    /// Settings for a single board of any size, such as a puzzle's
+    pub fn for_board(width: u32, height: u32, num_mines: u32) -> Self {
+        GameSettings {
+            width,
+            height,
+            num_mines,
+            use_numerals: true,
+            no_flags: false,
+            num_boards: 1,
+            placement: MinePlacement::Uniform,
+            shape: BoardShape::Rectangle,
+            layers: 1,
+            ui_width: width as f32 * SQUARE_UI_SIZE + BOARD_UI_EXTRA_WIDTH,
+            ui_height: height as f32 * SQUARE_UI_SIZE + BOARD_UI_EXTRA_HEIGHT,
+        }
+    }
+
+    /// Settings for a difficulty level, with flagging disabled if `no_flags` is set
+    pub fn settings_for_mode(difficulty: &GameDifficulty, no_flags: bool, num_boards: u32) -> Self {
+        GameSettings {
+            no_flags,
+            num_boards: num_boards.max(1),
+            ..Self::settings_for_difficulty(difficulty)
+        }
+    }
+
+    /// An empty board to these settings, whose mines are placed from `seed`
+    pub fn new_board(&self, seed: u64) -> GameBoard {
+        let mut board = GameBoard::new_layered(self.width, self.height, self.layers, seed);
+        board.no_flags = self.no_flags;
+        board.placement = self.placement;
+        self.shape.apply(&mut board);
+        board
+    }
+
+    /// Columns and rows the boards are laid out in, two to a row
+    pub fn board_layout(&self) -> (u32, u32) {
+        (
+            self.num_boards.min(2),
+            self.num_boards / 2 + self.num_boards % 2,
+        )
+    }
+
+    /// The board a square on the grid of all the boards belongs to, and where it is on that
+    /// board. The layers of a layered board are stacked on the grid.
+    pub fn board_coordinate(&self, coord: &Coordinate) -> Option<(usize, Coordinate)> {
+        let (columns, rows) = self.board_layout();
+        let (width, height) = (self.width, self.height * self.layers);
+        (coord.x < columns * width && coord.y < rows * height).then(|| {
+            (
+                (coord.y / height * columns + coord.x / width) as usize,
+                Coordinate {
+                    x: coord.x % width,
+                    y: coord.y % height,
+                },
+            )
+        })
+    }
 }
 
-#[derive(Clone, Deserialize, Serialize)]
+/// Position and size of the window when the app last closed
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct WindowGeometry {
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+
+    /// Size of the monitor the window was on. The position is only restored on a monitor of
+    /// the same size, so the window can't open off screen after the displays change.
+    pub monitor_width: f32,
+    pub monitor_height: f32,
+}
+
+impl WindowGeometry {
+    pub fn from_viewport(viewport: &egui::ViewportInfo) -> Option<Self> {
+        let outer = viewport.outer_rect?;
+        let inner = viewport.inner_rect?;
+        let monitor = viewport.monitor_size?;
+        Some(WindowGeometry {
+            x: outer.min.x,
+            y: outer.min.y,
+            width: inner.width(),
+            height: inner.height(),
+            monitor_width: monitor.x,
+            monitor_height: monitor.y,
+        })
+    }
+
+    pub fn is_on_monitor(&self, monitor_size: egui::Vec2) -> bool {
+        self.monitor_width == monitor_size.x && self.monitor_height == monitor_size.y
+    }
+}
+
+#[derive(Clone, PartialEq, Deserialize, Serialize)]
+#[serde(default)]
 pub struct AppState {
+    pub version: i64,
     pub difficulty: GameDifficulty,
+    /// Play without flags. Wins go on separate leaderboards.
+    pub no_flags: bool,
+    /// Boards played at once. Wins go on separate leaderboards.
+    pub num_boards: u32,
+    /// How the mines are spread over the boards. Anything but uniform is kept off the
+    /// leaderboard.
+    pub mine_placement: MinePlacement,
+    /// The outline of the boards. Anything but a rectangle is kept off the leaderboard.
+    pub board_shape: BoardShape,
+    /// Layers stacked into a 3D board, which is played on its own. More than one is kept
+    /// off the leaderboard.
+    pub layers: u32,
     pub left_click_chord: bool,
     pub theme: VisualTheme,
+    pub language: Language,
+    pub numeral_style: NumeralStyle,
     pub fog_of_war: bool,
+    /// Squares within this many squares of the mouse or keyboard cursor are clear of fog
+    pub fog_radius: f32,
+    /// Squares seen clearly once stay dimly visible under the fog
+    pub fog_memory: bool,
+    pub auto_pause: bool,
+    /// Hide the window in the system tray, pausing the game, when it's minimized mid-game
+    #[cfg(all(feature = "tray", not(target_arch = "wasm32")))]
+    pub minimize_to_tray: bool,
+    /// Ask before a new game, restart or exit throws away a game in progress
+    pub confirm_abandon: bool,
+    /// Hold back a chord that would set off a mine until it's made again. Games where one
+    /// is held back stay off the leaderboard, unless they're played with time penalties.
+    pub safe_chords: bool,
+    /// Wrong flags and held back chords add to the time instead of keeping the game off the
+    /// leaderboard
+    pub time_penalties: bool,
+    /// Refuse to place more flags than there are mines
+    pub strict_flags: bool,
+    /// Shake the mine counter when a flag is placed with no mines left to flag
+    pub flag_warning_shake: bool,
+    /// Show a cascade spreading out in rings from the square played
+    pub reveal_animation: bool,
+    /// Holding the flag button or Alt on a numeral marks the covered squares around it
+    pub constraint_assist: bool,
+    /// Reveal the squares a single numeral proves safe `auto_open_seconds` after each
+    /// reveal. Games played with it are practice.
+    pub auto_open: bool,
+    pub auto_open_seconds: f64,
+    /// Notify the player of milestones like a new personal best
+    pub notifications: bool,
+
+    /// Start a new game automatically `auto_restart_seconds` after a loss
+    pub auto_restart: bool,
+    pub auto_restart_seconds: f64,
+    /// Save the game in progress every `autosave_seconds`, so it can be recovered after a
+    /// crash
+    pub autosave: bool,
+    pub autosave_seconds: f64,
+    /// Write the game's stats for a streaming overlay every `overlay_seconds` they change
+    pub overlay: bool,
+    pub overlay_seconds: f64,
+    pub timer_precision: TimerPrecision,
+
+    /// Show how many safe squares are left to reveal
+    pub show_progress: bool,
+    /// Show a map of boards too big for the window, for getting around them
+    pub show_minimap: bool,
+    /// Show whether a safe square can be deduced, or the player has to guess
+    pub show_guess_indicator: bool,
+    /// Earn points and levels by winning, with board skins to unlock
+    pub progression: bool,
+    /// Only used once the player's level has unlocked it
+    pub board_skin: BoardSkin,
+
+    /// Right dragging across the board flags every unrevealed square passed over
+    pub paint_flags: bool,
+
+    /// Swap the left and right mouse buttons on the board
+    pub swap_buttons: bool,
+
+    /// On touchscreens, a long press flags and a two finger tap chords
+    pub touch_gestures: bool,
+    pub long_press_seconds: f64,
+    /// Longest a two finger touch can last and still count as a tap
+    pub two_finger_tap_seconds: f64,
+    pub tap_mode: TapMode,
+
+    pub window: Option<WindowGeometry>,
+    pub always_on_top: bool,
+    /// Hide the bottom panel, leaving just the board
+    pub compact_mode: bool,
+    /// Hide everything but the board and a small timer, for streaming and playing without
+    /// distractions
+    pub zen_mode: bool,
+    /// How much the window can be seen through in zen mode, from 0 for not at all
+    pub zen_transparency: f32,
+
+    /// Name the player's winning times are recorded under
+    pub player_name: String,
+    /// Keep only the fastest `leaderboard_max_entries` wins on each leaderboard
+    pub leaderboard_limit: bool,
+    pub leaderboard_max_entries: u32,
+
+    /// Names of the players in the last hot-seat race
+    pub player_names: [String; 2],
+
+    /// Square size and speed of the last replay exported
+    pub replay_cell_size: u32,
+    pub replay_seconds_per_play: f64,
+
+    /// Submit winning times to the global leaderboard at `online_endpoint`
+    #[cfg(feature = "online")]
+    pub online_enabled: bool,
+    #[cfg(feature = "online")]
+    pub online_endpoint: String,
+
+    /// Check once a day for a newer release
+    #[cfg(feature = "updates")]
+    pub check_for_updates: bool,
+    /// Day of the last check for a newer release, as `YYYY-MM-DD`
+    #[cfg(feature = "updates")]
+    pub update_checked_on: Option<String>,
+    /// The latest release found by the last check
+    #[cfg(feature = "updates")]
+    pub latest_release: Option<Release>,
+
+    /// Hotkeys for a new game and pausing that work while the window isn't focused. Either
+    /// can be left empty.
+    #[cfg(all(feature = "hotkeys", not(target_arch = "wasm32")))]
+    pub global_hotkeys: bool,
+    #[cfg(all(feature = "hotkeys", not(target_arch = "wasm32")))]
+    pub new_game_hotkey: String,
+    #[cfg(all(feature = "hotkeys", not(target_arch = "wasm32")))]
+    pub pause_hotkey: String,
+
+    /// Drive a LiveSplit timer at `livesplit_address`, splitting as the game passes each of
+    /// `livesplit_milestones`, percentages of the board's 3BV
+    #[cfg(feature = "livesplit")]
+    pub livesplit_enabled: bool,
+    #[cfg(feature = "livesplit")]
+    pub livesplit_address: String,
+    #[cfg(feature = "livesplit")]
+    pub livesplit_milestones: String,
+
+    /// Name shown to the other players of a network race and to spectators
+    #[cfg(feature = "net")]
+    pub net_name: String,
+    /// Host last joined for a network race
+    #[cfg(feature = "net")]
+    pub net_address: String,
+    /// Player last watched
+    #[cfg(feature = "net")]
+    pub spectate_address: String,
+    /// Host last joined for a co-op game
+    #[cfg(feature = "net")]
+    pub coop_address: String,
 }
 
 impl Default for AppState {
     fn default() -> Self {
         Self {
+            version: <AppState as Versioned>::VERSION,
             difficulty: GameDifficulty::Intermediate,
+            no_flags: false,
+            num_boards: 1,
+            mine_placement: MinePlacement::Uniform,
+            board_shape: BoardShape::Rectangle,
+            layers: 1,
             left_click_chord: false,
             theme: VisualTheme::Dark,
+            language: Language::English,
+            numeral_style: NumeralStyle::Images,
             fog_of_war: false,
+            fog_radius: DEFAULT_FOG_RADIUS,
+            fog_memory: false,
+            auto_pause: true,
+            #[cfg(all(feature = "tray", not(target_arch = "wasm32")))]
+            minimize_to_tray: false,
+            confirm_abandon: true,
+            safe_chords: false,
+            time_penalties: false,
+            strict_flags: false,
+            flag_warning_shake: true,
+            reveal_animation: true,
+            constraint_assist: true,
+            auto_open: false,
+            auto_open_seconds: DEFAULT_AUTO_OPEN_SECONDS,
+            notifications: false,
+            auto_restart: false,
+            auto_restart_seconds: DEFAULT_AUTO_RESTART_SECONDS,
+            autosave: true,
+            autosave_seconds: DEFAULT_AUTOSAVE_SECONDS,
+            overlay: false,
+            overlay_seconds: DEFAULT_OVERLAY_SECONDS,
+            timer_precision: TimerPrecision::Seconds,
+            show_progress: false,
+            show_minimap: true,
+            show_guess_indicator: false,
+            progression: false,
+            board_skin: BoardSkin::Classic,
+            paint_flags: false,
+            swap_buttons: false,
+            touch_gestures: true,
+            long_press_seconds: DEFAULT_LONG_PRESS_SECONDS,
+            two_finger_tap_seconds: DEFAULT_TWO_FINGER_TAP_SECONDS,
+            tap_mode: TapMode::Reveal,
+            window: None,
+            always_on_top: false,
+            compact_mode: false,
+            zen_mode: false,
+            zen_transparency: 0.0,
+            player_name: whoami::realname(),
+            leaderboard_limit: true,
+            leaderboard_max_entries: DEFAULT_LEADERBOARD_MAX_ENTRIES,
+            player_names: ["Player 1".to_string(), "Player 2".to_string()],
+            replay_cell_size: DEFAULT_REPLAY_CELL_SIZE,
+            replay_seconds_per_play: DEFAULT_REPLAY_SECONDS_PER_PLAY,
+            #[cfg(feature = "online")]
+            online_enabled: false,
+            #[cfg(feature = "online")]
+            online_endpoint: String::new(),
+            #[cfg(feature = "updates")]
+            check_for_updates: false,
+            #[cfg(feature = "updates")]
+            update_checked_on: None,
+            #[cfg(feature = "updates")]
+            latest_release: None,
+            #[cfg(all(feature = "hotkeys", not(target_arch = "wasm32")))]
+            global_hotkeys: false,
+            #[cfg(all(feature = "hotkeys", not(target_arch = "wasm32")))]
+            new_game_hotkey: "Ctrl+Alt+N".to_string(),
+            #[cfg(all(feature = "hotkeys", not(target_arch = "wasm32")))]
+            pause_hotkey: "Ctrl+Alt+P".to_string(),
+            #[cfg(feature = "livesplit")]
+            livesplit_enabled: false,
+            #[cfg(feature = "livesplit")]
+            livesplit_address: format!("localhost:{}", DEFAULT_LIVESPLIT_PORT),
+            #[cfg(feature = "livesplit")]
+            livesplit_milestones: "25, 50, 75, 100".to_string(),
+            #[cfg(feature = "net")]
+            net_name: whoami::realname(),
+            #[cfg(feature = "net")]
+            net_address: format!("127.0.0.1:{}", DEFAULT_NET_PORT),
+            #[cfg(feature = "net")]
+            spectate_address: format!("127.0.0.1:{}", DEFAULT_SPECTATE_PORT),
+            #[cfg(feature = "net")]
+            coop_address: format!("127.0.0.1:{}", DEFAULT_COOP_PORT),
         }
     }
 }
 
+const CONFIG_FILE_NAME: &str = "minesofrust.toml";
+
+impl Versioned for AppState {
+    // 1: Added the version field
+    const VERSION: i64 = 1;
+}
+
 impl AppState {
-    pub fn load_from_userhome() -> Result<Self> {
-        let config_file_path = dirs::home_dir().unwrap().join(".apoapsys/minesofrust.toml");
-        if config_file_path.exists() {
-            println!(
-                "Window state config file exists at path: {:?}",
-                config_file_path
-            );
-            let t = std::fs::read_to_string(config_file_path)?;
-            Ok(toml::from_str(&t)?)
-        } else {
-            println!("Window state config file does not exist. Will be created on exit");
-            Err(anyhow!("Config file does not exist"))
+    /// Copies the options edited in the settings window from `settings`, leaving the
+    /// difficulty, window state and other things changed elsewhere as they are
+    pub fn apply_settings(&mut self, settings: &AppState) {
+        self.player_name = settings.player_name.clone();
+        self.leaderboard_limit = settings.leaderboard_limit;
+        self.leaderboard_max_entries = settings.leaderboard_max_entries;
+        self.left_click_chord = settings.left_click_chord;
+        self.fog_of_war = settings.fog_of_war;
+        self.fog_radius = settings.fog_radius;
+        self.fog_memory = settings.fog_memory;
+        self.auto_pause = settings.auto_pause;
+        #[cfg(all(feature = "tray", not(target_arch = "wasm32")))]
+        {
+            self.minimize_to_tray = settings.minimize_to_tray;
+        }
+        self.confirm_abandon = settings.confirm_abandon;
+        self.safe_chords = settings.safe_chords;
+        self.time_penalties = settings.time_penalties;
+        self.strict_flags = settings.strict_flags;
+        self.notifications = settings.notifications;
+        self.flag_warning_shake = settings.flag_warning_shake;
+        self.reveal_animation = settings.reveal_animation;
+        self.constraint_assist = settings.constraint_assist;
+        self.auto_open = settings.auto_open;
+        self.auto_open_seconds = settings.auto_open_seconds;
+        self.auto_restart = settings.auto_restart;
+        self.auto_restart_seconds = settings.auto_restart_seconds;
+        self.autosave = settings.autosave;
+        self.autosave_seconds = settings.autosave_seconds;
+        self.overlay = settings.overlay;
+        self.overlay_seconds = settings.overlay_seconds;
+        self.theme = settings.theme.clone();
+        self.zen_transparency = settings.zen_transparency;
+        self.language = settings.language;
+        self.numeral_style = settings.numeral_style.clone();
+        self.timer_precision = settings.timer_precision.clone();
+        self.show_progress = settings.show_progress;
+        self.show_minimap = settings.show_minimap;
+        self.show_guess_indicator = settings.show_guess_indicator;
+        self.progression = settings.progression;
+        self.board_skin = settings.board_skin;
+        self.paint_flags = settings.paint_flags;
+        self.swap_buttons = settings.swap_buttons;
+        self.touch_gestures = settings.touch_gestures;
+        self.long_press_seconds = settings.long_press_seconds;
+        self.two_finger_tap_seconds = settings.two_finger_tap_seconds;
+        #[cfg(feature = "online")]
+        {
+            self.online_enabled = settings.online_enabled;
+            self.online_endpoint = settings.online_endpoint.clone();
+        }
+        #[cfg(feature = "updates")]
+        {
+            self.check_for_updates = settings.check_for_updates;
+        }
+        #[cfg(all(feature = "hotkeys", not(target_arch = "wasm32")))]
+        {
+            self.global_hotkeys = settings.global_hotkeys;
+            self.new_game_hotkey = settings.new_game_hotkey.clone();
+            self.pause_hotkey = settings.pause_hotkey.clone();
+        }
+        #[cfg(feature = "livesplit")]
+        {
+            self.livesplit_enabled = settings.livesplit_enabled;
+            self.livesplit_address = settings.livesplit_address.clone();
+            self.livesplit_milestones = settings.livesplit_milestones.clone();
         }
     }
 
-    pub fn save_to_userhome(&self) {
-        let toml_str = toml::to_string(&self).unwrap();
-        let apoapsys_config_dir = dirs::home_dir().unwrap().join(".apoapsys/");
-        if !apoapsys_config_dir.exists() {
-            fs::create_dir(&apoapsys_config_dir).expect("Failed to create config directory");
+    /// The most wins kept on each leaderboard, if there's a limit
+    pub fn max_leaderboard_entries(&self) -> Option<usize> {
+        self.leaderboard_limit
+            .then_some(self.leaderboard_max_entries as usize)
+    }
+
+    /// Sets up the mouse and touch settings for the way the player likes to play
+    pub fn set_input_style(&mut self, style: InputStyle) {
+        self.left_click_chord = style != InputStyle::Classic;
+        self.touch_gestures = style == InputStyle::Touch;
+        if style == InputStyle::Touch {
+            self.tap_mode = TapMode::Reveal;
         }
-        let config_file_path = apoapsys_config_dir.join("minesofrust.toml");
-        let mut f = File::create(config_file_path).expect("Failed to create config file");
-        f.write_all(toml_str.as_bytes())
-            .expect("Failed to write to config file");
-        println!("{}", toml_str);
     }
+
+    /// True if applying `settings` wouldn't change anything
+    pub fn has_same_settings(&self, settings: &AppState) -> bool {
+        let mut applied = self.clone();
+        applied.apply_settings(settings);
+        applied == *self
+    }
+
+    pub fn load_from_userhome() -> Result<Option<Self>> {
+        config::load_toml(CONFIG_FILE_NAME)
+    }
+
+    pub fn save_to_userhome(&self) -> Result<()> {
+        config::save_toml(self, CONFIG_FILE_NAME)
+    }
+}
+
+#[test]
+fn test_load_unversioned_state() -> Result<()> {
+    let state: AppState = config::parse_versioned(
+        r#"
+        difficulty = "Expert"
+        left_click_chord = true
+        theme = "Light"
+        fog_of_war = false
+        "#,
+    )?;
+    assert_eq!(state.version, <AppState as Versioned>::VERSION);
+    assert!(state.difficulty == GameDifficulty::Expert);
+    assert!(state.left_click_chord);
+    Ok(())
+}
+
+#[test]
+fn test_apply_settings() {
+    let mut state = AppState {
+        difficulty: GameDifficulty::Expert,
+        compact_mode: true,
+        ..Default::default()
+    };
+    let mut draft = state.clone();
+    assert!(state.has_same_settings(&draft));
+
+    draft.theme = VisualTheme::Light;
+    draft.fog_of_war = true;
+    draft.confirm_abandon = false;
+    draft.strict_flags = true;
+    draft.safe_chords = true;
+    draft.time_penalties = true;
+    draft.notifications = true;
+    draft.board_skin = BoardSkin::Ocean;
+    draft.difficulty = GameDifficulty::Beginner;
+    draft.compact_mode = false;
+    assert!(!state.has_same_settings(&draft));
+
+    state.apply_settings(&draft);
+    assert!(state.has_same_settings(&draft));
+    assert!(state.theme == VisualTheme::Light && state.fog_of_war);
+    assert!(!state.confirm_abandon && state.strict_flags && state.safe_chords);
+    assert!(state.time_penalties);
+    assert!(state.notifications && state.board_skin == BoardSkin::Ocean);
+    // Not edited in the settings window, so left alone
+    assert!(state.difficulty == GameDifficulty::Expert && state.compact_mode);
+}
+
+#[test]
+fn test_set_input_style() {
+    let mut state = AppState::default();
+    state.set_input_style(InputStyle::Classic);
+    assert!(!state.left_click_chord && !state.touch_gestures);
+    state.set_input_style(InputStyle::LeftClickChord);
+    assert!(state.left_click_chord && !state.touch_gestures);
+    state.tap_mode = TapMode::Flag;
+    state.set_input_style(InputStyle::Touch);
+    assert!(state.left_click_chord && state.touch_gestures);
+    assert_eq!(state.tap_mode, TapMode::Reveal);
+}
+
+#[test]
+fn test_window_geometry_round_trip() -> Result<()> {
+    let state = AppState::default();
+    let reloaded: AppState = config::parse_versioned(&toml::to_string(&state)?)?;
+    assert!(reloaded.window.is_none());
+
+    let geometry = WindowGeometry {
+        x: 100.0,
+        y: 50.0,
+        width: 655.0,
+        height: 885.0,
+        monitor_width: 1920.0,
+        monitor_height: 1080.0,
+    };
+    let state = AppState {
+        window: Some(geometry.clone()),
+        ..Default::default()
+    };
+    let reloaded: AppState = config::parse_versioned(&toml::to_string(&state)?)?;
+    assert_eq!(reloaded.window, Some(geometry.clone()));
+    assert!(geometry.is_on_monitor(egui::vec2(1920.0, 1080.0)));
+    assert!(!geometry.is_on_monitor(egui::vec2(2560.0, 1440.0)));
+    Ok(())
+}
+
+#[test]
+fn test_board_layout() {
+    let layout =
+        |n| GameSettings::settings_for_mode(&GameDifficulty::Expert, false, n).board_layout();
+    assert_eq!(layout(0), (1, 1));
+    assert_eq!(layout(1), (1, 1));
+    assert_eq!(layout(2), (2, 1));
+    assert_eq!(layout(4), (2, 2));
 }
@@ -0,0 +1,115 @@
+use std::sync::mpsc::{self, Receiver};
+
+use anyhow::Result;
+use tray_icon::menu::{Menu, MenuEvent, MenuItem, PredefinedMenuItem};
+use tray_icon::{Icon, TrayIcon, TrayIconBuilder};
+
+use crate::i18n::tr;
+
+/// Something picked from the tray icon's menu
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TrayAction {
+    NewGame,
+    Pause,
+    ShowHide,
+    Quit,
+}
+
+impl TrayAction {
+    fn all() -> [TrayAction; 4] {
+        [
+            TrayAction::NewGame,
+            TrayAction::Pause,
+            TrayAction::ShowHide,
+            TrayAction::Quit,
+        ]
+    }
+
+    fn id(&self) -> &'static str {
+        match *self {
+            TrayAction::NewGame => "new-game",
+            TrayAction::Pause => "pause",
+            TrayAction::ShowHide => "show-hide",
+            TrayAction::Quit => "quit",
+        }
+    }
+
+    fn label(&self) -> &'static str {
+        match *self {
+            TrayAction::NewGame => tr("menu-new"),
+            TrayAction::Pause => tr("tray-pause"),
+            TrayAction::ShowHide => tr("tray-show-hide"),
+            TrayAction::Quit => tr("menu-exit"),
+        }
+    }
+}
+
+fn build_icon() -> Result<TrayIcon> {
+    let menu = Menu::new();
+    TrayAction::all().iter().try_for_each(|action| {
+        if *action == TrayAction::Quit {
+            menu.append(&PredefinedMenuItem::separator())?;
+        }
+        menu.append(&MenuItem::with_id(action.id(), action.label(), true, None))
+    })?;
+    let image = eframe::icon_data::from_png_bytes(&include_bytes!("../assets/icon-256.png")[..])?;
+    Ok(TrayIconBuilder::new()
+        .with_menu(Box::new(menu))
+        .with_tooltip(tr("tray-tooltip"))
+        .with_icon(Icon::from_rgba(image.rgba, image.width, image.height)?)
+        .build()?)
+}
+
+/// The app's icon in the system tray, with a menu of quick actions. Actions picked from the
+/// menu wake the app up and are picked up by `poll()`.
+pub struct Tray {
+    actions: Receiver<TrayAction>,
+    /// The icon is taken out of the tray when this is dropped. On Linux it belongs to the
+    /// GTK thread instead.
+    #[cfg(not(target_os = "linux"))]
+    _icon: TrayIcon,
+}
+
+impl Tray {
+    /// Puts the icon in the tray. Call once the app is running: on macOS the icon can only be
+    /// made after the event loop has started.
+    pub fn new(ctx: &egui::Context) -> Result<Self> {
+        let (sender, actions) = mpsc::channel();
+        let ctx = ctx.clone();
+        MenuEvent::set_event_handler(Some(move |event: MenuEvent| {
+            if let Some(action) = TrayAction::all().into_iter().find(|a| event.id == a.id()) {
+                let _ = sender.send(action);
+                ctx.request_repaint();
+            }
+        }));
+
+        // The tray needs GTK on Linux, which egui doesn't run, so it gets a thread of its own
+        #[cfg(target_os = "linux")]
+        {
+            let (started, result) = mpsc::channel();
+            std::thread::spawn(move || {
+                let icon = gtk::init()
+                    .map_err(anyhow::Error::from)
+                    .and_then(|_| build_icon());
+                let _ = started.send(icon.as_ref().err().map(|e| format!("{:#}", e)));
+                if icon.is_ok() {
+                    gtk::main();
+                }
+            });
+            match result.recv() {
+                Ok(Some(e)) => Err(anyhow::anyhow!(e)),
+                _ => Ok(Tray { actions }),
+            }
+        }
+        #[cfg(not(target_os = "linux"))]
+        Ok(Tray {
+            actions,
+            _icon: build_icon()?,
+        })
+    }
+
+    /// Actions picked since the last call. Call once per frame.
+    pub fn poll(&self) -> Vec<TrayAction> {
+        self.actions.try_iter().collect()
+    }
+}
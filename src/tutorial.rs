@@ -0,0 +1,132 @@
+use crate::constants;
+use crate::i18n::tr;
+use crate::minesweeper::{Coordinate, GameBoard, RevealType};
+use crate::state::GameSettings;
+
+/// The board the tutorial is played on. Revealing the middle opens everything but the
+/// mines and the two squares beside the top and left ones, which are left for chording.
+const TUTORIAL_BOARD: &str = "\
+......1*1
+......111
+.........
+11.......
+*1.....11
+11.....1*
+";
+
+/// A kind of play the tutorial asks for
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum TutorialAction {
+    Reveal,
+    Flag,
+    Chord,
+}
+
+impl TutorialAction {
+    fn allows(&self, play_type: &RevealType) -> bool {
+        match *self {
+            TutorialAction::Reveal => {
+                matches!(play_type, RevealType::Reveal | RevealType::RevealChord)
+            }
+            TutorialAction::Flag => *play_type == RevealType::Flag,
+            TutorialAction::Chord => {
+                matches!(play_type, RevealType::Chord | RevealType::RevealChord)
+            }
+        }
+    }
+}
+
+/// Each step's play, the square it's made on, and the text explaining it
+const STEPS: [(TutorialAction, (u32, u32), &str); 5] = [
+    (TutorialAction::Reveal, (4, 2), "tutorial-reveal"),
+    (TutorialAction::Flag, (7, 0), "tutorial-flag"),
+    (TutorialAction::Chord, (8, 1), "tutorial-chord"),
+    (TutorialAction::Flag, (0, 4), "tutorial-flag-again"),
+    (TutorialAction::Chord, (1, 4), "tutorial-chord-again"),
+];
+
+/// A guided game on a scripted board, teaching revealing, numerals, flagging and chording
+/// one step at a time. Only the play each step asks for is let through.
+#[derive(Debug, Clone, Default)]
+pub struct Tutorial {
+    step: usize,
+    /// The last play tried wasn't the one asked for
+    blocked: bool,
+}
+
+impl Tutorial {
+    pub fn board() -> GameBoard {
+        GameBoard::from_ascii(TUTORIAL_BOARD).expect("The tutorial board is valid")
+    }
+
+    /// Settings for the tutorial's board, with room for the instructions
+    pub fn settings() -> GameSettings {
+        let board = Tutorial::board();
+        let settings = GameSettings::for_board(board.width, board.height, board.num_mines);
+        GameSettings {
+            ui_height: settings.ui_height + constants::TUTORIAL_UI_HEIGHT,
+            ..settings
+        }
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.step >= STEPS.len()
+    }
+
+    pub fn is_blocked(&self) -> bool {
+        self.blocked
+    }
+
+    /// The square the current step is played on
+    pub fn target(&self) -> Option<Coordinate> {
+        STEPS
+            .get(self.step)
+            .map(|(_, target, _)| Coordinate::from(*target))
+    }
+
+    pub fn instructions(&self) -> &'static str {
+        STEPS
+            .get(self.step)
+            .map_or(tr("tutorial-done"), |(_, _, text)| tr(text))
+    }
+
+    /// Moves on to the next step if `play_type` at `coord` is the play the current step asks
+    /// for. Returns whether the play should be made.
+    pub fn accept(&mut self, coord: &Coordinate, play_type: &RevealType) -> bool {
+        let accepted = STEPS.get(self.step).is_some_and(|(action, target, _)| {
+            *coord == Coordinate::from(*target) && action.allows(play_type)
+        });
+        if accepted {
+            self.step += 1;
+        }
+        self.blocked = !accepted;
+        accepted
+    }
+}
+
+#[test]
+fn test_tutorial() -> Result<(), crate::minesweeper::Error> {
+    let mut board = Tutorial::board();
+    let mut tutorial = Tutorial::default();
+    let mut play = |x, y, play_type: RevealType| {
+        let accepted = tutorial.accept(&Coordinate::from((x, y)), &play_type);
+        if accepted {
+            board.play(x, y, play_type)?;
+        }
+        Ok::<_, crate::minesweeper::Error>((accepted, board.is_win_configuration()))
+    };
+
+    assert_eq!(play(0, 0, RevealType::Reveal)?, (false, false));
+    assert_eq!(play(4, 2, RevealType::Reveal)?, (true, false));
+    assert_eq!(play(7, 0, RevealType::Reveal)?, (false, false));
+    assert_eq!(play(7, 0, RevealType::Flag)?, (true, false));
+    assert_eq!(play(8, 1, RevealType::Chord)?, (true, false));
+    assert_eq!(play(0, 4, RevealType::Flag)?, (true, false));
+    // Clearing the last safe square wins
+    assert_eq!(play(1, 4, RevealType::RevealChord)?, (true, true));
+    assert_eq!(play(8, 5, RevealType::Flag)?, (false, true));
+
+    assert!(tutorial.is_finished());
+    assert!(tutorial.target().is_none());
+    Ok(())
+}
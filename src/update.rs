@@ -0,0 +1,114 @@
+use std::sync::{Arc, Mutex};
+
+use serde::{Deserialize, Serialize};
+
+/// The newest release published on GitHub. Only the release is asked for, with nothing
+/// about the player or their games sent along.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct Release {
+    #[serde(rename = "tag_name")]
+    pub version: String,
+    /// The release's page, for the player to download it from
+    #[serde(rename = "html_url")]
+    pub url: String,
+}
+
+impl Release {
+    /// Whether the release is newer than the version running
+    pub fn is_newer(&self) -> bool {
+        is_newer_than(&self.version, env!("CARGO_PKG_VERSION"))
+    }
+}
+
+/// The parts of a version like `v1.2.3`. Anything after a part's digits, like `-beta`, is
+/// ignored.
+fn version_parts(version: &str) -> Vec<u64> {
+    version
+        .trim()
+        .trim_start_matches('v')
+        .split('.')
+        .map(|p| {
+            p.chars()
+                .take_while(|c| c.is_ascii_digit())
+                .collect::<String>()
+                .parse()
+                .unwrap_or(0)
+        })
+        .collect()
+}
+
+fn is_newer_than(version: &str, current: &str) -> bool {
+    version_parts(version) > version_parts(current)
+}
+
+fn latest_release_url() -> String {
+    format!(
+        "https://api.github.com/repos/{}/releases/latest",
+        env!("CARGO_PKG_REPOSITORY").trim_start_matches("https://github.com/")
+    )
+}
+
+/// Asks GitHub for the latest release in the background. The release is picked up by
+/// `poll()`.
+#[derive(Clone, Default)]
+pub struct UpdateCheck {
+    reply: Arc<Mutex<Option<Result<Release, String>>>>,
+    checking: bool,
+}
+
+impl UpdateCheck {
+    pub fn is_checking(&self) -> bool {
+        self.checking
+    }
+
+    pub fn start(&mut self, ctx: &egui::Context) {
+        if self.checking {
+            return;
+        }
+        self.checking = true;
+        let mut request = ehttp::Request::get(latest_release_url());
+        request
+            .headers
+            .insert("Accept", "application/vnd.github+json");
+        let reply = self.reply.clone();
+        let ctx = ctx.clone();
+        ehttp::fetch(request, move |result| {
+            let result = result.and_then(|response| {
+                if !response.ok {
+                    return Err(format!(
+                        "Failed to check for updates: {} {}",
+                        response.status, response.status_text
+                    ));
+                }
+                response
+                    .json::<Release>()
+                    .map_err(|e| format!("Invalid release: {}", e))
+            });
+            if let Ok(mut r) = reply.lock() {
+                *r = Some(result);
+            }
+            ctx.request_repaint();
+        });
+    }
+
+    /// The result of the check once it's finished. Call once per frame.
+    pub fn poll(&mut self) -> Option<Result<Release, String>> {
+        let result = self.reply.lock().ok()?.take()?;
+        self.checking = false;
+        Some(result)
+    }
+}
+
+#[test]
+fn test_is_newer() {
+    assert!(is_newer_than("v0.2.0", "0.1.9"));
+    assert!(is_newer_than("1.0", "0.9.9"));
+    assert!(is_newer_than("v0.1.10", "0.1.9"));
+    assert!(!is_newer_than("v0.1.0", "0.1.0"));
+    assert!(!is_newer_than("0.1.0-beta", "0.1.0"));
+    assert!(!is_newer_than("v0.0.9", "0.1.0"));
+    assert_eq!(
+        latest_release_url(),
+        "https://api.github.com/repos/kmgill/minesweeper_web/releases/latest"
+    );
+}
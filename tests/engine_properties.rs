@@ -0,0 +1,172 @@
+// Property tests for the minesweeper engine: random boards are played with random
+// sequences of plays, checking after every play that the board is still consistent.
+use std::collections::HashSet;
+
+use proptest::prelude::*;
+
+use minesofrust_web::minesweeper::*;
+
+/// Width, height, number of mines and seed of a random board
+fn board_params() -> impl Strategy<Value = (u32, u32, u32, u64)> {
+    (1..16_u32, 1..16_u32).prop_flat_map(|(w, h)| (Just(w), Just(h), 0..=w * h, any::<u64>()))
+}
+
+fn play_type() -> impl Strategy<Value = RevealType> {
+    prop_oneof![
+        Just(RevealType::Reveal),
+        Just(RevealType::RevealChord),
+        Just(RevealType::Chord),
+        Just(RevealType::Flag),
+    ]
+}
+
+/// Plays as fractions of the board's width and height, so they fit any board
+fn plays() -> impl Strategy<Value = Vec<(f64, f64, RevealType)>> {
+    prop::collection::vec((0.0..1.0, 0.0..1.0, play_type()), 0..40)
+}
+
+fn new_board(width: u32, height: u32, num_mines: u32, seed: u64) -> GameBoard {
+    let mut gb = GameBoard::new_seeded(width, height, seed);
+    gb.populate_mines(num_mines).unwrap();
+    gb.populate_numerals().unwrap();
+    gb
+}
+
+/// Every square on the board, row by row
+fn all_squares(gb: &GameBoard) -> Vec<Square> {
+    (0..gb.height)
+        .flat_map(|y| (0..gb.width).map(move |x| gb.get_square(x, y).unwrap()))
+        .collect()
+}
+
+/// Checks the cached counters and numerals against the squares themselves
+fn check_consistent(gb: &GameBoard) -> Result<(), TestCaseError> {
+    let squares = all_squares(gb);
+    let count = |f: fn(&Square) -> bool| squares.iter().filter(|s| f(s)).count() as u32;
+    let revealed = count(|s| s.is_revealed);
+    prop_assert_eq!(gb.num_revealed(), revealed);
+    prop_assert_eq!(squares.len() as u32 - revealed, count(|s| !s.is_revealed));
+    prop_assert_eq!(gb.num_flags(), count(|s| s.is_flagged));
+    prop_assert_eq!(gb.num_detonated(), count(|s| s.is_revealed && s.is_mine()));
+    prop_assert_eq!(gb.num_safe_revealed() + gb.num_detonated(), revealed);
+    prop_assert_eq!(gb.num_mines, count(|s| s.is_mine()));
+    prop_assert_eq!(count(|s| s.is_revealed && s.is_flagged), 0);
+
+    for y in 0..gb.height {
+        for x in 0..gb.width {
+            let sqr = gb.get_square(x, y).unwrap();
+            if !sqr.is_mine() {
+                prop_assert_eq!(sqr.numeral, gb.mined_neighbor_count(x, y).unwrap());
+            }
+        }
+    }
+
+    prop_assert!(!(gb.is_win_configuration() && gb.is_loss_configuration()));
+    Ok(())
+}
+
+fn flagged(gb: &GameBoard) -> HashSet<usize> {
+    all_squares(gb)
+        .iter()
+        .enumerate()
+        .filter(|(_, s)| s.is_flagged)
+        .map(|(i, _)| i)
+        .collect()
+}
+
+proptest! {
+    #[test]
+    fn board_stays_consistent(
+        (width, height, num_mines, seed) in board_params(),
+        plays in plays(),
+    ) {
+        let mut gb = new_board(width, height, num_mines, seed);
+        check_consistent(&gb)?;
+
+        for (fx, fy, play_type) in plays {
+            // Like the game, stop once it's over
+            if gb.is_win_configuration() || gb.is_loss_configuration() {
+                break;
+            }
+            let (x, y) = ((fx * width as f64) as u32, (fy * height as f64) as u32);
+            let flagged_before = flagged(&gb);
+            let is_flag = play_type == RevealType::Flag;
+            gb.play(x, y, play_type).unwrap();
+            check_consistent(&gb)?;
+
+            // Only a flag play changes flags, and no play reveals a flagged square
+            if !is_flag {
+                prop_assert_eq!(&flagged(&gb), &flagged_before);
+            }
+            let squares = all_squares(&gb);
+            for idx in flagged_before {
+                prop_assert!(!squares[idx].is_revealed);
+            }
+        }
+    }
+
+    #[test]
+    fn first_click_is_kept_clear(
+        (width, height, num_mines, seed) in board_params(),
+        (fx, fy) in (0.0..1.0, 0.0..1.0),
+    ) {
+        let keep_clear = Coordinate::from(((fx * width as f64) as u32, (fy * height as f64) as u32));
+        let mut gb = GameBoard::new_seeded(width, height, seed);
+        match gb.populate_mines_around(num_mines, Some(keep_clear.clone())) {
+            Ok(()) => {
+                prop_assert_eq!(gb.num_mines, num_mines);
+                gb.populate_numerals().unwrap();
+                gb.play(keep_clear.x, keep_clear.y, RevealType::Reveal).unwrap();
+                prop_assert!(!gb.is_loss_configuration());
+                check_consistent(&gb)?;
+            }
+            // Only when there's no room left for the mines
+            Err(_) => prop_assert!(num_mines > 0),
+        }
+    }
+
+    #[test]
+    fn board_text_round_trips((width, height, num_mines, seed) in board_params()) {
+        let gb = new_board(width, height, num_mines, seed);
+        let reloaded = GameBoard::from_ascii(&gb.to_ascii(true)).unwrap();
+        prop_assert_eq!(all_squares(&reloaded), all_squares(&gb));
+        prop_assert_eq!(reloaded.bbbv(), gb.bbbv());
+    }
+}
+
+fn load_fixture(name: &str) -> GameBoard {
+    let path = format!("{}/tests/fixtures/{}", env!("CARGO_MANIFEST_DIR"), name);
+    let text = std::fs::read_to_string(&path).unwrap();
+    GameBoard::from_ascii(&text).unwrap()
+}
+
+#[test]
+fn test_fixtures() {
+    // Name, size, mines and 3BV of each fixture
+    [
+        ("empty.txt", 9, 3, 0, 1),
+        ("corners.txt", 5, 5, 4, 1),
+        ("one_two_one.txt", 3, 3, 2, 2),
+        ("expert.txt", 30, 16, 80, 126),
+    ]
+    .into_iter()
+    .for_each(|(name, width, height, num_mines, bbbv)| {
+        let gb = load_fixture(name);
+        assert_eq!((gb.width, gb.height), (width, height), "{}", name);
+        assert_eq!(gb.num_mines, num_mines, "{}", name);
+        assert_eq!(gb.bbbv(), bbbv, "{}", name);
+        check_consistent(&gb).unwrap();
+    });
+}
+
+#[test]
+fn test_fixture_cascade_never_reveals_flags() {
+    // Flagging a blank in the bottom row and opening the blank next to it leaves the flag
+    // covered, even though the cascade runs right past it
+    let mut gb = load_fixture("one_two_one.txt");
+    gb.play(1, 2, RevealType::Flag).unwrap();
+    gb.play(0, 2, RevealType::Reveal).unwrap();
+    assert!(gb.get_square(1, 2).unwrap().is_flagged);
+    assert!(!gb.get_square(1, 2).unwrap().is_revealed);
+    check_consistent(&gb).unwrap();
+}